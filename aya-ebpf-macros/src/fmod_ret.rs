@@ -0,0 +1,149 @@
+use std::borrow::Cow;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{ItemFn, Result};
+
+use crate::args::{err_on_unknown_args, pop_bool_arg, pop_string_arg};
+
+pub(crate) struct FModRet {
+    item: ItemFn,
+    function: Option<String>,
+    sleepable: bool,
+}
+
+impl FModRet {
+    pub(crate) fn parse(attrs: TokenStream, item: TokenStream) -> Result<Self> {
+        let item = syn::parse2(item)?;
+        let mut args = syn::parse2(attrs)?;
+        let function = pop_string_arg(&mut args, "function");
+        let sleepable = pop_bool_arg(&mut args, "sleepable");
+        err_on_unknown_args(&args)?;
+        Ok(Self {
+            item,
+            function,
+            sleepable,
+        })
+    }
+
+    pub(crate) fn expand(&self) -> TokenStream {
+        let Self {
+            item,
+            function,
+            sleepable,
+        } = self;
+        let ItemFn {
+            attrs: _,
+            vis,
+            sig,
+            block: _,
+        } = item;
+        let section_prefix = if *sleepable { "fmod_ret.s" } else { "fmod_ret" };
+        let section_name: Cow<'_, _> = if let Some(function) = function {
+            format!("{}/{}", section_prefix, function).into()
+        } else {
+            section_prefix.into()
+        };
+        // A non-zero return value overrides the return value of the probed
+        // function, so the value returned by the user function is passed through.
+        let fn_name = &sig.ident;
+        quote! {
+            #[unsafe(no_mangle)]
+            #[unsafe(link_section = #section_name)]
+            #vis fn #fn_name(ctx: *mut ::core::ffi::c_void) -> i32 {
+                return #fn_name(::aya_ebpf::programs::FModRetContext::new(ctx));
+
+                #item
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn test_fmod_ret() {
+        let prog = FModRet::parse(
+            parse_quote! {},
+            parse_quote! {
+                fn security_file_open(ctx: &mut FModRetContext) -> i32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        let expanded = prog.expand();
+        let expected = quote! {
+            #[unsafe(no_mangle)]
+            #[unsafe(link_section = "fmod_ret")]
+            fn security_file_open(ctx: *mut ::core::ffi::c_void) -> i32 {
+                return security_file_open(::aya_ebpf::programs::FModRetContext::new(ctx));
+
+                fn security_file_open(ctx: &mut FModRetContext) -> i32 {
+                    0
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), expanded.to_string());
+    }
+
+    #[test]
+    fn test_fmod_ret_with_function() {
+        let prog = FModRet::parse(
+            parse_quote! {
+                function = "security_file_open"
+            },
+            parse_quote! {
+                fn security_file_open(ctx: &mut FModRetContext) -> i32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        let expanded = prog.expand();
+        let expected = quote! {
+            #[unsafe(no_mangle)]
+            #[unsafe(link_section = "fmod_ret/security_file_open")]
+            fn security_file_open(ctx: *mut ::core::ffi::c_void) -> i32 {
+                return security_file_open(::aya_ebpf::programs::FModRetContext::new(ctx));
+
+                fn security_file_open(ctx: &mut FModRetContext) -> i32 {
+                    0
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), expanded.to_string());
+    }
+
+    #[test]
+    fn test_fmod_ret_sleepable() {
+        let prog = FModRet::parse(
+            parse_quote! {
+                function = "security_file_open", sleepable
+            },
+            parse_quote! {
+                fn security_file_open(ctx: &mut FModRetContext) -> i32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        let expanded = prog.expand();
+        let expected = quote! {
+            #[unsafe(no_mangle)]
+            #[unsafe(link_section = "fmod_ret.s/security_file_open")]
+            fn security_file_open(ctx: *mut ::core::ffi::c_void) -> i32 {
+                return security_file_open(::aya_ebpf::programs::FModRetContext::new(ctx));
+
+                fn security_file_open(ctx: &mut FModRetContext) -> i32 {
+                    0
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), expanded.to_string());
+    }
+}
@@ -5,7 +5,7 @@ use proc_macro2_diagnostics::{Diagnostic, SpanDiagnosticExt as _};
 use quote::quote;
 use syn::{ItemFn, spanned::Spanned as _};
 
-use crate::args::{err_on_unknown_args, pop_string_arg};
+use crate::args::{err_on_unknown_args, pop_bool_arg, pop_string_arg};
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) enum KProbeKind {
@@ -27,6 +27,7 @@ pub(crate) struct KProbe {
     kind: KProbeKind,
     function: Option<String>,
     offset: Option<u64>,
+    multi: bool,
     item: ItemFn,
 }
 
@@ -45,13 +46,18 @@ impl KProbe {
             .map(str::parse)
             .transpose()
             .map_err(|err| span.error(format!("failed to parse `offset` argument: {}", err)))?;
+        let multi = pop_bool_arg(&mut args, "multi");
         err_on_unknown_args(&args)?;
+        if multi && offset.is_some() {
+            return Err(span.error("`offset` can't be used with `multi`"));
+        }
 
         Ok(Self {
             kind,
             item,
             function,
             offset,
+            multi,
         })
     }
 
@@ -60,6 +66,7 @@ impl KProbe {
             kind,
             function,
             offset,
+            multi,
             item,
         } = self;
         let ItemFn {
@@ -68,11 +75,15 @@ impl KProbe {
             sig,
             block: _,
         } = item;
+        let mut prefix = kind.to_string();
+        if *multi {
+            prefix.push_str(".multi");
+        }
         let section_name: Cow<'_, _> = match function {
-            None => self.kind.to_string().into(),
+            None => prefix.into(),
             Some(function) => match offset {
-                None => format!("{kind}/{function}").into(),
-                Some(offset) => format!("{kind}/{function}+{offset}").into(),
+                None => format!("{prefix}/{function}").into(),
+                Some(offset) => format!("{prefix}/{function}+{offset}").into(),
             },
         };
         let probe_type = if section_name.as_ref().starts_with("kprobe") {
@@ -224,4 +235,89 @@ mod tests {
             .to_string()
         );
     }
+
+    #[test]
+    fn test_kprobe_multi() {
+        let kprobe = KProbe::parse(
+            KProbeKind::KProbe,
+            parse_quote! {
+                function = "tcp_*",
+                multi
+            },
+            parse_quote! {
+                fn foo(ctx: ProbeContext) -> u32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            kprobe.expand().to_string(),
+            quote! {
+                #[unsafe(no_mangle)]
+                #[unsafe(link_section = "kprobe.multi/tcp_*")]
+                fn foo(ctx: *mut ::core::ffi::c_void) -> u32 {
+                    let _ = foo(::aya_ebpf::programs::ProbeContext::new(ctx));
+                    return 0;
+
+                    fn foo(ctx: ProbeContext) -> u32 {
+                        0
+                    }
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_kretprobe_multi() {
+        let kprobe = KProbe::parse(
+            KProbeKind::KRetProbe,
+            parse_quote! {
+                multi
+            },
+            parse_quote! {
+                fn foo(ctx: RetProbeContext) -> u32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            kprobe.expand().to_string(),
+            quote! {
+                #[unsafe(no_mangle)]
+                #[unsafe(link_section = "kretprobe.multi")]
+                fn foo(ctx: *mut ::core::ffi::c_void) -> u32 {
+                    let _ = foo(::aya_ebpf::programs::RetProbeContext::new(ctx));
+                    return 0;
+
+                    fn foo(ctx: RetProbeContext) -> u32 {
+                        0
+                    }
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_kprobe_multi_with_offset() {
+        assert!(
+            KProbe::parse(
+                KProbeKind::KProbe,
+                parse_quote! {
+                    function = "tcp_*",
+                    offset = "10",
+                    multi
+                },
+                parse_quote! {
+                    fn foo(ctx: ProbeContext) -> u32 {
+                        0
+                    }
+                },
+            )
+            .is_err()
+        );
+    }
 }
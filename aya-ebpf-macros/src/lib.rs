@@ -9,19 +9,26 @@ mod cgroup_sysctl;
 mod fentry;
 mod fexit;
 mod flow_dissector;
+mod fmod_ret;
 mod kprobe;
 mod lsm;
+mod lsm_cgroup;
 mod map;
+mod netfilter;
 mod perf_event;
 mod raw_tracepoint;
 mod sk_lookup;
 mod sk_msg;
+mod sk_reuseport;
 mod sk_skb;
 mod sock_ops;
 mod socket_filter;
+mod struct_ops;
+mod syscall;
 mod tc;
 mod tracepoint;
 mod uprobe;
+mod usdt;
 mod xdp;
 
 use btf_tracepoint::BtfTracePoint;
@@ -34,20 +41,27 @@ use cgroup_sysctl::CgroupSysctl;
 use fentry::FEntry;
 use fexit::FExit;
 use flow_dissector::FlowDissector;
+use fmod_ret::FModRet;
 use kprobe::{KProbe, KProbeKind};
 use lsm::Lsm;
+use lsm_cgroup::LsmCgroup;
 use map::Map;
+use netfilter::Netfilter;
 use perf_event::PerfEvent;
 use proc_macro::TokenStream;
 use raw_tracepoint::RawTracePoint;
 use sk_lookup::SkLookup;
 use sk_msg::SkMsg;
+use sk_reuseport::SkReuseport;
 use sk_skb::{SkSkb, SkSkbKind};
 use sock_ops::SockOps;
 use socket_filter::SocketFilter;
+use struct_ops::StructOps;
+use syscall::Syscall;
 use tc::SchedClassifier;
 use tracepoint::TracePoint;
 use uprobe::{UProbe, UProbeKind};
+use usdt::Usdt;
 use xdp::Xdp;
 
 #[proc_macro_attribute]
@@ -96,6 +110,45 @@ pub fn uretprobe(attrs: TokenStream, item: TokenStream) -> TokenStream {
     }
     .into()
 }
+
+/// Marks a function as a USDT (user statically-defined tracing) eBPF program.
+///
+/// USDT programs are attached to the probes that applications declare with
+/// `DTRACE_PROBE` or `STAP_PROBE` macros. The probe arguments can be read with
+/// [`UsdtContext::arg`](../aya_ebpf/programs/usdt/struct.UsdtContext.html#method.arg),
+/// using the argument specs aya records when attaching the program.
+///
+/// `UsdtContext` requires the `usdt` feature of `aya-ebpf`, which declares the
+/// `AYA_USDT_SPECS` map holding the argument specs.
+///
+/// `sleepable` marks the program as sleepable.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.15.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya_ebpf::{macros::usdt, programs::UsdtContext};
+///
+/// #[usdt]
+/// fn request_start(ctx: UsdtContext) -> u32 {
+///     match ctx.arg(0) {
+///         Ok(_request_id) => 0,
+///         Err(_) => 1,
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn usdt(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match Usdt::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog.expand(),
+        Err(err) => err.into_compile_error(),
+    }
+    .into()
+}
+
 #[proc_macro_attribute]
 pub fn sock_ops(attrs: TokenStream, item: TokenStream) -> TokenStream {
     match SockOps::parse(attrs.into(), item.into()) {
@@ -254,6 +307,10 @@ pub fn perf_event(attrs: TokenStream, item: TokenStream) -> TokenStream {
 /// be attached to. See `/sys/kernel/debug/tracing/events` for a list of which
 /// events can be traced.
 ///
+/// Pass `writable` to make the program a writable raw tracepoint, which gets a
+/// `RawTracePointWritableContext` and can modify the buffer of tracepoints
+/// that expose one. Writable raw tracepoints require kernel 5.2.
+///
 /// # Minimum kernel version
 ///
 /// The minimum kernel version required to use this feature is 4.7.
@@ -328,6 +385,39 @@ pub fn lsm(attrs: TokenStream, item: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Marks a function as an LSM program that can be attached to Linux LSM hooks
+/// for the tasks of a cgroup.
+///
+/// The hook name is the first argument to the macro.
+///
+/// The program must return 1 to allow the operation, or 0 to deny it. It can
+/// keep per-cgroup state in a [`CgroupStorage`] map.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 6.0.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya_ebpf::{macros::lsm_cgroup, programs::LsmContext};
+///
+/// #[lsm_cgroup(hook = "socket_bind")]
+/// pub fn socket_bind(_ctx: LsmContext) -> i32 {
+///     1
+/// }
+/// ```
+///
+/// [`CgroupStorage`]: ../aya_ebpf/maps/cgroup_storage/struct.CgroupStorage.html
+#[proc_macro_attribute]
+pub fn lsm_cgroup(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match LsmCgroup::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog.expand(),
+        Err(err) => err.into_compile_error(),
+    }
+    .into()
+}
+
 /// Marks a function as a [BTF-enabled raw tracepoint][1] eBPF program that can be attached at
 /// a pre-defined kernel trace point.
 ///
@@ -544,6 +634,47 @@ pub fn fexit(attrs: TokenStream, item: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Marks a function as a fmod_ret eBPF program that can override the return
+/// value of a kernel function. fmod_ret programs run before the function and,
+/// when they return a non-zero value, the function is skipped and the value is
+/// returned to its caller instead. Only functions that allow error injection,
+/// such as the `security_*` hooks, can be attached to.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.7.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #![expect(non_camel_case_types)]
+/// use aya_ebpf::{cty::c_int, macros::fmod_ret, programs::FModRetContext};
+/// # type file = u32;
+///
+/// #[fmod_ret(function = "security_file_open")]
+/// fn security_file_open(ctx: FModRetContext) -> i32 {
+///     match unsafe { try_security_file_open(ctx) } {
+///         Ok(ret) => ret,
+///         Err(ret) => ret,
+///     }
+/// }
+///
+/// unsafe fn try_security_file_open(ctx: FModRetContext) -> Result<i32, i32> {
+///     let _file: *const file = ctx.arg(0);
+///     let ret: c_int = ctx.ret(1);
+///
+///     Ok(ret)
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn fmod_ret(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match FModRet::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog.expand(),
+        Err(err) => err.into_compile_error(),
+    }
+    .into()
+}
+
 /// Marks a function as an eBPF Flow Dissector program.
 ///
 /// Flow dissector is a program type that parses metadata out of the packets.
@@ -612,6 +743,119 @@ pub fn sk_lookup(attrs: TokenStream, item: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Marks a function as an eBPF Socket Reuseport program that can be attached
+/// to a group of sockets bound with `SO_REUSEPORT`.
+///
+/// The program selects the socket handling a packet or connection with
+/// [`SkReuseportContext::select_reuseport`]. It must return `SK_PASS` (1) to accept it,
+/// or `SK_DROP` (0) to drop it. Use `#[sk_reuseport(migrate)]` for programs
+/// that also select the socket requests are migrated to when a listener is
+/// closed.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.19, and 5.14
+/// with `migrate`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya_ebpf::{
+///     macros::{map, sk_reuseport},
+///     maps::ReusePortSockArray,
+///     programs::SkReuseportContext,
+/// };
+///
+/// #[map]
+/// static WORKERS: ReusePortSockArray = ReusePortSockArray::with_max_entries(4, 0);
+///
+/// #[sk_reuseport]
+/// pub fn select_worker(ctx: SkReuseportContext) -> u32 {
+///     let _ = ctx.select_reuseport(&WORKERS, ctx.hash() % 4, 0);
+///     1
+/// }
+/// ```
+///
+/// [`SkReuseportContext::select_reuseport`]: ../aya_ebpf/programs/sk_reuseport/struct.SkReuseportContext.html#method.select_reuseport
+#[proc_macro_attribute]
+pub fn sk_reuseport(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match SkReuseport::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog.expand(),
+        Err(err) => err.emit_as_expr_tokens(),
+    }
+    .into()
+}
+
+/// Marks a function as an eBPF syscall program.
+///
+/// Syscall programs aren't attached to a hook: they are run from user space
+/// with a context struct of the caller's choosing, which the program accesses
+/// through [`SyscallContext::args`]. They are always sleepable and can call
+/// helpers such as `bpf_sys_bpf`.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.14.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya_ebpf::{macros::syscall, programs::SyscallContext};
+///
+/// #[repr(C)]
+/// pub struct Args {
+///     value: u64,
+/// }
+///
+/// #[syscall]
+/// pub fn double(ctx: SyscallContext<Args>) -> i32 {
+///     let args = ctx.args();
+///     unsafe { (*args).value *= 2 };
+///     0
+/// }
+/// ```
+///
+/// [`SyscallContext::args`]: ../aya_ebpf/programs/syscall/struct.SyscallContext.html#method.args
+#[proc_macro_attribute]
+pub fn syscall(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match Syscall::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog.expand(),
+        Err(err) => err.emit_as_expr_tokens(),
+    }
+    .into()
+}
+
+/// Marks a function as an eBPF netfilter program that can be attached to a
+/// netfilter hook.
+///
+/// The function must return `NF_ACCEPT` (1) to let the packet through or
+/// `NF_DROP` (0) to drop it.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 6.4.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya_ebpf::{macros::netfilter, programs::NfContext};
+///
+/// const NF_ACCEPT: i32 = 1;
+///
+/// #[netfilter]
+/// pub fn accept_all(_ctx: NfContext) -> i32 {
+///     NF_ACCEPT
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn netfilter(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match Netfilter::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog.expand(),
+        Err(err) => err.emit_as_expr_tokens(),
+    }
+    .into()
+}
+
 /// Marks a function as a cgroup device eBPF program that can be attached to a
 /// cgroup.
 ///
@@ -641,3 +885,39 @@ pub fn cgroup_device(attrs: TokenStream, item: TokenStream) -> TokenStream {
     }
     .into()
 }
+
+/// Marks a function as a `struct_ops` eBPF program.
+///
+/// `struct_ops` programs implement the callbacks of a kernel operations
+/// structure, such as `tcp_congestion_ops`. The programs are referenced by a
+/// struct_ops map declared in the `.struct_ops` or `.struct_ops.link` section,
+/// which is registered with the kernel from user space.
+///
+/// An optional `name` may be given to append a suffix to the section name,
+/// and `sleepable` marks the program as sleepable.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.6.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #![expect(non_camel_case_types)]
+/// use aya_ebpf::{macros::struct_ops, programs::StructOpsContext};
+/// # type sock = u32;
+///
+/// #[struct_ops]
+/// fn aya_ssthresh(ctx: StructOpsContext) -> i32 {
+///     let _sk: *const sock = unsafe { ctx.arg(0) };
+///     2
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn struct_ops(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match StructOps::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog.expand(),
+        Err(err) => err.into_compile_error(),
+    }
+    .into()
+}
@@ -4,11 +4,12 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{ItemFn, Result};
 
-use crate::args::{err_on_unknown_args, pop_string_arg};
+use crate::args::{err_on_unknown_args, pop_bool_arg, pop_string_arg};
 
 pub(crate) struct RawTracePoint {
     item: ItemFn,
     tracepoint: Option<String>,
+    writable: bool,
 }
 
 impl RawTracePoint {
@@ -16,29 +17,44 @@ impl RawTracePoint {
         let item = syn::parse2(item)?;
         let mut args = syn::parse2(attrs)?;
         let tracepoint = pop_string_arg(&mut args, "tracepoint");
+        let writable = pop_bool_arg(&mut args, "writable");
         err_on_unknown_args(&args)?;
-        Ok(Self { item, tracepoint })
+        Ok(Self {
+            item,
+            tracepoint,
+            writable,
+        })
     }
 
     pub(crate) fn expand(&self) -> TokenStream {
-        let Self { item, tracepoint } = self;
+        let Self {
+            item,
+            tracepoint,
+            writable,
+        } = self;
         let ItemFn {
             attrs: _,
             vis,
             sig,
             block: _,
         } = item;
+        let section_prefix = if *writable { "raw_tp.w" } else { "raw_tp" };
         let section_name: Cow<'_, _> = if let Some(tracepoint) = tracepoint {
-            format!("raw_tp/{}", tracepoint).into()
+            format!("{section_prefix}/{tracepoint}").into()
         } else {
-            "raw_tp".into()
+            section_prefix.into()
+        };
+        let context = if *writable {
+            quote! { ::aya_ebpf::programs::RawTracePointWritableContext }
+        } else {
+            quote! { ::aya_ebpf::programs::RawTracePointContext }
         };
         let fn_name = &sig.ident;
         quote! {
             #[unsafe(no_mangle)]
             #[unsafe(link_section = #section_name)]
             #vis fn #fn_name(ctx: *mut ::core::ffi::c_void) -> u32 {
-                let _ = #fn_name(::aya_ebpf::programs::RawTracePointContext::new(ctx));
+                let _ = #fn_name(#context::new(ctx));
                 return 0;
 
                 #item
@@ -79,4 +95,31 @@ mod tests {
         };
         assert_eq!(expected.to_string(), expanded.to_string());
     }
+
+    #[test]
+    fn test_raw_tracepoint_writable() {
+        let prog = RawTracePoint::parse(
+            parse_quote! { tracepoint = "nbd_send_request", writable },
+            parse_quote! {
+                fn prog(ctx: &mut ::aya_ebpf::programs::RawTracePointWritableContext) -> i32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        let expanded = prog.expand();
+        let expected = quote! {
+            #[unsafe(no_mangle)]
+            #[unsafe(link_section = "raw_tp.w/nbd_send_request")]
+            fn prog(ctx: *mut ::core::ffi::c_void) -> u32 {
+                let _ = prog(::aya_ebpf::programs::RawTracePointWritableContext::new(ctx));
+                return 0;
+
+                fn prog(ctx: &mut ::aya_ebpf::programs::RawTracePointWritableContext) -> i32 {
+                    0
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), expanded.to_string());
+    }
 }
@@ -0,0 +1,105 @@
+use proc_macro2::TokenStream;
+use proc_macro2_diagnostics::Diagnostic;
+use quote::quote;
+use syn::ItemFn;
+
+use crate::args::{Args, err_on_unknown_args, pop_bool_arg};
+
+pub(crate) struct SkReuseport {
+    item: ItemFn,
+    migrate: bool,
+}
+
+impl SkReuseport {
+    pub(crate) fn parse(attrs: TokenStream, item: TokenStream) -> Result<Self, Diagnostic> {
+        let item = syn::parse2(item)?;
+        let mut args: Args = syn::parse2(attrs)?;
+        let migrate = pop_bool_arg(&mut args, "migrate");
+        err_on_unknown_args(&args)?;
+        Ok(Self { item, migrate })
+    }
+
+    pub(crate) fn expand(&self) -> TokenStream {
+        let Self { item, migrate } = self;
+        let ItemFn {
+            attrs: _,
+            vis,
+            sig,
+            block: _,
+        } = item;
+        let section_name = if *migrate {
+            "sk_reuseport/migrate"
+        } else {
+            "sk_reuseport"
+        };
+        let fn_name = &sig.ident;
+        quote! {
+            #[unsafe(no_mangle)]
+            #[unsafe(link_section = #section_name)]
+            #vis fn #fn_name(ctx: *mut ::aya_ebpf::bindings::sk_reuseport_md) -> u32 {
+                return #fn_name(::aya_ebpf::programs::SkReuseportContext::new(ctx));
+
+                #item
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn test_sk_reuseport() {
+        let prog = SkReuseport::parse(
+            parse_quote! {},
+            parse_quote! {
+                fn prog(ctx: &mut ::aya_ebpf::programs::SkReuseportContext) -> u32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        let expanded = prog.expand();
+        let expected = quote! {
+            #[unsafe(no_mangle)]
+            #[unsafe(link_section = "sk_reuseport")]
+            fn prog(ctx: *mut ::aya_ebpf::bindings::sk_reuseport_md) -> u32 {
+                return prog(::aya_ebpf::programs::SkReuseportContext::new(ctx));
+
+                fn prog(ctx: &mut ::aya_ebpf::programs::SkReuseportContext) -> u32 {
+                    0
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), expanded.to_string());
+    }
+
+    #[test]
+    fn test_sk_reuseport_migrate() {
+        let prog = SkReuseport::parse(
+            parse_quote! { migrate },
+            parse_quote! {
+                fn prog(ctx: &mut ::aya_ebpf::programs::SkReuseportContext) -> u32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        let expanded = prog.expand();
+        let expected = quote! {
+            #[unsafe(no_mangle)]
+            #[unsafe(link_section = "sk_reuseport/migrate")]
+            fn prog(ctx: *mut ::aya_ebpf::bindings::sk_reuseport_md) -> u32 {
+                return prog(::aya_ebpf::programs::SkReuseportContext::new(ctx));
+
+                fn prog(ctx: &mut ::aya_ebpf::programs::SkReuseportContext) -> u32 {
+                    0
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), expanded.to_string());
+    }
+}
@@ -30,6 +30,7 @@ pub(crate) struct UProbe {
     offset: Option<u64>,
     item: ItemFn,
     sleepable: bool,
+    multi: bool,
 }
 
 impl UProbe {
@@ -49,7 +50,11 @@ impl UProbe {
             .transpose()
             .map_err(|err| span.error(format!("failed to parse `offset` argument: {}", err)))?;
         let sleepable = pop_bool_arg(&mut args, "sleepable");
+        let multi = pop_bool_arg(&mut args, "multi");
         err_on_unknown_args(&args)?;
+        if multi && offset.is_some() {
+            return Err(span.error("`offset` can't be used with `multi`"));
+        }
         Ok(Self {
             kind,
             item,
@@ -57,6 +62,7 @@ impl UProbe {
             function,
             offset,
             sleepable,
+            multi,
         })
     }
 
@@ -68,6 +74,7 @@ impl UProbe {
             offset,
             item,
             sleepable,
+            multi,
         } = self;
         let ItemFn {
             attrs: _,
@@ -76,6 +83,9 @@ impl UProbe {
             block: _,
         } = item;
         let mut prefix = kind.to_string();
+        if *multi {
+            prefix.push_str(".multi");
+        }
         if *sleepable {
             prefix.push_str(".s");
         }
@@ -179,6 +189,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn uprobe_multi() {
+        let uprobe = UProbe::parse(
+            UProbeKind::UProbe,
+            parse_quote! {
+                path = "libc.so.6",
+                function = "malloc*",
+                multi
+            },
+            parse_quote! {
+                fn foo(ctx: ProbeContext) -> u32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            uprobe.expand().unwrap().to_string(),
+            quote! {
+                #[unsafe(no_mangle)]
+                #[unsafe(link_section = "uprobe.multi/libc.so.6:malloc*")]
+                fn foo(ctx: *mut ::core::ffi::c_void) -> u32 {
+                    let _ = foo(::aya_ebpf::programs::ProbeContext::new(ctx));
+                    return 0;
+
+                    fn foo(ctx: ProbeContext) -> u32 {
+                        0
+                    }
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn uretprobe_multi_sleepable() {
+        let uprobe = UProbe::parse(
+            UProbeKind::URetProbe,
+            parse_quote! {
+                multi,
+                sleepable
+            },
+            parse_quote! {
+                fn foo(ctx: RetProbeContext) -> u32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            uprobe.expand().unwrap().to_string(),
+            quote! {
+                #[unsafe(no_mangle)]
+                #[unsafe(link_section = "uretprobe.multi.s")]
+                fn foo(ctx: *mut ::core::ffi::c_void) -> u32 {
+                    let _ = foo(::aya_ebpf::programs::RetProbeContext::new(ctx));
+                    return 0;
+
+                    fn foo(ctx: RetProbeContext) -> u32 {
+                        0
+                    }
+                }
+            }
+            .to_string()
+        );
+    }
+
     #[test]
     fn uprobe_with_path() {
         let uprobe = UProbe::parse(
@@ -0,0 +1,105 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{ItemFn, Result};
+
+use crate::args::{err_on_unknown_args, pop_bool_arg};
+
+pub(crate) struct Usdt {
+    item: ItemFn,
+    sleepable: bool,
+}
+
+impl Usdt {
+    pub(crate) fn parse(attrs: TokenStream, item: TokenStream) -> Result<Self> {
+        let item = syn::parse2(item)?;
+        let mut args = syn::parse2(attrs)?;
+        let sleepable = pop_bool_arg(&mut args, "sleepable");
+        err_on_unknown_args(&args)?;
+        Ok(Self { item, sleepable })
+    }
+
+    pub(crate) fn expand(&self) -> TokenStream {
+        let Self { item, sleepable } = self;
+        let ItemFn {
+            attrs: _,
+            vis,
+            sig,
+            block: _,
+        } = item;
+        let section_name = if *sleepable { "usdt.s" } else { "usdt" };
+        let fn_name = &sig.ident;
+        quote! {
+            #[unsafe(no_mangle)]
+            #[unsafe(link_section = #section_name)]
+            #vis fn #fn_name(ctx: *mut ::core::ffi::c_void) -> u32 {
+                let _ = #fn_name(::aya_ebpf::programs::UsdtContext::new(ctx));
+                return 0;
+
+                #item
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn test_usdt() {
+        let usdt = Usdt::parse(
+            parse_quote! {},
+            parse_quote! {
+                fn foo(ctx: UsdtContext) -> u32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        let expanded = usdt.expand();
+        let expected = quote! {
+            #[unsafe(no_mangle)]
+            #[unsafe(link_section = "usdt")]
+            fn foo(ctx: *mut ::core::ffi::c_void) -> u32 {
+                let _ = foo(::aya_ebpf::programs::UsdtContext::new(ctx));
+                return 0;
+
+                fn foo(ctx: UsdtContext) -> u32 {
+                    0
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), expanded.to_string());
+    }
+
+    #[test]
+    fn test_usdt_sleepable() {
+        let usdt = Usdt::parse(
+            parse_quote! {
+                sleepable
+            },
+            parse_quote! {
+                fn foo(ctx: UsdtContext) -> u32 {
+                    0
+                }
+            },
+        )
+        .unwrap();
+        let expanded = usdt.expand();
+        let expected = quote! {
+            #[unsafe(no_mangle)]
+            #[unsafe(link_section = "usdt.s")]
+            fn foo(ctx: *mut ::core::ffi::c_void) -> u32 {
+                let _ = foo(::aya_ebpf::programs::UsdtContext::new(ctx));
+                return 0;
+
+                fn foo(ctx: UsdtContext) -> u32 {
+                    0
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), expanded.to_string());
+    }
+}
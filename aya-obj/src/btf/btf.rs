@@ -2,6 +2,7 @@ use alloc::{
     borrow::{Cow, ToOwned as _},
     format,
     string::String,
+    sync::Arc,
     vec,
     vec::Vec,
 };
@@ -15,10 +16,11 @@ use crate::{
     Object,
     btf::{
         Array, BtfEnum, BtfKind, BtfMember, BtfType, Const, Enum, FuncInfo, FuncLinkage, Int,
-        IntEncoding, LineInfo, Struct, Typedef, Union, VarLinkage,
+        IntEncoding, LineInfo, Struct, Typedef, Union, Var, VarLinkage,
         info::{FuncSecInfo, LineSecInfo},
         relocation::Relocation,
     },
+    externs::{KCONFIG_SECTION, KSYMS_SECTION},
     generated::{btf_ext_header, btf_header},
     util::{HashMap, bytes_of},
 };
@@ -245,6 +247,10 @@ pub struct Btf {
     header: btf_header,
     strings: Vec<u8>,
     types: BtfTypes,
+    // The BTF that split BTF, such as the BTF of kernel modules, is built on top of.
+    base: Option<Arc<Btf>>,
+    // The name of the kernel module the BTF was loaded from.
+    module: Option<String>,
     _endianness: Endianness,
 }
 
@@ -264,6 +270,8 @@ impl Btf {
             },
             strings: vec![0],
             types: BtfTypes::default(),
+            base: None,
+            module: None,
             _endianness: Endianness::default(),
         }
     }
@@ -274,7 +282,16 @@ impl Btf {
     }
 
     pub(crate) fn types(&self) -> impl Iterator<Item = &BtfType> {
-        self.types.types.iter()
+        // the first type of split BTF is the one following the last type of the base
+        self.base
+            .iter()
+            .flat_map(|base| base.types.types.iter())
+            .chain(self.types.types.iter().skip(self.base.is_some().into()))
+    }
+
+    // Returns the id of the first type that isn't part of the base BTF.
+    fn first_type_id(&self) -> u32 {
+        self.base.as_ref().map_or(0, |base| base.types.len() as u32)
     }
 
     /// Adds a string to BTF metadata, returning an offset
@@ -296,12 +313,73 @@ impl Btf {
         type_id as u32
     }
 
+    /// Builds the BTF metadata needed to create a local storage map with values
+    /// of `value_size` bytes.
+    ///
+    /// The kernel requires local storage maps to be created with BTF describing
+    /// their key and value types. The key is an `int` and the value a
+    /// `char[value_size]`. Returns the BTF along with the key and value type ids.
+    pub fn local_storage(value_size: u32) -> (Btf, u32, u32) {
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("int");
+        let int_type = BtfType::Int(Int::new(name_offset, 4, IntEncoding::Signed, 0));
+        let int_type_id = btf.add_type(int_type);
+        let name_offset = btf.add_string("char");
+        let char_type = BtfType::Int(Int::new(name_offset, 1, IntEncoding::Char, 0));
+        let char_type_id = btf.add_type(char_type);
+        let array_type = BtfType::Array(Array::new(0, char_type_id, int_type_id, value_size));
+        let array_type_id = btf.add_type(array_type);
+        (btf, int_type_id, array_type_id)
+    }
+
     /// Loads BTF metadata from `/sys/kernel/btf/vmlinux`.
     #[cfg(feature = "std")]
     pub fn from_sys_fs() -> Result<Btf, BtfError> {
         Btf::parse_file("/sys/kernel/btf/vmlinux", Endianness::default())
     }
 
+    /// Loads the BTF metadata of the kernel module `module` from
+    /// `/sys/kernel/btf/<module>`.
+    ///
+    /// Module BTF is split BTF built on top of the BTF of the kernel, which
+    /// must be passed as `base`. See [`Btf::from_sys_fs`].
+    #[cfg(feature = "std")]
+    pub fn from_sys_fs_module(module: &str, base: Arc<Btf>) -> Result<Btf, BtfError> {
+        use std::{borrow::ToOwned as _, fs, path::Path};
+        let path = Path::new("/sys/kernel/btf").join(module);
+        let mut btf = Btf::parse_split(
+            &fs::read(&path).map_err(|error| BtfError::FileError {
+                path: path.to_owned(),
+                error,
+            })?,
+            base,
+            Endianness::default(),
+        )?;
+        btf.module = Some(module.to_owned());
+        Ok(btf)
+    }
+
+    /// Returns the names of the kernel modules whose BTF metadata is available
+    /// in `/sys/kernel/btf`.
+    #[cfg(feature = "std")]
+    pub fn sys_fs_modules() -> Result<Vec<String>, BtfError> {
+        use std::{fs, path::PathBuf};
+        const SYS_FS_BTF: &str = "/sys/kernel/btf";
+        let file_error = |error| BtfError::FileError {
+            path: PathBuf::from(SYS_FS_BTF),
+            error,
+        };
+        let mut modules = Vec::new();
+        for entry in fs::read_dir(SYS_FS_BTF).map_err(file_error)? {
+            let name = entry.map_err(file_error)?.file_name();
+            let name = name.to_string_lossy();
+            if name != "vmlinux" {
+                modules.push(name.into_owned());
+            }
+        }
+        Ok(modules)
+    }
+
     /// Loads BTF metadata from the given `path`.
     #[cfg(feature = "std")]
     pub fn parse_file<P: AsRef<std::path::Path>>(
@@ -319,6 +397,31 @@ impl Btf {
         )
     }
 
+    /// Parses split BTF from binary data of the given endianness.
+    ///
+    /// Split BTF, such as the one of kernel modules in `/sys/kernel/btf`, extends
+    /// `base`: its type ids and string offsets continue from the ones of `base`.
+    pub fn parse_split(
+        data: &[u8],
+        base: Arc<Btf>,
+        endianness: Endianness,
+    ) -> Result<Btf, BtfError> {
+        let mut btf = Btf::parse(data, endianness)?;
+        btf.base = Some(base);
+        Ok(btf)
+    }
+
+    /// Returns the BTF that split BTF is built on top of.
+    pub fn base(&self) -> Option<&Arc<Btf>> {
+        self.base.as_ref()
+    }
+
+    /// Returns the name of the kernel module the BTF was loaded from with
+    /// [`Btf::from_sys_fs_module`].
+    pub fn module_name(&self) -> Option<&str> {
+        self.module.as_deref()
+    }
+
     /// Parses BTF from binary data of the given endianness
     pub fn parse(data: &[u8], endianness: Endianness) -> Result<Btf, BtfError> {
         if data.len() < mem::size_of::<btf_header>() {
@@ -341,6 +444,8 @@ impl Btf {
             header,
             strings,
             types,
+            base: None,
+            module: None,
             _endianness: endianness,
         })
     }
@@ -371,7 +476,15 @@ impl Btf {
         Ok(types)
     }
 
-    pub(crate) fn string_at(&self, offset: u32) -> Result<Cow<'_, str>, BtfError> {
+    pub(crate) fn string_at(&self, mut offset: u32) -> Result<Cow<'_, str>, BtfError> {
+        if let Some(base) = &self.base {
+            let base_len = base.strings.len() as u32;
+            if offset < base_len {
+                return base.string_at(offset);
+            }
+            offset -= base_len;
+        }
+
         let btf_header {
             hdr_len,
             mut str_off,
@@ -398,11 +511,51 @@ impl Btf {
     }
 
     pub(crate) fn type_by_id(&self, type_id: u32) -> Result<&BtfType, BtfError> {
-        self.types.type_by_id(type_id)
+        match &self.base {
+            Some(base) if type_id < self.first_type_id() => base.type_by_id(type_id),
+            // skip the BtfType::Unknown placeholder of the split types
+            Some(_) => self
+                .types
+                .type_by_id(type_id - self.first_type_id() + 1)
+                .map_err(|_| BtfError::UnknownBtfType { type_id }),
+            None => self.types.type_by_id(type_id),
+        }
     }
 
     pub(crate) fn resolve_type(&self, root_type_id: u32) -> Result<u32, BtfError> {
-        self.types.resolve_type(root_type_id)
+        let mut type_id = root_type_id;
+        for () in core::iter::repeat_n((), MAX_RESOLVE_DEPTH) {
+            let ty = self.type_by_id(type_id)?;
+
+            use BtfType::*;
+            match ty {
+                Volatile(ty) => {
+                    type_id = ty.btf_type;
+                    continue;
+                }
+                Const(ty) => {
+                    type_id = ty.btf_type;
+                    continue;
+                }
+                Restrict(ty) => {
+                    type_id = ty.btf_type;
+                    continue;
+                }
+                Typedef(ty) => {
+                    type_id = ty.btf_type;
+                    continue;
+                }
+                TypeTag(ty) => {
+                    type_id = ty.btf_type;
+                    continue;
+                }
+                _ => return Ok(type_id),
+            }
+        }
+
+        Err(BtfError::MaximumTypeDepthReached {
+            type_id: root_type_id,
+        })
     }
 
     pub(crate) fn type_name(&self, ty: &BtfType) -> Result<Cow<'_, str>, BtfError> {
@@ -414,8 +567,10 @@ impl Btf {
     }
 
     /// Returns a type id matching the type name and [BtfKind]
+    ///
+    /// For split BTF, only the types that aren't part of the base are searched.
     pub fn id_by_type_name_kind(&self, name: &str, kind: BtfKind) -> Result<u32, BtfError> {
-        for (type_id, ty) in self.types().enumerate() {
+        for (type_id, ty) in self.types().enumerate().skip(self.first_type_id() as usize) {
             if ty.kind() != kind {
                 continue;
             }
@@ -434,7 +589,7 @@ impl Btf {
         let mut type_id = root_type_id;
         let mut n_elems = 1;
         for () in core::iter::repeat_n((), MAX_RESOLVE_DEPTH) {
-            let ty = self.types.type_by_id(type_id)?;
+            let ty = self.type_by_id(type_id)?;
             let size = match ty {
                 BtfType::Array(Array { array, .. }) => {
                     n_elems = array.len;
@@ -497,6 +652,29 @@ impl Btf {
                 0,
             )))
         });
+        // Likewise for the INT type that `.ksyms` VARs are rewritten to, and the
+        // VAR that replaces `.ksyms` FUNCs.
+        let ksyms_ids = (features.btf_datasec
+            && self.types().any(|t| {
+                matches!(t, BtfType::DataSec(_))
+                    && self.type_name(t).is_ok_and(|name| name == KSYMS_SECTION)
+            }))
+        .then(|| {
+            let name_offset = self.add_string("int");
+            let int_id = self.add_type(BtfType::Int(Int::new(
+                name_offset,
+                4,
+                IntEncoding::Signed,
+                0,
+            )));
+            let name_offset = self.add_string("dummy_ksym");
+            let dummy_var_id = self.add_type(BtfType::Var(Var::new(
+                name_offset,
+                int_id,
+                VarLinkage::Global,
+            )));
+            (int_id, dummy_var_id)
+        });
         let mut types = mem::take(&mut self.types);
         for i in 0..types.types.len() {
             let t = &mut types.types[i];
@@ -514,6 +692,14 @@ impl Btf {
                 BtfType::Var(v) if !features.btf_datasec => {
                     *t = BtfType::Int(Int::new(v.name_offset, 1, IntEncoding::None, 0));
                 }
+                // Fixup extern VAR.
+                //
+                // The kernel only accepts static and global allocated VARs. Externs
+                // get allocated in the maps created for their DATASEC, so mark them
+                // as such.
+                BtfType::Var(v) if v.linkage == VarLinkage::Extern => {
+                    v.linkage = VarLinkage::Global;
+                }
                 // Sanitize DATASEC if they are not supported.
                 BtfType::DataSec(d) if !features.btf_datasec => {
                     debug!("{}: not supported. replacing with STRUCT", kind);
@@ -547,6 +733,39 @@ impl Btf {
                     let t = &mut types.types[i];
                     *t = BtfType::Struct(Struct::new(name_offset, members, entries.len() as u32));
                 }
+                // Fixup the `.ksyms` DATASEC.
+                //
+                // Kernel symbols aren't allocated anywhere, but the kernel only
+                // accepts DATASECs made of allocated VARs. Pretend each of them is
+                // an int, like libbpf does. Kernel functions are replaced by a dummy
+                // VAR.
+                BtfType::DataSec(d)
+                    if features.btf_datasec && self.string_at(d.name_offset)? == KSYMS_SECTION =>
+                {
+                    let (int_id, dummy_var_id) = ksyms_ids.ok_or(BtfError::InvalidDatasec)?;
+                    let mut entries = mem::take(&mut d.entries);
+                    let mut offset = 0;
+                    for e in entries.iter_mut() {
+                        match types.types.get_mut(e.btf_type as usize) {
+                            Some(BtfType::Var(var)) => {
+                                var.btf_type = int_id;
+                                var.linkage = VarLinkage::Global;
+                            }
+                            Some(BtfType::Func(_)) => e.btf_type = dummy_var_id,
+                            _ => return Err(BtfError::InvalidDatasec),
+                        }
+                        e.offset = offset;
+                        e.size = mem::size_of::<i32>() as u32;
+                        offset += e.size;
+                    }
+
+                    // Must reborrow here because we borrow `types` mutably above.
+                    let BtfType::DataSec(d) = &mut types.types[i] else {
+                        unreachable!();
+                    };
+                    d.entries = entries;
+                    d.size = offset;
+                }
                 // Fixup DATASEC.
                 //
                 // DATASEC sizes aren't always set by LLVM so we need to fix them
@@ -564,7 +783,9 @@ impl Btf {
                     }
 
                     // There are some cases when the compiler does indeed populate the size.
-                    if d.size > 0 {
+                    //
+                    // Extern DATASECs are laid out by us, so always fix them up.
+                    if d.size > 0 && name != KCONFIG_SECTION {
                         debug!("{} {}: size fixup not required", kind, name);
                     } else {
                         // We need to get the size of the section from the ELF file.
@@ -1050,42 +1271,6 @@ impl BtfTypes {
             .get(type_id as usize)
             .ok_or(BtfError::UnknownBtfType { type_id })
     }
-
-    pub(crate) fn resolve_type(&self, root_type_id: u32) -> Result<u32, BtfError> {
-        let mut type_id = root_type_id;
-        for () in core::iter::repeat_n((), MAX_RESOLVE_DEPTH) {
-            let ty = self.type_by_id(type_id)?;
-
-            use BtfType::*;
-            match ty {
-                Volatile(ty) => {
-                    type_id = ty.btf_type;
-                    continue;
-                }
-                Const(ty) => {
-                    type_id = ty.btf_type;
-                    continue;
-                }
-                Restrict(ty) => {
-                    type_id = ty.btf_type;
-                    continue;
-                }
-                Typedef(ty) => {
-                    type_id = ty.btf_type;
-                    continue;
-                }
-                TypeTag(ty) => {
-                    type_id = ty.btf_type;
-                    continue;
-                }
-                _ => return Ok(type_id),
-            }
-        }
-
-        Err(BtfError::MaximumTypeDepthReached {
-            type_id: root_type_id,
-        })
-    }
 }
 
 #[derive(Debug)]
@@ -1500,6 +1685,132 @@ mod tests {
         Btf::parse(&raw, Endianness::default()).unwrap();
     }
 
+    #[test]
+    fn test_fixup_ksyms_datasec() {
+        let mut btf = Btf::new();
+        let const_void_type_id = btf.add_type(BtfType::Const(Const::new(0)));
+        let name_offset = btf.add_string("bpf_prog_active");
+        let var_type_id = btf.add_type(BtfType::Var(Var::new(
+            name_offset,
+            const_void_type_id,
+            VarLinkage::Extern,
+        )));
+        let proto_type_id = btf.add_type(BtfType::FuncProto(FuncProto::new(vec![], 0)));
+        let name_offset = btf.add_string("bpf_rcu_read_lock");
+        let func_type_id = btf.add_type(BtfType::Func(Func::new(
+            name_offset,
+            proto_type_id,
+            FuncLinkage::Extern,
+        )));
+
+        let name_offset = btf.add_string(KSYMS_SECTION);
+        let variables = vec![
+            DataSecEntry {
+                btf_type: var_type_id,
+                offset: 0,
+                size: 0,
+            },
+            DataSecEntry {
+                btf_type: func_type_id,
+                offset: 0,
+                size: 0,
+            },
+        ];
+        let datasec_type_id =
+            btf.add_type(BtfType::DataSec(DataSec::new(name_offset, variables, 0)));
+
+        let features = BtfFeatures {
+            btf_func: true,
+            btf_datasec: true,
+            ..Default::default()
+        };
+
+        btf.fixup_and_sanitize(&HashMap::new(), &HashMap::new(), &features)
+            .unwrap();
+
+        assert_matches!(btf.type_by_id(var_type_id).unwrap(), BtfType::Var(var) => {
+            assert_eq!(var.linkage, VarLinkage::Global);
+            assert_matches!(
+                btf.type_by_id(var.btf_type).unwrap(),
+                BtfType::Int(int) if int.size == 4
+            );
+        });
+        assert_matches!(btf.type_by_id(datasec_type_id).unwrap(), BtfType::DataSec(fixed) => {
+            assert_eq!(fixed.size, 8);
+            assert_matches!(*fixed.entries, [
+                DataSecEntry {
+                    btf_type,
+                    offset: 0,
+                    size: 4,
+                },
+                DataSecEntry {
+                    btf_type: dummy_var_type_id,
+                    offset: 4,
+                    size: 4,
+                },
+            ] if btf_type == var_type_id => {
+                assert_matches!(
+                    btf.type_by_id(dummy_var_type_id).unwrap(),
+                    BtfType::Var(var) if var.linkage == VarLinkage::Global
+                );
+            });
+        });
+        // Ensure we can convert to bytes and back again
+        let raw = btf.to_bytes();
+        Btf::parse(&raw, Endianness::default()).unwrap();
+    }
+
+    #[test]
+    fn test_parse_split() {
+        let mut base = Btf::new();
+        let name_offset = base.add_string("int");
+        let int_type_id = base.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let base_strings_len = base.strings.len() as u32;
+        let base = Arc::new(base);
+
+        // split BTF string offsets and type ids continue from the base
+        let mut split = Btf::new();
+        let name_offset = base_strings_len + split.add_string("nf_conntrack_lookup");
+        let proto_type_id = int_type_id + 1;
+        split.add_type(BtfType::FuncProto(FuncProto::new(vec![], int_type_id)));
+        let func_type_id = int_type_id + 2;
+        split.add_type(BtfType::Func(Func::new(
+            name_offset,
+            proto_type_id,
+            FuncLinkage::Global,
+        )));
+
+        let split = Btf::parse_split(&split.to_bytes(), base, Endianness::default()).unwrap();
+        assert_eq!(
+            split
+                .id_by_type_name_kind("nf_conntrack_lookup", BtfKind::Func)
+                .unwrap(),
+            func_type_id
+        );
+        // only the split types are searched
+        assert_matches!(
+            split.id_by_type_name_kind("int", BtfKind::Int),
+            Err(BtfError::UnknownBtfTypeName { .. })
+        );
+        assert_matches!(split.type_by_id(func_type_id).unwrap(), BtfType::Func(func) => {
+            assert_eq!(func.btf_type, proto_type_id);
+        });
+        assert_matches!(split.type_by_id(proto_type_id).unwrap(), BtfType::FuncProto(proto) => {
+            assert_eq!(proto.return_type, int_type_id);
+            assert_eq!(split.type_name(split.type_by_id(proto.return_type).unwrap()).unwrap(), "int");
+        });
+        assert_eq!(split.types().count(), func_type_id as usize + 1);
+        assert_matches!(
+            split.type_by_id(func_type_id + 1),
+            Err(BtfError::UnknownBtfType { type_id }) if type_id == func_type_id + 1
+        );
+    }
+
     #[test]
     fn test_sanitize_func_and_proto() {
         let mut btf = Btf::new();
@@ -1928,4 +2239,17 @@ mod tests {
         let raw = btf.to_bytes();
         Btf::parse(&raw, Endianness::default()).unwrap();
     }
+
+    #[test]
+    fn test_local_storage() {
+        let (btf, key_type_id, value_type_id) = Btf::local_storage(16);
+        assert_matches!(btf.type_by_id(key_type_id).unwrap(), BtfType::Int(_));
+        assert_eq!(btf.type_size(key_type_id).unwrap(), 4);
+        assert_matches!(btf.type_by_id(value_type_id).unwrap(), BtfType::Array(_));
+        assert_eq!(btf.type_size(value_type_id).unwrap(), 16);
+
+        // Ensure we can convert to bytes and back again.
+        let raw = btf.to_bytes();
+        Btf::parse(&raw, Endianness::default()).unwrap();
+    }
 }
@@ -0,0 +1,949 @@
+//! Extern variable handling.
+//!
+//! eBPF programs can declare variables whose values are not known at compile
+//! time and have to be provided by the loader. Such variables are emitted as
+//! undefined ELF symbols, and their types are described by special BTF
+//! `DATASEC`s.
+
+use alloc::{
+    borrow::ToOwned as _,
+    string::{String, ToString as _},
+    vec,
+    vec::Vec,
+};
+use core::mem;
+
+use log::debug;
+use object::Endianness;
+
+use crate::{
+    EbpfSectionKind, Object, ParseError,
+    btf::{Btf, BtfError, BtfKind, BtfType, FuncLinkage, IntEncoding},
+    generated::{BPF_F_RDONLY_PROG, bpf_map_type::BPF_MAP_TYPE_ARRAY},
+    maps::{LegacyMap, Map, bpf_map_def},
+    relocation::RawFd,
+    util::HashMap,
+};
+
+/// The name of the `DATASEC` containing kernel config extern variables.
+pub const KCONFIG_SECTION: &str = ".kconfig";
+
+/// The name of the `DATASEC` containing kernel symbol extern variables.
+pub const KSYMS_SECTION: &str = ".ksyms";
+
+const LINUX_KERNEL_VERSION: &str = "LINUX_KERNEL_VERSION";
+
+/// The value representation of a `.kconfig` extern variable, derived from
+/// its BTF type.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum KconfigType {
+    /// `bool`, accepting `y` and `n`.
+    Bool,
+    /// `enum libbpf_tristate`, accepting `y`, `n` and `m`.
+    Tristate,
+    /// `char`, holding the raw `y`, `n` or `m` character.
+    Char,
+    /// An integer of the given size in bytes.
+    Int { size: u32, signed: bool },
+    /// A fixed size `char` array holding a string value.
+    CharArray { len: u32 },
+}
+
+impl KconfigType {
+    fn from_btf(btf: &Btf, type_id: u32) -> Result<Option<Self>, BtfError> {
+        let type_id = btf.resolve_type(type_id)?;
+        let ty = match btf.type_by_id(type_id)? {
+            BtfType::Int(int) => match (int.encoding(), int.size) {
+                (IntEncoding::Bool, 1) => Self::Bool,
+                (IntEncoding::Char, 1) => Self::Char,
+                (encoding, size @ (1 | 2 | 4 | 8)) => Self::Int {
+                    size,
+                    signed: encoding == IntEncoding::Signed,
+                },
+                _ => return Ok(None),
+            },
+            ty @ BtfType::Enum(e) if e.size == 4 && btf.type_name(ty)? == "libbpf_tristate" => {
+                Self::Tristate
+            }
+            BtfType::Array(array) => {
+                let element_type = btf.resolve_type(array.array.element_type)?;
+                match btf.type_by_id(element_type)? {
+                    BtfType::Int(int) if int.size == 1 => Self::CharArray {
+                        len: array.array.len,
+                    },
+                    _ => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(ty))
+    }
+
+    fn size(&self) -> u32 {
+        match self {
+            Self::Bool | Self::Char => 1,
+            Self::Tristate => 4,
+            Self::Int { size, .. } => *size,
+            Self::CharArray { len } => *len,
+        }
+    }
+
+    fn align(&self) -> u32 {
+        match self {
+            Self::CharArray { .. } => 1,
+            ty => ty.size(),
+        }
+    }
+}
+
+/// An extern variable referenced by the object.
+#[derive(Debug, Clone)]
+pub(crate) struct Extern {
+    pub(crate) name: String,
+    pub(crate) is_weak: bool,
+    pub(crate) kind: ExternKind,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum ExternKind {
+    /// A variable in the `.kconfig` map, resolved from the kernel config.
+    Kconfig { ty: KconfigType, offset: u32 },
+    /// A kernel symbol, resolved from kernel BTF when `typed` and from
+    /// kallsyms otherwise.
+    Ksym {
+        typed: bool,
+        value: Option<KsymValue>,
+    },
+    /// A kernel function, called with `BPF_PSEUDO_KFUNC_CALL`.
+    Kfunc { value: Option<KfuncValue> },
+}
+
+/// The resolved value of a `.ksyms` extern variable.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum KsymValue {
+    /// The id of the variable in kernel BTF.
+    BtfId(u32),
+    /// The address of the symbol. Missing weak symbols resolve to `0`.
+    Address(u64),
+}
+
+/// The resolved value of a kernel function extern.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum KfuncValue {
+    /// The id of the function in the BTF object `btf_fd`, at index
+    /// `btf_fd_index` of the program `fd_array`. Both are `0` for vmlinux.
+    BtfId {
+        id: u32,
+        btf_fd_index: i16,
+        btf_fd: RawFd,
+    },
+    /// The function wasn't found and calls to it must be unreachable.
+    Missing,
+}
+
+/// An undefined symbol that could refer to an extern variable.
+pub(crate) struct ExternSymbol {
+    pub(crate) index: usize,
+    pub(crate) name: String,
+    pub(crate) is_weak: bool,
+}
+
+impl Object {
+    /// Matches the undefined symbols of the object against the extern
+    /// `DATASEC`s found in BTF, and creates the maps backing them.
+    pub(crate) fn parse_externs(&mut self, symbols: Vec<ExternSymbol>) -> Result<(), ParseError> {
+        if symbols.is_empty() {
+            return Ok(());
+        }
+        let Some(btf) = &self.btf else {
+            // without BTF there's no way to know the types of externs
+            return Ok(());
+        };
+
+        let mut symbols: HashMap<String, ExternSymbol> =
+            symbols.into_iter().map(|s| (s.name.clone(), s)).collect();
+
+        let mut externs = Vec::new();
+        let mut kconfig_size = 0u32;
+        for ty in btf.types() {
+            let BtfType::DataSec(datasec) = ty else {
+                continue;
+            };
+            match btf.type_name(ty)?.as_ref() {
+                KCONFIG_SECTION => {}
+                KSYMS_SECTION => {
+                    for entry in &datasec.entries {
+                        let ty = btf.type_by_id(entry.btf_type)?;
+                        let name = btf.type_name(ty)?;
+                        let Some(symbol) = symbols.remove(name.as_ref()) else {
+                            continue;
+                        };
+                        let kind = match ty {
+                            BtfType::Var(var) => {
+                                // typeless ksyms are declared as `const void`
+                                let typed = btf.resolve_type(var.btf_type)? != 0;
+                                debug!("ksym extern {name}: typed {typed}");
+                                ExternKind::Ksym { typed, value: None }
+                            }
+                            BtfType::Func(_) => {
+                                debug!("kfunc extern {name}");
+                                ExternKind::Kfunc { value: None }
+                            }
+                            _ => return Err(BtfError::InvalidDatasec.into()),
+                        };
+                        externs.push((
+                            symbol.index,
+                            Extern {
+                                name: symbol.name,
+                                is_weak: symbol.is_weak,
+                                kind,
+                            },
+                        ));
+                    }
+                    continue;
+                }
+                _ => continue,
+            }
+            // Lay out the variables in DATASEC order so that their offsets are
+            // monotonically increasing, which is what the kernel expects when
+            // it validates the DATASEC.
+            for entry in &datasec.entries {
+                let BtfType::Var(var) = btf.type_by_id(entry.btf_type)? else {
+                    return Err(BtfError::InvalidDatasec.into());
+                };
+                let name = btf.string_at(var.name_offset)?;
+                let Some(symbol) = symbols.remove(name.as_ref()) else {
+                    continue;
+                };
+                let ty = KconfigType::from_btf(btf, var.btf_type)?.ok_or_else(|| {
+                    ParseError::UnsupportedExtern {
+                        name: name.to_string(),
+                    }
+                })?;
+                let offset = kconfig_size.next_multiple_of(ty.align());
+                kconfig_size = offset + ty.size();
+                debug!("kconfig extern {name}: {ty:?} at offset {offset}");
+                externs.push((
+                    symbol.index,
+                    Extern {
+                        name: symbol.name,
+                        is_weak: symbol.is_weak,
+                        kind: ExternKind::Kconfig { ty, offset },
+                    },
+                ));
+            }
+        }
+        // Kernel functions declared without `__ksym`, like the ones of Rust
+        // programs, only appear as extern FUNCs.
+        for ty in btf.types() {
+            let BtfType::Func(func) = ty else {
+                continue;
+            };
+            if func.linkage() != FuncLinkage::Extern {
+                continue;
+            }
+            let name = btf.type_name(ty)?;
+            let Some(symbol) = symbols.remove(name.as_ref()) else {
+                continue;
+            };
+            debug!("kfunc extern {name}");
+            externs.push((
+                symbol.index,
+                Extern {
+                    name: symbol.name,
+                    is_weak: symbol.is_weak,
+                    kind: ExternKind::Kfunc { value: None },
+                },
+            ));
+        }
+        self.externs.extend(externs);
+
+        if kconfig_size > 0 {
+            // the variables are fixed up to their offset in the map when
+            // sanitizing BTF
+            for extern_ in self.externs.values() {
+                if let ExternKind::Kconfig { offset, .. } = extern_.kind {
+                    self.symbol_offset_by_name
+                        .insert(extern_.name.clone(), offset as u64);
+                }
+            }
+            let size = kconfig_size.next_multiple_of(8);
+            self.section_infos.insert(
+                KCONFIG_SECTION.to_owned(),
+                (object::SectionIndex(0), size as u64),
+            );
+            self.maps.insert(
+                KCONFIG_SECTION.to_owned(),
+                Map::Legacy(LegacyMap {
+                    inner_def: None,
+                    map_extra: 0,
+                    // there's no ELF section backing this map
+                    section_index: 0,
+                    section_kind: EbpfSectionKind::Kconfig,
+                    symbol_index: None,
+                    def: bpf_map_def {
+                        map_type: BPF_MAP_TYPE_ARRAY as u32,
+                        key_size: mem::size_of::<u32>() as u32,
+                        value_size: size,
+                        max_entries: 1,
+                        map_flags: BPF_F_RDONLY_PROG,
+                        ..Default::default()
+                    },
+                    data: vec![0; size as usize],
+                }),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the names of the `.kconfig` extern variables referenced by the
+    /// object.
+    pub fn kconfig_externs(&self) -> impl Iterator<Item = &str> {
+        self.externs
+            .values()
+            .filter(|e| matches!(e.kind, ExternKind::Kconfig { .. }))
+            .map(|e| e.name.as_str())
+    }
+
+    /// Resolves the `.ksyms` extern variables referenced by the object.
+    ///
+    /// Typed kernel symbols are looked up by name in `target_btf`, while
+    /// typeless ones are resolved to their address with `symbol_address`.
+    /// Weak symbols that can't be found resolve to `NULL`.
+    pub fn resolve_ksyms(
+        &mut self,
+        target_btf: Option<&Btf>,
+        mut symbol_address: impl FnMut(&str) -> Option<u64>,
+    ) -> Result<(), ParseError> {
+        for Extern {
+            name,
+            is_weak,
+            kind,
+        } in self.externs.values_mut()
+        {
+            let ExternKind::Ksym { typed, value } = kind else {
+                continue;
+            };
+            let resolved = if *typed {
+                target_btf
+                    .and_then(|btf| btf.id_by_type_name_kind(name, BtfKind::Var).ok())
+                    .map(KsymValue::BtfId)
+            } else {
+                symbol_address(name).map(KsymValue::Address)
+            };
+            let resolved = match resolved {
+                Some(resolved) => resolved,
+                None if *is_weak => KsymValue::Address(0),
+                None => return Err(ParseError::KsymNotFound { name: name.clone() }),
+            };
+            debug!("resolved ksym {name} to {resolved:?}");
+            *value = Some(resolved);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the names of the kernel functions called by the object.
+    pub fn kfunc_externs(&self) -> impl Iterator<Item = &str> {
+        self.externs
+            .values()
+            .filter(|e| matches!(e.kind, ExternKind::Kfunc { .. }))
+            .map(|e| e.name.as_str())
+    }
+
+    /// Resolves the kernel functions called by the object.
+    ///
+    /// `resolve` returns the BTF id of a function, the index of the BTF object
+    /// defining it in the `fd_array` passed to the kernel when loading
+    /// programs, and the fd of that BTF object. The index and the fd are `0`
+    /// for vmlinux. Calls to weak functions that can't be found are poisoned,
+    /// so that only programs that can reach them fail to load.
+    pub fn resolve_kfuncs(
+        &mut self,
+        mut resolve: impl FnMut(&str) -> Option<(u32, i16, RawFd)>,
+    ) -> Result<(), ParseError> {
+        for Extern {
+            name,
+            is_weak,
+            kind,
+        } in self.externs.values_mut()
+        {
+            let ExternKind::Kfunc { value } = kind else {
+                continue;
+            };
+            let resolved = match resolve(name) {
+                Some((id, btf_fd_index, btf_fd)) => KfuncValue::BtfId {
+                    id,
+                    btf_fd_index,
+                    btf_fd,
+                },
+                None if *is_weak => KfuncValue::Missing,
+                None => return Err(ParseError::KfuncNotFound { name: name.clone() }),
+            };
+            debug!("resolved kfunc {name} to {resolved:?}");
+            *value = Some(resolved);
+        }
+
+        Ok(())
+    }
+
+    /// Populates the `.kconfig` map with the values of the extern variables
+    /// referenced by the object.
+    ///
+    /// `config` is the content of a kernel config file, in the format of
+    /// `/proc/config.gz` or `/boot/config-$(uname -r)`. `kernel_version` is
+    /// the value of `LINUX_KERNEL_VERSION`, encoded like `LINUX_VERSION_CODE`.
+    pub fn patch_kconfig(&mut self, config: &str, kernel_version: u32) -> Result<(), ParseError> {
+        let values = parse_kconfig(config);
+        let endianness = self.endianness;
+
+        let Some(map) = self.maps.get_mut(KCONFIG_SECTION) else {
+            return Ok(());
+        };
+        let data = map.data_mut();
+        for Extern {
+            name,
+            is_weak,
+            kind,
+        } in self.externs.values()
+        {
+            let ExternKind::Kconfig { ty, offset } = *kind else {
+                continue;
+            };
+            let value = if name == LINUX_KERNEL_VERSION {
+                KconfigValue::Number(kernel_version.into())
+            } else if name.starts_with("CONFIG_") {
+                match values.get(name.as_str()) {
+                    Some(value) => KconfigValue::parse(value).ok_or_else(|| {
+                        ParseError::InvalidKconfigValue {
+                            name: name.clone(),
+                            value: (*value).to_owned(),
+                        }
+                    })?,
+                    None if *is_weak => continue,
+                    None => {
+                        return Err(ParseError::KconfigValueNotFound { name: name.clone() });
+                    }
+                }
+            } else {
+                return Err(ParseError::UnsupportedExtern { name: name.clone() });
+            };
+
+            let start = offset as usize;
+            let end = start + ty.size() as usize;
+            value
+                .write(ty, &mut data[start..end], endianness)
+                .ok_or_else(|| ParseError::InvalidKconfigValue {
+                    name: name.clone(),
+                    value: value.to_string(),
+                })?;
+        }
+
+        Ok(())
+    }
+}
+
+// Parses the `CONFIG_FOO=value` lines of a kernel config file. Options that
+// are not set are commented out and therefore ignored.
+fn parse_kconfig(config: &str) -> HashMap<&str, &str> {
+    config
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KconfigValue<'a> {
+    Tristate(u8),
+    String(&'a str),
+    Number(i128),
+}
+
+impl<'a> KconfigValue<'a> {
+    fn parse(value: &'a str) -> Option<Self> {
+        match value {
+            "y" | "n" | "m" => Some(Self::Tristate(value.as_bytes()[0])),
+            _ => {
+                if let Some(s) = value.strip_prefix('"') {
+                    return s.strip_suffix('"').map(Self::String);
+                }
+                let (negative, digits) = match value.strip_prefix('-') {
+                    Some(digits) => (true, digits),
+                    None => (false, value),
+                };
+                let number = match digits
+                    .strip_prefix("0x")
+                    .or_else(|| digits.strip_prefix("0X"))
+                {
+                    Some(hex) => u64::from_str_radix(hex, 16),
+                    None => digits.parse::<u64>(),
+                }
+                .ok()?;
+                let number = i128::from(number);
+                Some(Self::Number(if negative { -number } else { number }))
+            }
+        }
+    }
+
+    // Writes the value to `out`, returning `None` if it can't be represented
+    // by the type of the extern variable.
+    fn write(&self, ty: KconfigType, out: &mut [u8], endianness: Endianness) -> Option<()> {
+        match (self, ty) {
+            (Self::Tristate(b'y'), KconfigType::Bool) => out[0] = 1,
+            (Self::Tristate(b'n'), KconfigType::Bool) => out[0] = 0,
+            (Self::Tristate(v), KconfigType::Tristate) => {
+                let v: u32 = match v {
+                    b'n' => 0,
+                    b'y' => 1,
+                    b'm' => 2,
+                    _ => return None,
+                };
+                out.copy_from_slice(&match endianness {
+                    Endianness::Little => v.to_le_bytes(),
+                    Endianness::Big => v.to_be_bytes(),
+                });
+            }
+            (Self::Tristate(v), KconfigType::Char) => out[0] = *v,
+            (Self::String(s), KconfigType::CharArray { len }) => {
+                // leave room for the NUL terminator, truncating if needed
+                let n = s.len().min(len.saturating_sub(1) as usize);
+                out[..n].copy_from_slice(&s.as_bytes()[..n]);
+            }
+            (Self::Number(n), KconfigType::Int { size, signed }) => {
+                let bits = size * 8;
+                let (min, max) = if signed {
+                    (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+                } else {
+                    (0, (1i128 << bits) - 1)
+                };
+                if *n < min || *n > max {
+                    return None;
+                }
+                let size = size as usize;
+                match endianness {
+                    Endianness::Little => out.copy_from_slice(&n.to_le_bytes()[..size]),
+                    Endianness::Big => out.copy_from_slice(&n.to_be_bytes()[16 - size..]),
+                }
+            }
+            (Self::Number(n @ (0 | 1)), KconfigType::Bool) => out[0] = *n as u8,
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+impl core::fmt::Display for KconfigValue<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Tristate(v) => write!(f, "{}", *v as char),
+            Self::String(s) => write!(f, "\"{s}\""),
+            Self::Number(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+    use crate::btf::{Const, DataSec, DataSecEntry, Func, FuncProto, Int, Var, VarLinkage};
+
+    fn fake_obj() -> Object {
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("bool");
+        let bool_type_id =
+            btf.add_type(BtfType::Int(Int::new(name_offset, 1, IntEncoding::Bool, 0)));
+        let name_offset = btf.add_string("int");
+        let int_type_id = btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+
+        let mut entries = Vec::new();
+        for (name, btf_type, size) in [
+            ("CONFIG_BPF", bool_type_id, 1),
+            ("CONFIG_HZ", int_type_id, 4),
+        ] {
+            let name_offset = btf.add_string(name);
+            let var_type_id = btf.add_type(BtfType::Var(Var::new(
+                name_offset,
+                btf_type,
+                VarLinkage::Extern,
+            )));
+            entries.push(DataSecEntry {
+                btf_type: var_type_id,
+                offset: 0,
+                size,
+            });
+        }
+        let name_offset = btf.add_string(KCONFIG_SECTION);
+        btf.add_type(BtfType::DataSec(DataSec::new(name_offset, entries, 0)));
+
+        let mut obj = Object::new(Endianness::Little, c"GPL".into(), None);
+        obj.btf = Some(btf);
+        obj.parse_externs(vec![
+            ExternSymbol {
+                index: 1,
+                name: "CONFIG_BPF".to_owned(),
+                is_weak: false,
+            },
+            ExternSymbol {
+                index: 2,
+                name: "CONFIG_HZ".to_owned(),
+                is_weak: false,
+            },
+        ])
+        .unwrap();
+        obj
+    }
+
+    #[test]
+    fn test_parse_externs() {
+        let obj = fake_obj();
+        assert_matches!(
+            obj.externs[&1].kind,
+            ExternKind::Kconfig {
+                ty: KconfigType::Bool,
+                offset: 0
+            }
+        );
+        assert_matches!(
+            obj.externs[&2].kind,
+            ExternKind::Kconfig {
+                ty: KconfigType::Int {
+                    size: 4,
+                    signed: true
+                },
+                offset: 4
+            }
+        );
+        let map = &obj.maps[KCONFIG_SECTION];
+        assert_eq!(map.section_kind(), EbpfSectionKind::Kconfig);
+        assert_eq!(map.value_size(), 8);
+        assert_eq!(map.map_flags(), BPF_F_RDONLY_PROG);
+    }
+
+    #[test]
+    fn test_patch_kconfig() {
+        let mut obj = fake_obj();
+        obj.patch_kconfig("CONFIG_BPF=y\nCONFIG_HZ=250\n", 0)
+            .unwrap();
+        assert_eq!(obj.maps[KCONFIG_SECTION].data(), [1, 0, 0, 0, 250, 0, 0, 0]);
+
+        let mut obj = fake_obj();
+        assert_matches!(
+            obj.patch_kconfig("CONFIG_BPF=y\n", 0),
+            Err(ParseError::KconfigValueNotFound { name }) if name == "CONFIG_HZ"
+        );
+
+        let mut obj = fake_obj();
+        assert_matches!(
+            obj.patch_kconfig("CONFIG_BPF=m\nCONFIG_HZ=250\n", 0),
+            Err(ParseError::InvalidKconfigValue { name, value }) if name == "CONFIG_BPF" && value == "m"
+        );
+    }
+
+    fn fake_ksyms_obj(is_weak: bool) -> Object {
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("int");
+        let int_type_id = btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let const_void_type_id = btf.add_type(BtfType::Const(Const::new(0)));
+
+        let mut entries = Vec::new();
+        for (name, btf_type) in [
+            ("runqueues", int_type_id),
+            ("bpf_prog_active", const_void_type_id),
+        ] {
+            let name_offset = btf.add_string(name);
+            let var_type_id = btf.add_type(BtfType::Var(Var::new(
+                name_offset,
+                btf_type,
+                VarLinkage::Extern,
+            )));
+            entries.push(DataSecEntry {
+                btf_type: var_type_id,
+                offset: 0,
+                size: 0,
+            });
+        }
+        let name_offset = btf.add_string(KSYMS_SECTION);
+        btf.add_type(BtfType::DataSec(DataSec::new(name_offset, entries, 0)));
+
+        let mut obj = Object::new(Endianness::Little, c"GPL".into(), None);
+        obj.btf = Some(btf);
+        obj.parse_externs(vec![
+            ExternSymbol {
+                index: 1,
+                name: "runqueues".to_owned(),
+                is_weak,
+            },
+            ExternSymbol {
+                index: 2,
+                name: "bpf_prog_active".to_owned(),
+                is_weak,
+            },
+        ])
+        .unwrap();
+        obj
+    }
+
+    #[test]
+    fn test_parse_ksyms() {
+        let obj = fake_ksyms_obj(false);
+        assert_matches!(
+            obj.externs[&1].kind,
+            ExternKind::Ksym {
+                typed: true,
+                value: None
+            }
+        );
+        assert_matches!(
+            obj.externs[&2].kind,
+            ExternKind::Ksym {
+                typed: false,
+                value: None
+            }
+        );
+        assert!(obj.maps.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_ksyms() {
+        let mut target_btf = Btf::new();
+        let name_offset = target_btf.add_string("rq");
+        let rq_type_id =
+            target_btf.add_type(BtfType::Int(Int::new(name_offset, 4, IntEncoding::None, 0)));
+        let name_offset = target_btf.add_string("runqueues");
+        let runqueues_id = target_btf.add_type(BtfType::Var(Var::new(
+            name_offset,
+            rq_type_id,
+            VarLinkage::Global,
+        )));
+
+        let mut obj = fake_ksyms_obj(false);
+        obj.resolve_ksyms(Some(&target_btf), |name| {
+            (name == "bpf_prog_active").then_some(0xffff_ffff_8123_4567)
+        })
+        .unwrap();
+        assert_matches!(
+            obj.externs[&1].kind,
+            ExternKind::Ksym { value: Some(KsymValue::BtfId(id)), .. } if id == runqueues_id
+        );
+        assert_matches!(
+            obj.externs[&2].kind,
+            ExternKind::Ksym {
+                value: Some(KsymValue::Address(0xffff_ffff_8123_4567)),
+                ..
+            }
+        );
+
+        let mut obj = fake_ksyms_obj(false);
+        assert_matches!(
+            obj.resolve_ksyms(None, |_| Some(1)),
+            Err(ParseError::KsymNotFound { name }) if name == "runqueues"
+        );
+
+        let mut obj = fake_ksyms_obj(true);
+        obj.resolve_ksyms(None, |_| None).unwrap();
+        for extern_ in obj.externs.values() {
+            assert_matches!(
+                extern_.kind,
+                ExternKind::Ksym {
+                    value: Some(KsymValue::Address(0)),
+                    ..
+                }
+            );
+        }
+    }
+
+    fn fake_kfuncs_obj(is_weak: bool) -> Object {
+        let mut btf = Btf::new();
+        let proto_type_id = btf.add_type(BtfType::FuncProto(FuncProto::new(vec![], 0)));
+
+        // declared with __ksym
+        let name_offset = btf.add_string("bpf_rcu_read_lock");
+        let func_type_id = btf.add_type(BtfType::Func(Func::new(
+            name_offset,
+            proto_type_id,
+            FuncLinkage::Extern,
+        )));
+        let name_offset = btf.add_string(KSYMS_SECTION);
+        btf.add_type(BtfType::DataSec(DataSec::new(
+            name_offset,
+            vec![DataSecEntry {
+                btf_type: func_type_id,
+                offset: 0,
+                size: 0,
+            }],
+            0,
+        )));
+
+        // declared without __ksym
+        let name_offset = btf.add_string("bpf_rcu_read_unlock");
+        btf.add_type(BtfType::Func(Func::new(
+            name_offset,
+            proto_type_id,
+            FuncLinkage::Extern,
+        )));
+
+        let mut obj = Object::new(Endianness::Little, c"GPL".into(), None);
+        obj.btf = Some(btf);
+        obj.parse_externs(vec![
+            ExternSymbol {
+                index: 1,
+                name: "bpf_rcu_read_lock".to_owned(),
+                is_weak,
+            },
+            ExternSymbol {
+                index: 2,
+                name: "bpf_rcu_read_unlock".to_owned(),
+                is_weak,
+            },
+        ])
+        .unwrap();
+        obj
+    }
+
+    #[test]
+    fn test_parse_kfuncs() {
+        let obj = fake_kfuncs_obj(false);
+        assert_matches!(obj.externs[&1].kind, ExternKind::Kfunc { value: None });
+        assert_matches!(obj.externs[&2].kind, ExternKind::Kfunc { value: None });
+        let mut kfuncs: Vec<_> = obj.kfunc_externs().collect();
+        kfuncs.sort();
+        assert_eq!(kfuncs, ["bpf_rcu_read_lock", "bpf_rcu_read_unlock"]);
+    }
+
+    #[test]
+    fn test_resolve_kfuncs() {
+        let mut obj = fake_kfuncs_obj(false);
+        obj.resolve_kfuncs(|name| match name {
+            "bpf_rcu_read_lock" => Some((10, 0, 0)),
+            _ => Some((20, 1, 42)),
+        })
+        .unwrap();
+        assert_matches!(
+            obj.externs[&1].kind,
+            ExternKind::Kfunc {
+                value: Some(KfuncValue::BtfId {
+                    id: 10,
+                    btf_fd_index: 0,
+                    btf_fd: 0
+                })
+            }
+        );
+        assert_matches!(
+            obj.externs[&2].kind,
+            ExternKind::Kfunc {
+                value: Some(KfuncValue::BtfId {
+                    id: 20,
+                    btf_fd_index: 1,
+                    btf_fd: 42
+                })
+            }
+        );
+
+        let mut obj = fake_kfuncs_obj(false);
+        assert_matches!(
+            obj.resolve_kfuncs(|name| (name == "bpf_rcu_read_lock").then_some((10, 0, 0))),
+            Err(ParseError::KfuncNotFound { name }) if name == "bpf_rcu_read_unlock"
+        );
+
+        let mut obj = fake_kfuncs_obj(true);
+        obj.resolve_kfuncs(|_| None).unwrap();
+        for extern_ in obj.externs.values() {
+            assert_matches!(
+                extern_.kind,
+                ExternKind::Kfunc {
+                    value: Some(KfuncValue::Missing)
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_kconfig() {
+        let config = "\
+#
+# Automatically generated file; DO NOT EDIT.
+#
+CONFIG_BPF=y
+CONFIG_HZ=250
+# CONFIG_BPF_JIT_ALWAYS_ON is not set
+CONFIG_DEFAULT_HOSTNAME=\"(none)\"
+";
+        let values = parse_kconfig(config);
+        assert_eq!(values.len(), 3);
+        assert_eq!(values["CONFIG_BPF"], "y");
+        assert_eq!(values["CONFIG_HZ"], "250");
+        assert_eq!(values["CONFIG_DEFAULT_HOSTNAME"], "\"(none)\"");
+        assert!(!values.contains_key("CONFIG_BPF_JIT_ALWAYS_ON"));
+    }
+
+    #[test]
+    fn test_parse_kconfig_value() {
+        assert_eq!(KconfigValue::parse("m"), Some(KconfigValue::Tristate(b'm')));
+        assert_eq!(
+            KconfigValue::parse("\"foo\""),
+            Some(KconfigValue::String("foo"))
+        );
+        assert_eq!(KconfigValue::parse("0x10"), Some(KconfigValue::Number(16)));
+        assert_eq!(KconfigValue::parse("-1"), Some(KconfigValue::Number(-1)));
+        assert_eq!(KconfigValue::parse("\"foo"), None);
+        assert_eq!(KconfigValue::parse("foo"), None);
+    }
+
+    #[test]
+    fn test_write_kconfig_value() {
+        let mut out = [0u8; 4];
+        KconfigValue::Tristate(b'm')
+            .write(KconfigType::Tristate, &mut out, Endianness::Little)
+            .unwrap();
+        assert_eq!(out, [2, 0, 0, 0]);
+
+        let mut out = [0u8; 1];
+        assert_matches!(
+            KconfigValue::Tristate(b'm').write(KconfigType::Bool, &mut out, Endianness::Little),
+            None
+        );
+
+        let mut out = [0u8; 2];
+        let ty = KconfigType::Int {
+            size: 2,
+            signed: false,
+        };
+        KconfigValue::Number(1000)
+            .write(ty, &mut out, Endianness::Big)
+            .unwrap();
+        assert_eq!(out, 1000u16.to_be_bytes());
+        assert_matches!(
+            KconfigValue::Number(65536).write(ty, &mut out, Endianness::Big),
+            None
+        );
+        assert_matches!(
+            KconfigValue::Number(-1).write(ty, &mut out, Endianness::Big),
+            None
+        );
+
+        let mut out = [0u8; 4];
+        KconfigValue::String("hello")
+            .write(
+                KconfigType::CharArray { len: 4 },
+                &mut out,
+                Endianness::Little,
+            )
+            .unwrap();
+        assert_eq!(&out, b"hel\0");
+    }
+}
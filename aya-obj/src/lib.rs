@@ -72,6 +72,7 @@ extern crate alloc;
 extern crate std;
 
 pub mod btf;
+pub mod externs;
 #[expect(
     clippy::all,
     missing_docs,
@@ -85,6 +86,7 @@ pub mod maps;
 pub mod obj;
 pub mod programs;
 pub mod relocation;
+pub mod struct_ops;
 mod util;
 
 pub use maps::Map;
@@ -1,9 +1,9 @@
 //! Map struct and type bindings.
 
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 use core::mem;
 
-use crate::{EbpfSectionKind, InvalidTypeBinding};
+use crate::{EbpfSectionKind, InvalidTypeBinding, struct_ops::StructOpsMap};
 
 impl TryFrom<u32> for crate::generated::bpf_map_type {
     type Error = InvalidTypeBinding<u32>;
@@ -62,6 +62,7 @@ pub struct BtfMapDef {
     pub(crate) value_size: u32,
     pub(crate) max_entries: u32,
     pub(crate) map_flags: u32,
+    pub(crate) map_extra: u64,
     pub(crate) pinning: PinningType,
     /// BTF type id of the map key
     pub btf_key_type_id: u32,
@@ -138,6 +139,8 @@ pub enum Map {
     Legacy(LegacyMap),
     /// A map defined in the `.maps` section
     Btf(BtfMap),
+    /// A map defined in the `.struct_ops` or `.struct_ops.link` sections
+    StructOps(StructOpsMap),
 }
 
 impl Map {
@@ -146,6 +149,7 @@ impl Map {
         match self {
             Map::Legacy(m) => m.def.map_type,
             Map::Btf(m) => m.def.map_type,
+            Map::StructOps(m) => m.def.map_type,
         }
     }
 
@@ -154,6 +158,7 @@ impl Map {
         match self {
             Map::Legacy(m) => m.def.key_size,
             Map::Btf(m) => m.def.key_size,
+            Map::StructOps(m) => m.def.key_size,
         }
     }
 
@@ -162,6 +167,7 @@ impl Map {
         match self {
             Map::Legacy(m) => m.def.value_size,
             Map::Btf(m) => m.def.value_size,
+            Map::StructOps(m) => m.def.value_size,
         }
     }
 
@@ -170,6 +176,7 @@ impl Map {
         match self {
             Map::Legacy(m) => m.def.value_size = size,
             Map::Btf(m) => m.def.value_size = size,
+            Map::StructOps(m) => m.def.value_size = size,
         }
     }
 
@@ -178,6 +185,7 @@ impl Map {
         match self {
             Map::Legacy(m) => m.def.max_entries,
             Map::Btf(m) => m.def.max_entries,
+            Map::StructOps(m) => m.def.max_entries,
         }
     }
 
@@ -186,6 +194,7 @@ impl Map {
         match self {
             Map::Legacy(m) => m.def.max_entries = v,
             Map::Btf(m) => m.def.max_entries = v,
+            Map::StructOps(m) => m.def.max_entries = v,
         }
     }
 
@@ -194,6 +203,25 @@ impl Map {
         match self {
             Map::Legacy(m) => m.def.map_flags,
             Map::Btf(m) => m.def.map_flags,
+            Map::StructOps(m) => m.def.map_flags,
+        }
+    }
+
+    /// Sets the map flags
+    pub fn set_map_flags(&mut self, flags: u32) {
+        match self {
+            Map::Legacy(m) => m.def.map_flags = flags,
+            Map::Btf(m) => m.def.map_flags = flags,
+            Map::StructOps(m) => m.def.map_flags = flags,
+        }
+    }
+
+    /// Returns the map extra, which is the start address of the user space mapping for arenas
+    pub fn map_extra(&self) -> u64 {
+        match self {
+            Map::Legacy(m) => m.map_extra,
+            Map::Btf(m) => m.def.map_extra,
+            Map::StructOps(_) => 0,
         }
     }
 
@@ -202,6 +230,7 @@ impl Map {
         match self {
             Map::Legacy(m) => m.def.pinning,
             Map::Btf(m) => m.def.pinning,
+            Map::StructOps(m) => m.def.pinning,
         }
     }
 
@@ -210,6 +239,7 @@ impl Map {
         match self {
             Map::Legacy(m) => &m.data,
             Map::Btf(m) => &m.data,
+            Map::StructOps(m) => &m.data,
         }
     }
 
@@ -218,6 +248,7 @@ impl Map {
         match self {
             Map::Legacy(m) => m.data.as_mut(),
             Map::Btf(m) => m.data.as_mut(),
+            Map::StructOps(m) => m.data.as_mut(),
         }
     }
 
@@ -226,6 +257,7 @@ impl Map {
         match self {
             Map::Legacy(m) => m.section_index,
             Map::Btf(m) => m.section_index,
+            Map::StructOps(m) => m.section_index,
         }
     }
 
@@ -234,6 +266,7 @@ impl Map {
         match self {
             Map::Legacy(m) => m.section_kind,
             Map::Btf(_) => EbpfSectionKind::BtfMaps,
+            Map::StructOps(_) => EbpfSectionKind::StructOps,
         }
     }
 
@@ -245,6 +278,49 @@ impl Map {
         match self {
             Map::Legacy(m) => m.symbol_index,
             Map::Btf(m) => Some(m.symbol_index),
+            Map::StructOps(m) => Some(m.symbol_index),
+        }
+    }
+
+    /// Returns the names of the maps to store at the given indices of a
+    /// map-of-maps when it's created.
+    ///
+    /// These are declared with `.values` initialisers of BTF maps.
+    pub fn initial_slots(&self) -> &[(u32, String)] {
+        match self {
+            Map::Btf(m) => &m.initial_slots,
+            Map::Legacy(_) | Map::StructOps(_) => &[],
+        }
+    }
+
+    /// Returns the template of the inner map for map-of-maps types.
+    ///
+    /// The template is used to create a map with the layout the kernel checks
+    /// inner maps against when they're inserted in the outer map.
+    pub fn inner(&self) -> Option<Map> {
+        match self {
+            Map::Legacy(m) => m.inner_def.map(|def| {
+                Map::Legacy(LegacyMap {
+                    def,
+                    inner_def: None,
+                    map_extra: 0,
+                    section_index: m.section_index,
+                    section_kind: m.section_kind,
+                    symbol_index: None,
+                    data: Vec::new(),
+                })
+            }),
+            Map::Btf(m) => m.inner_def.map(|def| {
+                Map::Btf(BtfMap {
+                    def,
+                    inner_def: None,
+                    initial_slots: Vec::new(),
+                    section_index: m.section_index,
+                    symbol_index: m.symbol_index,
+                    data: Vec::new(),
+                })
+            }),
+            Map::StructOps(_) => None,
         }
     }
 }
@@ -257,6 +333,8 @@ impl Map {
 pub struct LegacyMap {
     /// The definition of the map
     pub def: bpf_map_def,
+    pub(crate) inner_def: Option<bpf_map_def>,
+    pub(crate) map_extra: u64,
     /// The section index
     pub section_index: usize,
     /// The section kind
@@ -271,11 +349,51 @@ pub struct LegacyMap {
     pub data: Vec<u8>,
 }
 
+impl LegacyMap {
+    /// Creates a map from its definition.
+    pub fn new(
+        def: bpf_map_def,
+        section_index: usize,
+        section_kind: EbpfSectionKind,
+        symbol_index: Option<usize>,
+        data: Vec<u8>,
+    ) -> Self {
+        Self {
+            def,
+            inner_def: None,
+            map_extra: 0,
+            section_index,
+            section_kind,
+            symbol_index,
+            data,
+        }
+    }
+
+    /// Returns the definition of the inner map template, for map-of-maps types.
+    pub fn inner_def(&self) -> Option<&bpf_map_def> {
+        self.inner_def.as_ref()
+    }
+
+    /// Sets the definition of the inner map template, for map-of-maps types.
+    pub fn set_inner_def(&mut self, inner_def: Option<bpf_map_def>) {
+        self.inner_def = inner_def;
+    }
+
+    /// Returns the start address of the user space mapping, for arenas.
+    pub fn map_extra(&self) -> u64 {
+        self.map_extra
+    }
+}
+
 /// A BTF-defined map, most likely from a `.maps` section.
 #[derive(Debug, Clone)]
 pub struct BtfMap {
     /// The definition of the map
     pub def: BtfMapDef,
+    pub(crate) inner_def: Option<BtfMapDef>,
+    // The names of the maps stored at the given indices of map-of-maps, from
+    // `.values` initialisers.
+    pub(crate) initial_slots: Vec<(u32, String)>,
     pub(crate) section_index: usize,
     pub(crate) symbol_index: usize,
     pub(crate) data: Vec<u8>,
@@ -19,17 +19,25 @@ use object::{
 
 use crate::{
     btf::{
-        Array, Btf, BtfError, BtfExt, BtfFeatures, BtfType, DataSecEntry, FuncSecInfo, LineSecInfo,
+        Array, Btf, BtfEnum64, BtfError, BtfExt, BtfFeatures, BtfType, DataSecEntry, Enum, Enum64,
+        FuncSecInfo, LineSecInfo, Struct, Var, VarLinkage,
     },
+    externs::{Extern, ExternSymbol, KCONFIG_SECTION},
     generated::{
-        BPF_CALL, BPF_F_RDONLY_PROG, BPF_JMP, BPF_K, bpf_func_id::*, bpf_insn, bpf_map_info,
-        bpf_map_type::BPF_MAP_TYPE_ARRAY,
+        BPF_CALL, BPF_F_LINK, BPF_F_RDONLY_PROG, BPF_JMP, BPF_K,
+        bpf_func_id::*,
+        bpf_insn, bpf_map_info,
+        bpf_map_type::{
+            BPF_MAP_TYPE_ARENA, BPF_MAP_TYPE_ARRAY, BPF_MAP_TYPE_ARRAY_OF_MAPS,
+            BPF_MAP_TYPE_HASH_OF_MAPS, BPF_MAP_TYPE_STRUCT_OPS,
+        },
     },
     maps::{BtfMap, BtfMapDef, LegacyMap, MINIMUM_MAP_SIZE, Map, PinningType, bpf_map_def},
     programs::{
         CgroupSockAddrAttachType, CgroupSockAttachType, CgroupSockoptAttachType, XdpAttachType,
     },
     relocation::*,
+    struct_ops::{STRUCT_OPS_LINK_SECTION, STRUCT_OPS_SECTION, StructOpsMap},
     util::HashMap,
 };
 
@@ -47,6 +55,7 @@ pub struct Features {
     devmap_prog_id: bool,
     prog_info_map_ids: bool,
     prog_info_gpl_compatible: bool,
+    array_mmap: bool,
     btf: Option<BtfFeatures>,
 }
 
@@ -63,6 +72,7 @@ impl Features {
         devmap_prog_id: bool,
         prog_info_map_ids: bool,
         prog_info_gpl_compatible: bool,
+        array_mmap: bool,
         btf: Option<BtfFeatures>,
     ) -> Self {
         Self {
@@ -75,6 +85,7 @@ impl Features {
             devmap_prog_id,
             prog_info_map_ids,
             prog_info_gpl_compatible,
+            array_mmap,
             btf,
         }
     }
@@ -127,6 +138,11 @@ impl Features {
         self.prog_info_gpl_compatible
     }
 
+    /// Returns whether array maps can be created with `BPF_F_MMAPABLE`.
+    pub fn array_mmap(&self) -> bool {
+        self.array_mmap
+    }
+
     /// If BTF is supported, returns which BTF features are supported.
     pub fn btf(&self) -> Option<&BtfFeatures> {
         self.btf.as_ref()
@@ -160,6 +176,20 @@ pub struct Object {
     // symbol_offset_by_name caches symbols that could be referenced from a
     // BTF VAR type so the offsets can be fixed up
     pub(crate) symbol_offset_by_name: HashMap<String, u64>,
+    // externs maps the index of undefined symbols to the extern variables
+    // they refer to
+    pub(crate) externs: HashMap<usize, Extern>,
+}
+
+/// The location of a global variable in one of the `.bss`, `.data` or `.rodata` maps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalVar {
+    /// The name of the map holding the variable.
+    pub map: String,
+    /// The offset of the variable in the value of the map.
+    pub offset: usize,
+    /// The size of the variable in bytes.
+    pub size: usize,
 }
 
 /// An eBPF program
@@ -225,28 +255,31 @@ pub struct Function {
 /// Currently, the following section names are not supported yet:
 /// - `flow_dissector`: `BPF_PROG_TYPE_FLOW_DISSECTOR`
 /// - `ksyscall+` or `kretsyscall+`
-/// - `usdt+`
-/// - `kprobe.multi+` or `kretprobe.multi+`: `BPF_TRACE_KPROBE_MULTI`
-/// - `lsm_cgroup+`
 /// - `lwt_in`, `lwt_out`, `lwt_seg6local`, `lwt_xmit`
-/// - `raw_tp.w+`, `raw_tracepoint.w+`
 /// - `action`
-/// - `sk_reuseport/migrate`, `sk_reuseport`
-/// - `syscall`
-/// - `struct_ops+`
-/// - `fmod_ret+`, `fmod_ret.s+`
 /// - `iter+`, `iter.s+`
 #[derive(Debug, Clone)]
 #[expect(missing_docs)]
 pub enum ProgramSection {
     KRetProbe,
     KProbe,
+    KRetProbeMulti,
+    KProbeMulti,
     UProbe {
         sleepable: bool,
     },
     URetProbe {
         sleepable: bool,
     },
+    UProbeMulti {
+        sleepable: bool,
+    },
+    URetProbeMulti {
+        sleepable: bool,
+    },
+    Usdt {
+        sleepable: bool,
+    },
     TracePoint,
     SocketFilter,
     Xdp {
@@ -271,9 +304,11 @@ pub enum ProgramSection {
     LircMode2,
     PerfEvent,
     RawTracePoint,
+    RawTracePointWritable,
     Lsm {
         sleepable: bool,
     },
+    LsmCgroup,
     BtfTracePoint,
     FEntry {
         sleepable: bool,
@@ -281,9 +316,17 @@ pub enum ProgramSection {
     FExit {
         sleepable: bool,
     },
+    FModRet {
+        sleepable: bool,
+    },
     FlowDissector,
     Extension,
     SkLookup,
+    SkReuseport {
+        migrate: bool,
+    },
+    Netfilter,
+    Syscall,
     CgroupSock {
         attach_type: CgroupSockAttachType,
     },
@@ -291,6 +334,9 @@ pub enum ProgramSection {
     Iter {
         sleepable: bool,
     },
+    StructOps {
+        sleepable: bool,
+    },
 }
 
 impl FromStr for ProgramSection {
@@ -314,10 +360,18 @@ impl FromStr for ProgramSection {
         Ok(match kind {
             "kprobe" => KProbe,
             "kretprobe" => KRetProbe,
+            "kprobe.multi" => KProbeMulti,
+            "kretprobe.multi" => KRetProbeMulti,
             "uprobe" => UProbe { sleepable: false },
             "uprobe.s" => UProbe { sleepable: true },
             "uretprobe" => URetProbe { sleepable: false },
             "uretprobe.s" => URetProbe { sleepable: true },
+            "uprobe.multi" => UProbeMulti { sleepable: false },
+            "uprobe.multi.s" => UProbeMulti { sleepable: true },
+            "uretprobe.multi" => URetProbeMulti { sleepable: false },
+            "uretprobe.multi.s" => URetProbeMulti { sleepable: true },
+            "usdt" => Usdt { sleepable: false },
+            "usdt.s" => Usdt { sleepable: true },
             "xdp" | "xdp.frags" => Xdp {
                 frags: kind == "xdp.frags",
                 attach_type: match pieces.next() {
@@ -434,17 +488,36 @@ impl FromStr for ProgramSection {
             "lirc_mode2" => LircMode2,
             "perf_event" => PerfEvent,
             "raw_tp" | "raw_tracepoint" => RawTracePoint,
+            "raw_tp.w" | "raw_tracepoint.w" => RawTracePointWritable,
             "lsm" => Lsm { sleepable: false },
             "lsm.s" => Lsm { sleepable: true },
+            "lsm_cgroup" => LsmCgroup,
             "fentry" => FEntry { sleepable: false },
             "fentry.s" => FEntry { sleepable: true },
             "fexit" => FExit { sleepable: false },
             "fexit.s" => FExit { sleepable: true },
+            "fmod_ret" => FModRet { sleepable: false },
+            "fmod_ret.s" => FModRet { sleepable: true },
             "flow_dissector" => FlowDissector,
             "freplace" => Extension,
             "sk_lookup" => SkLookup,
+            "sk_reuseport" => SkReuseport {
+                migrate: match pieces.next() {
+                    None => false,
+                    Some("migrate") => true,
+                    Some(_) => {
+                        return Err(ParseError::InvalidProgramSection {
+                            section: section.to_owned(),
+                        });
+                    }
+                },
+            },
+            "netfilter" => Netfilter,
+            "syscall" => Syscall,
             "iter" => Iter { sleepable: false },
             "iter.s" => Iter { sleepable: true },
+            "struct_ops" => StructOps { sleepable: false },
+            "struct_ops.s" => StructOps { sleepable: true },
             _ => {
                 return Err(ParseError::InvalidProgramSection {
                     section: section.to_owned(),
@@ -474,6 +547,7 @@ impl Object {
 
         let mut bpf_obj = Object::new(endianness, license, kernel_version);
 
+        let mut extern_symbols = Vec::new();
         if let Some(symbol_table) = obj.symbol_table() {
             for symbol in symbol_table.symbols() {
                 let name = symbol
@@ -498,7 +572,13 @@ impl Object {
                         .or_default()
                         .push(symbol.index().0);
                 }
-                if symbol.is_global() || symbol.kind() == SymbolKind::Data {
+                if symbol.is_undefined() && !name.is_empty() {
+                    extern_symbols.push(ExternSymbol {
+                        index: symbol.index().0,
+                        name,
+                        is_weak: symbol.is_weak(),
+                    });
+                } else if symbol.is_global() || symbol.kind() == SymbolKind::Data {
                     bpf_obj.symbol_offset_by_name.insert(name, symbol.address());
                 }
             }
@@ -524,10 +604,16 @@ impl Object {
             bpf_obj.parse_section(Section::try_from(&s)?)?;
         }
 
+        bpf_obj.parse_externs(extern_symbols)?;
+
         Ok(bpf_obj)
     }
 
-    fn new(endianness: Endianness, license: CString, kernel_version: Option<u32>) -> Object {
+    pub(crate) fn new(
+        endianness: Endianness,
+        license: CString,
+        kernel_version: Option<u32>,
+    ) -> Object {
         Object {
             endianness,
             license,
@@ -542,6 +628,7 @@ impl Object {
             symbols_by_section: HashMap::new(),
             section_infos: HashMap::new(),
             symbol_offset_by_name: HashMap::new(),
+            externs: HashMap::new(),
         }
     }
 
@@ -594,6 +681,84 @@ impl Object {
         Ok(())
     }
 
+    /// Returns the global variables defined in the `.bss`, `.data` and `.rodata` sections,
+    /// keyed by name.
+    ///
+    /// Variables are looked up in the `DATASEC`s of the object BTF. Objects without BTF fall back
+    /// to the symbol table.
+    pub fn global_vars(&self) -> Result<HashMap<String, GlobalVar>, BtfError> {
+        let is_data_map = |map: &Map| {
+            matches!(
+                map.section_kind(),
+                EbpfSectionKind::Bss | EbpfSectionKind::Data | EbpfSectionKind::Rodata
+            )
+        };
+
+        let mut vars = HashMap::new();
+        match &self.btf {
+            Some(btf) if !btf.is_empty() => {
+                for t in btf.types() {
+                    let BtfType::DataSec(d) = t else {
+                        continue;
+                    };
+                    let map = btf.string_at(d.name_offset)?;
+                    if !self.maps.get(map.as_ref()).is_some_and(is_data_map) {
+                        continue;
+                    }
+                    for e in &d.entries {
+                        let BtfType::Var(var) = btf.type_by_id(e.btf_type)? else {
+                            return Err(BtfError::InvalidDatasec);
+                        };
+                        let name = btf.string_at(var.name_offset)?;
+                        // LLVM doesn't always fill in the offsets of non-static variables, see
+                        // `Btf::fixup_and_sanitize`.
+                        let offset = match self.symbol_offset_by_name.get(name.as_ref()) {
+                            Some(offset) if d.size == 0 && var.linkage != VarLinkage::Static => {
+                                *offset as usize
+                            }
+                            _ => e.offset as usize,
+                        };
+                        vars.insert(
+                            name.into_owned(),
+                            GlobalVar {
+                                map: map.to_string(),
+                                offset,
+                                size: e.size as usize,
+                            },
+                        );
+                    }
+                }
+            }
+            _ => {
+                for symbol in self.symbol_table.values() {
+                    let (Some(name), Some(section_index)) = (&symbol.name, symbol.section_index)
+                    else {
+                        continue;
+                    };
+                    if symbol.kind != SymbolKind::Data || !symbol.is_definition {
+                        continue;
+                    }
+                    let Some((map, _)) = self
+                        .maps
+                        .iter()
+                        .find(|(_, m)| is_data_map(m) && m.section_index() == section_index)
+                    else {
+                        continue;
+                    };
+                    vars.insert(
+                        name.clone(),
+                        GlobalVar {
+                            map: map.clone(),
+                            offset: symbol.address as usize,
+                            size: symbol.size as usize,
+                        },
+                    );
+                }
+            }
+        }
+        Ok(vars)
+    }
+
     fn parse_btf(&mut self, section: &Section) -> Result<(), BtfError> {
         self.btf = Some(Btf::parse(section.data, self.endianness)?);
 
@@ -774,16 +939,56 @@ impl Object {
                 if type_name == section.name {
                     // each btf_var_secinfo contains a map
                     for info in &datasec.entries {
-                        let (map_name, def) = parse_btf_map_def(btf, info)?;
+                        let (map_name, def, inner_def) = parse_btf_map_def(btf, info)?;
                         let symbol_index =
                             maps.get(&map_name)
                                 .ok_or_else(|| ParseError::SymbolNotFound {
                                     name: map_name.to_string(),
                                 })?;
+                        let mut initial_slots = Vec::new();
+                        // Only map-of-maps are populated from `values`, other maps such
+                        // as program arrays keep ignoring it.
+                        let values_offset = match inner_def {
+                            Some(_) => btf_map_values_offset(btf, info)?,
+                            None => None,
+                        };
+                        if let Some(values_offset) = values_offset {
+                            // `.values = { [i] = &map }` initialisers are emitted as
+                            // relocations against the pointers in the `values` array.
+                            let start = u64::from(info.offset) + values_offset;
+                            let end = u64::from(info.offset) + u64::from(info.size);
+                            for rel in section
+                                .relocations
+                                .iter()
+                                .filter(|rel| (start..end).contains(&rel.offset))
+                            {
+                                let symbol = self.symbol_table.get(&rel.symbol_index);
+                                let target = match symbol {
+                                    Some(Symbol {
+                                        section_index: Some(section_index),
+                                        name: Some(name),
+                                        ..
+                                    }) if *section_index == section.index.0
+                                        && maps.contains_key(name) =>
+                                    {
+                                        name.clone()
+                                    }
+                                    _ => {
+                                        return Err(ParseError::UnsupportedMapValues {
+                                            name: map_name,
+                                        });
+                                    }
+                                };
+                                let index = (rel.offset - start) / mem::size_of::<u64>() as u64;
+                                initial_slots.push((index as u32, target));
+                            }
+                        }
                         self.maps.insert(
                             map_name,
                             Map::Btf(BtfMap {
                                 def,
+                                inner_def,
+                                initial_slots,
                                 section_index: section.index.0,
                                 symbol_index: *symbol_index,
                                 data: Vec::new(),
@@ -796,6 +1001,96 @@ impl Object {
         Ok(())
     }
 
+    fn parse_struct_ops(&mut self, section: &Section) -> Result<(), ParseError> {
+        let btf = self.btf.as_ref().ok_or(ParseError::NoBTF)?;
+        let symbols: HashMap<&String, &Symbol> = self
+            .symbols_by_section
+            .get(&section.index)
+            .ok_or(ParseError::NoSymbolsForSection {
+                section_name: section.name.to_owned(),
+            })?
+            .iter()
+            .filter_map(|s| {
+                let symbol = self.symbol_table.get(s).unwrap();
+                symbol.name.as_ref().map(|name| (name, symbol))
+            })
+            .collect();
+
+        for t in btf.types() {
+            let BtfType::DataSec(datasec) = t else {
+                continue;
+            };
+            if btf.type_name(t)? != section.name {
+                continue;
+            }
+            // each btf_var_secinfo contains a struct_ops map
+            for info in &datasec.entries {
+                let var = btf.type_by_id(info.btf_type)?;
+                let BtfType::Var(Var { btf_type, .. }) = var else {
+                    return Err(BtfError::InvalidDatasec.into());
+                };
+                let map_name = btf.type_name(var)?.to_string();
+                let invalid_map = || ParseError::InvalidStructOpsMap {
+                    name: map_name.clone(),
+                };
+                let btf_type_id = btf.resolve_type(*btf_type)?;
+                let ty = btf.type_by_id(btf_type_id)?;
+                let BtfType::Struct(Struct { size, .. }) = ty else {
+                    return Err(invalid_map());
+                };
+                let symbol = symbols
+                    .get(&map_name)
+                    .ok_or_else(|| ParseError::SymbolNotFound {
+                        name: map_name.clone(),
+                    })?;
+                let start = symbol.address as usize;
+                let local_data = section
+                    .data
+                    .get(start..start + *size as usize)
+                    .ok_or_else(invalid_map)?
+                    .to_vec();
+                // function pointers are relocated against the programs
+                // implementing them
+                let relocations = section
+                    .relocations
+                    .iter()
+                    .filter_map(|rel| {
+                        let offset = (rel.offset as usize).checked_sub(start)?;
+                        (offset < local_data.len()).then_some((offset, rel.symbol_index))
+                    })
+                    .collect();
+                self.maps.insert(
+                    map_name.clone(),
+                    Map::StructOps(StructOpsMap {
+                        def: BtfMapDef {
+                            map_type: BPF_MAP_TYPE_STRUCT_OPS as u32,
+                            key_size: mem::size_of::<u32>() as u32,
+                            value_size: *size,
+                            max_entries: 1,
+                            map_flags: if section.name == STRUCT_OPS_LINK_SECTION {
+                                BPF_F_LINK
+                            } else {
+                                0
+                            },
+                            ..Default::default()
+                        },
+                        type_name: btf.type_name(ty)?.to_string(),
+                        section_index: section.index.0,
+                        symbol_index: symbol.index,
+                        btf_type_id,
+                        local_data,
+                        relocations,
+                        data: Vec::new(),
+                        btf_vmlinux_type_id: 0,
+                        btf_vmlinux_value_type_id: 0,
+                        programs: Vec::new(),
+                    }),
+                );
+            }
+        }
+        Ok(())
+    }
+
     // Parses multiple map definition contained in a single `maps` section (which is
     // different from `.maps` which is used for BTF). We can tell where each map is
     // based on the symbol table.
@@ -819,9 +1114,40 @@ impl Object {
                 .as_ref()
                 .ok_or(ParseError::MapSymbolNameNotFound { i: *i })?;
             let def = parse_map_def(name, data)?;
+            // Map-of-maps are followed by the definition of the inner map,
+            // which is used as a template when creating the outer map.
+            let inner_def = match def.map_type {
+                x if (x == BPF_MAP_TYPE_ARRAY_OF_MAPS as u32
+                    || x == BPF_MAP_TYPE_HASH_OF_MAPS as u32)
+                    && data.len() > mem::size_of::<bpf_map_def>() =>
+                {
+                    Some(parse_map_def(name, &data[mem::size_of::<bpf_map_def>()..])?)
+                }
+                _ => None,
+            };
+            // Arenas are followed by the start address of their user space
+            // mapping.
+            let map_extra = match def.map_type {
+                x if x == BPF_MAP_TYPE_ARENA as u32 => {
+                    let offset = mem::size_of::<bpf_map_def>().next_multiple_of(8);
+                    let extra = data
+                        .get(offset..offset + mem::size_of::<u64>())
+                        .and_then(|extra| extra.try_into().ok())
+                        .ok_or_else(|| ParseError::InvalidMapDefinition {
+                            name: name.to_owned(),
+                        })?;
+                    match self.endianness {
+                        Endianness::Big => u64::from_be_bytes(extra),
+                        Endianness::Little => u64::from_le_bytes(extra),
+                    }
+                }
+                _ => 0,
+            };
             maps.insert(
                 name.to_string(),
                 Map::Legacy(LegacyMap {
+                    inner_def,
+                    map_extra,
                     section_index: section.index.0,
                     section_kind: section.kind,
                     symbol_index: Some(sym.index),
@@ -852,6 +1178,7 @@ impl Object {
             EbpfSectionKind::Btf => self.parse_btf(&section)?,
             EbpfSectionKind::BtfExt => self.parse_btf_ext(&section)?,
             EbpfSectionKind::BtfMaps => self.parse_btf_maps(&section)?,
+            EbpfSectionKind::StructOps => self.parse_struct_ops(&section)?,
             EbpfSectionKind::Maps => {
                 // take out self.maps so we can borrow the iterator below
                 // without cloning or collecting
@@ -887,7 +1214,10 @@ impl Object {
                     );
                 }
             }
-            EbpfSectionKind::Undefined | EbpfSectionKind::License | EbpfSectionKind::Version => {}
+            EbpfSectionKind::Undefined
+            | EbpfSectionKind::License
+            | EbpfSectionKind::Version
+            | EbpfSectionKind::Kconfig => {}
         }
 
         Ok(())
@@ -1007,6 +1337,36 @@ pub enum ParseError {
     /// No BTF parsed for object
     #[error("no BTF parsed for object")]
     NoBTF,
+
+    #[error("extern `{name}` is not supported")]
+    UnsupportedExtern { name: String },
+
+    #[error("value of non-weak kconfig extern `{name}` not found")]
+    KconfigValueNotFound { name: String },
+
+    #[error("invalid value `{value}` for kconfig extern `{name}`")]
+    InvalidKconfigValue { name: String, value: String },
+
+    #[error("kernel symbol `{name}` not found")]
+    KsymNotFound { name: String },
+
+    #[error("kernel function `{name}` not found")]
+    KfuncNotFound { name: String },
+
+    #[error("map `{name}` has `values` initialisers that aren't maps")]
+    UnsupportedMapValues { name: String },
+
+    #[error("invalid struct_ops map `{name}`")]
+    InvalidStructOpsMap { name: String },
+
+    #[error("struct_ops type `{name}` not found in the kernel BTF")]
+    StructOpsTypeNotFound { name: String },
+
+    #[error("member `{member}` of struct_ops map `{map}` not found in the kernel type")]
+    StructOpsMemberNotFound { map: String, member: String },
+
+    #[error("member `{member}` of struct_ops map `{map}` doesn't match the kernel type")]
+    InvalidStructOpsMember { map: String, member: String },
 }
 
 /// Invalid bindings to the bpf type from the parsed/received value.
@@ -1042,6 +1402,10 @@ pub enum EbpfSectionKind {
     License,
     /// `version`
     Version,
+    /// `.kconfig`
+    Kconfig,
+    /// `.struct_ops` and `.struct_ops.link`
+    StructOps,
 }
 
 impl EbpfSectionKind {
@@ -1066,6 +1430,10 @@ impl EbpfSectionKind {
             EbpfSectionKind::Btf
         } else if name == ".BTF.ext" {
             EbpfSectionKind::BtfExt
+        } else if name == KCONFIG_SECTION {
+            EbpfSectionKind::Kconfig
+        } else if name == STRUCT_OPS_SECTION || name == STRUCT_OPS_LINK_SECTION {
+            EbpfSectionKind::StructOps
         } else {
             EbpfSectionKind::Undefined
         }
@@ -1192,6 +1560,23 @@ fn get_map_field(btf: &Btf, type_id: u32) -> Result<u32, BtfError> {
     Ok(arr.len)
 }
 
+// `__ulong` fields are declared as an anonymous enum holding their value,
+// which may not fit in the length of an array.
+fn get_map_field_long(btf: &Btf, type_id: u32) -> Result<u64, BtfError> {
+    match btf.type_by_id(type_id)? {
+        BtfType::Enum(Enum { variants, .. }) if variants.len() == 1 => Ok(variants[0].value.into()),
+        BtfType::Enum64(Enum64 { variants, .. }) if variants.len() == 1 => {
+            let BtfEnum64 {
+                value_low,
+                value_high,
+                ..
+            } = variants[0];
+            Ok((u64::from(value_high) << 32) | u64::from(value_low))
+        }
+        _ => get_map_field(btf, type_id).map(Into::into),
+    }
+}
+
 // Parsed '.bss' '.data' and '.rodata' sections. These sections are arrays of
 // bytes and are relocated based on their section index.
 fn parse_data_map_section(section: &Section) -> Result<Map, ParseError> {
@@ -1227,6 +1612,8 @@ fn parse_data_map_section(section: &Section) -> Result<Map, ParseError> {
         _ => unreachable!(),
     };
     Ok(Map::Legacy(LegacyMap {
+        inner_def: None,
+        map_extra: 0,
         section_index: section.index.0,
         section_kind: section.kind,
         // Data maps don't require symbols to be relocated
@@ -1256,7 +1643,10 @@ fn parse_map_def(name: &str, data: &[u8]) -> Result<bpf_map_def, ParseError> {
     }
 }
 
-fn parse_btf_map_def(btf: &Btf, info: &DataSecEntry) -> Result<(String, BtfMapDef), BtfError> {
+fn parse_btf_map_def(
+    btf: &Btf,
+    info: &DataSecEntry,
+) -> Result<(String, BtfMapDef, Option<BtfMapDef>), BtfError> {
     let ty = match btf.type_by_id(info.btf_type)? {
         BtfType::Var(var) => var,
         other => {
@@ -1266,8 +1656,6 @@ fn parse_btf_map_def(btf: &Btf, info: &DataSecEntry) -> Result<(String, BtfMapDe
         }
     };
     let map_name = btf.string_at(ty.name_offset)?;
-    let mut map_def = BtfMapDef::default();
-
     // Safety: union
     let root_type = btf.resolve_type(ty.btf_type)?;
     let s = match btf.type_by_id(root_type)? {
@@ -1279,6 +1667,36 @@ fn parse_btf_map_def(btf: &Btf, info: &DataSecEntry) -> Result<(String, BtfMapDe
         }
     };
 
+    let (map_def, inner_def) = parse_btf_map_members(btf, s)?;
+    Ok((map_name.to_string(), map_def, inner_def))
+}
+
+// Returns the byte offset of the `values` member of a BTF map definition, if
+// it has one.
+fn btf_map_values_offset(btf: &Btf, info: &DataSecEntry) -> Result<Option<u64>, BtfError> {
+    let BtfType::Var(var) = btf.type_by_id(info.btf_type)? else {
+        return Ok(None);
+    };
+    let BtfType::Struct(s) = btf.type_by_id(btf.resolve_type(var.btf_type)?)? else {
+        return Ok(None);
+    };
+    for m in &s.members {
+        if btf.string_at(m.name_offset)? == "values" {
+            return Ok(Some((s.member_bit_offset(m) / 8) as u64));
+        }
+    }
+    Ok(None)
+}
+
+// Parses the members of a BTF map definition, returning the definition of the
+// map and, for map-of-maps, the definition of the inner map template.
+fn parse_btf_map_members(
+    btf: &Btf,
+    s: &Struct,
+) -> Result<(BtfMapDef, Option<BtfMapDef>), BtfError> {
+    let mut map_def = BtfMapDef::default();
+    let mut inner_def = None;
+
     for m in &s.members {
         match btf.string_at(m.name_offset)?.as_ref() {
             "type" => {
@@ -1313,12 +1731,41 @@ fn parse_btf_map_def(btf: &Btf, info: &DataSecEntry) -> Result<(String, BtfMapDe
             "value_size" => {
                 map_def.value_size = get_map_field(btf, m.btf_type)?;
             }
+            "values" => {
+                // `__array(values, struct inner)` declares an array of
+                // pointers to the inner map definition.
+                let BtfType::Array(Array { array, .. }) = btf.type_by_id(m.btf_type)? else {
+                    return Err(BtfError::UnexpectedBtfType {
+                        type_id: m.btf_type,
+                    });
+                };
+                let element_type = btf.resolve_type(array.element_type)?;
+                let BtfType::Ptr(pty) = btf.type_by_id(element_type)? else {
+                    return Err(BtfError::UnexpectedBtfType {
+                        type_id: element_type,
+                    });
+                };
+                let inner_type = btf.resolve_type(pty.btf_type)?;
+                match btf.type_by_id(inner_type)? {
+                    BtfType::Struct(inner) => {
+                        let (def, _) = parse_btf_map_members(btf, inner)?;
+                        map_def.value_size = mem::size_of::<u32>() as u32;
+                        inner_def = Some(def);
+                    }
+                    _ => {
+                        debug!("skipping initial values of map");
+                    }
+                }
+            }
             "max_entries" => {
                 map_def.max_entries = get_map_field(btf, m.btf_type)?;
             }
             "map_flags" => {
                 map_def.map_flags = get_map_field(btf, m.btf_type)?;
             }
+            "map_extra" => {
+                map_def.map_extra = get_map_field_long(btf, m.btf_type)?;
+            }
             "pinning" => {
                 let pinning = get_map_field(btf, m.btf_type)?;
                 map_def.pinning = PinningType::try_from(pinning).unwrap_or_else(|_| {
@@ -1332,19 +1779,22 @@ fn parse_btf_map_def(btf: &Btf, info: &DataSecEntry) -> Result<(String, BtfMapDe
             }
         }
     }
-    Ok((map_name.to_string(), map_def))
+    Ok((map_def, inner_def))
 }
 
 /// Parses a [bpf_map_info] into a [Map].
 pub fn parse_map_info(info: bpf_map_info, pinned: PinningType) -> Map {
     if info.btf_key_type_id != 0 {
         Map::Btf(BtfMap {
+            inner_def: None,
+            initial_slots: Vec::new(),
             def: BtfMapDef {
                 map_type: info.type_,
                 key_size: info.key_size,
                 value_size: info.value_size,
                 max_entries: info.max_entries,
                 map_flags: info.map_flags,
+                map_extra: info.map_extra,
                 pinning: pinned,
                 btf_key_type_id: info.btf_key_type_id,
                 btf_value_type_id: info.btf_value_type_id,
@@ -1355,6 +1805,8 @@ pub fn parse_map_info(info: bpf_map_info, pinned: PinningType) -> Map {
         })
     } else {
         Map::Legacy(LegacyMap {
+            inner_def: None,
+            map_extra: info.map_extra,
             def: bpf_map_def {
                 map_type: info.type_,
                 key_size: info.key_size,
@@ -1435,7 +1887,10 @@ mod tests {
     use assert_matches::assert_matches;
 
     use super::*;
-    use crate::generated::btf_ext_header;
+    use crate::{
+        btf::{BtfEnum, BtfMember, DataSec, FuncProto, Int, IntEncoding, Ptr, Var, VarLinkage},
+        generated::{BPF_F_MMAPABLE, bpf_map_type::BPF_MAP_TYPE_PROG_ARRAY, btf_ext_header},
+    };
 
     const FAKE_INS_LEN: u64 = 8;
 
@@ -1610,6 +2065,8 @@ mod tests {
                 ),
             ),
             Ok(Map::Legacy(LegacyMap {
+                inner_def: None,
+                map_extra: 0,
                 section_index: 0,
                 section_kind: EbpfSectionKind::Data,
                 symbol_index: None,
@@ -1740,6 +2197,86 @@ mod tests {
         assert!(obj.maps.contains_key("foo"));
     }
 
+    #[test]
+    fn test_parse_section_map_arena() {
+        #[repr(C)]
+        struct ArenaDef {
+            def: bpf_map_def,
+            map_extra: u64,
+        }
+
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", mem::size_of::<ArenaDef>() as u64);
+        obj.parse_section(fake_section(
+            EbpfSectionKind::Maps,
+            "maps",
+            bytes_of(&ArenaDef {
+                def: bpf_map_def {
+                    map_type: BPF_MAP_TYPE_ARENA as u32,
+                    max_entries: 4,
+                    map_flags: BPF_F_MMAPABLE,
+                    ..Default::default()
+                },
+                map_extra: 1 << 44,
+            }),
+            None,
+        ))
+        .unwrap();
+        let map = obj.maps.get("foo").unwrap();
+        assert_eq!(map.map_type(), BPF_MAP_TYPE_ARENA as u32);
+        assert_eq!(map.max_entries(), 4);
+        assert_eq!(map.map_extra(), 1 << 44);
+    }
+
+    #[test]
+    fn test_parse_section_map_arena_big_endian() {
+        #[repr(C)]
+        struct ArenaDef {
+            def: bpf_map_def,
+            map_extra: u64,
+        }
+
+        let mut obj = fake_obj();
+        obj.endianness = Endianness::Big;
+        fake_sym(&mut obj, 0, 0, "foo", mem::size_of::<ArenaDef>() as u64);
+        obj.parse_section(fake_section(
+            EbpfSectionKind::Maps,
+            "maps",
+            bytes_of(&ArenaDef {
+                def: bpf_map_def {
+                    map_type: BPF_MAP_TYPE_ARENA as u32,
+                    max_entries: 4,
+                    map_flags: BPF_F_MMAPABLE,
+                    ..Default::default()
+                },
+                map_extra: (1u64 << 44).to_be(),
+            }),
+            None,
+        ))
+        .unwrap();
+        assert_eq!(obj.maps.get("foo").unwrap().map_extra(), 1 << 44);
+    }
+
+    #[test]
+    fn test_parse_section_map_arena_no_map_extra() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", mem::size_of::<bpf_map_def>() as u64);
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Maps,
+                "maps",
+                bytes_of(&bpf_map_def {
+                    map_type: BPF_MAP_TYPE_ARENA as u32,
+                    max_entries: 4,
+                    map_flags: BPF_F_MMAPABLE,
+                    ..Default::default()
+                }),
+                None,
+            )),
+            Err(ParseError::InvalidMapDefinition { name }) if name == "foo"
+        );
+    }
+
     #[test]
     fn test_parse_multiple_program_in_same_section() {
         let mut obj = fake_obj();
@@ -1839,6 +2376,363 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_section_map_of_maps() {
+        let mut obj = fake_obj();
+        fake_sym(
+            &mut obj,
+            0,
+            0,
+            "foo",
+            2 * mem::size_of::<bpf_map_def>() as u64,
+        );
+        let outer = bpf_map_def {
+            map_type: BPF_MAP_TYPE_ARRAY_OF_MAPS as u32,
+            key_size: 4,
+            value_size: 4,
+            max_entries: 8,
+            ..Default::default()
+        };
+        let inner = bpf_map_def {
+            map_type: BPF_MAP_TYPE_ARRAY as u32,
+            key_size: 4,
+            value_size: 8,
+            max_entries: 1,
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        buf.extend(bytes_of(&outer));
+        buf.extend(bytes_of(&inner));
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Maps,
+                "maps",
+                buf.as_slice(),
+                None
+            )),
+            Ok(())
+        );
+        let map = obj.maps.get("foo").unwrap();
+        assert_matches!(map, Map::Legacy(m) => {
+            assert_eq!(m.def, outer);
+            assert_eq!(m.inner_def, Some(inner));
+        });
+        assert_matches!(map.inner(), Some(Map::Legacy(m)) => {
+            assert_eq!(m.def, inner);
+            assert_eq!(m.inner_def, None);
+        });
+    }
+
+    #[test]
+    fn test_parse_btf_map_def_of_maps() {
+        let mut btf = Btf::new();
+        let int_name = btf.add_string("int");
+        let int_type = btf.add_type(BtfType::Int(Int::new(int_name, 4, IntEncoding::Signed, 0)));
+        // __uint(name, value) is encoded as `int (*name)[value]`.
+        let uint = |btf: &mut Btf, value| {
+            let array = btf.add_type(BtfType::Array(Array::new(0, int_type, int_type, value)));
+            btf.add_type(BtfType::Ptr(Ptr::new(0, array)))
+        };
+        let array_of_maps = uint(&mut btf, BPF_MAP_TYPE_ARRAY_OF_MAPS as u32);
+        let array = uint(&mut btf, BPF_MAP_TYPE_ARRAY as u32);
+        let outer_entries = uint(&mut btf, 8);
+        let inner_entries = uint(&mut btf, 1);
+        let int_ptr = btf.add_type(BtfType::Ptr(Ptr::new(0, int_type)));
+
+        let member = |btf: &mut Btf, name, btf_type| BtfMember {
+            name_offset: btf.add_string(name),
+            btf_type,
+            offset: 0,
+        };
+        let inner_members = vec![
+            member(&mut btf, "type", array),
+            member(&mut btf, "key", int_ptr),
+            member(&mut btf, "value", int_ptr),
+            member(&mut btf, "max_entries", inner_entries),
+        ];
+        let inner = btf.add_type(BtfType::Struct(Struct::new(0, inner_members, 32)));
+        let inner_ptr = btf.add_type(BtfType::Ptr(Ptr::new(0, inner)));
+        let values = btf.add_type(BtfType::Array(Array::new(0, inner_ptr, int_type, 0)));
+        let outer_members = vec![
+            member(&mut btf, "type", array_of_maps),
+            member(&mut btf, "key", int_ptr),
+            member(&mut btf, "max_entries", outer_entries),
+            member(&mut btf, "values", values),
+        ];
+        let outer = btf.add_type(BtfType::Struct(Struct::new(0, outer_members, 24)));
+        let var_name = btf.add_string("outer");
+        let var = btf.add_type(BtfType::Var(Var::new(var_name, outer, VarLinkage::Global)));
+
+        let (name, def, inner_def) = parse_btf_map_def(
+            &btf,
+            &DataSecEntry {
+                btf_type: var,
+                offset: 0,
+                size: 24,
+            },
+        )
+        .unwrap();
+        assert_eq!(name, "outer");
+        assert_eq!(def.map_type, BPF_MAP_TYPE_ARRAY_OF_MAPS as u32);
+        assert_eq!(def.key_size, 4);
+        assert_eq!(def.value_size, 4);
+        assert_eq!(def.max_entries, 8);
+        assert_matches!(inner_def, Some(inner_def) => {
+            assert_eq!(inner_def.map_type, BPF_MAP_TYPE_ARRAY as u32);
+            assert_eq!(inner_def.key_size, 4);
+            assert_eq!(inner_def.value_size, 4);
+            assert_eq!(inner_def.max_entries, 1);
+        });
+    }
+
+    // Builds the BTF of a `.maps` section with an array `inner` at offset 0
+    // and an array of maps `outer` at offset 32, declared as:
+    //
+    // struct { ...; __array(values, struct inner_def); } outer = {
+    //     .values = { [1] = &inner },
+    // };
+    fn fake_btf_map_of_maps(obj: &mut Object) {
+        let mut btf = Btf::new();
+        let int_name = btf.add_string("int");
+        let int_type = btf.add_type(BtfType::Int(Int::new(int_name, 4, IntEncoding::Signed, 0)));
+        let uint = |btf: &mut Btf, value| {
+            let array = btf.add_type(BtfType::Array(Array::new(0, int_type, int_type, value)));
+            btf.add_type(BtfType::Ptr(Ptr::new(0, array)))
+        };
+        let array_of_maps = uint(&mut btf, BPF_MAP_TYPE_ARRAY_OF_MAPS as u32);
+        let array = uint(&mut btf, BPF_MAP_TYPE_ARRAY as u32);
+        let entries = uint(&mut btf, 2);
+        let int_ptr = btf.add_type(BtfType::Ptr(Ptr::new(0, int_type)));
+
+        let member = |btf: &mut Btf, name, btf_type, offset| BtfMember {
+            name_offset: btf.add_string(name),
+            btf_type,
+            offset,
+        };
+        let inner_members = vec![
+            member(&mut btf, "type", array, 0),
+            member(&mut btf, "key", int_ptr, 64),
+            member(&mut btf, "value", int_ptr, 128),
+            member(&mut btf, "max_entries", entries, 192),
+        ];
+        let inner = btf.add_type(BtfType::Struct(Struct::new(0, inner_members, 32)));
+        let inner_ptr = btf.add_type(BtfType::Ptr(Ptr::new(0, inner)));
+        let values = btf.add_type(BtfType::Array(Array::new(0, inner_ptr, int_type, 0)));
+        let outer_members = vec![
+            member(&mut btf, "type", array_of_maps, 0),
+            member(&mut btf, "key", int_ptr, 64),
+            member(&mut btf, "max_entries", entries, 128),
+            member(&mut btf, "values", values, 192),
+        ];
+        let outer = btf.add_type(BtfType::Struct(Struct::new(0, outer_members, 24)));
+
+        let inner_name = btf.add_string("inner");
+        let inner_var = btf.add_type(BtfType::Var(Var::new(
+            inner_name,
+            inner,
+            VarLinkage::Global,
+        )));
+        let outer_name = btf.add_string("outer");
+        let outer_var = btf.add_type(BtfType::Var(Var::new(
+            outer_name,
+            outer,
+            VarLinkage::Global,
+        )));
+        let datasec_name = btf.add_string(".maps");
+        btf.add_type(BtfType::DataSec(DataSec::new(
+            datasec_name,
+            vec![
+                DataSecEntry {
+                    btf_type: inner_var,
+                    offset: 0,
+                    size: 32,
+                },
+                DataSecEntry {
+                    btf_type: outer_var,
+                    offset: 32,
+                    // The struct followed by the two initialised pointers.
+                    size: 40,
+                },
+            ],
+            72,
+        )));
+        obj.btf = Some(btf);
+        fake_sym(obj, 0, 0, "inner", 32);
+        fake_sym(obj, 0, 32, "outer", 40);
+    }
+
+    #[test]
+    fn test_parse_section_btf_map_of_maps_values() {
+        let mut obj = fake_obj();
+        fake_btf_map_of_maps(&mut obj);
+
+        let mut section = fake_section(EbpfSectionKind::BtfMaps, ".maps", &[0; 72], None);
+        section.relocations.push(Relocation {
+            offset: 32 + 24 + 8,
+            size: 64,
+            symbol_index: 1,
+        });
+        obj.parse_section(section).unwrap();
+
+        assert_eq!(
+            obj.maps.get("outer").unwrap().initial_slots(),
+            &[(1, "inner".to_owned())]
+        );
+        assert_eq!(obj.maps.get("inner").unwrap().initial_slots(), &[]);
+    }
+
+    #[test]
+    fn test_parse_section_btf_map_of_maps_values_not_map() {
+        let mut obj = fake_obj();
+        fake_btf_map_of_maps(&mut obj);
+        fake_sym(&mut obj, 1, 0, "prog", 8);
+
+        let mut section = fake_section(EbpfSectionKind::BtfMaps, ".maps", &[0; 72], None);
+        section.relocations.push(Relocation {
+            offset: 32 + 24,
+            size: 64,
+            symbol_index: 3,
+        });
+        assert_matches!(
+            obj.parse_section(section),
+            Err(ParseError::UnsupportedMapValues { name }) if name == "outer"
+        );
+    }
+
+    #[test]
+    fn test_parse_section_btf_prog_array_values() {
+        // struct { ...; __array(values, int (void *)); } jump_table = {
+        //     .values = { [0] = &prog },
+        // };
+        let mut obj = fake_obj();
+        let mut btf = Btf::new();
+        let int_name = btf.add_string("int");
+        let int_type = btf.add_type(BtfType::Int(Int::new(int_name, 4, IntEncoding::Signed, 0)));
+        let uint = |btf: &mut Btf, value| {
+            let array = btf.add_type(BtfType::Array(Array::new(0, int_type, int_type, value)));
+            btf.add_type(BtfType::Ptr(Ptr::new(0, array)))
+        };
+        let prog_array = uint(&mut btf, BPF_MAP_TYPE_PROG_ARRAY as u32);
+        let entries = uint(&mut btf, 2);
+        let int_ptr = btf.add_type(BtfType::Ptr(Ptr::new(0, int_type)));
+        let prog_type = btf.add_type(BtfType::FuncProto(FuncProto::new(vec![], int_type)));
+        let prog_ptr = btf.add_type(BtfType::Ptr(Ptr::new(0, prog_type)));
+        let values = btf.add_type(BtfType::Array(Array::new(0, prog_ptr, int_type, 0)));
+
+        let member = |btf: &mut Btf, name, btf_type, offset| BtfMember {
+            name_offset: btf.add_string(name),
+            btf_type,
+            offset,
+        };
+        let members = vec![
+            member(&mut btf, "type", prog_array, 0),
+            member(&mut btf, "key", int_ptr, 64),
+            member(&mut btf, "max_entries", entries, 128),
+            member(&mut btf, "values", values, 192),
+        ];
+        let jump_table = btf.add_type(BtfType::Struct(Struct::new(0, members, 24)));
+        let var_name = btf.add_string("jump_table");
+        let var = btf.add_type(BtfType::Var(Var::new(
+            var_name,
+            jump_table,
+            VarLinkage::Global,
+        )));
+        let datasec_name = btf.add_string(".maps");
+        btf.add_type(BtfType::DataSec(DataSec::new(
+            datasec_name,
+            vec![DataSecEntry {
+                btf_type: var,
+                offset: 0,
+                size: 40,
+            }],
+            40,
+        )));
+        obj.btf = Some(btf);
+        fake_sym(&mut obj, 0, 0, "jump_table", 40);
+        fake_sym(&mut obj, 1, 0, "prog", 8);
+
+        let mut section = fake_section(EbpfSectionKind::BtfMaps, ".maps", &[0; 40], None);
+        section.relocations.push(Relocation {
+            offset: 24,
+            size: 64,
+            symbol_index: 2,
+        });
+        obj.parse_section(section).unwrap();
+
+        let map = obj.maps.get("jump_table").unwrap();
+        assert_eq!(map.map_type(), BPF_MAP_TYPE_PROG_ARRAY as u32);
+        assert_eq!(map.initial_slots(), &[]);
+    }
+
+    #[test]
+    fn test_parse_section_struct_ops() {
+        let mut obj = fake_obj();
+        let mut btf = Btf::new();
+        let int_name = btf.add_string("int");
+        let int_type = btf.add_type(BtfType::Int(Int::new(int_name, 4, IntEncoding::Signed, 0)));
+        let proto = btf.add_type(BtfType::FuncProto(FuncProto::new(vec![], int_type)));
+        let func_ptr = btf.add_type(BtfType::Ptr(Ptr::new(0, proto)));
+        let ops_name = btf.add_string("aya_ops");
+        let init_name = btf.add_string("init");
+        let flags_name = btf.add_string("flags");
+        let ops = btf.add_type(BtfType::Struct(Struct::new(
+            ops_name,
+            vec![
+                BtfMember {
+                    name_offset: init_name,
+                    btf_type: func_ptr,
+                    offset: 0,
+                },
+                BtfMember {
+                    name_offset: flags_name,
+                    btf_type: int_type,
+                    offset: 64,
+                },
+            ],
+            16,
+        )));
+        let var_name = btf.add_string("ops");
+        let var = btf.add_type(BtfType::Var(Var::new(var_name, ops, VarLinkage::Global)));
+        let datasec_name = btf.add_string(STRUCT_OPS_LINK_SECTION);
+        btf.add_type(BtfType::DataSec(DataSec::new(
+            datasec_name,
+            vec![DataSecEntry {
+                btf_type: var,
+                offset: 0,
+                size: 16,
+            }],
+            16,
+        )));
+        obj.btf = Some(btf);
+        fake_sym(&mut obj, 0, 0, "ops", 16);
+        fake_sym(&mut obj, 1, 0, "aya_init", 8);
+
+        let data = [0u8; 16];
+        let mut section = fake_section(
+            EbpfSectionKind::StructOps,
+            STRUCT_OPS_LINK_SECTION,
+            &data,
+            None,
+        );
+        section.relocations.push(Relocation {
+            offset: 0,
+            size: 64,
+            symbol_index: 2,
+        });
+        obj.parse_section(section).unwrap();
+
+        assert_matches!(obj.maps.get("ops"), Some(Map::StructOps(map)) => {
+            assert_eq!(map.type_name(), "aya_ops");
+            assert_eq!(map.def.map_type, BPF_MAP_TYPE_STRUCT_OPS as u32);
+            assert_eq!(map.def.key_size, 4);
+            assert_eq!(map.def.value_size, 16);
+            assert_eq!(map.def.max_entries, 1);
+            assert_eq!(map.def.map_flags, BPF_F_LINK);
+            assert_eq!(map.btf_type_id, ops);
+            assert_eq!(map.relocations, vec![(0, 2)]);
+        });
+    }
+
     #[test]
     fn test_parse_section_data() {
         let mut obj = fake_obj();
@@ -1922,23 +2816,126 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_section_uprobe() {
+    fn test_parse_section_kprobe_multi() {
         let mut obj = fake_obj();
         fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+        fake_sym(&mut obj, 1, 0, "bar", FAKE_INS_LEN);
 
         assert_matches!(
             obj.parse_section(fake_section(
                 EbpfSectionKind::Program,
-                "uprobe/foo",
+                "kprobe.multi/tcp_*",
                 bytes_of(&fake_ins()),
                 None
             )),
             Ok(())
         );
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "kretprobe.multi",
+                bytes_of(&fake_ins()),
+                Some(1),
+            )),
+            Ok(())
+        );
         assert_matches!(
             obj.programs.get("foo"),
             Some(Program {
-                section: ProgramSection::UProbe { .. },
+                section: ProgramSection::KProbeMulti,
+                ..
+            })
+        );
+        assert_matches!(
+            obj.programs.get("bar"),
+            Some(Program {
+                section: ProgramSection::KRetProbeMulti,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_section_uprobe() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "uprobe/foo",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::UProbe { .. },
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_section_uprobe_multi() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+        fake_sym(&mut obj, 1, 0, "bar", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "uprobe.multi/libc.so.6:malloc*",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "uretprobe.multi.s",
+                bytes_of(&fake_ins()),
+                Some(1),
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::UProbeMulti { sleepable: false },
+                ..
+            })
+        );
+        assert_matches!(
+            obj.programs.get("bar"),
+            Some(Program {
+                section: ProgramSection::URetProbeMulti { sleepable: true },
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_section_usdt() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "usdt/libfoo.so:provider:name",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::Usdt { sleepable: false },
                 ..
             })
         );
@@ -2170,6 +3167,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_section_raw_tp_writable() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+        fake_sym(&mut obj, 1, 0, "bar", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "raw_tp.w/foo",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::RawTracePointWritable,
+                ..
+            })
+        );
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "raw_tracepoint.w/bar",
+                bytes_of(&fake_ins()),
+                Some(1)
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("bar"),
+            Some(Program {
+                section: ProgramSection::RawTracePointWritable,
+                ..
+            })
+        );
+    }
+
     #[test]
     fn test_parse_section_lsm() {
         let mut obj = fake_obj();
@@ -2196,6 +3234,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_section_lsm_cgroup() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "lsm_cgroup/foo",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::LsmCgroup,
+                ..
+            })
+        );
+    }
+
     #[test]
     fn test_parse_section_lsm_sleepable() {
         let mut obj = fake_obj();
@@ -2389,6 +3450,144 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_section_fmod_ret() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "fmod_ret/foo",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::FModRet { sleepable: false },
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_section_fmod_ret_sleepable() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "fmod_ret.s/foo",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::FModRet { sleepable: true },
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_section_sk_reuseport() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "sk_reuseport",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::SkReuseport { migrate: false },
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_section_sk_reuseport_migrate() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "sk_reuseport/migrate",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::SkReuseport { migrate: true },
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_section_syscall() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "syscall",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::Syscall,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_section_netfilter() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "netfilter",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::Netfilter,
+                ..
+            })
+        );
+    }
+
     #[test]
     fn test_parse_section_cgroup_skb_ingress_unnamed() {
         let mut obj = fake_obj();
@@ -2591,6 +3790,8 @@ mod tests {
         obj.maps.insert(
             ".rodata".to_owned(),
             Map::Legacy(LegacyMap {
+                inner_def: None,
+                map_extra: 0,
                 def: bpf_map_def {
                     map_type: BPF_MAP_TYPE_ARRAY as u32,
                     key_size: mem::size_of::<u32>() as u32,
@@ -2630,6 +3831,138 @@ mod tests {
         assert_eq!(test_data, map.data());
     }
 
+    #[test]
+    fn test_global_vars_btf() {
+        let mut obj = fake_obj();
+        obj.parse_section(fake_section(
+            EbpfSectionKind::Bss,
+            ".bss",
+            &[0; 16],
+            Some(1),
+        ))
+        .unwrap();
+        obj.symbol_offset_by_name.insert("counter".to_owned(), 8);
+
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("int");
+        let int_type_id = btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let name_offset = btf.add_string("flag");
+        let flag_type_id = btf.add_type(BtfType::Var(Var::new(
+            name_offset,
+            int_type_id,
+            VarLinkage::Static,
+        )));
+        let name_offset = btf.add_string("counter");
+        let counter_type_id = btf.add_type(BtfType::Var(Var::new(
+            name_offset,
+            int_type_id,
+            VarLinkage::Global,
+        )));
+        let name_offset = btf.add_string(".bss");
+        btf.add_type(BtfType::DataSec(DataSec::new(
+            name_offset,
+            vec![
+                DataSecEntry {
+                    btf_type: flag_type_id,
+                    offset: 4,
+                    size: 4,
+                },
+                DataSecEntry {
+                    btf_type: counter_type_id,
+                    offset: 0,
+                    size: 4,
+                },
+            ],
+            0,
+        )));
+        obj.btf = Some(btf);
+
+        let vars = obj.global_vars().unwrap();
+        assert_eq!(vars.len(), 2);
+        assert_eq!(
+            vars.get("flag"),
+            Some(&GlobalVar {
+                map: ".bss".to_owned(),
+                offset: 4,
+                size: 4,
+            })
+        );
+        // The offset of non-static variables is fixed up from the symbol table.
+        assert_eq!(
+            vars.get("counter"),
+            Some(&GlobalVar {
+                map: ".bss".to_owned(),
+                offset: 8,
+                size: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_global_vars_symbols() {
+        let mut obj = fake_obj();
+        obj.parse_section(fake_section(
+            EbpfSectionKind::Data,
+            ".data",
+            &[0; 16],
+            Some(1),
+        ))
+        .unwrap();
+        for (index, section_index, name, address, size) in
+            [(1, 1, "counter", 8, 8), (2, 2, "not_global", 0, 4)]
+        {
+            obj.symbol_table.insert(
+                index,
+                Symbol {
+                    index,
+                    section_index: Some(section_index),
+                    name: Some(name.to_owned()),
+                    address,
+                    size,
+                    is_definition: true,
+                    kind: SymbolKind::Data,
+                },
+            );
+        }
+
+        assert_eq!(
+            obj.global_vars().unwrap(),
+            HashMap::from([(
+                "counter".to_owned(),
+                GlobalVar {
+                    map: ".data".to_owned(),
+                    offset: 8,
+                    size: 8,
+                }
+            )])
+        );
+    }
+
+    #[test]
+    fn test_get_map_field_long() {
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("__unique_value0");
+        let enum_type_id = btf.add_type(BtfType::Enum(Enum::new(
+            0,
+            false,
+            vec![BtfEnum::new(name_offset, 4096)],
+        )));
+        let name_offset = btf.add_string("__unique_value1");
+        let enum64_type_id = btf.add_type(BtfType::Enum64(Enum64::new(
+            0,
+            false,
+            vec![BtfEnum64::new(name_offset, 1 << 44)],
+        )));
+
+        assert_eq!(get_map_field_long(&btf, enum_type_id).unwrap(), 4096);
+        assert_eq!(get_map_field_long(&btf, enum64_type_id).unwrap(), 1 << 44);
+    }
+
     #[test]
     fn test_parse_btf_map_section() {
         let mut obj = fake_obj();
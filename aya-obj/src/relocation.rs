@@ -8,9 +8,10 @@ use object::{SectionIndex, SymbolKind};
 
 use crate::{
     EbpfSectionKind,
+    externs::{Extern, ExternKind, KfuncValue, KsymValue},
     generated::{
-        BPF_CALL, BPF_JMP, BPF_K, BPF_PSEUDO_CALL, BPF_PSEUDO_FUNC, BPF_PSEUDO_MAP_FD,
-        BPF_PSEUDO_MAP_VALUE, bpf_insn,
+        BPF_CALL, BPF_JMP, BPF_K, BPF_PSEUDO_BTF_ID, BPF_PSEUDO_CALL, BPF_PSEUDO_FUNC,
+        BPF_PSEUDO_KFUNC_CALL, BPF_PSEUDO_MAP_FD, BPF_PSEUDO_MAP_VALUE, bpf_insn,
     },
     maps::Map,
     obj::{Function, Object},
@@ -18,12 +19,15 @@ use crate::{
 };
 
 #[cfg(feature = "std")]
-type RawFd = std::os::fd::RawFd;
+pub(crate) type RawFd = std::os::fd::RawFd;
 #[cfg(not(feature = "std"))]
-type RawFd = core::ffi::c_int;
+pub(crate) type RawFd = core::ffi::c_int;
 
 pub(crate) const INS_SIZE: usize = mem::size_of::<bpf_insn>();
 
+// The helper id calls to missing weak kernel functions are replaced with, same as libbpf.
+const POISON_CALL_KFUNC: i32 = 2002000000;
+
 /// The error type returned by [`Object::relocate_maps`] and [`Object::relocate_calls`]
 #[derive(thiserror::Error, Debug)]
 #[error("error relocating `{function}`")]
@@ -66,6 +70,15 @@ pub enum RelocationError {
         caller_name: String,
     },
 
+    /// Unsupported relocation against a function
+    #[error("unsupported {size}-bit function relocation while relocating `{caller_name}`")]
+    UnsupportedTextRelocation {
+        /// The relocation size in bits
+        size: u8,
+        /// The caller name
+        caller_name: String,
+    },
+
     /// Unknown function
     #[error(
         "program at section {section_index} and address {address:#x} was not found while relocating"
@@ -85,6 +98,27 @@ pub enum RelocationError {
         /// The relocation number
         relocation_number: usize,
     },
+
+    /// The map backing an extern variable was not created
+    #[error("map for extern `{name}` not found")]
+    ExternMapNotFound {
+        /// The extern name
+        name: String,
+    },
+
+    /// A kernel function was called before being resolved
+    #[error("kernel function `{name}` was not resolved")]
+    UnresolvedKfunc {
+        /// The extern name
+        name: String,
+    },
+
+    /// A kernel symbol extern was relocated before being resolved
+    #[error("kernel symbol `{name}` was not resolved")]
+    UnresolvedKsym {
+        /// The extern name
+        name: String,
+    },
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -116,7 +150,14 @@ impl Object {
     ) -> Result<(), EbpfRelocationError> {
         let mut maps_by_section = HashMap::new();
         let mut maps_by_symbol = HashMap::new();
+        let mut kconfig_fd = None;
         for (name, fd, map) in maps {
+            if map.section_kind() == EbpfSectionKind::Kconfig {
+                // the .kconfig map isn't backed by an ELF section, it's
+                // referenced through the extern symbols instead
+                kconfig_fd = Some(fd);
+                continue;
+            }
             maps_by_section.insert(map.section_index(), (name, fd, map));
             if let Some(index) = map.symbol_index() {
                 maps_by_symbol.insert(index, (name, fd, map));
@@ -131,6 +172,8 @@ impl Object {
                     &maps_by_section,
                     &maps_by_symbol,
                     &self.symbol_table,
+                    &self.externs,
+                    kconfig_fd,
                     text_sections,
                 )
                 .map_err(|error| EbpfRelocationError {
@@ -181,12 +224,15 @@ impl Object {
     }
 }
 
+#[expect(clippy::too_many_arguments)]
 fn relocate_maps<'a, I: Iterator<Item = &'a Relocation>>(
     fun: &mut Function,
     relocations: I,
     maps_by_section: &HashMap<usize, (&str, RawFd, &Map)>,
     maps_by_symbol: &HashMap<usize, (&str, RawFd, &Map)>,
     symbol_table: &HashMap<usize, Symbol>,
+    externs: &HashMap<usize, Extern>,
+    kconfig_fd: Option<RawFd>,
     text_sections: &HashSet<usize>,
 ) -> Result<(), RelocationError> {
     let section_offset = fun.section_offset;
@@ -218,7 +264,81 @@ fn relocate_maps<'a, I: Iterator<Item = &'a Relocation>>(
             })?;
 
         let Some(section_index) = sym.section_index else {
-            // this is not a map relocation
+            // extern variables are stored in maps created by the loader
+            if let Some(Extern { name, kind, .. }) = externs.get(&rel.symbol_index) {
+                match kind {
+                    ExternKind::Kconfig { offset, .. } => {
+                        let fd = kconfig_fd.ok_or_else(|| RelocationError::ExternMapNotFound {
+                            name: name.clone(),
+                        })?;
+                        debug!("relocating kconfig extern {name} at insn {ins_index}");
+                        instructions[ins_index].set_src_reg(BPF_PSEUDO_MAP_VALUE as u8);
+                        instructions[ins_index].imm = fd;
+                        instructions[ins_index + 1].imm = *offset as i32;
+                    }
+                    ExternKind::Ksym { value, .. } => {
+                        let value = value.ok_or_else(|| RelocationError::UnresolvedKsym {
+                            name: name.clone(),
+                        })?;
+                        debug!("relocating ksym extern {name} at insn {ins_index}");
+                        match value {
+                            KsymValue::BtfId(id) => {
+                                instructions[ins_index].set_src_reg(BPF_PSEUDO_BTF_ID as u8);
+                                instructions[ins_index].imm = id as i32;
+                                // vmlinux BTF
+                                instructions[ins_index + 1].imm = 0;
+                            }
+                            KsymValue::Address(address) => {
+                                instructions[ins_index].set_src_reg(0);
+                                instructions[ins_index].imm = address as i32;
+                                instructions[ins_index + 1].imm = (address >> 32) as i32;
+                            }
+                        }
+                    }
+                    ExternKind::Kfunc { value } => {
+                        let value = value.ok_or_else(|| RelocationError::UnresolvedKfunc {
+                            name: name.clone(),
+                        })?;
+                        if insn_is_call(&instructions[ins_index]) {
+                            debug!("relocating kfunc call {name} at insn {ins_index}");
+                            let ins = &mut instructions[ins_index];
+                            match value {
+                                KfuncValue::BtfId {
+                                    id, btf_fd_index, ..
+                                } => {
+                                    ins.set_src_reg(BPF_PSEUDO_KFUNC_CALL as u8);
+                                    ins.imm = id as i32;
+                                    ins.off = btf_fd_index;
+                                }
+                                KfuncValue::Missing => {
+                                    // Turn the call into a call to an invalid helper,
+                                    // which the verifier only rejects if it's reachable.
+                                    ins.set_src_reg(0);
+                                    ins.imm = POISON_CALL_KFUNC;
+                                    ins.off = 0;
+                                }
+                            }
+                        } else {
+                            // the address of the function is taken, e.g. by
+                            // bpf_ksym_exists()
+                            debug!("relocating kfunc reference {name} at insn {ins_index}");
+                            match value {
+                                KfuncValue::BtfId { id, btf_fd, .. } => {
+                                    instructions[ins_index].set_src_reg(BPF_PSEUDO_BTF_ID as u8);
+                                    instructions[ins_index].imm = id as i32;
+                                    instructions[ins_index + 1].imm = btf_fd;
+                                }
+                                KfuncValue::Missing => {
+                                    instructions[ins_index].set_src_reg(0);
+                                    instructions[ins_index].imm = 0;
+                                    instructions[ins_index + 1].imm = 0;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            // otherwise this is not a map relocation
             continue;
         };
 
@@ -369,13 +489,14 @@ impl<'a> FunctionLinker<'a> {
                         .map(|sym| (rel, sym))
                 })
                 .filter(|(_rel, sym)| {
-                    // only consider text relocations, data relocations are
-                    // relocated in relocate_maps()
-                    sym.kind == SymbolKind::Text
-                        || sym
-                            .section_index
-                            .map(|section_index| self.text_sections.contains(&section_index))
-                            .unwrap_or(false)
+                    // only consider text relocations, data relocations and calls
+                    // to kernel functions are relocated in relocate_maps()
+                    sym.section_index.is_some()
+                        && (sym.kind == SymbolKind::Text
+                            || sym
+                                .section_index
+                                .map(|section_index| self.text_sections.contains(&section_index))
+                                .unwrap_or(false))
                 });
 
             // not a call and not a text relocation, we don't need to do anything
@@ -385,14 +506,22 @@ impl<'a> FunctionLinker<'a> {
 
             let (callee_section_index, callee_address) = if let Some((rel, sym)) = rel {
                 let address = match sym.kind {
-                    SymbolKind::Text => sym.address,
+                    SymbolKind::Text if is_call => sym.address,
+                    // ld_imm64 loading the address of a function, eg a callback
+                    // passed to bpf_loop()
+                    SymbolKind::Text => sym.address + ins.imm as u64,
                     // R_BPF_64_32 this is a call
                     SymbolKind::Section if rel.size == 32 => {
                         sym.address + (ins.imm + 1) as u64 * INS_SIZE as u64
                     }
                     // R_BPF_64_64 this is a ld_imm64 text relocation
                     SymbolKind::Section if rel.size == 64 => sym.address + ins.imm as u64,
-                    _ => todo!(), // FIXME: return an error here,
+                    _ => {
+                        return Err(RelocationError::UnsupportedTextRelocation {
+                            size: rel.size,
+                            caller_name: fun.name.clone(),
+                        });
+                    }
                 };
                 (sym.section_index.unwrap(), address)
             } else {
@@ -505,7 +634,10 @@ mod test {
     use alloc::{string::ToString as _, vec, vec::Vec};
 
     use super::*;
-    use crate::maps::{BtfMap, LegacyMap};
+    use crate::{
+        externs::KconfigType,
+        maps::{BtfMap, LegacyMap},
+    };
 
     fn fake_sym(index: usize, section_index: usize, address: u64, name: &str, size: u64) -> Symbol {
         Symbol {
@@ -525,6 +657,8 @@ mod test {
 
     fn fake_legacy_map(symbol_index: usize) -> Map {
         Map::Legacy(LegacyMap {
+            inner_def: None,
+            map_extra: 0,
             def: Default::default(),
             section_index: 0,
             section_kind: EbpfSectionKind::Undefined,
@@ -535,6 +669,8 @@ mod test {
 
     fn fake_btf_map(symbol_index: usize) -> Map {
         Map::Btf(BtfMap {
+            inner_def: None,
+            initial_slots: Vec::new(),
             def: Default::default(),
             section_index: 0,
             symbol_index,
@@ -584,6 +720,8 @@ mod test {
             &maps_by_section,
             &maps_by_symbol,
             &symbol_table,
+            &HashMap::new(),
+            None,
             &HashSet::new(),
         )
         .unwrap();
@@ -640,6 +778,8 @@ mod test {
             &maps_by_section,
             &maps_by_symbol,
             &symbol_table,
+            &HashMap::new(),
+            None,
             &HashSet::new(),
         )
         .unwrap();
@@ -679,6 +819,8 @@ mod test {
             &maps_by_section,
             &maps_by_symbol,
             &symbol_table,
+            &HashMap::new(),
+            None,
             &HashSet::new(),
         )
         .unwrap();
@@ -735,6 +877,8 @@ mod test {
             &maps_by_section,
             &maps_by_symbol,
             &symbol_table,
+            &HashMap::new(),
+            None,
             &HashSet::new(),
         )
         .unwrap();
@@ -745,4 +889,369 @@ mod test {
         assert_eq!(fun.instructions[1].src_reg(), BPF_PSEUDO_MAP_FD as u8);
         assert_eq!(fun.instructions[1].imm, 2);
     }
+
+    #[test]
+    fn test_kconfig_extern_relocation() {
+        let mut fun = fake_func(
+            "test",
+            vec![
+                ins(&[0x18, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                ins(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            ],
+        );
+
+        let symbol_table = HashMap::from([(
+            1,
+            Symbol {
+                section_index: None,
+                ..fake_sym(1, 0, 0, "CONFIG_HZ", 0)
+            },
+        )]);
+        let externs = HashMap::from([(
+            1,
+            Extern {
+                name: "CONFIG_HZ".to_string(),
+                is_weak: false,
+                kind: ExternKind::Kconfig {
+                    ty: KconfigType::Int {
+                        size: 4,
+                        signed: false,
+                    },
+                    offset: 8,
+                },
+            },
+        )]);
+
+        let relocations = [Relocation {
+            offset: 0x0,
+            symbol_index: 1,
+            size: 64,
+        }];
+
+        relocate_maps(
+            &mut fun,
+            relocations.iter(),
+            &HashMap::new(),
+            &HashMap::new(),
+            &symbol_table,
+            &externs,
+            Some(42),
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        assert_eq!(fun.instructions[0].src_reg(), BPF_PSEUDO_MAP_VALUE as u8);
+        assert_eq!(fun.instructions[0].imm, 42);
+        assert_eq!(fun.instructions[1].imm, 8);
+
+        assert_matches::assert_matches!(
+            relocate_maps(
+                &mut fun,
+                relocations.iter(),
+                &HashMap::new(),
+                &HashMap::new(),
+                &symbol_table,
+                &externs,
+                None,
+                &HashSet::new(),
+            ),
+            Err(RelocationError::ExternMapNotFound { name }) if name == "CONFIG_HZ"
+        );
+    }
+
+    #[test]
+    fn test_ksym_extern_relocation() {
+        let mut fun = fake_func(
+            "test",
+            vec![
+                ins(&[0x18, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                ins(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                ins(&[0x18, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                ins(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            ],
+        );
+
+        let symbol_table = HashMap::from([
+            (
+                1,
+                Symbol {
+                    section_index: None,
+                    ..fake_sym(1, 0, 0, "runqueues", 0)
+                },
+            ),
+            (
+                2,
+                Symbol {
+                    section_index: None,
+                    ..fake_sym(2, 0, 0, "bpf_prog_active", 0)
+                },
+            ),
+        ]);
+        let mut externs = HashMap::from([
+            (
+                1,
+                Extern {
+                    name: "runqueues".to_string(),
+                    is_weak: false,
+                    kind: ExternKind::Ksym {
+                        typed: true,
+                        value: Some(KsymValue::BtfId(1234)),
+                    },
+                },
+            ),
+            (
+                2,
+                Extern {
+                    name: "bpf_prog_active".to_string(),
+                    is_weak: false,
+                    kind: ExternKind::Ksym {
+                        typed: false,
+                        value: Some(KsymValue::Address(0xffff_ffff_8123_4567)),
+                    },
+                },
+            ),
+        ]);
+
+        let relocations = [
+            Relocation {
+                offset: 0x0,
+                symbol_index: 1,
+                size: 64,
+            },
+            Relocation {
+                offset: mem::size_of::<bpf_insn>() as u64 * 2,
+                symbol_index: 2,
+                size: 64,
+            },
+        ];
+
+        relocate_maps(
+            &mut fun,
+            relocations.iter(),
+            &HashMap::new(),
+            &HashMap::new(),
+            &symbol_table,
+            &externs,
+            None,
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        assert_eq!(fun.instructions[0].src_reg(), BPF_PSEUDO_BTF_ID as u8);
+        assert_eq!(fun.instructions[0].imm, 1234);
+        assert_eq!(fun.instructions[1].imm, 0);
+        assert_eq!(fun.instructions[2].src_reg(), 0);
+        assert_eq!(fun.instructions[2].imm, 0x8123_4567_u32 as i32);
+        assert_eq!(fun.instructions[3].imm, -1);
+
+        externs.get_mut(&1).unwrap().kind = ExternKind::Ksym {
+            typed: true,
+            value: None,
+        };
+        assert_matches::assert_matches!(
+            relocate_maps(
+                &mut fun,
+                relocations.iter(),
+                &HashMap::new(),
+                &HashMap::new(),
+                &symbol_table,
+                &externs,
+                None,
+                &HashSet::new(),
+            ),
+            Err(RelocationError::UnresolvedKsym { name }) if name == "runqueues"
+        );
+    }
+
+    #[test]
+    fn test_kfunc_extern_relocation() {
+        let mut fun = fake_func(
+            "test",
+            vec![
+                // call -1
+                ins(&[0x85, 0x10, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff]),
+                // call -1
+                ins(&[0x85, 0x10, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff]),
+                // r1 = 0 ll
+                ins(&[0x18, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                ins(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            ],
+        );
+
+        let symbol_table = HashMap::from([
+            (
+                1,
+                Symbol {
+                    section_index: None,
+                    ..fake_sym(1, 0, 0, "bpf_task_acquire", 0)
+                },
+            ),
+            (
+                2,
+                Symbol {
+                    section_index: None,
+                    ..fake_sym(2, 0, 0, "aya_missing_kfunc", 0)
+                },
+            ),
+        ]);
+        let externs = HashMap::from([
+            (
+                1,
+                Extern {
+                    name: "bpf_task_acquire".to_string(),
+                    is_weak: false,
+                    kind: ExternKind::Kfunc {
+                        value: Some(KfuncValue::BtfId {
+                            id: 1234,
+                            btf_fd_index: 1,
+                            btf_fd: 42,
+                        }),
+                    },
+                },
+            ),
+            (
+                2,
+                Extern {
+                    name: "aya_missing_kfunc".to_string(),
+                    is_weak: true,
+                    kind: ExternKind::Kfunc {
+                        value: Some(KfuncValue::Missing),
+                    },
+                },
+            ),
+        ]);
+
+        let relocations = [
+            Relocation {
+                offset: 0x0,
+                symbol_index: 1,
+                size: 32,
+            },
+            Relocation {
+                offset: mem::size_of::<bpf_insn>() as u64,
+                symbol_index: 2,
+                size: 32,
+            },
+            Relocation {
+                offset: mem::size_of::<bpf_insn>() as u64 * 2,
+                symbol_index: 1,
+                size: 64,
+            },
+        ];
+
+        relocate_maps(
+            &mut fun,
+            relocations.iter(),
+            &HashMap::new(),
+            &HashMap::new(),
+            &symbol_table,
+            &externs,
+            None,
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        assert_eq!(fun.instructions[0].src_reg(), BPF_PSEUDO_KFUNC_CALL as u8);
+        assert_eq!(fun.instructions[0].imm, 1234);
+        assert_eq!(fun.instructions[0].off, 1);
+        assert_eq!(fun.instructions[1].src_reg(), 0);
+        assert_eq!(fun.instructions[1].imm, POISON_CALL_KFUNC);
+        assert_eq!(fun.instructions[2].src_reg(), BPF_PSEUDO_BTF_ID as u8);
+        assert_eq!(fun.instructions[2].imm, 1234);
+        assert_eq!(fun.instructions[3].imm, 42);
+    }
+
+    fn fake_text_func(name: &str, address: u64, instructions: Vec<bpf_insn>) -> Function {
+        Function {
+            address,
+            section_index: SectionIndex(1),
+            section_offset: address as usize,
+            ..fake_func(name, instructions)
+        }
+    }
+
+    fn link_callback(sym: Symbol, imm: i32, size: u8) -> Result<Function, RelocationError> {
+        let mut prog = fake_func(
+            "prog",
+            vec![
+                // r2 = imm ll
+                ins(&[0x18, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                ins(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                // call bpf_loop
+                ins(&[0x85, 0x00, 0x00, 0x00, 0xb5, 0x00, 0x00, 0x00]),
+                // exit
+                ins(&[0x95, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            ],
+        );
+        prog.instructions[0].imm = imm;
+
+        let fun = || {
+            vec![
+                // r0 = 0
+                ins(&[0xb7, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+                // exit
+                ins(&[0x95, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            ]
+        };
+        let functions = BTreeMap::from([
+            ((0, 0), prog.clone()),
+            ((1, 0), fake_text_func("other", 0, fun())),
+            ((1, 16), fake_text_func("callback", 16, fun())),
+        ]);
+        let relocations = HashMap::from([(
+            SectionIndex(0),
+            HashMap::from([(
+                0,
+                Relocation {
+                    offset: 0,
+                    symbol_index: sym.index,
+                    size,
+                },
+            )]),
+        )]);
+        let symbol_table = HashMap::from([(sym.index, sym)]);
+        let text_sections = HashSet::from([1]);
+
+        FunctionLinker::new(&functions, &relocations, &symbol_table, &text_sections).link(&prog)
+    }
+
+    #[test]
+    fn test_pseudo_func_section_relocation() {
+        let sym = Symbol {
+            kind: SymbolKind::Section,
+            ..fake_sym(1, 1, 0, ".text", 0)
+        };
+        let prog = link_callback(sym, 16, 64).unwrap();
+
+        // only the callback is linked, right after the program
+        assert_eq!(prog.instructions.len(), 6);
+        assert_eq!(prog.instructions[0].src_reg(), BPF_PSEUDO_FUNC as u8);
+        assert_eq!(prog.instructions[0].imm, 3);
+        assert_eq!(prog.instructions[4].code, 0xb7);
+    }
+
+    #[test]
+    fn test_pseudo_func_symbol_relocation() {
+        let sym = Symbol {
+            kind: SymbolKind::Text,
+            ..fake_sym(1, 1, 16, "callback", 16)
+        };
+        let prog = link_callback(sym, 0, 64).unwrap();
+
+        assert_eq!(prog.instructions.len(), 6);
+        assert_eq!(prog.instructions[0].src_reg(), BPF_PSEUDO_FUNC as u8);
+        assert_eq!(prog.instructions[0].imm, 3);
+    }
+
+    #[test]
+    fn test_unsupported_text_relocation() {
+        let sym = Symbol {
+            kind: SymbolKind::Section,
+            ..fake_sym(1, 1, 0, ".text", 0)
+        };
+        assert_matches::assert_matches!(
+            link_callback(sym, 16, 16),
+            Err(RelocationError::UnsupportedTextRelocation { size: 16, caller_name }) if caller_name == "prog"
+        );
+    }
 }
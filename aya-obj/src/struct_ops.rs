@@ -0,0 +1,463 @@
+//! Struct ops handling.
+//!
+//! `struct_ops` maps let eBPF programs implement the callbacks of kernel
+//! operations structures such as `tcp_congestion_ops`. The maps are declared
+//! as variables of the kernel struct type in the `.struct_ops` and
+//! `.struct_ops.link` sections. Since the layout of the struct in the object
+//! file doesn't necessarily match the one of the running kernel, the map
+//! values are translated using the kernel BTF before the maps are created.
+
+use alloc::{
+    borrow::ToOwned as _,
+    format,
+    string::{String, ToString as _},
+    vec,
+    vec::Vec,
+};
+
+use object::{Endianness, SymbolKind};
+
+use crate::{
+    Object, ParseError,
+    btf::{Btf, BtfKind, BtfType},
+    maps::{BtfMapDef, Map},
+};
+
+/// The name of the section containing struct_ops maps.
+pub const STRUCT_OPS_SECTION: &str = ".struct_ops";
+
+/// The name of the section containing struct_ops maps that are registered
+/// through a link.
+pub const STRUCT_OPS_LINK_SECTION: &str = ".struct_ops.link";
+
+// The kernel wraps each struct_ops type `T` in a `struct bpf_struct_ops_T`,
+// which is the value type of the map.
+const STRUCT_OPS_VALUE_PREFIX: &str = "bpf_struct_ops_";
+
+/// A program implementing a member of a struct_ops map.
+#[derive(Debug, Clone)]
+pub struct StructOpsProgram {
+    /// The name of the program.
+    pub name: String,
+    /// The index of the member of the kernel struct implemented by the
+    /// program.
+    pub member_index: u32,
+    /// The offset of the program file descriptor in the map value.
+    pub offset: usize,
+}
+
+/// A struct_ops map, from a `.struct_ops` or `.struct_ops.link` section.
+#[derive(Debug, Clone)]
+pub struct StructOpsMap {
+    /// The definition of the map
+    pub def: BtfMapDef,
+    pub(crate) type_name: String,
+    pub(crate) section_index: usize,
+    pub(crate) symbol_index: usize,
+    // BTF type id of the struct in the object BTF
+    pub(crate) btf_type_id: u32,
+    // the value of the struct as laid out in the object file
+    pub(crate) local_data: Vec<u8>,
+    // offsets of the function pointers in local_data, and the indices of the
+    // symbols they refer to
+    pub(crate) relocations: Vec<(usize, usize)>,
+    // the following fields are set by Object::fixup_struct_ops
+    pub(crate) data: Vec<u8>,
+    pub(crate) btf_vmlinux_type_id: u32,
+    pub(crate) btf_vmlinux_value_type_id: u32,
+    pub(crate) programs: Vec<StructOpsProgram>,
+}
+
+impl StructOpsMap {
+    /// Returns the name of the kernel struct implemented by the map.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Returns the BTF type id of the kernel struct in the kernel BTF.
+    pub fn btf_vmlinux_type_id(&self) -> u32 {
+        self.btf_vmlinux_type_id
+    }
+
+    /// Returns the BTF type id of the map value in the kernel BTF.
+    pub fn btf_vmlinux_value_type_id(&self) -> u32 {
+        self.btf_vmlinux_value_type_id
+    }
+
+    /// Returns the programs implementing the members of the struct.
+    pub fn programs(&self) -> &[StructOpsProgram] {
+        &self.programs
+    }
+}
+
+impl Object {
+    /// Lays out the struct_ops maps according to the kernel BTF.
+    ///
+    /// Members are matched by name. Function pointers are recorded as
+    /// [`StructOpsProgram`]s, whose file descriptors are written in the map
+    /// value when the map is registered, and other members are copied to the
+    /// offsets used by the kernel.
+    pub fn fixup_struct_ops(&mut self, target_btf: &Btf) -> Result<(), ParseError> {
+        let Self {
+            btf,
+            maps,
+            functions,
+            symbol_table,
+            endianness,
+            ..
+        } = self;
+        let Some(btf) = btf else {
+            return Ok(());
+        };
+
+        for (map_name, map) in maps.iter_mut() {
+            let Map::StructOps(map) = map else {
+                continue;
+            };
+
+            let type_not_found = || ParseError::StructOpsTypeNotFound {
+                name: map.type_name.clone(),
+            };
+            let kernel_type_id = target_btf
+                .id_by_type_name_kind(&map.type_name, BtfKind::Struct)
+                .map_err(|_| type_not_found())?;
+            let BtfType::Struct(kernel_type) = target_btf.type_by_id(kernel_type_id)? else {
+                return Err(type_not_found());
+            };
+            let value_type_id = target_btf
+                .id_by_type_name_kind(
+                    &format!("{STRUCT_OPS_VALUE_PREFIX}{}", map.type_name),
+                    BtfKind::Struct,
+                )
+                .map_err(|_| type_not_found())?;
+            let BtfType::Struct(value_type) = target_btf.type_by_id(value_type_id)? else {
+                return Err(type_not_found());
+            };
+            let data_offset = value_type
+                .members
+                .iter()
+                .find(|m| m.btf_type == kernel_type_id)
+                .map(|m| value_type.member_bit_offset(m) / 8)
+                .ok_or_else(type_not_found)?;
+            let BtfType::Struct(local_type) = btf.type_by_id(map.btf_type_id)? else {
+                return Err(ParseError::InvalidStructOpsMap {
+                    name: map_name.clone(),
+                });
+            };
+
+            let mut data = vec![0u8; value_type.size as usize];
+            let mut programs = Vec::new();
+            for member in &local_type.members {
+                let member_name = btf.string_at(member.name_offset)?;
+                let invalid_member = || ParseError::InvalidStructOpsMember {
+                    map: map_name.clone(),
+                    member: member_name.to_string(),
+                };
+                let local_offset = local_type.member_bit_offset(member) / 8;
+                let local_size = btf.type_size(member.btf_type)?;
+                let local_value = map
+                    .local_data
+                    .get(local_offset..local_offset + local_size)
+                    .ok_or_else(invalid_member)?;
+
+                let Some((member_index, kernel_member)) =
+                    kernel_type.members.iter().enumerate().find(|(_, m)| {
+                        target_btf
+                            .string_at(m.name_offset)
+                            .is_ok_and(|name| name == member_name)
+                    })
+                else {
+                    // members unknown to the kernel are only allowed if unset
+                    if local_value.iter().any(|b| *b != 0)
+                        || map.relocations.iter().any(|(o, _)| *o == local_offset)
+                    {
+                        return Err(ParseError::StructOpsMemberNotFound {
+                            map: map_name.clone(),
+                            member: member_name.to_string(),
+                        });
+                    }
+                    continue;
+                };
+                if local_type.member_bit_field_size(member) != 0
+                    || kernel_type.member_bit_field_size(kernel_member) != 0
+                {
+                    return Err(invalid_member());
+                }
+                let kernel_offset = data_offset + kernel_type.member_bit_offset(kernel_member) / 8;
+
+                let local_ty = btf.type_by_id(btf.resolve_type(member.btf_type)?)?;
+                let kernel_ty =
+                    target_btf.type_by_id(target_btf.resolve_type(kernel_member.btf_type)?)?;
+                if local_ty.kind() != kernel_ty.kind() {
+                    return Err(invalid_member());
+                }
+
+                if let BtfType::Ptr(kernel_ptr) = kernel_ty {
+                    let Some(&(_, symbol_index)) = map
+                        .relocations
+                        .iter()
+                        .find(|(offset, _)| *offset == local_offset)
+                    else {
+                        // unset callback
+                        continue;
+                    };
+                    let pointee = target_btf.resolve_type(kernel_ptr.btf_type)?;
+                    if !matches!(target_btf.type_by_id(pointee)?, BtfType::FuncProto(_)) {
+                        return Err(invalid_member());
+                    }
+                    let symbol = symbol_table.get(&symbol_index).ok_or_else(invalid_member)?;
+                    let address = match symbol.kind {
+                        // relocations against the section symbol store the
+                        // offset of the function in the relocated value
+                        SymbolKind::Section => {
+                            let addend = local_value.try_into().map_err(|_| invalid_member())?;
+                            symbol.address
+                                + match endianness {
+                                    Endianness::Big => u64::from_be_bytes(addend),
+                                    Endianness::Little => u64::from_le_bytes(addend),
+                                }
+                        }
+                        _ => symbol.address,
+                    };
+                    let function = symbol
+                        .section_index
+                        .and_then(|section_index| functions.get(&(section_index, address)))
+                        .ok_or_else(invalid_member)?;
+                    programs.push(StructOpsProgram {
+                        name: function.name.to_owned(),
+                        member_index: member_index as u32,
+                        offset: kernel_offset,
+                    });
+                    continue;
+                }
+
+                if target_btf.type_size(kernel_member.btf_type)? != local_size {
+                    return Err(invalid_member());
+                }
+                data[kernel_offset..kernel_offset + local_size].copy_from_slice(local_value);
+            }
+
+            map.def.value_size = value_type.size;
+            map.data = data;
+            map.btf_vmlinux_type_id = kernel_type_id;
+            map.btf_vmlinux_value_type_id = value_type_id;
+            map.programs = programs;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use object::SymbolKind;
+
+    use super::*;
+    use crate::{
+        Function,
+        btf::{BtfMember, FuncProto, Int, IntEncoding, Ptr, Struct},
+        generated::{BPF_F_LINK, bpf_map_type::BPF_MAP_TYPE_STRUCT_OPS},
+        relocation::Symbol,
+    };
+
+    // Builds a struct with the given members, each described by its name,
+    // type and offset in bytes.
+    fn add_struct(btf: &mut Btf, name: &str, members: &[(&str, u32, u32)], size: u32) -> u32 {
+        let name_offset = btf.add_string(name);
+        let members = members
+            .iter()
+            .map(|(name, btf_type, offset)| BtfMember {
+                name_offset: btf.add_string(name),
+                btf_type: *btf_type,
+                offset: offset * 8,
+            })
+            .collect();
+        btf.add_type(BtfType::Struct(Struct::new(name_offset, members, size)))
+    }
+
+    #[test]
+    fn test_fixup_struct_ops() {
+        // the object declares `struct aya_ops { int (*init)(void); int flags; }`
+        let mut local_btf = Btf::new();
+        let name_offset = local_btf.add_string("int");
+        let int = local_btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let proto = local_btf.add_type(BtfType::FuncProto(FuncProto::new(vec![], int)));
+        let ptr = local_btf.add_type(BtfType::Ptr(Ptr::new(0, proto)));
+        let local_type = add_struct(
+            &mut local_btf,
+            "aya_ops",
+            &[("init", ptr, 0), ("flags", int, 8)],
+            16,
+        );
+
+        // the kernel has `struct aya_ops { int flags; void *data; int (*init)(void); }`
+        // wrapped in `struct bpf_struct_ops_aya_ops { int state; struct aya_ops data; }`
+        let mut kernel_btf = Btf::new();
+        let name_offset = kernel_btf.add_string("int");
+        let int = kernel_btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let proto = kernel_btf.add_type(BtfType::FuncProto(FuncProto::new(vec![], int)));
+        let func_ptr = kernel_btf.add_type(BtfType::Ptr(Ptr::new(0, proto)));
+        let void_ptr = kernel_btf.add_type(BtfType::Ptr(Ptr::new(0, 0)));
+        let kernel_type = add_struct(
+            &mut kernel_btf,
+            "aya_ops",
+            &[
+                ("flags", int, 0),
+                ("data", void_ptr, 8),
+                ("init", func_ptr, 16),
+            ],
+            24,
+        );
+        let value_type = add_struct(
+            &mut kernel_btf,
+            "bpf_struct_ops_aya_ops",
+            &[("state", int, 0), ("data", kernel_type, 8)],
+            32,
+        );
+
+        let mut obj = Object::new(Endianness::Little, Default::default(), None);
+        obj.btf = Some(local_btf);
+        obj.symbol_table.insert(
+            1,
+            Symbol {
+                index: 1,
+                section_index: Some(3),
+                name: Some("aya_init".to_owned()),
+                address: 0,
+                size: 16,
+                is_definition: true,
+                kind: SymbolKind::Text,
+            },
+        );
+        obj.functions.insert(
+            (3, 0),
+            Function {
+                address: 0,
+                name: "aya_init".to_owned(),
+                section_index: object::SectionIndex(3),
+                section_offset: 0,
+                instructions: vec![],
+                func_info: Default::default(),
+                line_info: Default::default(),
+                func_info_rec_size: 0,
+                line_info_rec_size: 0,
+            },
+        );
+        let mut local_data = vec![0u8; 16];
+        local_data[8..12].copy_from_slice(&42i32.to_le_bytes());
+        obj.maps.insert(
+            "ops".to_owned(),
+            Map::StructOps(StructOpsMap {
+                def: BtfMapDef {
+                    map_type: BPF_MAP_TYPE_STRUCT_OPS as u32,
+                    key_size: 4,
+                    value_size: 16,
+                    max_entries: 1,
+                    map_flags: BPF_F_LINK,
+                    ..Default::default()
+                },
+                type_name: "aya_ops".to_owned(),
+                section_index: 2,
+                symbol_index: 0,
+                btf_type_id: local_type,
+                local_data,
+                relocations: vec![(0, 1)],
+                data: Vec::new(),
+                btf_vmlinux_type_id: 0,
+                btf_vmlinux_value_type_id: 0,
+                programs: Vec::new(),
+            }),
+        );
+
+        obj.fixup_struct_ops(&kernel_btf).unwrap();
+
+        let Some(Map::StructOps(map)) = obj.maps.get("ops") else {
+            panic!("unexpected map")
+        };
+        assert_eq!(map.btf_vmlinux_type_id(), kernel_type);
+        assert_eq!(map.btf_vmlinux_value_type_id(), value_type);
+        assert_eq!(map.def.value_size, 32);
+        let mut expected = vec![0u8; 32];
+        expected[8..12].copy_from_slice(&42i32.to_le_bytes());
+        assert_eq!(map.data, expected);
+        assert_matches::assert_matches!(
+            map.programs(),
+            [StructOpsProgram {
+                name,
+                member_index: 2,
+                offset: 24,
+            }] if name == "aya_init"
+        );
+    }
+
+    #[test]
+    fn test_fixup_struct_ops_unknown_member() {
+        let mut local_btf = Btf::new();
+        let name_offset = local_btf.add_string("int");
+        let int = local_btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let local_type = add_struct(&mut local_btf, "aya_ops", &[("flags", int, 0)], 4);
+
+        let mut kernel_btf = Btf::new();
+        let name_offset = kernel_btf.add_string("int");
+        let int = kernel_btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let kernel_type = add_struct(&mut kernel_btf, "aya_ops", &[("mode", int, 0)], 4);
+        add_struct(
+            &mut kernel_btf,
+            "bpf_struct_ops_aya_ops",
+            &[("data", kernel_type, 0)],
+            4,
+        );
+
+        let mut obj = Object::new(Endianness::Little, Default::default(), None);
+        obj.btf = Some(local_btf);
+        obj.maps.insert(
+            "ops".to_owned(),
+            Map::StructOps(StructOpsMap {
+                def: BtfMapDef {
+                    map_type: BPF_MAP_TYPE_STRUCT_OPS as u32,
+                    key_size: 4,
+                    value_size: 4,
+                    max_entries: 1,
+                    ..Default::default()
+                },
+                type_name: "aya_ops".to_owned(),
+                section_index: 2,
+                symbol_index: 0,
+                btf_type_id: local_type,
+                local_data: 1u32.to_le_bytes().to_vec(),
+                relocations: Vec::new(),
+                data: Vec::new(),
+                btf_vmlinux_type_id: 0,
+                btf_vmlinux_value_type_id: 0,
+                programs: Vec::new(),
+            }),
+        );
+
+        assert_matches::assert_matches!(
+            obj.fixup_struct_ops(&kernel_btf),
+            Err(ParseError::StructOpsMemberNotFound { map, member }) if map == "ops" && member == "flags"
+        );
+    }
+}
@@ -1,42 +1,53 @@
 use std::{
     borrow::Cow,
     collections::{HashMap, HashSet},
-    fs, io,
+    fmt, fs, io,
+    marker::PhantomData,
+    mem,
+    ops::Deref,
     os::fd::{AsFd as _, AsRawFd as _},
     path::{Path, PathBuf},
     sync::{Arc, LazyLock},
 };
 
 use aya_obj::{
-    EbpfSectionKind, Features, Object, ParseError, ProgramSection,
-    btf::{Btf, BtfError, BtfFeatures, BtfRelocationError},
+    EbpfSectionKind, Features, GlobalVar, Object, ParseError, ProgramSection,
+    btf::{Btf, BtfError, BtfFeatures, BtfKind, BtfRelocationError},
     generated::{
-        BPF_F_SLEEPABLE, BPF_F_XDP_HAS_FRAGS,
+        BPF_CALL, BPF_F_MMAPABLE, BPF_F_NO_PREALLOC, BPF_F_SLEEPABLE, BPF_F_XDP_HAS_FRAGS, BPF_JMP,
+        BPF_PSEUDO_KFUNC_CALL,
+        bpf_attach_type::{BPF_TRACE_KPROBE_MULTI, BPF_TRACE_UPROBE_MULTI},
         bpf_map_type::{self, *},
     },
     relocation::EbpfRelocationError,
 };
+use libc::{MAP_SHARED, PROT_READ, PROT_WRITE};
 use log::{debug, warn};
 use thiserror::Error;
 
 use crate::{
-    maps::{Map, MapData, MapError},
+    maps::{MMap, Map, MapData, MapError},
     programs::{
         BtfTracePoint, CgroupDevice, CgroupSkb, CgroupSkbAttachType, CgroupSock, CgroupSockAddr,
-        CgroupSockopt, CgroupSysctl, Extension, FEntry, FExit, FlowDissector, Iter, KProbe,
-        LircMode2, Lsm, PerfEvent, ProbeKind, Program, ProgramData, ProgramError, RawTracePoint,
-        SchedClassifier, SkLookup, SkMsg, SkSkb, SkSkbKind, SockOps, SocketFilter, TracePoint,
-        UProbe, Xdp,
+        CgroupSockopt, CgroupSysctl, Extension, FEntry, FExit, FModRet, FlowDissector, Iter,
+        KProbe, LircMode2, Lsm, LsmCgroup, Netfilter, PerfEvent, ProbeKind, Program, ProgramData,
+        ProgramError, RawTracePoint, RawTracePointWritable, SchedClassifier, SkLookup, SkMsg,
+        SkReuseport, SkReuseportKind, SkSkb, SkSkbKind, SockOps, SocketFilter, StructOps, Syscall,
+        TracePoint, UProbe, Usdt, Xdp,
     },
     sys::{
-        bpf_load_btf, is_bpf_cookie_supported, is_bpf_global_data_supported,
-        is_btf_datasec_supported, is_btf_decl_tag_supported, is_btf_enum64_supported,
-        is_btf_float_supported, is_btf_func_global_supported, is_btf_func_supported,
-        is_btf_supported, is_btf_type_tag_supported, is_info_gpl_compatible_supported,
-        is_info_map_ids_supported, is_perf_link_supported, is_probe_read_kernel_supported,
-        is_prog_id_supported, is_prog_name_supported, retry_with_verifier_logs,
+        SyscallError, bpf_load_btf, bpf_map_update_elem, is_array_mmap_supported,
+        is_bpf_cookie_supported, is_bpf_global_data_supported, is_btf_datasec_supported,
+        is_btf_decl_tag_supported, is_btf_enum64_supported, is_btf_float_supported,
+        is_btf_func_global_supported, is_btf_func_supported, is_btf_supported,
+        is_btf_type_tag_supported, is_info_gpl_compatible_supported, is_info_map_ids_supported,
+        is_perf_link_supported, is_probe_read_kernel_supported, is_prog_id_supported,
+        is_prog_name_supported, retry_with_verifier_logs,
+    },
+    util::{
+        KernelVersion, ModuleBtf, bytes_of, bytes_of_slice, kernel_config, kernel_module_btfs,
+        kernel_symbols, nr_cpus, page_size,
     },
-    util::{bytes_of, bytes_of_slice, nr_cpus, page_size},
 };
 
 /// Marker trait for types that can safely be converted to and from byte slices.
@@ -84,6 +95,7 @@ fn detect_features() -> Features {
         is_prog_id_supported(BPF_MAP_TYPE_DEVMAP),
         is_info_map_ids_supported(),
         is_info_gpl_compatible_supported(),
+        is_array_mmap_supported(),
         btf,
     );
     debug!("BPF Feature Detection: {:#?}", f);
@@ -101,6 +113,12 @@ pub fn features() -> &'static Features {
 /// relocations. You can use `EbpfLoader` to customize some of the loading
 /// options.
 ///
+/// On kernels that support it (5.5 and later), the `.bss`, `.data` and
+/// `.rodata` maps are created with the `BPF_F_MMAPABLE` flag, so that
+/// [`Ebpf::global`] and [`Ebpf::global_mut`] can access them without syscalls.
+/// The flag is visible to anything that inspects the maps, for example
+/// `bpftool map show`.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -118,13 +136,33 @@ pub fn features() -> &'static Features {
 /// ```
 #[derive(Debug)]
 pub struct EbpfLoader<'a> {
-    btf: Option<Cow<'a, Btf>>,
+    btf: Option<TargetBtf<'a>>,
     map_pin_path: Option<PathBuf>,
     globals: HashMap<&'a str, (&'a [u8], bool)>,
     max_entries: HashMap<&'a str, u32>,
     extensions: HashSet<&'a str>,
     verifier_log_level: VerifierLogLevel,
     allow_unsupported_maps: bool,
+    kconfig: Option<&'a str>,
+}
+
+/// The BTF that the object is relocated against.
+#[derive(Debug)]
+enum TargetBtf<'a> {
+    /// The BTF of the running kernel, shared with the split BTF of kernel modules.
+    Kernel(Arc<Btf>),
+    Custom(&'a Btf),
+}
+
+impl Deref for TargetBtf<'_> {
+    type Target = Btf;
+
+    fn deref(&self) -> &Btf {
+        match self {
+            Self::Kernel(btf) => btf,
+            Self::Custom(btf) => btf,
+        }
+    }
 }
 
 /// Builder style API for advanced loading of eBPF programs.
@@ -156,13 +194,14 @@ impl<'a> EbpfLoader<'a> {
     /// Creates a new loader instance.
     pub fn new() -> Self {
         Self {
-            btf: Btf::from_sys_fs().ok().map(Cow::Owned),
+            btf: Btf::from_sys_fs().ok().map(Arc::new).map(TargetBtf::Kernel),
             map_pin_path: None,
             globals: HashMap::new(),
             max_entries: HashMap::new(),
             extensions: HashSet::new(),
             verifier_log_level: VerifierLogLevel::default(),
             allow_unsupported_maps: false,
+            kconfig: None,
         }
     }
 
@@ -184,7 +223,7 @@ impl<'a> EbpfLoader<'a> {
     /// # Ok::<(), aya::EbpfError>(())
     /// ```
     pub fn btf(&mut self, btf: Option<&'a Btf>) -> &mut Self {
-        self.btf = btf.map(Cow::Borrowed);
+        self.btf = btf.map(TargetBtf::Custom);
         self
     }
 
@@ -343,6 +382,31 @@ impl<'a> EbpfLoader<'a> {
         self
     }
 
+    /// Sets the kernel config used to resolve `.kconfig` extern variables.
+    ///
+    /// eBPF programs can declare extern variables in the `.kconfig` section
+    /// to read kernel config options such as `CONFIG_HZ`, as well as
+    /// `LINUX_KERNEL_VERSION`. By default the loader reads the config of the
+    /// running kernel from `/proc/config.gz`, falling back to
+    /// `/boot/config-$(uname -r)`. Use this method to provide the config
+    /// contents from a different source, for example in tests.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use aya::EbpfLoader;
+    ///
+    /// let bpf = EbpfLoader::new()
+    ///     .kconfig("CONFIG_BPF=y\nCONFIG_HZ=250\n")
+    ///     .load_file("file.o")?;
+    /// # Ok::<(), aya::EbpfError>(())
+    /// ```
+    ///
+    pub fn kconfig(&mut self, config: &'a str) -> &mut Self {
+        self.kconfig = Some(config);
+        self
+    }
+
     /// Loads eBPF bytecode from a file.
     ///
     /// # Examples
@@ -386,9 +450,31 @@ impl<'a> EbpfLoader<'a> {
             extensions,
             verifier_log_level,
             allow_unsupported_maps,
+            kconfig,
         } = self;
         let mut obj = Object::parse(data)?;
         obj.patch_map_data(globals.clone())?;
+        let global_vars = obj.global_vars()?;
+
+        if obj.kconfig_externs().next().is_some() {
+            let config = match kconfig {
+                Some(config) => Cow::Borrowed(*config),
+                None if obj
+                    .kconfig_externs()
+                    .any(|name| name.starts_with("CONFIG_")) =>
+                {
+                    Cow::Owned(
+                        kernel_config()
+                            .map_err(|(path, error)| EbpfError::FileError { path, error })?,
+                    )
+                }
+                None => Cow::Borrowed(""),
+            };
+            let kernel_version = KernelVersion::current()
+                .map(KernelVersion::code)
+                .unwrap_or(0);
+            obj.patch_kconfig(&config, kernel_version)?;
+        }
 
         let btf_fd = if let Some(features) = &FEATURES.btf() {
             if let Some(btf) = obj.fixup_and_sanitize_btf(features)? {
@@ -401,15 +487,23 @@ impl<'a> EbpfLoader<'a> {
                                 ProgramSection::Extension
                                 | ProgramSection::FEntry { sleepable: _ }
                                 | ProgramSection::FExit { sleepable: _ }
+                                | ProgramSection::FModRet { sleepable: _ }
                                 | ProgramSection::Lsm { sleepable: _ }
+                                | ProgramSection::LsmCgroup
                                 | ProgramSection::BtfTracePoint
-                                | ProgramSection::Iter { sleepable: _ } => {
+                                | ProgramSection::Iter { sleepable: _ }
+                                | ProgramSection::StructOps { sleepable: _ } => {
                                     return Err(EbpfError::BtfError(err));
                                 }
                                 ProgramSection::KRetProbe
                                 | ProgramSection::KProbe
+                                | ProgramSection::KRetProbeMulti
+                                | ProgramSection::KProbeMulti
                                 | ProgramSection::UProbe { sleepable: _ }
                                 | ProgramSection::URetProbe { sleepable: _ }
+                                | ProgramSection::UProbeMulti { sleepable: _ }
+                                | ProgramSection::URetProbeMulti { sleepable: _ }
+                                | ProgramSection::Usdt { sleepable: _ }
                                 | ProgramSection::TracePoint
                                 | ProgramSection::SocketFilter
                                 | ProgramSection::Xdp {
@@ -430,7 +524,11 @@ impl<'a> EbpfLoader<'a> {
                                 | ProgramSection::LircMode2
                                 | ProgramSection::PerfEvent
                                 | ProgramSection::RawTracePoint
+                                | ProgramSection::RawTracePointWritable
                                 | ProgramSection::SkLookup
+                                | ProgramSection::SkReuseport { migrate: _ }
+                                | ProgramSection::Netfilter
+                                | ProgramSection::Syscall
                                 | ProgramSection::FlowDissector
                                 | ProgramSection::CgroupSock { attach_type: _ }
                                 | ProgramSection::CgroupDevice => {}
@@ -451,11 +549,111 @@ impl<'a> EbpfLoader<'a> {
 
         if let Some(btf) = &btf {
             obj.relocate_btf(btf)?;
+            obj.fixup_struct_ops(btf)?;
         }
+        let mut kallsyms = None;
+        obj.resolve_ksyms(btf.as_deref(), |name| {
+            kallsyms
+                .get_or_insert_with(|| match kernel_symbols() {
+                    Ok(symbols) => symbols
+                        .into_iter()
+                        .map(|(address, name)| (name, address))
+                        .collect(),
+                    Err(error) => {
+                        warn!("failed to read kernel symbols: {error}");
+                        HashMap::new()
+                    }
+                })
+                .get(name)
+                .copied()
+        })?;
+
+        // kfuncs are looked up in vmlinux first, then in the BTF of kernel modules, in
+        // which case the module BTF fd is passed to the kernel in fd_array.
+        let in_vmlinux = |name: &str| {
+            btf.as_deref()
+                .and_then(|btf| btf.id_by_type_name_kind(name, BtfKind::Func).ok())
+        };
+        let modules = match btf.as_ref() {
+            Some(btf) if obj.kfunc_externs().any(|name| in_vmlinux(name).is_none()) => {
+                // module BTF is built on top of the BTF of the running kernel, which
+                // custom BTF might not be
+                let vmlinux = match btf {
+                    TargetBtf::Kernel(vmlinux) => Ok(Arc::clone(vmlinux)),
+                    TargetBtf::Custom(_) => Btf::from_sys_fs().map(Arc::new),
+                };
+                match vmlinux {
+                    Ok(vmlinux) => kernel_module_btfs(&vmlinux).unwrap_or_else(|err| {
+                        warn!("failed to load the BTF of kernel modules: {err}");
+                        Vec::new()
+                    }),
+                    Err(err) => {
+                        warn!("failed to load the BTF of the kernel: {err}");
+                        Vec::new()
+                    }
+                }
+            }
+            _ => Vec::new(),
+        };
+        let mut used_modules = Vec::new();
+        obj.resolve_kfuncs(|name| {
+            if let Some(id) = in_vmlinux(name) {
+                return Some((id, 0, 0));
+            }
+            for (index, module) in modules.iter().enumerate() {
+                let Ok(id) = module.btf.id_by_type_name_kind(name, BtfKind::Func) else {
+                    continue;
+                };
+                debug!("found kfunc {name} in module {}", module.name);
+                let fd_index = used_modules
+                    .iter()
+                    .position(|used| *used == index)
+                    .unwrap_or_else(|| {
+                        used_modules.push(index);
+                        used_modules.len() - 1
+                    });
+                // index 0 is reserved for vmlinux
+                return Some((id, (fd_index + 1) as i16, module.fd.as_raw_fd()));
+            }
+            None
+        })?;
+        let mut module_fds: Vec<_> = modules
+            .into_iter()
+            .map(|ModuleBtf { fd, .. }| Some(fd))
+            .collect();
+        let fd_array: Vec<_> = used_modules
+            .into_iter()
+            .filter_map(|module| module_fds[module].take())
+            .map(Arc::new)
+            .collect();
+
+        // struct_ops programs are loaded against the kernel struct and member they implement
+        let struct_ops_members: HashMap<_, _> = obj
+            .maps
+            .values()
+            .filter_map(|map| match map {
+                aya_obj::Map::StructOps(map) => Some(map),
+                _ => None,
+            })
+            .flat_map(|map| {
+                map.programs().iter().map(|program| {
+                    (
+                        program.name.clone(),
+                        (map.btf_vmlinux_type_id(), program.member_index),
+                    )
+                })
+            })
+            .collect();
+
         let mut maps = HashMap::new();
         for (name, mut obj) in obj.maps.drain() {
-            if let (false, EbpfSectionKind::Bss | EbpfSectionKind::Data | EbpfSectionKind::Rodata) =
-                (FEATURES.bpf_global_data(), obj.section_kind())
+            if let (
+                false,
+                EbpfSectionKind::Bss
+                | EbpfSectionKind::Data
+                | EbpfSectionKind::Rodata
+                | EbpfSectionKind::Kconfig,
+            ) = (FEATURES.bpf_global_data(), obj.section_kind())
             {
                 continue;
             }
@@ -482,8 +680,27 @@ impl<'a> EbpfLoader<'a> {
                 Ok(BPF_MAP_TYPE_DEVMAP | BPF_MAP_TYPE_DEVMAP_HASH) => {
                     obj.set_value_size(if FEATURES.devmap_prog_id() { 8 } else { 4 })
                 }
+                Ok(
+                    BPF_MAP_TYPE_SK_STORAGE
+                    | BPF_MAP_TYPE_INODE_STORAGE
+                    | BPF_MAP_TYPE_TASK_STORAGE
+                    | BPF_MAP_TYPE_CGRP_STORAGE,
+                ) => {
+                    // Local storage is allocated when it's first used, the kernel
+                    // rejects these maps without this flag.
+                    obj.set_map_flags(obj.map_flags() | BPF_F_NO_PREALLOC)
+                }
                 _ => (),
             }
+            if FEATURES.array_mmap()
+                && matches!(
+                    obj.section_kind(),
+                    EbpfSectionKind::Bss | EbpfSectionKind::Data | EbpfSectionKind::Rodata
+                )
+            {
+                // Allow globals to be accessed without syscalls, see `Ebpf::global`.
+                obj.set_map_flags(obj.map_flags() | BPF_F_MMAPABLE);
+            }
             let btf_fd = btf_fd.as_deref().map(|fd| fd.as_fd());
             let mut map = match obj.pinning() {
                 PinningType::None => MapData::create(obj, &name, btf_fd)?,
@@ -501,6 +718,21 @@ impl<'a> EbpfLoader<'a> {
             maps.insert(name, map);
         }
 
+        // Store the maps declared with `.values` initialisers in the map-of-maps
+        // holding them. The parser only accepts initialisers naming maps of the
+        // same object.
+        for map in maps.values() {
+            for (index, inner) in map.obj().initial_slots() {
+                let inner_fd = maps[inner].fd().as_fd().as_raw_fd();
+                bpf_map_update_elem(map.fd().as_fd(), Some(index), &inner_fd, 0)
+                    .map_err(|io_error| SyscallError {
+                        call: "bpf_map_update_elem",
+                        io_error,
+                    })
+                    .map_err(MapError::from)?;
+            }
+        }
+
         let text_sections = obj
             .functions
             .keys()
@@ -515,6 +747,14 @@ impl<'a> EbpfLoader<'a> {
         obj.relocate_calls(&text_sections)?;
         obj.sanitize_functions(&FEATURES);
 
+        // The argument specs of USDT probes, filled when attaching them.
+        let usdt_specs = maps
+            .get("AYA_USDT_SPECS")
+            .map(|map| map.fd().try_clone())
+            .transpose()
+            .map_err(MapError::from)?
+            .map(Arc::new);
+
         let programs = obj
             .programs
             .drain()
@@ -523,10 +763,16 @@ impl<'a> EbpfLoader<'a> {
 
                 let prog_name = FEATURES.bpf_name().then(|| name.clone().into());
                 let section = prog_obj.section.clone();
+                // only programs calling kfuncs defined in modules need the module BTF fds
+                let calls_module_kfuncs = function_obj.instructions.iter().any(|ins| {
+                    ins.code == (BPF_JMP | BPF_CALL) as u8
+                        && ins.src_reg() == BPF_PSEUDO_KFUNC_CALL as u8
+                        && ins.off != 0
+                });
                 let obj = (prog_obj, function_obj);
 
                 let btf_fd = btf_fd.as_ref().map(Arc::clone);
-                let program = if extensions.contains(name.as_str()) {
+                let mut program = if extensions.contains(name.as_str()) {
                     Program::Extension(Extension {
                         data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
                     })
@@ -540,6 +786,24 @@ impl<'a> EbpfLoader<'a> {
                             data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
                             kind: ProbeKind::KRetProbe,
                         }),
+                        ProgramSection::KProbeMulti => {
+                            let mut data =
+                                ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level);
+                            data.expected_attach_type = Some(BPF_TRACE_KPROBE_MULTI);
+                            Program::KProbe(KProbe {
+                                data,
+                                kind: ProbeKind::KProbe,
+                            })
+                        }
+                        ProgramSection::KRetProbeMulti => {
+                            let mut data =
+                                ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level);
+                            data.expected_attach_type = Some(BPF_TRACE_KPROBE_MULTI);
+                            Program::KProbe(KProbe {
+                                data,
+                                kind: ProbeKind::KRetProbe,
+                            })
+                        }
                         ProgramSection::UProbe { sleepable } => {
                             let mut data =
                                 ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level);
@@ -562,6 +826,41 @@ impl<'a> EbpfLoader<'a> {
                                 kind: ProbeKind::URetProbe,
                             })
                         }
+                        ProgramSection::UProbeMulti { sleepable } => {
+                            let mut data =
+                                ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level);
+                            data.expected_attach_type = Some(BPF_TRACE_UPROBE_MULTI);
+                            if *sleepable {
+                                data.flags = BPF_F_SLEEPABLE;
+                            }
+                            Program::UProbe(UProbe {
+                                data,
+                                kind: ProbeKind::UProbe,
+                            })
+                        }
+                        ProgramSection::URetProbeMulti { sleepable } => {
+                            let mut data =
+                                ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level);
+                            data.expected_attach_type = Some(BPF_TRACE_UPROBE_MULTI);
+                            if *sleepable {
+                                data.flags = BPF_F_SLEEPABLE;
+                            }
+                            Program::UProbe(UProbe {
+                                data,
+                                kind: ProbeKind::URetProbe,
+                            })
+                        }
+                        ProgramSection::Usdt { sleepable } => {
+                            let mut data =
+                                ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level);
+                            if *sleepable {
+                                data.flags = BPF_F_SLEEPABLE;
+                            }
+                            Program::Usdt(Usdt {
+                                data,
+                                specs: usdt_specs.clone(),
+                            })
+                        }
                         ProgramSection::TracePoint => Program::TracePoint(TracePoint {
                             data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
                         }),
@@ -633,6 +932,11 @@ impl<'a> EbpfLoader<'a> {
                         ProgramSection::PerfEvent => Program::PerfEvent(PerfEvent {
                             data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
                         }),
+                        ProgramSection::RawTracePointWritable => {
+                            Program::RawTracePointWritable(RawTracePointWritable {
+                                data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
+                            })
+                        }
                         ProgramSection::RawTracePoint => Program::RawTracePoint(RawTracePoint {
                             data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
                         }),
@@ -644,6 +948,9 @@ impl<'a> EbpfLoader<'a> {
                             }
                             Program::Lsm(Lsm { data })
                         }
+                        ProgramSection::LsmCgroup => Program::LsmCgroup(LsmCgroup {
+                            data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
+                        }),
                         ProgramSection::BtfTracePoint => Program::BtfTracePoint(BtfTracePoint {
                             data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
                         }),
@@ -663,6 +970,14 @@ impl<'a> EbpfLoader<'a> {
                             }
                             Program::FExit(FExit { data })
                         }
+                        ProgramSection::FModRet { sleepable } => {
+                            let mut data =
+                                ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level);
+                            if *sleepable {
+                                data.flags = BPF_F_SLEEPABLE;
+                            }
+                            Program::FModRet(FModRet { data })
+                        }
                         ProgramSection::FlowDissector => Program::FlowDissector(FlowDissector {
                             data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
                         }),
@@ -672,6 +987,22 @@ impl<'a> EbpfLoader<'a> {
                         ProgramSection::SkLookup => Program::SkLookup(SkLookup {
                             data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
                         }),
+                        ProgramSection::SkReuseport { migrate } => {
+                            Program::SkReuseport(SkReuseport {
+                                data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
+                                kind: if *migrate {
+                                    SkReuseportKind::SelectOrMigrate
+                                } else {
+                                    SkReuseportKind::Select
+                                },
+                            })
+                        }
+                        ProgramSection::Netfilter => Program::Netfilter(Netfilter {
+                            data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
+                        }),
+                        ProgramSection::Syscall => Program::Syscall(Syscall {
+                            data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
+                        }),
                         ProgramSection::CgroupSock { attach_type, .. } => {
                             Program::CgroupSock(CgroupSock {
                                 data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
@@ -689,8 +1020,24 @@ impl<'a> EbpfLoader<'a> {
                             }
                             Program::Iter(Iter { data })
                         }
+                        ProgramSection::StructOps { sleepable } => {
+                            let mut data =
+                                ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level);
+                            if *sleepable {
+                                data.flags = BPF_F_SLEEPABLE;
+                            }
+                            let member = struct_ops_members.get(&name);
+                            data.attach_btf_id = member.map(|(type_id, _)| *type_id);
+                            Program::StructOps(StructOps {
+                                data,
+                                member_index: member.map(|(_, member_index)| *member_index),
+                            })
+                        }
                     }
                 };
+                if calls_module_kfuncs {
+                    program.set_fd_array(fd_array.clone());
+                }
                 (name, program)
             })
             .collect();
@@ -699,7 +1046,11 @@ impl<'a> EbpfLoader<'a> {
             .map(|data| parse_map(data, *allow_unsupported_maps))
             .collect::<Result<HashMap<String, Map>, EbpfError>>()?;
 
-        Ok(Ebpf { maps, programs })
+        Ok(Ebpf {
+            maps,
+            programs,
+            globals: global_vars,
+        })
     }
 }
 
@@ -710,9 +1061,14 @@ fn parse_map(
     let (name, map) = data;
     let map_type = bpf_map_type::try_from(map.obj().map_type()).map_err(MapError::from)?;
     let map = match map_type {
+        BPF_MAP_TYPE_ARENA => Map::Arena(map),
         BPF_MAP_TYPE_ARRAY => Map::Array(map),
         BPF_MAP_TYPE_PERCPU_ARRAY => Map::PerCpuArray(map),
+        BPF_MAP_TYPE_CGROUP_STORAGE_DEPRECATED => Map::CgroupStorage(map),
+        BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE_DEPRECATED => Map::PerCpuCgroupStorage(map),
         BPF_MAP_TYPE_PROG_ARRAY => Map::ProgramArray(map),
+        BPF_MAP_TYPE_ARRAY_OF_MAPS => Map::ArrayOfMaps(map),
+        BPF_MAP_TYPE_HASH_OF_MAPS => Map::HashOfMaps(map),
         BPF_MAP_TYPE_HASH => Map::HashMap(map),
         BPF_MAP_TYPE_LRU_HASH => Map::LruHashMap(map),
         BPF_MAP_TYPE_PERCPU_HASH => Map::PerCpuHashMap(map),
@@ -721,6 +1077,7 @@ fn parse_map(
         BPF_MAP_TYPE_RINGBUF => Map::RingBuf(map),
         BPF_MAP_TYPE_SOCKHASH => Map::SockHash(map),
         BPF_MAP_TYPE_SOCKMAP => Map::SockMap(map),
+        BPF_MAP_TYPE_REUSEPORT_SOCKARRAY => Map::ReusePortSockArray(map),
         BPF_MAP_TYPE_BLOOM_FILTER => Map::BloomFilter(map),
         BPF_MAP_TYPE_LPM_TRIE => Map::LpmTrie(map),
         BPF_MAP_TYPE_STACK => Map::Stack(map),
@@ -730,6 +1087,11 @@ fn parse_map(
         BPF_MAP_TYPE_DEVMAP => Map::DevMap(map),
         BPF_MAP_TYPE_DEVMAP_HASH => Map::DevMapHash(map),
         BPF_MAP_TYPE_XSKMAP => Map::XskMap(map),
+        BPF_MAP_TYPE_STRUCT_OPS => Map::StructOps(map),
+        BPF_MAP_TYPE_SK_STORAGE => Map::SkStorage(map),
+        BPF_MAP_TYPE_INODE_STORAGE => Map::InodeStorage(map),
+        BPF_MAP_TYPE_TASK_STORAGE => Map::TaskStorage(map),
+        BPF_MAP_TYPE_CGRP_STORAGE => Map::CgrpStorage(map),
         m_type => {
             if allow_unsupported_maps {
                 Map::Unsupported(map)
@@ -851,6 +1213,7 @@ impl Default for EbpfLoader<'_> {
 pub struct Ebpf {
     maps: HashMap<String, Map>,
     programs: HashMap<String, Program>,
+    globals: HashMap<String, GlobalVar>,
 }
 
 /// The main entry point into the library, used to work with eBPF programs and maps.
@@ -987,6 +1350,120 @@ impl Ebpf {
         self.maps.iter_mut().map(|(name, map)| (name.as_str(), map))
     }
 
+    /// Returns a handle to the global variable with the given name.
+    ///
+    /// Global variables live in the `.bss`, `.data` and `.rodata` maps. Their offset in the maps
+    /// is found using the BTF of the object, or its symbol table if it has no BTF. The handle
+    /// reads the variable through a memory mapping of the map, without any syscalls.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.5.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GlobalError::NotFound`] if the variable doesn't exist,
+    /// [`GlobalError::InvalidSize`] if its size doesn't match `T`, [`MapError::NotMmapable`] if
+    /// the kernel doesn't support memory mapping the global data maps.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let bpf = aya::Ebpf::load(&[])?;
+    /// let packets = bpf.global::<u64>("PACKETS")?;
+    /// println!("{} packets", packets.read());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn global<T: Pod>(&self, name: &str) -> Result<Global<'_, T>, GlobalError> {
+        let (mmap, offset) = self.mmap_global::<T>(name, false)?;
+        Ok(Global {
+            mmap,
+            offset,
+            _t: PhantomData,
+        })
+    }
+
+    /// Returns a mutable handle to the global variable with the given name.
+    ///
+    /// Writes through the handle are visible to the eBPF programs right away. See
+    /// [`Ebpf::global`] for how variables are looked up.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.5.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Ebpf::global`], and [`GlobalError::ReadOnly`] if the variable
+    /// is in `.rodata`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let mut bpf = aya::Ebpf::load(&[])?;
+    /// let mut enabled = bpf.global_mut::<u8>("ENABLED")?;
+    /// enabled.write(1);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn global_mut<T: Pod>(&mut self, name: &str) -> Result<GlobalMut<'_, T>, GlobalError> {
+        let (mmap, offset) = self.mmap_global::<T>(name, true)?;
+        Ok(GlobalMut {
+            mmap,
+            offset,
+            _t: PhantomData,
+        })
+    }
+
+    fn mmap_global<T: Pod>(
+        &self,
+        name: &str,
+        writable: bool,
+    ) -> Result<(MMap, usize), GlobalError> {
+        let GlobalVar { map, offset, size } =
+            self.globals
+                .get(name)
+                .ok_or_else(|| GlobalError::NotFound {
+                    name: name.to_owned(),
+                })?;
+        let expected = mem::size_of::<T>();
+        if *size != expected {
+            return Err(GlobalError::InvalidSize {
+                name: name.to_owned(),
+                size: *size,
+                expected,
+            });
+        }
+        let align = mem::align_of::<T>();
+        if offset % align != 0 {
+            return Err(GlobalError::Misaligned {
+                name: name.to_owned(),
+                offset: *offset,
+                align,
+            });
+        }
+        let Some(Map::Array(data)) = self.maps.get(map) else {
+            return Err(GlobalError::MapNotFound { name: map.clone() });
+        };
+        let obj = data.obj();
+        if writable && obj.section_kind() == EbpfSectionKind::Rodata {
+            return Err(GlobalError::ReadOnly {
+                name: name.to_owned(),
+            });
+        }
+        if obj.map_flags() & BPF_F_MMAPABLE == 0 {
+            return Err(MapError::NotMmapable.into());
+        }
+        let prot = if writable {
+            PROT_READ | PROT_WRITE
+        } else {
+            PROT_READ
+        };
+        let len = (obj.value_size() as usize).next_multiple_of(page_size());
+        let mmap =
+            MMap::new(data.fd().as_fd(), len, prot, MAP_SHARED, 0).map_err(MapError::from)?;
+        Ok((mmap, *offset))
+    }
+
     /// Returns a reference to the program with the given name.
     ///
     /// You can use this to inspect a program and its properties. To load and attach a program, use
@@ -1122,6 +1599,58 @@ pub enum EbpfError {
 #[deprecated(since = "0.13.0", note = "use `EbpfError` instead")]
 pub type BpfError = EbpfError;
 
+/// The error type returned by [`Ebpf::global`] and [`Ebpf::global_mut`].
+#[derive(Debug, Error)]
+pub enum GlobalError {
+    /// The global variable doesn't exist
+    #[error("global variable `{name}` not found")]
+    NotFound {
+        /// The variable name
+        name: String,
+    },
+
+    /// The map holding the global variable doesn't exist, for instance because it was taken with
+    /// [`Ebpf::take_map`]
+    #[error("map `{name}` holding the global variable not found")]
+    MapNotFound {
+        /// The map name
+        name: String,
+    },
+
+    /// The size of the global variable doesn't match the requested type
+    #[error("global variable `{name}` is {size} bytes long, expected {expected}")]
+    InvalidSize {
+        /// The variable name
+        name: String,
+        /// The size of the variable
+        size: usize,
+        /// The size of the requested type
+        expected: usize,
+    },
+
+    /// The global variable isn't suitably aligned for the requested type
+    #[error("global variable `{name}` at offset {offset} isn't aligned to {align} bytes")]
+    Misaligned {
+        /// The variable name
+        name: String,
+        /// The offset of the variable in its map
+        offset: usize,
+        /// The alignment of the requested type
+        align: usize,
+    },
+
+    /// The global variable is read-only
+    #[error("global variable `{name}` is read-only")]
+    ReadOnly {
+        /// The variable name
+        name: String,
+    },
+
+    #[error("map error: {0}")]
+    /// A map error
+    MapError(#[from] MapError),
+}
+
 fn load_btf(
     raw_btf: Vec<u8>,
     verifier_log_level: VerifierLogLevel,
@@ -1159,3 +1688,64 @@ impl<'a, T: Pod> From<&'a T> for GlobalData<'a> {
         }
     }
 }
+
+/// A global variable of a loaded eBPF object, see [`Ebpf::global`].
+pub struct Global<'a, T: Pod> {
+    mmap: MMap,
+    offset: usize,
+    _t: PhantomData<&'a T>,
+}
+
+impl<T: Pod> fmt::Debug for Global<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Global")
+            .field("offset", &self.offset)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Pod> Global<'_, T> {
+    /// Returns the value of the variable, with a volatile read.
+    pub fn read(&self) -> T {
+        let Self { mmap, offset, _t } = self;
+        unsafe {
+            mmap.ptr
+                .as_ptr()
+                .byte_add(*offset)
+                .cast::<T>()
+                .read_volatile()
+        }
+    }
+}
+
+/// A mutable global variable of a loaded eBPF object, see [`Ebpf::global_mut`].
+pub struct GlobalMut<'a, T: Pod> {
+    mmap: MMap,
+    offset: usize,
+    _t: PhantomData<&'a mut T>,
+}
+
+impl<T: Pod> fmt::Debug for GlobalMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlobalMut")
+            .field("offset", &self.offset)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Pod> GlobalMut<'_, T> {
+    fn ptr(&self) -> *mut T {
+        let Self { mmap, offset, _t } = self;
+        unsafe { mmap.ptr.as_ptr().byte_add(*offset) }.cast()
+    }
+
+    /// Returns the value of the variable, with a volatile read.
+    pub fn read(&self) -> T {
+        unsafe { self.ptr().read_volatile() }
+    }
+
+    /// Sets the value of the variable, with a volatile write.
+    pub fn write(&mut self, value: T) {
+        unsafe { self.ptr().write_volatile(value) }
+    }
+}
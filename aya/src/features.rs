@@ -0,0 +1,341 @@
+//! Probing for the eBPF features supported by the running kernel.
+//!
+//! Unlike [`features()`](crate::features()), which only reports the features
+//! aya relies on when loading objects, the functions in this module probe for
+//! support of specific program types, map types, helpers and link types, much
+//! like `bpftool feature probe` does. This makes it possible to pick an
+//! implementation at runtime, for example falling back to a perf event array
+//! when ring buffers aren't available.
+//!
+//! Probing requires the same privileges as loading programs and creating maps.
+//! Results are cached for the lifetime of the process; failed probes are not.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use aya::{
+//!     features,
+//!     maps::MapType,
+//!     programs::{LinkType, ProgramType},
+//! };
+//!
+//! if features::is_map_supported(MapType::RingBuf)? {
+//!     // use a ring buffer
+//! } else {
+//!     // fall back to a perf event array
+//! }
+//!
+//! let tcx = features::is_link_type_supported(LinkType::Tcx)?;
+//! let fentry = features::is_program_supported(ProgramType::Tracing)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    collections::HashMap,
+    io,
+    os::fd::AsFd as _,
+    sync::{LazyLock, Mutex},
+};
+
+use aya_obj::generated::{
+    bpf_attach_type::{self, *},
+    bpf_map_type,
+    bpf_prog_type::{self, *},
+};
+use libc::{E2BIG, EACCES, EAFNOSUPPORT, EBADF, EINVAL, ENODEV, EOPNOTSUPP, ESRCH};
+
+use crate::{
+    maps::{MapError, MapType},
+    programs::{LinkType, ProgramError, ProgramType},
+    sys::{
+        SyscallError, bpf_probe_link_create, bpf_probe_map_create, bpf_probe_prog_load,
+        is_perf_link_supported,
+    },
+    util::KernelVersion,
+};
+
+// Kernel-internal error code, not exported by libc.
+pub(crate) const ENOTSUPP: i32 = 524;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+enum Probe {
+    Program(u32),
+    Map(u32),
+    Helper(u32, u32),
+    Link(u32),
+}
+
+static PROBES: LazyLock<Mutex<HashMap<Probe, bool>>> = LazyLock::new(Default::default);
+
+fn cached<E>(probe: Probe, run: impl FnOnce() -> Result<bool, E>) -> Result<bool, E> {
+    if let Some(supported) = PROBES.lock().unwrap().get(&probe) {
+        return Ok(*supported);
+    }
+    // The lock isn't held while probing since probes may depend on each other.
+    let supported = run()?;
+    PROBES.lock().unwrap().insert(probe, supported);
+    Ok(supported)
+}
+
+fn is_unsupported(error: &io::Error) -> bool {
+    matches!(error.raw_os_error(), Some(EINVAL | E2BIG | EOPNOTSUPP))
+}
+
+fn log_contains(log_buf: &[u8], needle: &str) -> bool {
+    log_buf
+        .windows(needle.len())
+        .any(|window| window == needle.as_bytes())
+}
+
+fn prog_type(program_type: ProgramType) -> Result<bpf_prog_type, ProgramError> {
+    bpf_prog_type::try_from(program_type as u32).map_err(|_| ProgramError::UnexpectedProgramType)
+}
+
+/// Returns whether the running kernel supports programs of type `program_type`.
+///
+/// A minimal program of the given type is loaded. Programs that need an attach
+/// target to be loaded (tracing, LSM, extension and struct_ops programs) are
+/// loaded with a bogus target, and support is inferred from the error the
+/// kernel returns.
+///
+/// # Errors
+///
+/// Returns an error if the probe fails for a reason other than lack of
+/// support, for example missing privileges.
+pub fn is_program_supported(program_type: ProgramType) -> Result<bool, ProgramError> {
+    cached(Probe::Program(program_type as u32), || {
+        let prog_type = prog_type(program_type)?;
+        let mut log_buf = [0u8; 256];
+        let io_error = match bpf_probe_prog_load(prog_type, None, None, &mut log_buf) {
+            Ok(_) => return Ok(true),
+            Err(io_error) => io_error,
+        };
+        let expected = match prog_type {
+            BPF_PROG_TYPE_TRACING | BPF_PROG_TYPE_LSM => {
+                Some((EINVAL, "attach_btf_id 1 is not a function"))
+            }
+            BPF_PROG_TYPE_EXT => Some((EINVAL, "Cannot replace kernel functions")),
+            BPF_PROG_TYPE_STRUCT_OPS => Some((ENOTSUPP, "")),
+            _ => None,
+        };
+        if let Some((errno, message)) = expected {
+            if io_error.raw_os_error() == Some(errno) && log_contains(&log_buf, message) {
+                return Ok(true);
+            }
+        }
+        if is_unsupported(&io_error) || io_error.raw_os_error() == Some(ENOTSUPP) {
+            Ok(false)
+        } else {
+            Err(SyscallError {
+                call: "bpf_prog_load",
+                io_error,
+            }
+            .into())
+        }
+    })
+}
+
+/// Returns whether the running kernel supports maps of type `map_type`.
+///
+/// # Errors
+///
+/// Returns an error if the probe fails for a reason other than lack of
+/// support, for example missing privileges.
+pub fn is_map_supported(map_type: MapType) -> Result<bool, MapError> {
+    cached(Probe::Map(map_type as u32), || {
+        let map_type = bpf_map_type::try_from(map_type as u32)?;
+        match bpf_probe_map_create(map_type) {
+            Ok(_) => Ok(true),
+            // struct_ops maps are created with a bogus BTF type.
+            Err(io_error)
+                if map_type == bpf_map_type::BPF_MAP_TYPE_STRUCT_OPS
+                    && io_error.raw_os_error() == Some(ENOTSUPP) =>
+            {
+                Ok(true)
+            }
+            Err(io_error) if is_unsupported(&io_error) => Ok(false),
+            Err(io_error) => Err(SyscallError {
+                call: "bpf_map_create",
+                io_error,
+            }
+            .into()),
+        }
+    })
+}
+
+/// Returns whether programs of type `program_type` can call the helper
+/// `helper_id`.
+///
+/// `helper_id` is one of the values of
+/// [`bpf_func_id`](aya_obj::generated::bpf_func_id). Helpers are always
+/// reported as supported for program types that need an attach target to be
+/// loaded, since the kernel rejects those before verifying the program.
+///
+/// # Errors
+///
+/// Returns an error if the probe fails for a reason other than lack of
+/// support, for example missing privileges.
+pub fn is_helper_supported(
+    program_type: ProgramType,
+    helper_id: u32,
+) -> Result<bool, ProgramError> {
+    cached(Probe::Helper(program_type as u32, helper_id), || {
+        if !is_program_supported(program_type)? {
+            return Ok(false);
+        }
+        let prog_type = prog_type(program_type)?;
+        if matches!(
+            prog_type,
+            BPF_PROG_TYPE_TRACING
+                | BPF_PROG_TYPE_LSM
+                | BPF_PROG_TYPE_EXT
+                | BPF_PROG_TYPE_STRUCT_OPS
+        ) {
+            return Ok(true);
+        }
+        let mut log_buf = vec![0u8; 4096];
+        match bpf_probe_prog_load(prog_type, None, Some(helper_id), &mut log_buf) {
+            Ok(_) => Ok(true),
+            // The verifier reports unknown helpers as "invalid func unknown#<id>"
+            // and helpers that aren't allowed for the program type as
+            // "unknown func <name>#<id>". Any other rejection means the helper is
+            // known but wasn't called with valid arguments.
+            Err(io_error)
+                if matches!(io_error.raw_os_error(), Some(EINVAL | EACCES)) && log_buf[0] != 0 =>
+            {
+                Ok(!log_contains(&log_buf, "invalid func ")
+                    && !log_contains(&log_buf, "unknown func "))
+            }
+            Err(io_error) => Err(SyscallError {
+                call: "bpf_prog_load",
+                io_error,
+            }
+            .into()),
+        }
+    })
+}
+
+/// Returns whether the running kernel supports links of type `link_type`.
+///
+/// Links are created with a bogus target and support is inferred from the
+/// error the kernel returns. Link types that can't be probed this way
+/// ([`LinkType::RawTracePoint`], [`LinkType::Tracing`], [`LinkType::Iter`],
+/// [`LinkType::Xdp`] and [`LinkType::StructOps`]) are detected from the kernel
+/// version.
+///
+/// # Errors
+///
+/// Returns an error if the probe fails for a reason other than lack of
+/// support, for example missing privileges.
+pub fn is_link_type_supported(link_type: LinkType) -> Result<bool, ProgramError> {
+    cached(Probe::Link(link_type as u32), || {
+        let (prog_type, attach_type, errno): (_, bpf_attach_type, _) = match link_type {
+            LinkType::RawTracePoint | LinkType::Tracing => {
+                return Ok(KernelVersion::at_least(5, 7, 0));
+            }
+            LinkType::Iter => return Ok(KernelVersion::at_least(5, 8, 0)),
+            LinkType::Xdp => return Ok(KernelVersion::at_least(5, 9, 0)),
+            LinkType::StructOps => return Ok(KernelVersion::at_least(6, 4, 0)),
+            LinkType::PerfEvent => return Ok(is_perf_link_supported()),
+            LinkType::Cgroup => (BPF_PROG_TYPE_CGROUP_SKB, BPF_CGROUP_INET_INGRESS, EBADF),
+            LinkType::Netns => (BPF_PROG_TYPE_FLOW_DISSECTOR, BPF_FLOW_DISSECTOR, EBADF),
+            LinkType::KProbeMulti => (BPF_PROG_TYPE_KPROBE, BPF_TRACE_KPROBE_MULTI, ESRCH),
+            LinkType::Netfilter => (BPF_PROG_TYPE_NETFILTER, BPF_NETFILTER, EAFNOSUPPORT),
+            LinkType::Tcx => (BPF_PROG_TYPE_SCHED_CLS, BPF_TCX_INGRESS, ENODEV),
+            LinkType::UProbeMulti => (BPF_PROG_TYPE_KPROBE, BPF_TRACE_UPROBE_MULTI, EBADF),
+            LinkType::Netkit => (BPF_PROG_TYPE_SCHED_CLS, BPF_NETKIT_PRIMARY, ENODEV),
+        };
+        let prog_fd = match bpf_probe_prog_load(prog_type, Some(attach_type), None, &mut []) {
+            Ok(prog_fd) => prog_fd,
+            Err(io_error) if is_unsupported(&io_error) => return Ok(false),
+            Err(io_error) => {
+                return Err(SyscallError {
+                    call: "bpf_prog_load",
+                    io_error,
+                }
+                .into());
+            }
+        };
+        let link = bpf_probe_link_create(prog_fd.as_fd(), attach_type);
+        Ok(matches!(link, Err(io_error) if io_error.raw_os_error() == Some(errno)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, slice};
+
+    use assert_matches::assert_matches;
+    use aya_obj::generated::bpf_cmd;
+    use libc::EPERM;
+
+    use super::*;
+    use crate::sys::{Syscall, override_syscall};
+
+    thread_local! {
+        // The errno and the verifier log the helper probe fails with.
+        static HELPER_PROBE: Cell<(i32, &'static [u8])> = const { Cell::new((0, b"")) };
+    }
+
+    // Mocks the program probe as successful and the helper probe as failing
+    // with `errno` and the verifier log `log`.
+    fn override_helper_probe(errno: i32, log: &'static [u8]) {
+        HELPER_PROBE.set((errno, log));
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_LOAD,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_3 };
+                // only the helper probe has a call instruction
+                if u.insn_cnt == 2 {
+                    return Ok(crate::MockableFd::mock_signed_fd().into());
+                }
+                let (errno, log) = HELPER_PROBE.get();
+                let log_buf =
+                    unsafe { slice::from_raw_parts_mut(u.log_buf as *mut u8, u.log_size as usize) };
+                log_buf[..log.len()].copy_from_slice(log);
+                Err((-1, io::Error::from_raw_os_error(errno)))
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EINVAL))),
+        });
+    }
+
+    #[test]
+    fn test_helper_supported() {
+        override_helper_probe(EACCES, b"R1 type=ctx expected=fp");
+        assert!(is_helper_supported(ProgramType::SocketFilter, 1000).unwrap());
+    }
+
+    #[test]
+    fn test_helper_unknown() {
+        override_helper_probe(EINVAL, b"invalid func unknown#1001");
+        assert!(!is_helper_supported(ProgramType::SocketFilter, 1001).unwrap());
+    }
+
+    #[test]
+    fn test_helper_not_allowed() {
+        override_helper_probe(EINVAL, b"unknown func bpf_probe_read#4");
+        assert!(!is_helper_supported(ProgramType::SocketFilter, 1002).unwrap());
+    }
+
+    #[test]
+    fn test_helper_eperm() {
+        override_helper_probe(EPERM, b"");
+        assert_matches!(
+            is_helper_supported(ProgramType::SocketFilter, 1003),
+            Err(ProgramError::SyscallError(SyscallError { call: "bpf_prog_load", io_error }))
+                if io_error.raw_os_error() == Some(EPERM)
+        );
+    }
+
+    #[test]
+    fn test_helper_empty_log() {
+        override_helper_probe(EINVAL, b"");
+        assert_matches!(
+            is_helper_supported(ProgramType::SocketFilter, 1004),
+            Err(ProgramError::SyscallError(SyscallError { call: "bpf_prog_load", io_error }))
+                if io_error.raw_os_error() == Some(EINVAL)
+        );
+    }
+}
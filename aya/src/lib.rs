@@ -73,6 +73,7 @@
 )]
 
 mod bpf;
+pub mod features;
 pub mod maps;
 pub mod pin;
 pub mod programs;
@@ -85,7 +86,7 @@ pub use aya_obj::btf::{Btf, BtfError};
 pub use bpf::*;
 pub use object::Endianness;
 #[doc(hidden)]
-pub use sys::netlink_set_link_up;
+pub use sys::{netlink_add_netkit, netlink_set_link_up};
 
 // See https://github.com/rust-lang/rust/pull/124210; this structure exists to avoid crashing the
 // process when we try to close a fake file descriptor.
@@ -0,0 +1,230 @@
+//! A memory region shared between eBPF programs and user space.
+//!
+//! See [`Arena`] for documentation and examples.
+use std::{borrow::Borrow, fmt, marker::PhantomData, os::fd::AsFd as _};
+
+use libc::{MAP_SHARED, PROT_READ, PROT_WRITE};
+
+use crate::{
+    maps::{MMap, MapData, MapError},
+    util::page_size,
+};
+
+/// A memory region shared between eBPF programs and user space.
+///
+/// Arenas are made of up to 4GiB of pages, which eBPF programs allocate with the
+/// `bpf_arena_alloc_pages` kfunc and user space maps into its memory with
+/// [`Arena::mmap`]. The kernel lays the arena out so that the lower 32 bits of
+/// the kernel and user space addresses of the pages are the same, so pointers
+/// stored in the arena by eBPF programs, after casting them to user space
+/// addresses, can be followed directly by user space and vice versa.
+///
+/// The `map_extra` field of the map definition is the address at which the
+/// arena is mapped in user space. When it's zero, the kernel picks the address
+/// on the first mapping and all later mappings must be made at the same address.
+/// Until then the verifier rejects the programs using the arena, so
+/// [`Arena::mmap`] must be called before loading them. Since the mapping
+/// borrows the arena, take the map out of [`Ebpf`](crate::Ebpf) to keep it
+/// mapped while loading the programs.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 6.9.
+///
+/// # Examples
+///
+/// ```no_run
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::maps::Arena;
+///
+/// let arena = Arena::try_from(bpf.take_map("ARENA").unwrap())?;
+/// let memory = arena.mmap()?;
+///
+/// // pointers handed out by the eBPF programs point into `memory`
+/// let node: u64 = 0x1000_0000_0000;
+/// if memory.contains(node as *const u8) {
+///     let value = unsafe { (node as *const u64).read_volatile() };
+///     println!("{value}");
+/// }
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+#[derive(Debug)]
+#[doc(alias = "BPF_MAP_TYPE_ARENA")]
+pub struct Arena<T> {
+    pub(crate) inner: T,
+}
+
+impl<T: Borrow<MapData>> Arena<T> {
+    pub(crate) fn new(map: T) -> Result<Self, MapError> {
+        Ok(Self { inner: map })
+    }
+
+    /// Returns the number of pages of the arena.
+    pub fn pages(&self) -> u32 {
+        self.inner.borrow().obj.max_entries()
+    }
+
+    /// Returns the address of the user space mapping of the arena requested
+    /// when it was created, or zero if it's picked by the kernel.
+    pub fn map_extra(&self) -> u64 {
+        self.inner.borrow().obj.map_extra()
+    }
+
+    /// Maps the arena into the memory of the process.
+    ///
+    /// The mapping is made at the address given by [`Arena::map_extra`], if
+    /// any. Pages that weren't allocated by the eBPF programs yet are allocated
+    /// when they're first accessed.
+    ///
+    /// If [`Arena::map_extra`] is zero, this must be called before loading the
+    /// programs using the arena: the verifier only accepts them once the user
+    /// space address of the arena is known.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::SyscallError`] if `mmap` fails, for instance because
+    /// the arena is already mapped at another address.
+    pub fn mmap(&self) -> Result<MmapArena<'_>, MapError> {
+        let data = self.inner.borrow();
+        let len = data.obj.max_entries() as usize * page_size();
+        let addr = data.obj.map_extra() as *mut _;
+        let mmap = MMap::new_at(
+            addr,
+            data.fd().as_fd(),
+            len,
+            PROT_READ | PROT_WRITE,
+            MAP_SHARED,
+            0,
+        )?;
+        Ok(MmapArena {
+            mmap,
+            _arena: PhantomData,
+        })
+    }
+}
+
+/// The user space mapping of an [`Arena`].
+///
+/// The mapping borrows the [`Arena`] and the memory is unmapped when it's
+/// dropped. It may be written by eBPF programs at any time, so it's only
+/// exposed through raw pointers.
+pub struct MmapArena<'a> {
+    mmap: MMap,
+    _arena: PhantomData<&'a ()>,
+}
+
+impl fmt::Debug for MmapArena<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MmapArena")
+            .field("ptr", &self.as_ptr())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl MmapArena<'_> {
+    /// Returns a pointer to the start of the arena.
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.mmap.ptr.as_ptr().cast()
+    }
+
+    /// Returns the size of the arena in bytes.
+    #[expect(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.mmap.as_ref().len()
+    }
+
+    /// Returns whether `ptr` points into the arena.
+    pub fn contains(&self, ptr: *const u8) -> bool {
+        let start = self.as_ptr().cast_const();
+        (start..start.wrapping_add(self.len())).contains(&ptr)
+    }
+
+    /// Returns a pointer to the byte at `offset` from the start of the arena.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::OutOfBounds`] if `offset` is past the end of the
+    /// arena.
+    pub fn ptr_at(&self, offset: usize) -> Result<*mut u8, MapError> {
+        if offset >= self.len() {
+            return Err(MapError::OutOfBounds {
+                index: u32::try_from(offset).unwrap_or(u32::MAX),
+                max_entries: u32::try_from(self.len()).unwrap_or(u32::MAX),
+            });
+        }
+        Ok(unsafe { self.as_ptr().add(offset) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use assert_matches::assert_matches;
+    use aya_obj::generated::{
+        BPF_F_MMAPABLE,
+        bpf_map_type::{BPF_MAP_TYPE_ARENA, BPF_MAP_TYPE_ARRAY},
+    };
+
+    use super::*;
+    use crate::{
+        maps::{
+            Map,
+            test_utils::{self, new_map},
+        },
+        sys::{SyscallError, TEST_MMAP_RET},
+    };
+
+    fn new_obj_map() -> aya_obj::Map {
+        let mut obj = test_utils::new_obj_map_with_max_entries::<u32>(BPF_MAP_TYPE_ARENA, 4);
+        obj.set_map_flags(BPF_F_MMAPABLE);
+        obj
+    }
+
+    #[test]
+    fn test_try_from_wrong_map() {
+        let map = new_map(test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_ARRAY));
+        let map = Map::Array(map);
+        assert_matches!(Arena::try_from(&map), Err(MapError::InvalidMapType { .. }));
+    }
+
+    #[test]
+    fn test_try_from_ok() {
+        let map = new_map(new_obj_map());
+        let map = Map::Arena(map);
+        let arena = Arena::try_from(&map).unwrap();
+        assert_eq!(arena.pages(), 4);
+        assert_eq!(arena.map_extra(), 0);
+    }
+
+    #[test]
+    fn test_mmap() {
+        let map = new_map(new_obj_map());
+        let arena = Arena::new(&map).unwrap();
+
+        let len = 4 * page_size();
+        let mut buf = vec![0u8; len];
+        TEST_MMAP_RET.with(|ret| *ret.borrow_mut() = buf.as_mut_ptr().cast());
+
+        let memory = arena.mmap().unwrap();
+        assert_eq!(memory.as_ptr(), buf.as_mut_ptr());
+        assert_eq!(memory.len(), len);
+        assert!(memory.contains(buf[10..].as_ptr()));
+        assert!(!memory.contains(buf.as_ptr().wrapping_add(len)));
+        assert_eq!(memory.ptr_at(10).unwrap(), buf[10..].as_mut_ptr());
+        assert_matches!(memory.ptr_at(len), Err(MapError::OutOfBounds { .. }));
+    }
+
+    #[test]
+    fn test_mmap_error() {
+        let map = new_map(new_obj_map());
+        let arena = Arena::new(&map).unwrap();
+
+        TEST_MMAP_RET.with(|ret| *ret.borrow_mut() = ptr::null_mut());
+        assert_matches!(
+            arena.mmap(),
+            Err(MapError::SyscallError(SyscallError { call: "mmap", .. }))
+        );
+    }
+}
@@ -1,13 +1,22 @@
 use std::{
     borrow::{Borrow, BorrowMut},
+    fmt,
     marker::PhantomData,
+    mem,
     os::fd::AsFd as _,
+    sync::atomic::{AtomicU32, AtomicU64},
 };
 
+use aya_obj::generated::BPF_F_MMAPABLE;
+use libc::{MAP_SHARED, PROT_READ, PROT_WRITE, c_int};
+
 use crate::{
     Pod,
-    maps::{IterableMap, MapData, MapError, check_bounds, check_kv_size},
+    maps::{
+        IterableMap, MMap, MapBatchIter, MapData, MapError, batch, check_bounds, check_kv_size,
+    },
     sys::{SyscallError, bpf_map_lookup_elem, bpf_map_update_elem},
+    util::page_size,
 };
 
 /// A fixed-size array.
@@ -77,6 +86,42 @@ impl<T: Borrow<MapData>, V: Pod> Array<T, V> {
     pub fn iter(&self) -> impl Iterator<Item = Result<V, MapError>> + '_ {
         (0..self.len()).map(move |i| self.get(&i, 0))
     }
+
+    /// Maps the array into the memory of the process, read-only.
+    ///
+    /// The elements of the returned [`MmapArray`] can be read without any
+    /// syscall, which makes polling large arrays much cheaper than with
+    /// [`Array::get`]. Use [`Array::mmap_mut`] to also write them.
+    ///
+    /// The map must have been created with the `BPF_F_MMAPABLE` flag, see
+    /// `Array::mmapable` in `aya-ebpf`.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.5.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::NotMmapable`] if the map wasn't created with the
+    /// `BPF_F_MMAPABLE` flag, [`MapError::SyscallError`] if `mmap` fails.
+    pub fn mmap(&self) -> Result<MmapArray<'_, V>, MapError> {
+        MmapArray::new(self.inner.borrow(), PROT_READ)
+    }
+
+    /// An iterator over the indices and elements of the array, reading up to
+    /// `batch_size` of them from the kernel at once. The iterator item type is
+    /// `Result<(u32, V), MapError>`.
+    ///
+    /// Falls back to reading one element at a time if the kernel doesn't
+    /// support batch operations on arrays, which were added in 5.7.
+    pub fn iter_batched(&self, batch_size: u32) -> MapBatchIter<'_, u32, V> {
+        MapBatchIter::new(
+            self.inner.borrow(),
+            batch::BatchValues::plain(),
+            batch_size,
+            false,
+        )
+    }
 }
 
 impl<T: BorrowMut<MapData>, V: Pod> Array<T, V> {
@@ -98,6 +143,41 @@ impl<T: BorrowMut<MapData>, V: Pod> Array<T, V> {
         })?;
         Ok(())
     }
+
+    /// Sets the elements at the given indices with a single syscall.
+    ///
+    /// Falls back to setting one element at a time if the kernel doesn't
+    /// support batch operations on arrays, which were added in 5.7.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::OutOfBounds`] if an index is out of bounds, [`MapError::SyscallError`]
+    /// if `bpf_map_update_batch` fails.
+    pub fn set_batch(&mut self, entries: &[(u32, V)], flags: u64) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        for (index, _) in entries {
+            check_bounds(data, *index)?;
+        }
+        batch::update_batch(data, &batch::BatchValues::plain(), entries, flags)
+    }
+
+    /// Maps the array into the memory of the process, read-write.
+    ///
+    /// Like [`Array::mmap`], but the elements of the returned
+    /// [`MmapArrayMut`] can also be written without any syscall.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.5.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::NotMmapable`] if the map wasn't created with the
+    /// `BPF_F_MMAPABLE` flag, [`MapError::SyscallError`] if `mmap` fails.
+    pub fn mmap_mut(&mut self) -> Result<MmapArrayMut<'_, V>, MapError> {
+        let inner = MmapArray::new(self.inner.borrow_mut(), PROT_READ | PROT_WRITE)?;
+        Ok(MmapArrayMut { inner })
+    }
 }
 
 impl<T: Borrow<MapData>, V: Pod> IterableMap<u32, V> for Array<T, V> {
@@ -109,3 +189,186 @@ impl<T: Borrow<MapData>, V: Pod> IterableMap<u32, V> for Array<T, V> {
         self.get(index, 0)
     }
 }
+
+/// An [`Array`] mapped read-only into the memory of the process.
+///
+/// Returned by [`Array::mmap`]. The elements live in memory shared with the
+/// eBPF programs using the map, so reads don't need any syscall. Since the
+/// eBPF programs may modify the elements at any time, they are read with
+/// volatile reads.
+///
+/// The mapping borrows the [`Array`], so the array can't be mapped
+/// read-write while it's alive. The memory is unmapped when it's dropped.
+///
+/// # Examples
+/// ```no_run
+/// # let bpf = aya::Ebpf::load(&[])?;
+/// use aya::maps::Array;
+///
+/// let array = Array::<_, u64>::try_from(bpf.map("COUNTERS").unwrap())?;
+/// let counters = array.mmap()?;
+/// let packets = counters.read(0)?;
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+pub struct MmapArray<'a, V: Pod> {
+    mmap: MMap,
+    len: u32,
+    _v: PhantomData<&'a V>,
+}
+
+impl<V: Pod> fmt::Debug for MmapArray<'_, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MmapArray")
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, V: Pod> MmapArray<'a, V> {
+    // The kernel rounds the size of the elements of arrays up to 8 bytes.
+    const STRIDE: usize = (mem::size_of::<V>() + 7) & !7;
+
+    fn new(data: &'a MapData, prot: c_int) -> Result<Self, MapError> {
+        if data.obj.map_flags() & BPF_F_MMAPABLE == 0 {
+            return Err(MapError::NotMmapable);
+        }
+        let len = data.obj.max_entries();
+        let size = (len as usize * Self::STRIDE).next_multiple_of(page_size());
+        let mmap = MMap::new(data.fd().as_fd(), size, prot, MAP_SHARED, 0)?;
+        Ok(Self {
+            mmap,
+            len,
+            _v: PhantomData,
+        })
+    }
+
+    /// Returns the number of elements in the array.
+    #[expect(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    fn element(&self, index: u32) -> Result<*mut V, MapError> {
+        let Self { mmap, len, _v } = self;
+        if index >= *len {
+            return Err(MapError::OutOfBounds {
+                index,
+                max_entries: *len,
+            });
+        }
+        Ok(unsafe { mmap.ptr.as_ptr().byte_add(index as usize * Self::STRIDE) }.cast())
+    }
+
+    /// Returns the value stored at the given index, with a volatile read.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::OutOfBounds`] if `index` is out of bounds.
+    pub fn read(&self, index: u32) -> Result<V, MapError> {
+        let element = self.element(index)?;
+        Ok(unsafe { element.read_volatile() })
+    }
+}
+
+/// An [`Array`] mapped read-write into the memory of the process.
+///
+/// Returned by [`Array::mmap_mut`]. Like [`MmapArray`], but the elements can
+/// also be written without any syscall. The mapping borrows the [`Array`]
+/// mutably, so it's the only mapping of the array in the process while it's
+/// alive, and [`MmapArrayMut::write`] takes a mutable reference.
+///
+/// The eBPF programs using the map may still modify the elements at any time.
+/// Use the atomic accessors for elements that are updated concurrently by the
+/// eBPF programs or by several threads.
+///
+/// # Examples
+/// ```no_run
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use std::sync::atomic::Ordering;
+///
+/// use aya::maps::Array;
+///
+/// let mut array = Array::<_, u64>::try_from(bpf.map_mut("COUNTERS").unwrap())?;
+/// let mut counters = array.mmap_mut()?;
+/// counters.write(0, 0)?;
+/// counters.atomic_u64(1)?.fetch_add(1, Ordering::Relaxed);
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+pub struct MmapArrayMut<'a, V: Pod> {
+    inner: MmapArray<'a, V>,
+}
+
+impl<V: Pod> fmt::Debug for MmapArrayMut<'_, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MmapArrayMut")
+            .field("len", &self.inner.len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<V: Pod> MmapArrayMut<'_, V> {
+    /// Returns the number of elements in the array.
+    #[expect(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u32 {
+        self.inner.len()
+    }
+
+    /// Returns the value stored at the given index, with a volatile read.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::OutOfBounds`] if `index` is out of bounds.
+    pub fn read(&self, index: u32) -> Result<V, MapError> {
+        self.inner.read(index)
+    }
+
+    /// Sets the value of the element at the given index, with a volatile
+    /// write.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::OutOfBounds`] if `index` is out of bounds.
+    pub fn write(&mut self, index: u32, value: V) -> Result<(), MapError> {
+        let element = self.inner.element(index)?;
+        unsafe { element.write_volatile(value) };
+        Ok(())
+    }
+
+    /// Returns the element at the given index as an [`AtomicU32`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::InvalidValueSize`] if `V` isn't 4 bytes long,
+    /// [`MapError::OutOfBounds`] if `index` is out of bounds.
+    pub fn atomic_u32(&self, index: u32) -> Result<&AtomicU32, MapError> {
+        let size = mem::size_of::<V>();
+        if size != mem::size_of::<AtomicU32>() {
+            return Err(MapError::InvalidValueSize {
+                size,
+                expected: mem::size_of::<AtomicU32>(),
+            });
+        }
+        let element = self.inner.element(index)?;
+        // The mapping is page aligned and elements are 8 bytes aligned.
+        Ok(unsafe { AtomicU32::from_ptr(element.cast()) })
+    }
+
+    /// Returns the element at the given index as an [`AtomicU64`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::InvalidValueSize`] if `V` isn't 8 bytes long,
+    /// [`MapError::OutOfBounds`] if `index` is out of bounds.
+    pub fn atomic_u64(&self, index: u32) -> Result<&AtomicU64, MapError> {
+        let size = mem::size_of::<V>();
+        if size != mem::size_of::<AtomicU64>() {
+            return Err(MapError::InvalidValueSize {
+                size,
+                expected: mem::size_of::<AtomicU64>(),
+            });
+        }
+        let element = self.inner.element(index)?;
+        // The mapping is page aligned and elements are 8 bytes aligned.
+        Ok(unsafe { AtomicU64::from_ptr(element.cast()) })
+    }
+}
@@ -0,0 +1,268 @@
+//! An array of eBPF maps.
+
+use std::{
+    borrow::{Borrow, BorrowMut},
+    os::fd::{AsFd as _, AsRawFd as _, RawFd},
+};
+
+use crate::{
+    maps::{MapData, MapError, MapKeys, check_bounds, check_kv_size},
+    sys::{SyscallError, bpf_map_delete_elem, bpf_map_lookup_elem, bpf_map_update_elem},
+};
+
+/// An array of eBPF maps.
+///
+/// The values of the array are maps which eBPF programs can look up and then
+/// access with the regular map helpers. All the inner maps must have the same
+/// layout as the inner map declared along with the array in the eBPF program.
+/// Replacing an inner map is atomic, which makes it possible to swap a whole map
+/// while eBPF programs are running.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.12.
+///
+/// # Examples
+/// ```no_run
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::maps::{ArrayOfMaps, MapData};
+///
+/// let mut outer = ArrayOfMaps::try_from(bpf.map_mut("OUTER").unwrap())?;
+///
+/// let inner = MapData::from_pin("/sys/fs/bpf/inner")?;
+/// outer.set(0, &inner, 0)?;
+///
+/// let id = outer.get(&0, 0)?;
+/// assert_eq!(id, inner.info()?.id());
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+#[doc(alias = "BPF_MAP_TYPE_ARRAY_OF_MAPS")]
+#[derive(Debug)]
+pub struct ArrayOfMaps<T> {
+    pub(crate) inner: T,
+}
+
+impl<T: Borrow<MapData>> ArrayOfMaps<T> {
+    pub(crate) fn new(map: T) -> Result<Self, MapError> {
+        let data = map.borrow();
+        check_kv_size::<u32, RawFd>(data)?;
+
+        Ok(Self { inner: map })
+    }
+
+    /// Returns the id of the map stored at the given index.
+    ///
+    /// The map can be opened with [`MapData::from_id`].
+    pub fn get(&self, index: &u32, flags: u64) -> Result<u32, MapError> {
+        let data = self.inner.borrow();
+        check_bounds(data, *index)?;
+        let fd = data.fd().as_fd();
+
+        let value = bpf_map_lookup_elem(fd, index, flags).map_err(|io_error| SyscallError {
+            call: "bpf_map_lookup_elem",
+            io_error,
+        })?;
+        value.ok_or(MapError::KeyNotFound)
+    }
+
+    /// An iterator over the indices of the array that point to a map. The iterator item type
+    /// is `Result<u32, MapError>`.
+    pub fn indices(&self) -> MapKeys<'_, u32> {
+        MapKeys::new(self.inner.borrow())
+    }
+}
+
+impl<T: BorrowMut<MapData>> ArrayOfMaps<T> {
+    /// Stores `value` at the given index, replacing any map previously stored there.
+    pub fn set(&mut self, index: u32, value: &MapData, flags: u64) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        check_bounds(data, index)?;
+        let fd = data.fd().as_fd();
+        let map_fd = value.fd().as_fd().as_raw_fd();
+
+        bpf_map_update_elem(fd, Some(&index), &map_fd, flags)
+            .map_err(|io_error| SyscallError {
+                call: "bpf_map_update_elem",
+                io_error,
+            })
+            .map_err(Into::into)
+    }
+
+    /// Clears the map stored at the given index.
+    pub fn clear_index(&mut self, index: &u32) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        check_bounds(data, *index)?;
+        let fd = data.fd().as_fd();
+
+        bpf_map_delete_elem(fd, index)
+            .map_err(|io_error| SyscallError {
+                call: "bpf_map_delete_elem",
+                io_error,
+            })
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use assert_matches::assert_matches;
+    use aya_obj::generated::{
+        bpf_cmd,
+        bpf_map_type::{BPF_MAP_TYPE_ARRAY, BPF_MAP_TYPE_ARRAY_OF_MAPS},
+    };
+    use libc::{EFAULT, ENOENT};
+
+    use super::*;
+    use crate::{
+        maps::{
+            Map,
+            test_utils::{self, new_map},
+        },
+        sys::{SysResult, Syscall, override_syscall},
+    };
+
+    fn new_obj_map() -> aya_obj::Map {
+        test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_ARRAY_OF_MAPS)
+    }
+
+    fn sys_error(value: i32) -> SysResult {
+        Err((-1, io::Error::from_raw_os_error(value)))
+    }
+
+    #[test]
+    fn test_wrong_key_size() {
+        let map = new_map(test_utils::new_obj_map::<u8>(BPF_MAP_TYPE_ARRAY_OF_MAPS));
+        assert_matches!(
+            ArrayOfMaps::new(&map),
+            Err(MapError::InvalidKeySize {
+                size: 4,
+                expected: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_from_wrong_map() {
+        let map = new_map(test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_ARRAY));
+        let map = Map::Array(map);
+        assert_matches!(
+            ArrayOfMaps::try_from(&map),
+            Err(MapError::InvalidMapType { .. })
+        );
+    }
+
+    #[test]
+    fn test_try_from_ok() {
+        let map = new_map(new_obj_map());
+        let map = Map::ArrayOfMaps(map);
+        let _: ArrayOfMaps<_> = map.try_into().unwrap();
+    }
+
+    #[test]
+    fn test_set_out_of_bounds() {
+        let mut map = new_map(new_obj_map());
+        let inner = new_map(test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_ARRAY));
+        let mut array = ArrayOfMaps::new(&mut map).unwrap();
+
+        assert_matches!(
+            array.set(1024, &inner, 0),
+            Err(MapError::OutOfBounds {
+                index: 1024,
+                max_entries: 1024
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_ok() {
+        let mut map = new_map(new_obj_map());
+        let inner = new_map(test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_ARRAY));
+        let mut array = ArrayOfMaps::new(&mut map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_ELEM,
+                attr,
+            } => {
+                let value = unsafe { attr.__bindgen_anon_2.__bindgen_anon_1.value } as *const u32;
+                assert_eq!(unsafe { *value }, crate::MockableFd::mock_unsigned_fd());
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+
+        assert_matches!(array.set(1, &inner, 0), Ok(()));
+    }
+
+    #[test]
+    fn test_get_out_of_bounds() {
+        let map = new_map(new_obj_map());
+        let array = ArrayOfMaps::new(&map).unwrap();
+
+        assert_matches!(
+            array.get(&1024, 0),
+            Err(MapError::OutOfBounds {
+                index: 1024,
+                max_entries: 1024
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_not_found() {
+        let map = new_map(new_obj_map());
+        let array = ArrayOfMaps::new(&map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_ELEM,
+                ..
+            } => sys_error(ENOENT),
+            _ => sys_error(EFAULT),
+        });
+
+        assert_matches!(array.get(&1, 0), Err(MapError::KeyNotFound));
+    }
+
+    #[test]
+    fn test_get_ok() {
+        let map = new_map(new_obj_map());
+        let array = ArrayOfMaps::new(&map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_ELEM,
+                attr,
+            } => {
+                let value = unsafe { attr.__bindgen_anon_2.__bindgen_anon_1.value } as *mut u32;
+                unsafe { value.write(42) };
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+
+        assert_matches!(array.get(&1, 0), Ok(42));
+    }
+
+    #[test]
+    fn test_clear_index_ok() {
+        let mut map = new_map(new_obj_map());
+        let mut array = ArrayOfMaps::new(&mut map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_DELETE_ELEM,
+                attr,
+            } => {
+                let key = unsafe { attr.__bindgen_anon_2.key } as *const u32;
+                assert_eq!(unsafe { *key }, 1);
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+
+        assert_matches!(array.clear_index(&1), Ok(()));
+    }
+}
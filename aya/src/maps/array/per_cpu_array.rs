@@ -6,7 +6,10 @@ use std::{
 
 use crate::{
     Pod,
-    maps::{IterableMap, MapData, MapError, PerCpuValues, check_bounds, check_kv_size},
+    maps::{
+        IterableMap, MapBatchIter, MapData, MapError, PerCpuValues, batch, check_bounds,
+        check_kv_size,
+    },
     sys::{SyscallError, bpf_map_lookup_elem_per_cpu, bpf_map_update_elem_per_cpu},
 };
 
@@ -97,6 +100,21 @@ impl<T: Borrow<MapData>, V: Pod> PerCpuArray<T, V> {
     pub fn iter(&self) -> impl Iterator<Item = Result<PerCpuValues<V>, MapError>> + '_ {
         (0..self.len()).map(move |i| self.get(&i, 0))
     }
+
+    /// An iterator over the indices and elements of the array, reading up to
+    /// `batch_size` of them from the kernel at once. The iterator item type is
+    /// `Result<(u32, PerCpuValues<V>), MapError>`.
+    ///
+    /// Falls back to reading one element at a time if the kernel doesn't
+    /// support batch operations on arrays, which were added in 5.7.
+    pub fn iter_batched(&self, batch_size: u32) -> MapBatchIter<'_, u32, PerCpuValues<V>> {
+        MapBatchIter::new(
+            self.inner.borrow(),
+            batch::BatchValues::per_cpu(),
+            batch_size,
+            false,
+        )
+    }
 }
 
 impl<T: BorrowMut<MapData>, V: Pod> PerCpuArray<T, V> {
@@ -119,6 +137,27 @@ impl<T: BorrowMut<MapData>, V: Pod> PerCpuArray<T, V> {
         })?;
         Ok(())
     }
+
+    /// Sets the elements at the given indices with a single syscall.
+    ///
+    /// Falls back to setting one element at a time if the kernel doesn't
+    /// support batch operations on arrays, which were added in 5.7.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::OutOfBounds`] if an index is out of bounds, [`MapError::SyscallError`]
+    /// if `bpf_map_update_batch` fails.
+    pub fn set_batch(
+        &mut self,
+        entries: &[(u32, PerCpuValues<V>)],
+        flags: u64,
+    ) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        for (index, _) in entries {
+            check_bounds(data, *index)?;
+        }
+        batch::update_batch(data, &batch::BatchValues::per_cpu(), entries, flags)
+    }
 }
 
 impl<T: Borrow<MapData>, V: Pod> IterableMap<u32, PerCpuValues<V>> for PerCpuArray<T, V> {
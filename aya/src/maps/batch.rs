@@ -0,0 +1,642 @@
+//! Batched map operations.
+use std::{
+    collections::VecDeque,
+    io,
+    mem::{self, MaybeUninit},
+    os::fd::{AsFd as _, BorrowedFd},
+    ptr,
+};
+
+use libc::{EINVAL, ENOENT, ENOSPC, EOPNOTSUPP};
+
+use crate::{
+    Pod,
+    features::ENOTSUPP,
+    maps::{MapData, MapError, PerCpuKernelMem, PerCpuValues},
+    sys::{
+        SyscallError, bpf_map_delete_batch, bpf_map_delete_elem, bpf_map_get_next_key,
+        bpf_map_lookup_and_delete_batch, bpf_map_lookup_batch, bpf_map_lookup_elem,
+        bpf_map_lookup_elem_per_cpu, bpf_map_update_batch, bpf_map_update_elem_ptr,
+    },
+    util::{bytes_of, nr_cpus},
+};
+
+// Returns whether a batch operation failed because it isn't supported, in
+// which case no entry was processed.
+//
+// Map types that don't implement an operation fail with ENOTSUPP. Kernels
+// without batch operations reject the commands with EINVAL, which is also
+// returned for invalid arguments, so EINVAL only means unsupported if an empty
+// batch, which is valid for every map type, is rejected too.
+fn is_batch_unsupported(fd: BorrowedFd<'_>, error: &io::Error) -> bool {
+    match error.raw_os_error() {
+        Some(EOPNOTSUPP | ENOTSUPP) => true,
+        Some(EINVAL) => matches!(
+            bpf_map_delete_batch::<u32>(fd, &[], 0),
+            Err(io_error) if io_error.raw_os_error() == Some(EINVAL)
+        ),
+        _ => false,
+    }
+}
+
+/// How the values of a map are stored in the buffers of batch operations.
+pub(crate) struct BatchValues<K, V> {
+    stride: fn() -> io::Result<usize>,
+    read: fn(&[u8]) -> V,
+    write: fn(&V, &mut Vec<u8>) -> io::Result<()>,
+    lookup: fn(BorrowedFd<'_>, &K) -> io::Result<Option<V>>,
+}
+
+impl<K: Pod, V: Pod> BatchValues<K, V> {
+    pub(crate) fn plain() -> Self {
+        Self {
+            stride: || Ok(mem::size_of::<V>()),
+            read: |bytes| {
+                assert_eq!(bytes.len(), mem::size_of::<V>());
+                unsafe { ptr::read_unaligned(bytes.as_ptr().cast()) }
+            },
+            write: |value, bytes| {
+                bytes.extend_from_slice(unsafe { bytes_of(value) });
+                Ok(())
+            },
+            lookup: |fd, key| bpf_map_lookup_elem(fd, key, 0),
+        }
+    }
+}
+
+impl<K: Pod, V: Pod> BatchValues<K, PerCpuValues<V>> {
+    pub(crate) fn per_cpu() -> Self {
+        Self {
+            stride: || {
+                let nr_cpus = nr_cpus().map_err(|(_, error)| error)?;
+                Ok(nr_cpus * ((mem::size_of::<V>() + 7) & !7))
+            },
+            read: |bytes| unsafe {
+                PerCpuValues::from_kernel_mem(PerCpuKernelMem {
+                    bytes: bytes.to_vec(),
+                })
+            },
+            write: |values, bytes| {
+                let mem = values.build_kernel_mem()?;
+                bytes.extend_from_slice(&mem.bytes);
+                Ok(())
+            },
+            lookup: |fd, key| bpf_map_lookup_elem_per_cpu(fd, key, 0),
+        }
+    }
+}
+
+/// Updates the given entries of the map with a single `BPF_MAP_UPDATE_BATCH`
+/// call, or one update per entry if the kernel doesn't support it.
+pub(crate) fn update_batch<K: Pod, V>(
+    map: &MapData,
+    values: &BatchValues<K, V>,
+    entries: &[(K, V)],
+    flags: u64,
+) -> Result<(), MapError> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let fd = map.fd().as_fd();
+    let stride = (values.stride)()?;
+    let keys = entries.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+    let mut bytes = Vec::with_capacity(entries.len() * stride);
+    for (_, value) in entries {
+        (values.write)(value, &mut bytes)?;
+    }
+
+    match bpf_map_update_batch(fd, &keys, &bytes, flags) {
+        Ok(()) => Ok(()),
+        Err(io_error) if is_batch_unsupported(fd, &io_error) => {
+            for (key, value) in keys.iter().zip(bytes.chunks_exact_mut(stride)) {
+                bpf_map_update_elem_ptr(fd, key, value.as_mut_ptr(), flags).map_err(
+                    |io_error| SyscallError {
+                        call: "bpf_map_update_elem",
+                        io_error,
+                    },
+                )?;
+            }
+            Ok(())
+        }
+        Err(io_error) => Err(SyscallError {
+            call: "bpf_map_update_batch",
+            io_error,
+        }
+        .into()),
+    }
+}
+
+/// Deletes the given keys from the map with a single `BPF_MAP_DELETE_BATCH`
+/// call, or one deletion per key if the kernel doesn't support it.
+pub(crate) fn delete_batch<K: Pod>(map: &MapData, keys: &[K]) -> Result<(), MapError> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let fd = map.fd().as_fd();
+    match bpf_map_delete_batch(fd, keys, 0) {
+        Ok(()) => Ok(()),
+        Err(io_error) if is_batch_unsupported(fd, &io_error) => {
+            for key in keys {
+                bpf_map_delete_elem(fd, key).map_err(|io_error| SyscallError {
+                    call: "bpf_map_delete_elem",
+                    io_error,
+                })?;
+            }
+            Ok(())
+        }
+        Err(io_error) => Err(SyscallError {
+            call: "bpf_map_delete_batch",
+            io_error,
+        }
+        .into()),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum BatchState<K> {
+    Batch { started: bool },
+    PerKey { key: Option<K> },
+    Done,
+}
+
+/// Iterator returned by `map.iter_batched()` and `map.drain_batched()`.
+///
+/// The entries are read from the kernel up to `batch_size` at a time with
+/// `BPF_MAP_LOOKUP_BATCH`, or `BPF_MAP_LOOKUP_AND_DELETE_BATCH` when draining,
+/// which is much faster than looking up each key and gives a consistent view of
+/// each batch. If the kernel or the map type doesn't support batch operations,
+/// the iterator falls back to reading the entries one key at a time.
+pub struct MapBatchIter<'coll, K: Pod, V> {
+    map: &'coll MapData,
+    values: BatchValues<K, V>,
+    batch_size: usize,
+    delete: bool,
+    state: BatchState<K>,
+    batch: Vec<u8>,
+    entries: VecDeque<(K, V)>,
+}
+
+impl<'coll, K: Pod, V> MapBatchIter<'coll, K, V> {
+    pub(crate) fn new(
+        map: &'coll MapData,
+        values: BatchValues<K, V>,
+        batch_size: u32,
+        delete: bool,
+    ) -> Self {
+        // The position in the map is a bucket index for hash maps and the last
+        // key read for the other map types.
+        let batch_len = mem::size_of::<K>().max(mem::size_of::<u32>());
+        Self {
+            map,
+            values,
+            batch_size: batch_size.max(1) as usize,
+            delete,
+            state: BatchState::Batch { started: false },
+            batch: vec![0; batch_len],
+            entries: VecDeque::new(),
+        }
+    }
+
+    fn next_batch(&mut self, started: bool) -> Result<(), MapError> {
+        let fd = self.map.fd().as_fd();
+        let stride = (self.values.stride)()?;
+        loop {
+            let mut keys = vec![MaybeUninit::<K>::uninit(); self.batch_size];
+            let mut values = vec![0u8; self.batch_size * stride];
+            let mut out_batch = vec![0u8; self.batch.len()];
+            let in_batch = started.then_some(self.batch.as_slice());
+            let mut count = 0;
+            let (call, result) = if self.delete {
+                (
+                    "bpf_map_lookup_and_delete_batch",
+                    bpf_map_lookup_and_delete_batch(
+                        fd,
+                        in_batch,
+                        &mut out_batch,
+                        &mut keys,
+                        &mut values,
+                        &mut count,
+                        0,
+                    ),
+                )
+            } else {
+                (
+                    "bpf_map_lookup_batch",
+                    bpf_map_lookup_batch(
+                        fd,
+                        in_batch,
+                        &mut out_batch,
+                        &mut keys,
+                        &mut values,
+                        &mut count,
+                        0,
+                    ),
+                )
+            };
+            let done = match result {
+                Ok(()) => false,
+                Err(io_error) if io_error.raw_os_error() == Some(ENOENT) => true,
+                // A hash bucket holds more entries than fit in the batch.
+                Err(io_error) if io_error.raw_os_error() == Some(ENOSPC) && count == 0 => {
+                    self.batch_size *= 2;
+                    continue;
+                }
+                Err(io_error) if !started && is_batch_unsupported(fd, &io_error) => {
+                    self.state = BatchState::PerKey { key: None };
+                    return Ok(());
+                }
+                Err(io_error) => return Err(SyscallError { call, io_error }.into()),
+            };
+
+            let read = self.values.read;
+            self.entries.extend(
+                keys.iter()
+                    .zip(values.chunks_exact(stride))
+                    .take(count as usize)
+                    .map(|(key, value)| (unsafe { key.assume_init() }, read(value))),
+            );
+            self.batch = out_batch;
+            self.state = if done {
+                BatchState::Done
+            } else {
+                BatchState::Batch { started: true }
+            };
+            return Ok(());
+        }
+    }
+
+    fn next_per_key(&mut self, mut key: Option<K>) -> Result<(), MapError> {
+        let fd = self.map.fd().as_fd();
+        loop {
+            // Drained entries are deleted as they are read, so the next one is
+            // always the first key of the map.
+            let prev = if self.delete { None } else { key.as_ref() };
+            let next = bpf_map_get_next_key(fd, prev).map_err(|io_error| SyscallError {
+                call: "bpf_map_get_next_key",
+                io_error,
+            })?;
+            let Some(next) = next else {
+                self.state = BatchState::Done;
+                return Ok(());
+            };
+            key = Some(next);
+            self.state = BatchState::PerKey { key };
+
+            let value = (self.values.lookup)(fd, &next).map_err(|io_error| SyscallError {
+                call: "bpf_map_lookup_elem",
+                io_error,
+            })?;
+            let Some(value) = value else {
+                continue;
+            };
+            if self.delete {
+                match bpf_map_delete_elem(fd, &next) {
+                    Ok(()) => {}
+                    Err(io_error) if io_error.raw_os_error() == Some(ENOENT) => continue,
+                    Err(io_error) => {
+                        return Err(SyscallError {
+                            call: "bpf_map_delete_elem",
+                            io_error,
+                        }
+                        .into());
+                    }
+                }
+            }
+            self.entries.push_back((next, value));
+            return Ok(());
+        }
+    }
+}
+
+impl<K: Pod, V> Iterator for MapBatchIter<'_, K, V> {
+    type Item = Result<(K, V), MapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.entries.pop_front() {
+                return Some(Ok(entry));
+            }
+            let result = match self.state {
+                BatchState::Batch { started } => self.next_batch(started),
+                BatchState::PerKey { key } => self.next_per_key(key),
+                BatchState::Done => return None,
+            };
+            if let Err(err) = result {
+                self.state = BatchState::Done;
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, slice};
+
+    use assert_matches::assert_matches;
+    use aya_obj::generated::{bpf_attr, bpf_cmd, bpf_map_type::BPF_MAP_TYPE_HASH};
+    use libc::EFAULT;
+
+    use super::*;
+    use crate::{
+        maps::{
+            HashMap,
+            test_utils::{self, new_map},
+        },
+        sys::{SysResult, Syscall, override_syscall},
+    };
+
+    fn new_obj_map() -> aya_obj::Map {
+        test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_HASH)
+    }
+
+    fn sys_error(value: i32) -> SysResult {
+        Err((-1, io::Error::from_raw_os_error(value)))
+    }
+
+    unsafe fn batch_keys<'a>(attr: &bpf_attr) -> &'a [u32] {
+        let u = unsafe { &attr.batch };
+        unsafe { slice::from_raw_parts(u.keys as *const u32, u.count as usize) }
+    }
+
+    unsafe fn batch_values<'a>(attr: &bpf_attr) -> &'a [u32] {
+        let u = unsafe { &attr.batch };
+        unsafe { slice::from_raw_parts(u.values as *const u32, u.count as usize) }
+    }
+
+    // Returns the entries (10, 100), (20, 200) and (30, 300), two at a time.
+    fn lookup_batch(attr: &mut bpf_attr) -> SysResult {
+        let u = unsafe { &mut attr.batch };
+        let keys = u.keys as *mut u32;
+        let values = u.values as *mut u32;
+        let out_batch = u.out_batch as *mut u32;
+        let start = if u.in_batch == 0 {
+            0
+        } else {
+            unsafe { *(u.in_batch as *const u32) }
+        };
+        let count = (3 - start).min(u.count);
+        for i in 0..count {
+            let key = (start + i + 1) * 10;
+            unsafe {
+                *keys.add(i as usize) = key;
+                *values.add(i as usize) = key * 10;
+            }
+        }
+        unsafe { *out_batch = start + count };
+        u.count = count;
+        if start + count == 3 {
+            sys_error(ENOENT)
+        } else {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_iter_batched() {
+        let map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_BATCH,
+                attr,
+            } => lookup_batch(attr),
+            _ => sys_error(EFAULT),
+        });
+        let hm = HashMap::<_, u32, u32>::new(&map).unwrap();
+        let items = hm.iter_batched(2).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(&items, &[(10, 100), (20, 200), (30, 300)])
+    }
+
+    #[test]
+    fn test_iter_batched_bucket_too_big() {
+        let map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_BATCH,
+                attr,
+            } => {
+                if unsafe { attr.batch.count } < 4 {
+                    attr.batch.count = 0;
+                    sys_error(ENOSPC)
+                } else {
+                    lookup_batch(attr)
+                }
+            }
+            _ => sys_error(EFAULT),
+        });
+        let hm = HashMap::<_, u32, u32>::new(&map).unwrap();
+        let items = hm.iter_batched(1).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(&items, &[(10, 100), (20, 200), (30, 300)])
+    }
+
+    #[test]
+    fn test_iter_batched_unsupported() {
+        let map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            // The kernel doesn't know the batch commands.
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_BATCH | bpf_cmd::BPF_MAP_DELETE_BATCH,
+                ..
+            } => sys_error(EINVAL),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_GET_NEXT_KEY,
+                attr,
+            } => {
+                let u = unsafe { &mut attr.__bindgen_anon_2 };
+                let next = match u.key {
+                    0 => 10,
+                    key => (unsafe { *(key as *const u32) }) + 10,
+                };
+                if next > 30 {
+                    return sys_error(ENOENT);
+                }
+                unsafe { *(u.__bindgen_anon_1.next_key as *mut u32) = next };
+                Ok(0)
+            }
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_ELEM,
+                attr,
+            } => {
+                let u = unsafe { &mut attr.__bindgen_anon_2 };
+                unsafe {
+                    *(u.__bindgen_anon_1.value as *mut u32) = *(u.key as *const u32) * 10;
+                }
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+        let hm = HashMap::<_, u32, u32>::new(&map).unwrap();
+        let items = hm.iter_batched(2).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(&items, &[(10, 100), (20, 200), (30, 300)])
+    }
+
+    #[test]
+    fn test_iter_batched_error() {
+        let map = new_map(new_obj_map());
+        override_syscall(|_| sys_error(EFAULT));
+        let hm = HashMap::<_, u32, u32>::new(&map).unwrap();
+        let mut items = hm.iter_batched(2);
+        assert_matches!(
+            items.next(),
+            Some(Err(MapError::SyscallError(SyscallError {
+                call: "bpf_map_lookup_batch",
+                io_error: _
+            })))
+        );
+        assert_matches!(items.next(), None);
+    }
+
+    #[test]
+    fn test_drain_batched() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_AND_DELETE_BATCH,
+                attr,
+            } => lookup_batch(attr),
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+        let items = hm.drain_batched(2).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(&items, &[(10, 100), (20, 200), (30, 300)])
+    }
+
+    #[test]
+    fn test_insert_batch() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_BATCH,
+                attr,
+            } => {
+                assert_eq!(unsafe { batch_keys(attr) }, &[1, 2]);
+                assert_eq!(unsafe { batch_values(attr) }, &[10, 20]);
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+        assert_matches!(hm.insert_batch(&[(1, 10), (2, 20)], 0), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_batch_unsupported() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_BATCH,
+                ..
+            } => sys_error(ENOTSUPP),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_ELEM,
+                ..
+            } => Ok(0),
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+        assert_matches!(hm.insert_batch(&[(1, 10), (2, 20)], 0), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_batch_kernel_unsupported() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_BATCH | bpf_cmd::BPF_MAP_DELETE_BATCH,
+                ..
+            } => sys_error(EINVAL),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_ELEM,
+                ..
+            } => Ok(0),
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+        assert_matches!(hm.insert_batch(&[(1, 10), (2, 20)], 0), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_batch_invalid_argument() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_BATCH,
+                ..
+            } => sys_error(EINVAL),
+            // The kernel supports batch operations, the arguments are invalid.
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_DELETE_BATCH,
+                attr,
+            } => {
+                assert_eq!(unsafe { attr.batch.count }, 0);
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+        assert_matches!(
+            hm.insert_batch(&[(1, 10), (2, 20)], 1 << 10),
+            Err(MapError::SyscallError(SyscallError {
+                call: "bpf_map_update_batch",
+                io_error,
+            })) if io_error.raw_os_error() == Some(EINVAL)
+        );
+    }
+
+    #[test]
+    fn test_remove_batch() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_DELETE_BATCH,
+                attr,
+            } => {
+                assert_eq!(unsafe { batch_keys(attr) }, &[1, 2]);
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+        assert_matches!(hm.remove_batch(&[1, 2]), Ok(()));
+    }
+
+    #[test]
+    fn test_remove_batch_invalid_argument() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_DELETE_BATCH,
+                attr,
+            } => {
+                if unsafe { attr.batch.count } == 0 {
+                    Ok(0)
+                } else {
+                    sys_error(EINVAL)
+                }
+            }
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+        assert_matches!(
+            hm.remove_batch(&[1, 2]),
+            Err(MapError::SyscallError(SyscallError {
+                call: "bpf_map_delete_batch",
+                io_error,
+            })) if io_error.raw_os_error() == Some(EINVAL)
+        );
+    }
+
+    #[test]
+    fn test_remove_batch_syscall_error() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|_| sys_error(EFAULT));
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+        assert_matches!(
+            hm.remove_batch(&[1, 2]),
+            Err(MapError::SyscallError(SyscallError {
+                call: "bpf_map_delete_batch",
+                io_error: _
+            }))
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Storage attached to cgroups.
+use std::{
+    borrow::{Borrow, BorrowMut},
+    marker::PhantomData,
+    os::fd::AsFd as _,
+};
+
+use crate::{
+    Pod,
+    maps::{IterableMap, MapData, MapError, MapIter, MapKeys, PerCpuValues, check_kv_size},
+    sys::{
+        SyscallError, bpf_map_lookup_elem, bpf_map_lookup_elem_per_cpu, bpf_map_update_elem,
+        bpf_map_update_elem_per_cpu,
+    },
+};
+
+/// The key of a [`CgroupStorage`] or [`PerCpuCgroupStorage`] map.
+///
+/// The kernel allocates one storage for each cgroup and attach type a program
+/// using the map is attached with.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[doc(alias = "bpf_cgroup_storage_key")]
+pub struct CgroupStorageKey {
+    /// The id of the cgroup, which is the inode number of its directory in the
+    /// cgroup filesystem.
+    pub cgroup_id: u64,
+    /// The `bpf_attach_type` the program was attached with.
+    pub attach_type: u32,
+    _pad: u32,
+}
+
+impl CgroupStorageKey {
+    /// Creates the key of the storage of the cgroup with id `cgroup_id` for
+    /// programs attached with `attach_type`.
+    pub const fn new(cgroup_id: u64, attach_type: u32) -> Self {
+        Self {
+            cgroup_id,
+            attach_type,
+            _pad: 0,
+        }
+    }
+}
+
+unsafe impl Pod for CgroupStorageKey {}
+
+/// Storage shared by the programs attached to a cgroup.
+///
+/// eBPF programs access the storage of the cgroup they run for with the
+/// `bpf_get_local_storage` helper. The storage is created when a program using
+/// the map is attached to a cgroup, and freed when it is detached, so user
+/// space can only read and update existing entries.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.19.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[derive(thiserror::Error, Debug)]
+/// # enum Error {
+/// #     #[error(transparent)]
+/// #     IO(#[from] std::io::Error),
+/// #     #[error(transparent)]
+/// #     Map(#[from] aya::maps::MapError),
+/// #     #[error(transparent)]
+/// #     Ebpf(#[from] aya::EbpfError)
+/// # }
+/// # let bpf = aya::Ebpf::load(&[])?;
+/// use std::os::unix::fs::MetadataExt as _;
+///
+/// use aya::maps::{CgroupStorage, CgroupStorageKey};
+/// use aya_obj::generated::bpf_attach_type::BPF_LSM_CGROUP;
+///
+/// let cgroup_id = std::fs::metadata("/sys/fs/cgroup/app")?.ino();
+/// let storage = CgroupStorage::<_, u64>::try_from(bpf.map("COUNTERS").unwrap())?;
+/// let count = storage.get(&CgroupStorageKey::new(cgroup_id, BPF_LSM_CGROUP as u32), 0)?;
+/// # Ok::<(), Error>(())
+/// ```
+#[doc(alias = "BPF_MAP_TYPE_CGROUP_STORAGE")]
+pub struct CgroupStorage<T, V: Pod> {
+    pub(crate) inner: T,
+    _v: PhantomData<V>,
+}
+
+impl<T: Borrow<MapData>, V: Pod> CgroupStorage<T, V> {
+    pub(crate) fn new(map: T) -> Result<Self, MapError> {
+        let data = map.borrow();
+        check_kv_size::<CgroupStorageKey, V>(data)?;
+
+        Ok(Self {
+            inner: map,
+            _v: PhantomData,
+        })
+    }
+
+    /// Returns the value stored for the given key.
+    pub fn get(&self, key: &CgroupStorageKey, flags: u64) -> Result<V, MapError> {
+        let fd = self.inner.borrow().fd().as_fd();
+        let value = bpf_map_lookup_elem(fd, key, flags).map_err(|io_error| SyscallError {
+            call: "bpf_map_lookup_elem",
+            io_error,
+        })?;
+        value.ok_or(MapError::KeyNotFound)
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order. The
+    /// iterator item type is `Result<(CgroupStorageKey, V), MapError>`.
+    pub fn iter(&self) -> MapIter<'_, CgroupStorageKey, V, Self> {
+        MapIter::new(self)
+    }
+
+    /// An iterator visiting all keys in arbitrary order. The iterator element
+    /// type is `Result<CgroupStorageKey, MapError>`.
+    pub fn keys(&self) -> MapKeys<'_, CgroupStorageKey> {
+        MapKeys::new(self.inner.borrow())
+    }
+}
+
+impl<T: BorrowMut<MapData>, V: Pod> CgroupStorage<T, V> {
+    /// Sets the value stored for the given key.
+    ///
+    /// The storage must exist: it is only created when a program using the map
+    /// is attached to the cgroup.
+    pub fn set(
+        &mut self,
+        key: &CgroupStorageKey,
+        value: impl Borrow<V>,
+        flags: u64,
+    ) -> Result<(), MapError> {
+        let fd = self.inner.borrow_mut().fd().as_fd();
+        bpf_map_update_elem(fd, Some(key), value.borrow(), flags).map_err(|io_error| {
+            SyscallError {
+                call: "bpf_map_update_elem",
+                io_error,
+            }
+        })?;
+        Ok(())
+    }
+}
+
+impl<T: Borrow<MapData>, V: Pod> IterableMap<CgroupStorageKey, V> for CgroupStorage<T, V> {
+    fn map(&self) -> &MapData {
+        self.inner.borrow()
+    }
+
+    fn get(&self, key: &CgroupStorageKey) -> Result<V, MapError> {
+        Self::get(self, key, 0)
+    }
+}
+
+/// Similar to [`CgroupStorage`] but each CPU holds a separate value for a
+/// given cgroup.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.20.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[derive(thiserror::Error, Debug)]
+/// # enum Error {
+/// #     #[error(transparent)]
+/// #     IO(#[from] std::io::Error),
+/// #     #[error(transparent)]
+/// #     Map(#[from] aya::maps::MapError),
+/// #     #[error(transparent)]
+/// #     Ebpf(#[from] aya::EbpfError)
+/// # }
+/// # let bpf = aya::Ebpf::load(&[])?;
+/// use aya::maps::PerCpuCgroupStorage;
+///
+/// let storage = PerCpuCgroupStorage::<_, u64>::try_from(bpf.map("COUNTERS").unwrap())?;
+/// for entry in storage.iter() {
+///     let (key, values) = entry?;
+///     let total: u64 = values.iter().sum();
+///     println!("cgroup {} counted {total}", key.cgroup_id);
+/// }
+/// # Ok::<(), Error>(())
+/// ```
+#[doc(alias = "BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE")]
+pub struct PerCpuCgroupStorage<T, V: Pod> {
+    pub(crate) inner: T,
+    _v: PhantomData<V>,
+}
+
+impl<T: Borrow<MapData>, V: Pod> PerCpuCgroupStorage<T, V> {
+    pub(crate) fn new(map: T) -> Result<Self, MapError> {
+        let data = map.borrow();
+        check_kv_size::<CgroupStorageKey, V>(data)?;
+
+        Ok(Self {
+            inner: map,
+            _v: PhantomData,
+        })
+    }
+
+    /// Returns a slice of values - one for each CPU - stored for the given key.
+    pub fn get(&self, key: &CgroupStorageKey, flags: u64) -> Result<PerCpuValues<V>, MapError> {
+        let fd = self.inner.borrow().fd().as_fd();
+        let values =
+            bpf_map_lookup_elem_per_cpu(fd, key, flags).map_err(|io_error| SyscallError {
+                call: "bpf_map_lookup_elem",
+                io_error,
+            })?;
+        values.ok_or(MapError::KeyNotFound)
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order. The
+    /// iterator item type is `Result<(CgroupStorageKey, PerCpuValues<V>), MapError>`.
+    pub fn iter(&self) -> MapIter<'_, CgroupStorageKey, PerCpuValues<V>, Self> {
+        MapIter::new(self)
+    }
+
+    /// An iterator visiting all keys in arbitrary order. The iterator element
+    /// type is `Result<CgroupStorageKey, MapError>`.
+    pub fn keys(&self) -> MapKeys<'_, CgroupStorageKey> {
+        MapKeys::new(self.inner.borrow())
+    }
+}
+
+impl<T: BorrowMut<MapData>, V: Pod> PerCpuCgroupStorage<T, V> {
+    /// Sets the values - one for each CPU - stored for the given key.
+    ///
+    /// The storage must exist: it is only created when a program using the map
+    /// is attached to the cgroup.
+    pub fn set(
+        &mut self,
+        key: &CgroupStorageKey,
+        values: PerCpuValues<V>,
+        flags: u64,
+    ) -> Result<(), MapError> {
+        let fd = self.inner.borrow_mut().fd().as_fd();
+        bpf_map_update_elem_per_cpu(fd, key, &values, flags).map_err(|io_error| SyscallError {
+            call: "bpf_map_update_elem",
+            io_error,
+        })?;
+        Ok(())
+    }
+}
+
+impl<T: Borrow<MapData>, V: Pod> IterableMap<CgroupStorageKey, PerCpuValues<V>>
+    for PerCpuCgroupStorage<T, V>
+{
+    fn map(&self) -> &MapData {
+        self.inner.borrow()
+    }
+
+    fn get(&self, key: &CgroupStorageKey) -> Result<PerCpuValues<V>, MapError> {
+        Self::get(self, key, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aya_obj::generated::bpf_map_type::{
+        BPF_MAP_TYPE_CGROUP_STORAGE_DEPRECATED, BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE_DEPRECATED,
+    };
+
+    use super::*;
+    use crate::maps::{Map, test_utils};
+
+    #[test]
+    fn test_try_from_ok() {
+        let map = Map::CgroupStorage(test_utils::new_map(test_utils::new_obj_map::<
+            CgroupStorageKey,
+        >(
+            BPF_MAP_TYPE_CGROUP_STORAGE_DEPRECATED,
+        )));
+        let _: CgroupStorage<_, u32> = map.try_into().unwrap();
+    }
+
+    #[test]
+    fn test_try_from_per_cpu_ok() {
+        let map = Map::PerCpuCgroupStorage(test_utils::new_map(test_utils::new_obj_map::<
+            CgroupStorageKey,
+        >(
+            BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE_DEPRECATED,
+        )));
+        let _: PerCpuCgroupStorage<_, u32> = map.try_into().unwrap();
+    }
+}
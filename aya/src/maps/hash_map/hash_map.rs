@@ -6,7 +6,10 @@ use std::{
 
 use crate::{
     Pod,
-    maps::{IterableMap, MapData, MapError, MapIter, MapKeys, check_kv_size, hash_map},
+    maps::{
+        IterableMap, MapBatchIter, MapData, MapError, MapIter, MapKeys, batch, check_kv_size,
+        hash_map,
+    },
     sys::{SyscallError, bpf_map_lookup_elem},
 };
 
@@ -72,6 +75,21 @@ impl<T: Borrow<MapData>, K: Pod, V: Pod> HashMap<T, K, V> {
     pub fn keys(&self) -> MapKeys<'_, K> {
         MapKeys::new(self.inner.borrow())
     }
+
+    /// An iterator visiting all key-value pairs in arbitrary order, reading up
+    /// to `batch_size` of them from the kernel at once. The iterator item type
+    /// is `Result<(K, V), MapError>`.
+    ///
+    /// Falls back to reading one entry at a time if the kernel doesn't support
+    /// batch operations, which were added in 5.6.
+    pub fn iter_batched(&self, batch_size: u32) -> MapBatchIter<'_, K, V> {
+        MapBatchIter::new(
+            self.inner.borrow(),
+            batch::BatchValues::plain(),
+            batch_size,
+            false,
+        )
+    }
 }
 
 impl<T: BorrowMut<MapData>, K: Pod, V: Pod> HashMap<T, K, V> {
@@ -89,6 +107,42 @@ impl<T: BorrowMut<MapData>, K: Pod, V: Pod> HashMap<T, K, V> {
     pub fn remove(&mut self, key: &K) -> Result<(), MapError> {
         hash_map::remove(self.inner.borrow_mut(), key)
     }
+
+    /// Inserts the given key-value pairs into the map with a single syscall.
+    ///
+    /// Falls back to inserting one entry at a time if the kernel doesn't
+    /// support batch operations, which were added in 5.6.
+    pub fn insert_batch(&mut self, entries: &[(K, V)], flags: u64) -> Result<(), MapError> {
+        batch::update_batch(
+            self.inner.borrow_mut(),
+            &batch::BatchValues::plain(),
+            entries,
+            flags,
+        )
+    }
+
+    /// Removes the given keys from the map with a single syscall.
+    ///
+    /// Falls back to removing one key at a time if the kernel doesn't support
+    /// batch operations, which were added in 5.6.
+    pub fn remove_batch(&mut self, keys: &[K]) -> Result<(), MapError> {
+        batch::delete_batch(self.inner.borrow_mut(), keys)
+    }
+
+    /// An iterator removing all key-value pairs from the map in arbitrary
+    /// order, up to `batch_size` of them at once, and yielding them. The
+    /// iterator item type is `Result<(K, V), MapError>`.
+    ///
+    /// Falls back to removing one entry at a time if the kernel doesn't support
+    /// batch operations, which were added in 5.6.
+    pub fn drain_batched(&mut self, batch_size: u32) -> MapBatchIter<'_, K, V> {
+        MapBatchIter::new(
+            self.inner.borrow_mut(),
+            batch::BatchValues::plain(),
+            batch_size,
+            true,
+        )
+    }
 }
 
 impl<T: Borrow<MapData>, K: Pod, V: Pod> IterableMap<K, V> for HashMap<T, K, V> {
@@ -0,0 +1,211 @@
+//! A hash map of eBPF maps.
+
+use std::{
+    borrow::{Borrow, BorrowMut},
+    marker::PhantomData,
+    os::fd::{AsFd as _, AsRawFd as _, RawFd},
+};
+
+use crate::{
+    Pod,
+    maps::{MapData, MapError, MapKeys, check_kv_size, hash_map},
+    sys::{SyscallError, bpf_map_lookup_elem},
+};
+
+/// A hash map of eBPF maps.
+///
+/// The values of the map are maps which eBPF programs can look up and then
+/// access with the regular map helpers. All the inner maps must have the same
+/// layout as the inner map declared along with the hash map in the eBPF program.
+/// Replacing an inner map is atomic, which makes it possible to swap a whole map
+/// while eBPF programs are running.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.12.
+///
+/// # Examples
+///
+/// ```no_run
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::maps::{HashOfMaps, MapData};
+///
+/// let mut tenants = HashOfMaps::<_, u32>::try_from(bpf.map_mut("TENANTS").unwrap())?;
+///
+/// let inner = MapData::from_pin("/sys/fs/bpf/tenant_42")?;
+/// tenants.insert(42, &inner, 0)?;
+///
+/// let id = tenants.get(&42, 0)?;
+/// assert_eq!(id, inner.info()?.id());
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+#[doc(alias = "BPF_MAP_TYPE_HASH_OF_MAPS")]
+#[derive(Debug)]
+pub struct HashOfMaps<T, K> {
+    pub(crate) inner: T,
+    _k: PhantomData<K>,
+}
+
+impl<T: Borrow<MapData>, K: Pod> HashOfMaps<T, K> {
+    pub(crate) fn new(map: T) -> Result<Self, MapError> {
+        let data = map.borrow();
+        check_kv_size::<K, RawFd>(data)?;
+
+        Ok(Self {
+            inner: map,
+            _k: PhantomData,
+        })
+    }
+
+    /// Returns the id of the map associated with the key.
+    ///
+    /// The map can be opened with [`MapData::from_id`].
+    pub fn get(&self, key: &K, flags: u64) -> Result<u32, MapError> {
+        let fd = self.inner.borrow().fd().as_fd();
+        let value = bpf_map_lookup_elem(fd, key, flags).map_err(|io_error| SyscallError {
+            call: "bpf_map_lookup_elem",
+            io_error,
+        })?;
+        value.ok_or(MapError::KeyNotFound)
+    }
+
+    /// An iterator visiting all keys in arbitrary order. The iterator element
+    /// type is `Result<K, MapError>`.
+    pub fn keys(&self) -> MapKeys<'_, K> {
+        MapKeys::new(self.inner.borrow())
+    }
+}
+
+impl<T: BorrowMut<MapData>, K: Pod> HashOfMaps<T, K> {
+    /// Inserts a map into the hash map, replacing any map previously stored
+    /// under the same key.
+    pub fn insert(
+        &mut self,
+        key: impl Borrow<K>,
+        value: &MapData,
+        flags: u64,
+    ) -> Result<(), MapError> {
+        let map_fd = value.fd().as_fd().as_raw_fd();
+        hash_map::insert(self.inner.borrow_mut(), key.borrow(), &map_fd, flags)
+    }
+
+    /// Removes a key from the map.
+    pub fn remove(&mut self, key: &K) -> Result<(), MapError> {
+        hash_map::remove(self.inner.borrow_mut(), key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use assert_matches::assert_matches;
+    use aya_obj::generated::{
+        bpf_cmd,
+        bpf_map_type::{BPF_MAP_TYPE_ARRAY, BPF_MAP_TYPE_HASH_OF_MAPS},
+    };
+    use libc::{EFAULT, ENOENT};
+
+    use super::*;
+    use crate::{
+        maps::{
+            Map,
+            test_utils::{self, new_map},
+        },
+        sys::{SysResult, Syscall, override_syscall},
+    };
+
+    fn new_obj_map() -> aya_obj::Map {
+        test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_HASH_OF_MAPS)
+    }
+
+    fn sys_error(value: i32) -> SysResult {
+        Err((-1, io::Error::from_raw_os_error(value)))
+    }
+
+    #[test]
+    fn test_wrong_key_size() {
+        let map = new_map(new_obj_map());
+        assert_matches!(
+            HashOfMaps::<_, u8>::new(&map),
+            Err(MapError::InvalidKeySize {
+                size: 1,
+                expected: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_from_wrong_map() {
+        let map = new_map(test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_ARRAY));
+        let map = Map::Array(map);
+        assert_matches!(
+            HashOfMaps::<_, u32>::try_from(&map),
+            Err(MapError::InvalidMapType { .. })
+        );
+    }
+
+    #[test]
+    fn test_try_from_ok() {
+        let map = new_map(new_obj_map());
+        let map = Map::HashOfMaps(map);
+        let _: HashOfMaps<_, u32> = map.try_into().unwrap();
+    }
+
+    #[test]
+    fn test_insert_ok() {
+        let mut map = new_map(new_obj_map());
+        let inner = new_map(test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_ARRAY));
+        let mut hm = HashOfMaps::<_, u32>::new(&mut map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_ELEM,
+                attr,
+            } => {
+                let value = unsafe { attr.__bindgen_anon_2.__bindgen_anon_1.value } as *const u32;
+                assert_eq!(unsafe { *value }, crate::MockableFd::mock_unsigned_fd());
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+
+        assert_matches!(hm.insert(1, &inner, 0), Ok(()));
+    }
+
+    #[test]
+    fn test_get_not_found() {
+        let map = new_map(new_obj_map());
+        let hm = HashOfMaps::<_, u32>::new(&map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_ELEM,
+                ..
+            } => sys_error(ENOENT),
+            _ => sys_error(EFAULT),
+        });
+
+        assert_matches!(hm.get(&1, 0), Err(MapError::KeyNotFound));
+    }
+
+    #[test]
+    fn test_get_ok() {
+        let map = new_map(new_obj_map());
+        let hm = HashOfMaps::<_, u32>::new(&map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_ELEM,
+                attr,
+            } => {
+                let value = unsafe { attr.__bindgen_anon_2.__bindgen_anon_1.value } as *mut u32;
+                unsafe { value.write(42) };
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+
+        assert_matches!(hm.get(&1, 0), Ok(42));
+    }
+}
@@ -8,7 +8,8 @@ use std::{
 use crate::{
     Pod,
     maps::{
-        IterableMap, MapData, MapError, MapIter, MapKeys, PerCpuValues, check_kv_size, hash_map,
+        IterableMap, MapBatchIter, MapData, MapError, MapIter, MapKeys, PerCpuValues, batch,
+        check_kv_size, hash_map,
     },
     sys::{SyscallError, bpf_map_lookup_elem_per_cpu, bpf_map_update_elem_per_cpu},
 };
@@ -82,6 +83,21 @@ impl<T: Borrow<MapData>, K: Pod, V: Pod> PerCpuHashMap<T, K, V> {
     pub fn keys(&self) -> MapKeys<'_, K> {
         MapKeys::new(self.inner.borrow())
     }
+
+    /// An iterator visiting all key-value pairs in arbitrary order, reading up
+    /// to `batch_size` of them from the kernel at once. The iterator item type
+    /// is `Result<(K, PerCpuValues<V>), MapError>`.
+    ///
+    /// Falls back to reading one entry at a time if the kernel doesn't support
+    /// batch operations, which were added in 5.6.
+    pub fn iter_batched(&self, batch_size: u32) -> MapBatchIter<'_, K, PerCpuValues<V>> {
+        MapBatchIter::new(
+            self.inner.borrow(),
+            batch::BatchValues::per_cpu(),
+            batch_size,
+            false,
+        )
+    }
 }
 
 impl<T: BorrowMut<MapData>, K: Pod, V: Pod> PerCpuHashMap<T, K, V> {
@@ -133,6 +149,46 @@ impl<T: BorrowMut<MapData>, K: Pod, V: Pod> PerCpuHashMap<T, K, V> {
     pub fn remove(&mut self, key: &K) -> Result<(), MapError> {
         hash_map::remove(self.inner.borrow_mut(), key)
     }
+
+    /// Inserts the given key-value pairs into the map with a single syscall.
+    ///
+    /// Falls back to inserting one entry at a time if the kernel doesn't
+    /// support batch operations, which were added in 5.6.
+    pub fn insert_batch(
+        &mut self,
+        entries: &[(K, PerCpuValues<V>)],
+        flags: u64,
+    ) -> Result<(), MapError> {
+        batch::update_batch(
+            self.inner.borrow_mut(),
+            &batch::BatchValues::per_cpu(),
+            entries,
+            flags,
+        )
+    }
+
+    /// Removes the given keys from the map with a single syscall.
+    ///
+    /// Falls back to removing one key at a time if the kernel doesn't support
+    /// batch operations, which were added in 5.6.
+    pub fn remove_batch(&mut self, keys: &[K]) -> Result<(), MapError> {
+        batch::delete_batch(self.inner.borrow_mut(), keys)
+    }
+
+    /// An iterator removing all key-value pairs from the map in arbitrary
+    /// order, up to `batch_size` of them at once, and yielding them. The
+    /// iterator item type is `Result<(K, PerCpuValues<V>), MapError>`.
+    ///
+    /// Falls back to removing one entry at a time if the kernel doesn't support
+    /// batch operations, which were added in 5.6.
+    pub fn drain_batched(&mut self, batch_size: u32) -> MapBatchIter<'_, K, PerCpuValues<V>> {
+        MapBatchIter::new(
+            self.inner.borrow_mut(),
+            batch::BatchValues::per_cpu(),
+            batch_size,
+            true,
+        )
+    }
 }
 
 impl<T: Borrow<MapData>, K: Pod, V: Pod> IterableMap<K, PerCpuValues<V>>
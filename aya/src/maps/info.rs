@@ -232,12 +232,14 @@ pub enum MapType {
     /// Introduced in kernel v4.11.
     #[doc(alias = "BPF_MAP_TYPE_LPM_TRIE")]
     LpmTrie = bpf_map_type::BPF_MAP_TYPE_LPM_TRIE as isize,
-    /// An Array of Maps map type.
+    /// An Array of Maps map type. See [`ArrayOfMaps`](super::array::ArrayOfMaps) for the map
+    /// implementation.
     ///
     /// Introduced in kernel v4.12.
     #[doc(alias = "BPF_MAP_TYPE_ARRAY_OF_MAPS")]
     ArrayOfMaps = bpf_map_type::BPF_MAP_TYPE_ARRAY_OF_MAPS as isize,
-    /// A Hash of Maps map type.
+    /// A Hash of Maps map type. See [`HashOfMaps`](super::hash_map::HashOfMaps) for the map
+    /// implementation.
     ///
     /// Introduced in kernel v4.12.
     #[doc(alias = "BPF_MAP_TYPE_HASH_OF_MAPS")]
@@ -267,19 +269,23 @@ pub enum MapType {
     /// Introduced in kernel v4.18.
     #[doc(alias = "BPF_MAP_TYPE_SOCKHASH")]
     SockHash = bpf_map_type::BPF_MAP_TYPE_SOCKHASH as isize,
-    /// A cGroup Storage map type.
+    /// A cGroup Storage map type. See [`CgroupStorage`](super::cgroup_storage::CgroupStorage)
+    /// for the map implementation.
     ///
     /// Introduced in kernel v4.19.
     // #[deprecated]
     #[doc(alias = "BPF_MAP_TYPE_CGROUP_STORAGE")]
     #[doc(alias = "BPF_MAP_TYPE_CGROUP_STORAGE_DEPRECATED")]
     CgroupStorage = bpf_map_type::BPF_MAP_TYPE_CGROUP_STORAGE as isize,
-    /// A Reuseport Socket Array map type.
+    /// A Reuseport Socket Array map type. See
+    /// [`ReusePortSockArray`](super::sock::ReusePortSockArray) for the map implementation.
     ///
     /// Introduced in kernel v4.19.
     #[doc(alias = "BPF_MAP_TYPE_REUSEPORT_SOCKARRAY")]
     ReuseportSockArray = bpf_map_type::BPF_MAP_TYPE_REUSEPORT_SOCKARRAY as isize,
-    /// A per-CPU cGroup Storage map type.
+    /// A per-CPU cGroup Storage map type. See
+    /// [`PerCpuCgroupStorage`](super::cgroup_storage::PerCpuCgroupStorage) for the map
+    /// implementation.
     ///
     /// Introduced in kernel v4.20.
     #[doc(alias = "BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE")]
@@ -295,7 +301,8 @@ pub enum MapType {
     /// Introduced in kernel v4.20.
     #[doc(alias = "BPF_MAP_TYPE_STACK")]
     Stack = bpf_map_type::BPF_MAP_TYPE_STACK as isize,
-    /// A Socket-local Storage map type.
+    /// A Socket-local Storage map type. See [`SkStorage`](super::local_storage::SkStorage) for
+    /// the map implementation.
     ///
     /// Introduced in kernel v5.2.
     #[doc(alias = "BPF_MAP_TYPE_SK_STORAGE")]
@@ -306,7 +313,8 @@ pub enum MapType {
     /// Introduced in kernel v5.4.
     #[doc(alias = "BPF_MAP_TYPE_DEVMAP_HASH")]
     DevMapHash = bpf_map_type::BPF_MAP_TYPE_DEVMAP_HASH as isize,
-    /// A Struct Ops map type.
+    /// A Struct Ops map type. See [`StructOpsMap`](super::struct_ops::StructOpsMap) for the
+    /// map implementation.
     ///
     /// Introduced in kernel v5.6.
     #[doc(alias = "BPF_MAP_TYPE_STRUCT_OPS")]
@@ -317,12 +325,14 @@ pub enum MapType {
     /// Introduced in kernel v5.8.
     #[doc(alias = "BPF_MAP_TYPE_RINGBUF")]
     RingBuf = bpf_map_type::BPF_MAP_TYPE_RINGBUF as isize,
-    /// An Inode Storage map type.
+    /// An Inode Storage map type. See [`InodeStorage`](super::local_storage::InodeStorage) for
+    /// the map implementation.
     ///
     /// Introduced in kernel v5.10.
     #[doc(alias = "BPF_MAP_TYPE_INODE_STORAGE")]
     InodeStorage = bpf_map_type::BPF_MAP_TYPE_INODE_STORAGE as isize,
-    /// A Task Storage map type.
+    /// A Task Storage map type. See [`TaskStorage`](super::local_storage::TaskStorage) for the
+    /// map implementation.
     ///
     /// Introduced in kernel v5.11.
     #[doc(alias = "BPF_MAP_TYPE_TASK_STORAGE")]
@@ -338,7 +348,8 @@ pub enum MapType {
     /// Introduced in kernel v6.1.
     #[doc(alias = "BPF_MAP_TYPE_USER_RINGBUF")]
     UserRingBuf = bpf_map_type::BPF_MAP_TYPE_USER_RINGBUF as isize,
-    /// A cGroup Storage map type.
+    /// A cGroup Storage map type. See [`CgrpStorage`](super::local_storage::CgrpStorage) for
+    /// the map implementation.
     ///
     /// Introduced in kernel v6.2.
     #[doc(alias = "BPF_MAP_TYPE_CGRP_STORAGE")]
@@ -0,0 +1,413 @@
+//! Storage attached to sockets, inodes, tasks and cgroups.
+//!
+//! Local storage maps hold one value for each kernel object they're used with.
+//! eBPF programs create and access the value of an object with the
+//! `bpf_*_storage_get` helpers, and user space with a file descriptor that
+//! refers to the object. The value is freed along with the object.
+use std::{
+    borrow::{Borrow, BorrowMut},
+    marker::PhantomData,
+    os::fd::{AsFd, AsRawFd as _, RawFd},
+};
+
+use crate::{
+    Pod,
+    maps::{MapData, MapError, check_kv_size},
+    sys::{SyscallError, bpf_map_delete_elem, bpf_map_lookup_elem, bpf_map_update_elem},
+};
+
+// Defines a local storage map type, with the name of the key argument of its
+// methods and a description of the object it refers to for their docs.
+macro_rules! local_storage {
+    (
+        $(#[$meta:meta])*
+        $ty:ident($key:ident: $object:literal)
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        pub struct $ty<T, V: Pod> {
+            pub(crate) inner: T,
+            _v: PhantomData<V>,
+        }
+
+        impl<T: Borrow<MapData>, V: Pod> $ty<T, V> {
+            pub(crate) fn new(map: T) -> Result<Self, MapError> {
+                check_kv_size::<RawFd, V>(map.borrow())?;
+
+                Ok(Self {
+                    inner: map,
+                    _v: PhantomData,
+                })
+            }
+
+            #[doc = concat!("Returns the value stored for ", $object, ".")]
+            pub fn get<I: AsFd>(&self, $key: &I, flags: u64) -> Result<V, MapError> {
+                get(self.inner.borrow(), $key, flags)
+            }
+        }
+
+        impl<T: BorrowMut<MapData>, V: Pod> $ty<T, V> {
+            #[doc = concat!("Stores a value for ", $object, ".")]
+            pub fn insert<I: AsFd>(
+                &mut self,
+                $key: &I,
+                value: impl Borrow<V>,
+                flags: u64,
+            ) -> Result<(), MapError> {
+                insert(self.inner.borrow_mut(), $key, value.borrow(), flags)
+            }
+
+            #[doc = concat!("Removes the value stored for ", $object, ".")]
+            pub fn remove<I: AsFd>(&mut self, $key: &I) -> Result<(), MapError> {
+                remove(self.inner.borrow_mut(), $key)
+            }
+        }
+    };
+}
+
+local_storage! {
+    /// Storage attached to sockets.
+    ///
+    /// The values are created by eBPF programs with `bpf_sk_storage_get`, or by
+    /// user space with [`SkStorage::insert`]. User space refers to sockets by
+    /// their file descriptor.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.2.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[derive(thiserror::Error, Debug)]
+    /// # enum Error {
+    /// #     #[error(transparent)]
+    /// #     IO(#[from] std::io::Error),
+    /// #     #[error(transparent)]
+    /// #     Map(#[from] aya::maps::MapError),
+    /// #     #[error(transparent)]
+    /// #     Ebpf(#[from] aya::EbpfError)
+    /// # }
+    /// # let mut bpf = aya::Ebpf::load(&[])?;
+    /// use std::net::TcpStream;
+    ///
+    /// use aya::maps::SkStorage;
+    ///
+    /// let mut storage = SkStorage::<_, u64>::try_from(bpf.map_mut("SOCKET_BYTES").unwrap())?;
+    /// let stream = TcpStream::connect("127.0.0.1:1234")?;
+    /// storage.insert(&stream, 0, 0)?;
+    /// let bytes = storage.get(&stream, 0)?;
+    /// # Ok::<(), Error>(())
+    /// ```
+    #[doc(alias = "BPF_MAP_TYPE_SK_STORAGE")]
+    SkStorage(socket: "the given socket")
+}
+
+local_storage! {
+    /// Storage attached to inodes.
+    ///
+    /// The values are created by eBPF programs with `bpf_inode_storage_get`, or by
+    /// user space with [`InodeStorage::insert`]. User space refers to inodes by the
+    /// file descriptor of a file opened from them.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.10.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[derive(thiserror::Error, Debug)]
+    /// # enum Error {
+    /// #     #[error(transparent)]
+    /// #     IO(#[from] std::io::Error),
+    /// #     #[error(transparent)]
+    /// #     Map(#[from] aya::maps::MapError),
+    /// #     #[error(transparent)]
+    /// #     Ebpf(#[from] aya::EbpfError)
+    /// # }
+    /// # let mut bpf = aya::Ebpf::load(&[])?;
+    /// use std::fs::File;
+    ///
+    /// use aya::maps::InodeStorage;
+    ///
+    /// let mut storage = InodeStorage::<_, u32>::try_from(bpf.map_mut("PROTECTED").unwrap())?;
+    /// let file = File::open("/etc/shadow")?;
+    /// storage.insert(&file, 1, 0)?;
+    /// # Ok::<(), Error>(())
+    /// ```
+    #[doc(alias = "BPF_MAP_TYPE_INODE_STORAGE")]
+    InodeStorage(file: "the inode of the given file")
+}
+
+local_storage! {
+    /// Storage attached to tasks.
+    ///
+    /// The values are created by eBPF programs with `bpf_task_storage_get`, or by
+    /// user space with [`TaskStorage::insert`]. User space refers to tasks by a
+    /// pidfd, as returned by `pidfd_open(2)`.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.11.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[derive(thiserror::Error, Debug)]
+    /// # enum Error {
+    /// #     #[error(transparent)]
+    /// #     IO(#[from] std::io::Error),
+    /// #     #[error(transparent)]
+    /// #     Map(#[from] aya::maps::MapError),
+    /// #     #[error(transparent)]
+    /// #     Ebpf(#[from] aya::EbpfError)
+    /// # }
+    /// # let mut bpf = aya::Ebpf::load(&[])?;
+    /// use std::os::fd::{FromRawFd as _, OwnedFd};
+    ///
+    /// use aya::maps::TaskStorage;
+    ///
+    /// let mut storage = TaskStorage::<_, u64>::try_from(bpf.map_mut("TASK_EVENTS").unwrap())?;
+    /// let pidfd = unsafe { libc::syscall(libc::SYS_pidfd_open, std::process::id(), 0) };
+    /// if pidfd < 0 {
+    ///     return Err(std::io::Error::last_os_error().into());
+    /// }
+    /// let pidfd = unsafe { OwnedFd::from_raw_fd(pidfd as i32) };
+    /// storage.insert(&pidfd, 0, 0)?;
+    /// let events = storage.get(&pidfd, 0)?;
+    /// # Ok::<(), Error>(())
+    /// ```
+    #[doc(alias = "BPF_MAP_TYPE_TASK_STORAGE")]
+    TaskStorage(pidfd: "the task referred to by the given pidfd")
+}
+
+local_storage! {
+    /// Storage attached to cgroups.
+    ///
+    /// The values are created by eBPF programs with `bpf_cgrp_storage_get`, or by
+    /// user space with [`CgrpStorage::insert`]. User space refers to cgroups by the
+    /// file descriptor of their directory in the cgroup filesystem.
+    ///
+    /// Unlike [`CgroupStorage`](super::CgroupStorage), the values aren't tied to
+    /// the programs attached to the cgroup and can be used by any program.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 6.2.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[derive(thiserror::Error, Debug)]
+    /// # enum Error {
+    /// #     #[error(transparent)]
+    /// #     IO(#[from] std::io::Error),
+    /// #     #[error(transparent)]
+    /// #     Map(#[from] aya::maps::MapError),
+    /// #     #[error(transparent)]
+    /// #     Ebpf(#[from] aya::EbpfError)
+    /// # }
+    /// # let mut bpf = aya::Ebpf::load(&[])?;
+    /// use std::fs::File;
+    ///
+    /// use aya::maps::CgrpStorage;
+    ///
+    /// let mut storage = CgrpStorage::<_, u64>::try_from(bpf.map_mut("LIMITS").unwrap())?;
+    /// let cgroup = File::open("/sys/fs/cgroup/app")?;
+    /// storage.insert(&cgroup, 1024, 0)?;
+    /// # Ok::<(), Error>(())
+    /// ```
+    #[doc(alias = "BPF_MAP_TYPE_CGRP_STORAGE")]
+    CgrpStorage(cgroup: "the given cgroup")
+}
+
+fn get<I: AsFd, V: Pod>(map: &MapData, object: &I, flags: u64) -> Result<V, MapError> {
+    let fd = map.fd().as_fd();
+    let key = object.as_fd().as_raw_fd();
+    let value = bpf_map_lookup_elem(fd, &key, flags).map_err(|io_error| SyscallError {
+        call: "bpf_map_lookup_elem",
+        io_error,
+    })?;
+    value.ok_or(MapError::KeyNotFound)
+}
+
+fn insert<I: AsFd, V: Pod>(
+    map: &MapData,
+    object: &I,
+    value: &V,
+    flags: u64,
+) -> Result<(), MapError> {
+    let fd = map.fd().as_fd();
+    let key = object.as_fd().as_raw_fd();
+    bpf_map_update_elem(fd, Some(&key), value, flags).map_err(|io_error| SyscallError {
+        call: "bpf_map_update_elem",
+        io_error,
+    })?;
+    Ok(())
+}
+
+fn remove<I: AsFd>(map: &MapData, object: &I) -> Result<(), MapError> {
+    let fd = map.fd().as_fd();
+    let key = object.as_fd().as_raw_fd();
+    bpf_map_delete_elem(fd, &key).map_err(|io_error| SyscallError {
+        call: "bpf_map_delete_elem",
+        io_error,
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, os::fd::BorrowedFd};
+
+    use assert_matches::assert_matches;
+    use aya_obj::generated::{
+        bpf_cmd,
+        bpf_map_type::{
+            BPF_MAP_TYPE_ARRAY, BPF_MAP_TYPE_CGRP_STORAGE, BPF_MAP_TYPE_INODE_STORAGE,
+            BPF_MAP_TYPE_SK_STORAGE, BPF_MAP_TYPE_TASK_STORAGE,
+        },
+    };
+    use libc::{EFAULT, ENOENT};
+
+    use super::*;
+    use crate::{
+        maps::{Map, test_utils},
+        sys::{SysResult, Syscall, override_syscall},
+    };
+
+    fn new_map(map_type: aya_obj::generated::bpf_map_type) -> MapData {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_BTF_LOAD,
+                ..
+            } => Ok(crate::MockableFd::mock_signed_fd().into()),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_CREATE,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_1 };
+                assert_eq!(u.btf_fd, crate::MockableFd::mock_unsigned_fd());
+                assert_ne!(u.btf_key_type_id, 0);
+                assert_ne!(u.btf_value_type_id, 0);
+                Ok(crate::MockableFd::mock_signed_fd().into())
+            }
+            call => panic!("unexpected syscall {call:?}"),
+        });
+        MapData::create(test_utils::new_obj_map::<RawFd>(map_type), "foo", None).unwrap()
+    }
+
+    fn fd() -> BorrowedFd<'static> {
+        unsafe { BorrowedFd::borrow_raw(42) }
+    }
+
+    fn sys_error(value: i32) -> SysResult {
+        Err((-1, io::Error::from_raw_os_error(value)))
+    }
+
+    #[test]
+    fn test_try_from_wrong_map() {
+        let map = Map::Array(test_utils::new_map(test_utils::new_obj_map::<u32>(
+            BPF_MAP_TYPE_ARRAY,
+        )));
+        assert_matches!(
+            SkStorage::<_, u32>::try_from(&map),
+            Err(MapError::InvalidMapType { .. })
+        );
+    }
+
+    #[test]
+    fn test_try_from_ok() {
+        let map = Map::SkStorage(new_map(BPF_MAP_TYPE_SK_STORAGE));
+        let _: SkStorage<_, u32> = (&map).try_into().unwrap();
+        let map = Map::InodeStorage(new_map(BPF_MAP_TYPE_INODE_STORAGE));
+        let _: InodeStorage<_, u32> = (&map).try_into().unwrap();
+        let map = Map::TaskStorage(new_map(BPF_MAP_TYPE_TASK_STORAGE));
+        let _: TaskStorage<_, u32> = (&map).try_into().unwrap();
+        let map = Map::CgrpStorage(new_map(BPF_MAP_TYPE_CGRP_STORAGE));
+        let _: CgrpStorage<_, u32> = (&map).try_into().unwrap();
+    }
+
+    #[test]
+    fn test_wrong_value_size() {
+        let map = new_map(BPF_MAP_TYPE_SK_STORAGE);
+        assert_matches!(
+            SkStorage::<_, u64>::new(&map),
+            Err(MapError::InvalidValueSize {
+                size: 8,
+                expected: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_get() {
+        let map = new_map(BPF_MAP_TYPE_TASK_STORAGE);
+        let storage = TaskStorage::<_, u32>::new(&map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_ELEM,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_2 };
+                assert_eq!(unsafe { *(u.key as *const RawFd) }, 42);
+                unsafe { *(u.__bindgen_anon_1.value as *mut u32) = 7 };
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+        assert_matches!(storage.get(&fd(), 0), Ok(7));
+    }
+
+    #[test]
+    fn test_get_not_found() {
+        let map = new_map(BPF_MAP_TYPE_CGRP_STORAGE);
+        let storage = CgrpStorage::<_, u32>::new(&map).unwrap();
+
+        override_syscall(|_| sys_error(ENOENT));
+        assert_matches!(storage.get(&fd(), 0), Err(MapError::KeyNotFound));
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut map = new_map(BPF_MAP_TYPE_SK_STORAGE);
+        let mut storage = SkStorage::<_, u32>::new(&mut map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_ELEM,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_2 };
+                assert_eq!(unsafe { *(u.key as *const RawFd) }, 42);
+                assert_eq!(unsafe { *(u.__bindgen_anon_1.value as *const u32) }, 7);
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+        assert_matches!(storage.insert(&fd(), 7, 0), Ok(()));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map = new_map(BPF_MAP_TYPE_INODE_STORAGE);
+        let mut storage = InodeStorage::<_, u32>::new(&mut map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_DELETE_ELEM,
+                ..
+            } => Ok(0),
+            _ => sys_error(EFAULT),
+        });
+        assert_matches!(storage.remove(&fd()), Ok(()));
+
+        override_syscall(|_| sys_error(ENOENT));
+        assert_matches!(
+            storage.remove(&fd()),
+            Err(MapError::SyscallError(SyscallError { call: "bpf_map_delete_elem", io_error })) if io_error.raw_os_error() == Some(ENOENT)
+        );
+    }
+}
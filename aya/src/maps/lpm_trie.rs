@@ -7,7 +7,7 @@ use std::{
 
 use crate::{
     Pod,
-    maps::{IterableMap, MapData, MapError, MapIter, MapKeys, check_kv_size},
+    maps::{IterableMap, MapBatchIter, MapData, MapError, MapIter, MapKeys, batch, check_kv_size},
     sys::{SyscallError, bpf_map_delete_elem, bpf_map_lookup_elem, bpf_map_update_elem},
 };
 
@@ -145,6 +145,21 @@ impl<T: Borrow<MapData>, K: Pod, V: Pod> LpmTrie<T, K, V> {
     pub fn keys(&self) -> MapKeys<'_, Key<K>> {
         MapKeys::new(self.inner.borrow())
     }
+
+    /// An iterator visiting all key-value pairs, reading up to `batch_size` of
+    /// them from the kernel at once. The iterator item type is
+    /// `Result<(Key<K>, V), MapError>`.
+    ///
+    /// Falls back to reading one entry at a time if the kernel doesn't support
+    /// batch operations on LPM tries.
+    pub fn iter_batched(&self, batch_size: u32) -> MapBatchIter<'_, Key<K>, V> {
+        MapBatchIter::new(
+            self.inner.borrow(),
+            batch::BatchValues::plain(),
+            batch_size,
+            false,
+        )
+    }
 }
 
 impl<T: BorrowMut<MapData>, K: Pod, V: Pod> LpmTrie<T, K, V> {
@@ -176,6 +191,28 @@ impl<T: BorrowMut<MapData>, K: Pod, V: Pod> LpmTrie<T, K, V> {
             })
             .map_err(Into::into)
     }
+
+    /// Inserts the given key-value pairs into the map with a single syscall.
+    ///
+    /// Falls back to inserting one entry at a time if the kernel doesn't
+    /// support batch operations on LPM tries.
+    pub fn insert_batch(&mut self, entries: &[(Key<K>, V)], flags: u64) -> Result<(), MapError> {
+        batch::update_batch(
+            self.inner.borrow_mut(),
+            &batch::BatchValues::plain(),
+            entries,
+            flags,
+        )
+    }
+
+    /// Removes the given keys from the map with a single syscall.
+    ///
+    /// Both the prefix and data of each key must match exactly. Falls back to
+    /// removing one key at a time if the kernel doesn't support batch
+    /// operations on LPM tries.
+    pub fn remove_batch(&mut self, keys: &[Key<K>]) -> Result<(), MapError> {
+        batch::delete_batch(self.inner.borrow_mut(), keys)
+    }
 }
 
 impl<T: Borrow<MapData>, K: Pod, V: Pod> IterableMap<Key<K>, V> for LpmTrie<T, K, V> {
@@ -76,10 +76,14 @@ use crate::{
     util::{KernelVersion, nr_cpus},
 };
 
+pub mod arena;
 pub mod array;
+mod batch;
 pub mod bloom_filter;
+pub mod cgroup_storage;
 pub mod hash_map;
 mod info;
+pub mod local_storage;
 pub mod lpm_trie;
 pub mod perf;
 pub mod queue;
@@ -87,12 +91,17 @@ pub mod ring_buf;
 pub mod sock;
 pub mod stack;
 pub mod stack_trace;
+pub mod struct_ops;
 pub mod xdp;
 
-pub use array::{Array, PerCpuArray, ProgramArray};
+pub use arena::{Arena, MmapArena};
+pub use array::{Array, ArrayOfMaps, MmapArray, MmapArrayMut, PerCpuArray, ProgramArray};
+pub use batch::MapBatchIter;
 pub use bloom_filter::BloomFilter;
-pub use hash_map::{HashMap, PerCpuHashMap};
+pub use cgroup_storage::{CgroupStorage, CgroupStorageKey, PerCpuCgroupStorage};
+pub use hash_map::{HashMap, HashOfMaps, PerCpuHashMap};
 pub use info::{MapInfo, MapType, loaded_maps};
+pub use local_storage::{CgrpStorage, InodeStorage, SkStorage, TaskStorage};
 pub use lpm_trie::LpmTrie;
 #[cfg(any(feature = "async_tokio", feature = "async_std"))]
 #[cfg_attr(docsrs, doc(cfg(any(feature = "async_tokio", feature = "async_std"))))]
@@ -100,9 +109,10 @@ pub use perf::AsyncPerfEventArray;
 pub use perf::PerfEventArray;
 pub use queue::Queue;
 pub use ring_buf::RingBuf;
-pub use sock::{SockHash, SockMap};
+pub use sock::{ReusePortSockArray, SockHash, SockMap};
 pub use stack::Stack;
 pub use stack_trace::StackTraceMap;
+pub use struct_ops::{StructOpsLink, StructOpsMap};
 pub use xdp::{CpuMap, DevMap, DevMapHash, XskMap};
 
 #[derive(Error, Debug)]
@@ -193,6 +203,17 @@ pub enum MapError {
     #[error("program ids are not supported by the current kernel")]
     ProgIdNotSupported,
 
+    /// A program referenced by a struct_ops map was not provided
+    #[error("program `{name}` referenced by the struct_ops map was not provided")]
+    StructOpsProgramNotFound {
+        /// Program name
+        name: String,
+    },
+
+    /// The map wasn't created with the `BPF_F_MMAPABLE` flag
+    #[error("the map was not created with the `BPF_F_MMAPABLE` flag")]
+    NotMmapable,
+
     /// Unsupported Map type
     #[error(
         "type of {name} ({map_type:?}) is unsupported; see `EbpfLoader::allow_unsupported_maps`"
@@ -223,7 +244,7 @@ impl MapFd {
         Self { fd }
     }
 
-    fn try_clone(&self) -> io::Result<Self> {
+    pub(crate) fn try_clone(&self) -> io::Result<Self> {
         let Self { fd } = self;
         let fd = fd.try_clone()?;
         Ok(Self { fd })
@@ -274,10 +295,18 @@ fn maybe_warn_rlimit() {
 /// eBPF map types.
 #[derive(Debug)]
 pub enum Map {
+    /// An [`Arena`] map.
+    Arena(MapData),
     /// An [`Array`] map.
     Array(MapData),
+    /// An [`ArrayOfMaps`] map.
+    ArrayOfMaps(MapData),
     /// A [`BloomFilter`] map.
     BloomFilter(MapData),
+    /// A [`CgroupStorage`] map.
+    CgroupStorage(MapData),
+    /// A [`CgrpStorage`] map.
+    CgrpStorage(MapData),
     /// A [`CpuMap`] map.
     CpuMap(MapData),
     /// A [`DevMap`] map.
@@ -286,12 +315,18 @@ pub enum Map {
     DevMapHash(MapData),
     /// A [`HashMap`] map.
     HashMap(MapData),
+    /// A [`HashOfMaps`] map.
+    HashOfMaps(MapData),
+    /// An [`InodeStorage`] map.
+    InodeStorage(MapData),
     /// A [`LpmTrie`] map.
     LpmTrie(MapData),
     /// A [`HashMap`] map that uses a LRU eviction policy.
     LruHashMap(MapData),
     /// A [`PerCpuArray`] map.
     PerCpuArray(MapData),
+    /// A [`PerCpuCgroupStorage`] map.
+    PerCpuCgroupStorage(MapData),
     /// A [`PerCpuHashMap`] map.
     PerCpuHashMap(MapData),
     /// A [`PerCpuHashMap`] map that uses a LRU eviction policy.
@@ -302,8 +337,12 @@ pub enum Map {
     ProgramArray(MapData),
     /// A [`Queue`] map.
     Queue(MapData),
+    /// A [`ReusePortSockArray`] map.
+    ReusePortSockArray(MapData),
     /// A [`RingBuf`] map.
     RingBuf(MapData),
+    /// A [`SkStorage`] map.
+    SkStorage(MapData),
     /// A [`SockHash`] map
     SockHash(MapData),
     /// A [`SockMap`] map.
@@ -312,6 +351,10 @@ pub enum Map {
     Stack(MapData),
     /// A [`StackTraceMap`] map.
     StackTraceMap(MapData),
+    /// A [`StructOpsMap`] map.
+    StructOps(MapData),
+    /// A [`TaskStorage`] map.
+    TaskStorage(MapData),
     /// An unsupported map type.
     Unsupported(MapData),
     /// A [`XskMap`] map.
@@ -322,25 +365,36 @@ impl Map {
     /// Returns the low level map type.
     fn map_type(&self) -> u32 {
         match self {
+            Self::Arena(map) => map.obj.map_type(),
             Self::Array(map) => map.obj.map_type(),
+            Self::ArrayOfMaps(map) => map.obj.map_type(),
             Self::BloomFilter(map) => map.obj.map_type(),
+            Self::CgroupStorage(map) => map.obj.map_type(),
+            Self::CgrpStorage(map) => map.obj.map_type(),
             Self::CpuMap(map) => map.obj.map_type(),
             Self::DevMap(map) => map.obj.map_type(),
             Self::DevMapHash(map) => map.obj.map_type(),
             Self::HashMap(map) => map.obj.map_type(),
+            Self::HashOfMaps(map) => map.obj.map_type(),
+            Self::InodeStorage(map) => map.obj.map_type(),
             Self::LpmTrie(map) => map.obj.map_type(),
             Self::LruHashMap(map) => map.obj.map_type(),
             Self::PerCpuArray(map) => map.obj.map_type(),
+            Self::PerCpuCgroupStorage(map) => map.obj.map_type(),
             Self::PerCpuHashMap(map) => map.obj.map_type(),
             Self::PerCpuLruHashMap(map) => map.obj.map_type(),
             Self::PerfEventArray(map) => map.obj.map_type(),
             Self::ProgramArray(map) => map.obj.map_type(),
             Self::Queue(map) => map.obj.map_type(),
+            Self::ReusePortSockArray(map) => map.obj.map_type(),
             Self::RingBuf(map) => map.obj.map_type(),
+            Self::SkStorage(map) => map.obj.map_type(),
             Self::SockHash(map) => map.obj.map_type(),
             Self::SockMap(map) => map.obj.map_type(),
             Self::Stack(map) => map.obj.map_type(),
             Self::StackTraceMap(map) => map.obj.map_type(),
+            Self::StructOps(map) => map.obj.map_type(),
+            Self::TaskStorage(map) => map.obj.map_type(),
             Self::Unsupported(map) => map.obj.map_type(),
             Self::XskMap(map) => map.obj.map_type(),
         }
@@ -352,25 +406,36 @@ impl Map {
     /// is deleted. All parent directories in the given `path` must already exist.
     pub fn pin<P: AsRef<Path>>(&self, path: P) -> Result<(), PinError> {
         match self {
+            Self::Arena(map) => map.pin(path),
             Self::Array(map) => map.pin(path),
+            Self::ArrayOfMaps(map) => map.pin(path),
             Self::BloomFilter(map) => map.pin(path),
+            Self::CgroupStorage(map) => map.pin(path),
+            Self::CgrpStorage(map) => map.pin(path),
             Self::CpuMap(map) => map.pin(path),
             Self::DevMap(map) => map.pin(path),
             Self::DevMapHash(map) => map.pin(path),
             Self::HashMap(map) => map.pin(path),
+            Self::HashOfMaps(map) => map.pin(path),
+            Self::InodeStorage(map) => map.pin(path),
             Self::LpmTrie(map) => map.pin(path),
             Self::LruHashMap(map) => map.pin(path),
             Self::PerCpuArray(map) => map.pin(path),
+            Self::PerCpuCgroupStorage(map) => map.pin(path),
             Self::PerCpuHashMap(map) => map.pin(path),
             Self::PerCpuLruHashMap(map) => map.pin(path),
             Self::PerfEventArray(map) => map.pin(path),
             Self::ProgramArray(map) => map.pin(path),
             Self::Queue(map) => map.pin(path),
+            Self::ReusePortSockArray(map) => map.pin(path),
             Self::RingBuf(map) => map.pin(path),
+            Self::SkStorage(map) => map.pin(path),
             Self::SockHash(map) => map.pin(path),
             Self::SockMap(map) => map.pin(path),
             Self::Stack(map) => map.pin(path),
             Self::StackTraceMap(map) => map.pin(path),
+            Self::StructOps(map) => map.pin(path),
+            Self::TaskStorage(map) => map.pin(path),
             Self::Unsupported(map) => map.pin(path),
             Self::XskMap(map) => map.pin(path),
         }
@@ -404,19 +469,33 @@ macro_rules! impl_map_pin {
 }
 
 impl_map_pin!(() {
+    Arena,
+    ArrayOfMaps,
     ProgramArray,
+    ReusePortSockArray,
     SockMap,
     StackTraceMap,
+    StructOpsMap,
     CpuMap,
     DevMap,
     DevMapHash,
     XskMap,
 });
 
+impl_map_pin!((K) {
+    HashOfMaps,
+});
+
 impl_map_pin!((V) {
     Array,
+    CgroupStorage,
+    CgrpStorage,
+    InodeStorage,
     PerCpuArray,
+    PerCpuCgroupStorage,
+    SkStorage,
     SockHash,
+    TaskStorage,
     BloomFilter,
     Queue,
     Stack,
@@ -480,27 +559,41 @@ macro_rules! impl_try_from_map {
 }
 
 impl_try_from_map!(() {
+    Arena,
+    ArrayOfMaps,
     CpuMap,
     DevMap,
     DevMapHash,
     PerfEventArray,
     ProgramArray,
+    ReusePortSockArray,
     RingBuf,
     SockMap,
     StackTraceMap,
+    StructOpsMap from StructOps,
     XskMap,
     #[cfg(any(feature = "async_tokio", feature = "async_std"))]
     #[cfg_attr(docsrs, doc(cfg(any(feature = "async_tokio", feature = "async_std"))))]
     AsyncPerfEventArray from PerfEventArray,
 });
 
+impl_try_from_map!((K) {
+    HashOfMaps,
+});
+
 impl_try_from_map!((V) {
     Array,
     BloomFilter,
+    CgroupStorage,
+    CgrpStorage,
+    InodeStorage,
     PerCpuArray,
+    PerCpuCgroupStorage,
     Queue,
+    SkStorage,
     SockHash,
     Stack,
+    TaskStorage,
 });
 
 impl_try_from_map!((K, V) {
@@ -578,7 +671,16 @@ impl MapData {
             }
         };
 
-        let fd = bpf_create_map(&c_name, &obj, btf_fd).map_err(|io_error| {
+        // Map-of-maps are created with an inner map which the kernel uses as a
+        // template for the maps that can be stored in them. The template is
+        // only needed while the outer map is being created.
+        let inner = obj
+            .inner()
+            .map(|inner| Self::create(inner, &format!("{name}.inner"), btf_fd))
+            .transpose()?;
+        let inner_map_fd = inner.as_ref().map(|inner| inner.fd.as_fd());
+
+        let fd = bpf_create_map(&c_name, &obj, inner_map_fd, btf_fd).map_err(|io_error| {
             if !KernelVersion::at_least(5, 11, 0) {
                 maybe_warn_rlimit();
             }
@@ -634,7 +736,9 @@ impl MapData {
 
     pub(crate) fn finalize(&mut self) -> Result<(), MapError> {
         let Self { obj, fd } = self;
-        if !obj.data().is_empty() {
+        // struct_ops maps can only be updated once, with the file descriptors of their programs,
+        // which happens when they are registered.
+        if !obj.data().is_empty() && obj.section_kind() != EbpfSectionKind::StructOps {
             bpf_map_update_elem_ptr(fd.as_fd(), &0 as *const _, obj.data_mut().as_mut_ptr(), 0)
                 .map_err(|io_error| SyscallError {
                     call: "bpf_map_update_elem",
@@ -642,7 +746,10 @@ impl MapData {
                 })
                 .map_err(MapError::from)?;
         }
-        if obj.section_kind() == EbpfSectionKind::Rodata {
+        if matches!(
+            obj.section_kind(),
+            EbpfSectionKind::Rodata | EbpfSectionKind::Kconfig
+        ) {
             bpf_map_freeze(fd.as_fd())
                 .map_err(|io_error| SyscallError {
                     call: "bpf_map_freeze",
@@ -948,8 +1055,8 @@ impl<T: Pod> Deref for PerCpuValues<T> {
 //
 // The data is unmapped in Drop.
 #[cfg_attr(test, derive(Debug))]
-struct MMap {
-    ptr: ptr::NonNull<c_void>,
+pub(crate) struct MMap {
+    pub(crate) ptr: ptr::NonNull<c_void>,
     len: usize,
 }
 
@@ -959,14 +1066,26 @@ unsafe impl Send for MMap {}
 unsafe impl Sync for MMap {}
 
 impl MMap {
-    fn new(
+    pub(crate) fn new(
+        fd: BorrowedFd<'_>,
+        len: usize,
+        prot: c_int,
+        flags: c_int,
+        offset: off_t,
+    ) -> Result<Self, SyscallError> {
+        Self::new_at(ptr::null_mut(), fd, len, prot, flags, offset)
+    }
+
+    // Like `new`, but passes `addr` to mmap as a hint of where to place the mapping.
+    pub(crate) fn new_at(
+        addr: *mut c_void,
         fd: BorrowedFd<'_>,
         len: usize,
         prot: c_int,
         flags: c_int,
         offset: off_t,
     ) -> Result<Self, SyscallError> {
-        match unsafe { mmap(ptr::null_mut(), len, prot, flags, fd, offset) } {
+        match unsafe { mmap(addr, len, prot, flags, fd, offset) } {
             MAP_FAILED => Err(SyscallError {
                 call: "mmap",
                 io_error: io::Error::last_os_error(),
@@ -1026,38 +1145,38 @@ mod test_utils {
     }
 
     pub(super) fn new_obj_map<K>(map_type: bpf_map_type) -> aya_obj::Map {
-        aya_obj::Map::Legacy(LegacyMap {
-            def: bpf_map_def {
+        aya_obj::Map::Legacy(LegacyMap::new(
+            bpf_map_def {
                 map_type: map_type as u32,
                 key_size: std::mem::size_of::<K>() as u32,
                 value_size: 4,
                 max_entries: 1024,
                 ..Default::default()
             },
-            section_index: 0,
-            section_kind: EbpfSectionKind::Maps,
-            data: Vec::new(),
-            symbol_index: None,
-        })
+            0,
+            EbpfSectionKind::Maps,
+            None,
+            Vec::new(),
+        ))
     }
 
     pub(super) fn new_obj_map_with_max_entries<K>(
         map_type: bpf_map_type,
         max_entries: u32,
     ) -> aya_obj::Map {
-        aya_obj::Map::Legacy(LegacyMap {
-            def: bpf_map_def {
+        aya_obj::Map::Legacy(LegacyMap::new(
+            bpf_map_def {
                 map_type: map_type as u32,
                 key_size: std::mem::size_of::<K>() as u32,
                 value_size: 4,
                 max_entries,
                 ..Default::default()
             },
-            section_index: 0,
-            section_kind: EbpfSectionKind::Maps,
-            data: Vec::new(),
-            symbol_index: None,
-        })
+            0,
+            EbpfSectionKind::Maps,
+            None,
+            Vec::new(),
+        ))
     }
 }
 
@@ -1070,7 +1189,10 @@ mod tests {
     use libc::EFAULT;
 
     use super::*;
-    use crate::sys::{Syscall, override_syscall};
+    use crate::{
+        bpf_map_def,
+        sys::{Syscall, override_syscall},
+    };
 
     fn new_obj_map() -> aya_obj::Map {
         test_utils::new_obj_map::<u32>(bpf_map_type::BPF_MAP_TYPE_HASH)
@@ -1130,6 +1252,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_create_map_of_maps() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_CREATE,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_1 };
+                if u.map_type == bpf_map_type::BPF_MAP_TYPE_ARRAY_OF_MAPS as u32 {
+                    assert_eq!(u.inner_map_fd, crate::MockableFd::mock_unsigned_fd());
+                } else {
+                    assert_eq!(u.map_type, bpf_map_type::BPF_MAP_TYPE_ARRAY as u32);
+                    assert_eq!(u.inner_map_fd, 0);
+                }
+                Ok(crate::MockableFd::mock_signed_fd().into())
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        let mut obj = test_utils::new_obj_map::<u32>(bpf_map_type::BPF_MAP_TYPE_ARRAY_OF_MAPS);
+        let aya_obj::Map::Legacy(map) = &mut obj else {
+            unreachable!()
+        };
+        map.set_inner_def(Some(bpf_map_def {
+            map_type: bpf_map_type::BPF_MAP_TYPE_ARRAY as u32,
+            key_size: 4,
+            value_size: 8,
+            max_entries: 1,
+            ..Default::default()
+        }));
+
+        assert_matches!(
+            MapData::create(obj, "foo", None),
+            Ok(MapData {
+                obj,
+                fd,
+            }) => {
+                assert_eq!(fd.as_fd().as_raw_fd(), crate::MockableFd::mock_signed_fd());
+                assert_eq!(obj.map_type(), bpf_map_type::BPF_MAP_TYPE_ARRAY_OF_MAPS as u32);
+            }
+        );
+    }
+
     #[test]
     fn test_create_perf_event_array() {
         override_syscall(|call| match call {
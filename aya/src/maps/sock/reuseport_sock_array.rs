@@ -0,0 +1,193 @@
+//! An array of sockets in a `SO_REUSEPORT` group.
+
+use std::{
+    borrow::{Borrow, BorrowMut},
+    os::fd::{AsFd as _, AsRawFd},
+};
+
+use crate::{
+    maps::{MapData, MapError, MapKeys, check_bounds, check_kv_size},
+    sys::{SyscallError, bpf_map_delete_elem, bpf_map_lookup_elem, bpf_map_update_elem},
+};
+
+/// An array of TCP or UDP sockets in a `SO_REUSEPORT` group.
+///
+/// A `ReusePortSockArray` is used by [`SkReuseport`](crate::programs::SkReuseport)
+/// programs to select the socket, among the sockets bound to the same address
+/// with `SO_REUSEPORT`, that handles an incoming connection or packet. All the
+/// sockets stored in the map must belong to the same group.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.19.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[derive(Debug, thiserror::Error)]
+/// # enum Error {
+/// #     #[error(transparent)]
+/// #     IO(#[from] std::io::Error),
+/// #     #[error(transparent)]
+/// #     Map(#[from] aya::maps::MapError),
+/// #     #[error(transparent)]
+/// #     Program(#[from] aya::programs::ProgramError),
+/// #     #[error(transparent)]
+/// #     Ebpf(#[from] aya::EbpfError)
+/// # }
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// # fn reuseport_listener() -> std::io::Result<std::net::TcpListener> { unimplemented!() }
+/// use aya::maps::ReusePortSockArray;
+/// use aya::programs::SkReuseport;
+///
+/// let workers = [reuseport_listener()?, reuseport_listener()?];
+///
+/// let mut map: ReusePortSockArray<_> = bpf.map_mut("WORKERS").unwrap().try_into()?;
+/// for (index, worker) in workers.iter().enumerate() {
+///     map.set(index as u32, worker, 0)?;
+/// }
+///
+/// let prog: &mut SkReuseport = bpf.program_mut("select_worker").unwrap().try_into()?;
+/// prog.load()?;
+/// prog.attach(&workers[0])?;
+/// # Ok::<(), Error>(())
+/// ```
+#[doc(alias = "BPF_MAP_TYPE_REUSEPORT_SOCKARRAY")]
+pub struct ReusePortSockArray<T> {
+    pub(crate) inner: T,
+}
+
+impl<T: Borrow<MapData>> ReusePortSockArray<T> {
+    pub(crate) fn new(map: T) -> Result<Self, MapError> {
+        let data = map.borrow();
+        check_kv_size::<u32, u64>(data)?;
+
+        Ok(Self { inner: map })
+    }
+
+    /// An iterator over the indices of the array that point to a socket. The iterator item type
+    /// is `Result<u32, MapError>`.
+    pub fn indices(&self) -> MapKeys<'_, u32> {
+        MapKeys::new(self.inner.borrow())
+    }
+
+    /// Returns the cookie of the socket stored at `index`.
+    ///
+    /// The cookie can be compared to the `SO_COOKIE` socket option of the
+    /// sockets of the group.
+    pub fn get(&self, index: &u32, flags: u64) -> Result<u64, MapError> {
+        let data = self.inner.borrow();
+        check_bounds(data, *index)?;
+        let fd = data.fd().as_fd();
+
+        let value = bpf_map_lookup_elem(fd, index, flags).map_err(|io_error| SyscallError {
+            call: "bpf_map_lookup_elem",
+            io_error,
+        })?;
+        value.ok_or(MapError::KeyNotFound)
+    }
+}
+
+impl<T: BorrowMut<MapData>> ReusePortSockArray<T> {
+    /// Stores a socket into the map.
+    ///
+    /// The socket must have `SO_REUSEPORT` set, and must be listening if it
+    /// is a TCP socket or bound if it is a UDP socket.
+    pub fn set<I: AsRawFd>(&mut self, index: u32, socket: &I, flags: u64) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        let fd = data.fd().as_fd();
+        check_bounds(data, index)?;
+        let socket = socket.as_raw_fd() as u64;
+        bpf_map_update_elem(fd, Some(&index), &socket, flags)
+            .map_err(|io_error| SyscallError {
+                call: "bpf_map_update_elem",
+                io_error,
+            })
+            .map_err(Into::into)
+    }
+
+    /// Removes the socket stored at `index` from the map.
+    pub fn clear_index(&mut self, index: &u32) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        let fd = data.fd().as_fd();
+        check_bounds(data, *index)?;
+        bpf_map_delete_elem(fd, index)
+            .map_err(|io_error| SyscallError {
+                call: "bpf_map_delete_elem",
+                io_error,
+            })
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use aya_obj::generated::bpf_map_type::{BPF_MAP_TYPE_ARRAY, BPF_MAP_TYPE_REUSEPORT_SOCKARRAY};
+
+    use super::*;
+    use crate::maps::{
+        Map,
+        test_utils::{self, new_map},
+    };
+
+    fn new_obj_map() -> aya_obj::Map {
+        let mut obj = test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_REUSEPORT_SOCKARRAY);
+        obj.set_value_size(8);
+        obj
+    }
+
+    #[test]
+    fn test_wrong_key_size() {
+        let mut obj = test_utils::new_obj_map::<u16>(BPF_MAP_TYPE_REUSEPORT_SOCKARRAY);
+        obj.set_value_size(8);
+        let map = new_map(obj);
+        assert_matches!(
+            ReusePortSockArray::new(&map).err(),
+            Some(MapError::InvalidKeySize {
+                size: 4,
+                expected: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_wrong_value_size() {
+        let map = new_map(test_utils::new_obj_map::<u32>(
+            BPF_MAP_TYPE_REUSEPORT_SOCKARRAY,
+        ));
+        assert_matches!(
+            ReusePortSockArray::new(&map).err(),
+            Some(MapError::InvalidValueSize {
+                size: 8,
+                expected: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_from_wrong_map() {
+        let map = new_map(test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_ARRAY));
+        let map = Map::Array(map);
+
+        assert_matches!(
+            ReusePortSockArray::try_from(&map).err(),
+            Some(MapError::InvalidMapType { .. })
+        );
+    }
+
+    #[test]
+    fn test_new_ok() {
+        let map = new_map(new_obj_map());
+
+        assert!(ReusePortSockArray::new(&map).is_ok());
+    }
+
+    #[test]
+    fn test_try_from_ok() {
+        let map = new_map(new_obj_map());
+
+        let map = Map::ReusePortSockArray(map);
+        assert!(ReusePortSockArray::try_from(&map).is_ok());
+    }
+}
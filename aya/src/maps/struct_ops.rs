@@ -0,0 +1,182 @@
+//! Struct ops maps.
+//!
+//! See [`StructOpsMap`] for documentation and examples.
+use std::{
+    borrow::{Borrow, BorrowMut},
+    os::fd::{AsFd as _, AsRawFd as _},
+};
+
+use aya_obj::generated::BPF_F_LINK;
+
+use crate::{
+    maps::{MapData, MapError, MapFd},
+    programs::{
+        ProgramFd,
+        links::{FdLink, LinkError},
+    },
+    sys::{SyscallError, bpf_link_create_struct_ops, bpf_map_delete_elem, bpf_map_update_elem_ptr},
+};
+
+/// A map implementing a kernel operations structure with eBPF programs.
+///
+/// struct_ops maps are declared in the `.struct_ops` or `.struct_ops.link`
+/// sections as variables of the kernel struct type, whose function pointers
+/// are set to the [`StructOps`](crate::programs::StructOps) programs
+/// implementing them. The layout of the struct is translated to the one of the
+/// running kernel when the object is loaded.
+///
+/// Once its programs are loaded, the map is registered with the kernel by
+/// calling [`StructOpsMap::register`]. Maps from the `.struct_ops.link`
+/// section are registered through a BPF link.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.6, and 6.4
+/// for maps from the `.struct_ops.link` section.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[derive(thiserror::Error, Debug)]
+/// # enum Error {
+/// #     #[error(transparent)]
+/// #     Map(#[from] aya::maps::MapError),
+/// #     #[error(transparent)]
+/// #     Program(#[from] aya::programs::ProgramError),
+/// #     #[error(transparent)]
+/// #     Ebpf(#[from] aya::EbpfError),
+/// # }
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::{maps::StructOpsMap, programs::StructOps};
+///
+/// for name in ["aya_ssthresh", "aya_cong_avoid", "aya_undo_cwnd"] {
+///     let program: &mut StructOps = bpf.program_mut(name).unwrap().try_into()?;
+///     program.load()?;
+/// }
+/// let mut ops: StructOpsMap<_> = bpf.take_map("aya_cc").unwrap().try_into()?;
+/// let link = ops.register(
+///     bpf.programs()
+///         .filter_map(|(name, program)| Some((name, program.fd().ok()?))),
+/// )?;
+///
+/// // the struct_ops is unregistered when the link is dropped
+/// drop(link);
+/// # Ok::<(), Error>(())
+/// ```
+#[doc(alias = "BPF_MAP_TYPE_STRUCT_OPS")]
+#[derive(Debug)]
+pub struct StructOpsMap<T> {
+    pub(crate) inner: T,
+}
+
+impl<T: Borrow<MapData>> StructOpsMap<T> {
+    pub(crate) fn new(map: T) -> Result<Self, MapError> {
+        let data = map.borrow();
+        let aya_obj::Map::StructOps(_) = data.obj else {
+            return Err(MapError::InvalidMapType {
+                map_type: data.obj.map_type(),
+            });
+        };
+        Ok(Self { inner: map })
+    }
+}
+
+impl<T: BorrowMut<MapData>> StructOpsMap<T> {
+    /// Registers the struct_ops with the kernel.
+    ///
+    /// `programs` maps program names to the file descriptors of the loaded
+    /// programs. It must contain all the programs referenced by the map, and
+    /// may contain other programs, which are ignored.
+    ///
+    /// The struct_ops is unregistered when the returned link is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::StructOpsProgramNotFound`] if a program referenced
+    /// by the map is missing from `programs`, or [`MapError::SyscallError`] if
+    /// `bpf_map_update_elem` or `bpf_link_create` fail.
+    pub fn register<'a>(
+        &mut self,
+        programs: impl IntoIterator<Item = (&'a str, &'a ProgramFd)>,
+    ) -> Result<StructOpsLink, MapError> {
+        let data = self.inner.borrow_mut();
+        let aya_obj::Map::StructOps(obj) = &data.obj else {
+            unreachable!("checked in StructOpsMap::new")
+        };
+        let programs: std::collections::HashMap<_, _> = programs.into_iter().collect();
+
+        let mut value = data.obj.data().to_vec();
+        for program in obj.programs() {
+            let fd = programs.get(program.name.as_str()).ok_or_else(|| {
+                MapError::StructOpsProgramNotFound {
+                    name: program.name.clone(),
+                }
+            })?;
+            // the kernel reads the program fds from the function pointer slots
+            let fd = fd.as_fd().as_raw_fd() as u64;
+            value[program.offset..program.offset + size_of::<u64>()]
+                .copy_from_slice(&fd.to_ne_bytes());
+        }
+
+        let fd = data.fd().as_fd();
+        bpf_map_update_elem_ptr(fd, &0u32, value.as_mut_ptr(), 0).map_err(|io_error| {
+            SyscallError {
+                call: "bpf_map_update_elem",
+                io_error,
+            }
+        })?;
+
+        let inner = if data.obj.map_flags() & BPF_F_LINK != 0 {
+            let link_fd = bpf_link_create_struct_ops(fd).map_err(|io_error| SyscallError {
+                call: "bpf_link_create",
+                io_error,
+            })?;
+            StructOpsLinkInner::FdLink(FdLink::new(link_fd))
+        } else {
+            StructOpsLinkInner::Map(data.fd().try_clone()?)
+        };
+        Ok(StructOpsLink { inner: Some(inner) })
+    }
+}
+
+/// A struct_ops registered with the kernel.
+///
+/// The struct_ops is unregistered when the link is dropped. Links of maps
+/// from the `.struct_ops.link` section can be converted to an [`FdLink`], for
+/// example to pin them.
+#[derive(Debug)]
+pub struct StructOpsLink {
+    // only None after the link was converted to an FdLink
+    inner: Option<StructOpsLinkInner>,
+}
+
+#[derive(Debug)]
+enum StructOpsLinkInner {
+    // registered with BPF_LINK_CREATE, unregistered when the link fd is closed
+    FdLink(FdLink),
+    // registered with BPF_MAP_UPDATE_ELEM, unregistered by deleting the element
+    Map(MapFd),
+}
+
+impl Drop for StructOpsLink {
+    fn drop(&mut self) {
+        let Self { inner } = self;
+        if let Some(StructOpsLinkInner::Map(fd)) = inner {
+            let _: Result<(), _> = bpf_map_delete_elem(fd.as_fd(), &0u32);
+        }
+    }
+}
+
+impl TryFrom<StructOpsLink> for FdLink {
+    type Error = LinkError;
+
+    fn try_from(mut value: StructOpsLink) -> Result<Self, Self::Error> {
+        match value.inner.take() {
+            Some(StructOpsLinkInner::FdLink(fd_link)) => Ok(fd_link),
+            inner => {
+                value.inner = inner;
+                Err(LinkError::InvalidLink)
+            }
+        }
+    }
+}
@@ -178,7 +178,7 @@ fn get_btf_info(
     // assume 4kb. if this is too small we can resize based on the size obtained in the response.
     let mut buf = vec![0u8; 4096];
     loop {
-        let info = sys::btf_obj_get_info_by_fd(btf_fd.as_fd(), &mut buf)?;
+        let info = sys::btf_obj_get_info_by_fd(btf_fd.as_fd(), &mut buf, &mut [])?;
         let btf_size = info.btf_size as usize;
         if btf_size > buf.len() {
             buf.resize(btf_size, 0u8);
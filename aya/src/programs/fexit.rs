@@ -7,7 +7,7 @@ use aya_obj::{
 
 use crate::programs::{
     FdLink, FdLinkId, ProgramData, ProgramError, ProgramType, define_link_wrapper, load_program,
-    utils::attach_raw_tracepoint,
+    utils::{attach_raw_tracepoint, find_btf_attach_target},
 };
 
 /// A program that can be attached to the exit point of (almost) anny kernel
@@ -59,9 +59,14 @@ impl FExit {
     /// Loads the program so it's executed when the kernel function `fn_name`
     /// is exited. The `btf` argument must contain the BTF info for the running
     /// kernel.
+    ///
+    /// To attach to a function of a kernel module, `btf` must be the BTF of
+    /// that module, see [`Btf::from_sys_fs_module`].
     pub fn load(&mut self, fn_name: &str, btf: &Btf) -> Result<(), ProgramError> {
         self.data.expected_attach_type = Some(BPF_TRACE_FEXIT);
-        self.data.attach_btf_id = Some(btf.id_by_type_name_kind(fn_name, BtfKind::Func)?);
+        let (btf_id, btf_fd) = find_btf_attach_target(btf, fn_name, BtfKind::Func)?;
+        self.data.attach_btf_id = Some(btf_id);
+        self.data.attach_btf_obj_fd = btf_fd;
         load_program(BPF_PROG_TYPE_TRACING, &mut self.data)
     }
 
@@ -0,0 +1,91 @@
+//! Fmod_ret programs.
+
+use aya_obj::{
+    btf::{Btf, BtfKind},
+    generated::{bpf_attach_type::BPF_MODIFY_RETURN, bpf_prog_type::BPF_PROG_TYPE_TRACING},
+};
+
+use crate::programs::{
+    FdLink, FdLinkId, ProgramData, ProgramError, ProgramType, define_link_wrapper, load_program,
+    utils::{attach_raw_tracepoint, find_btf_attach_target},
+};
+
+/// A program that can override the return value of a kernel function.
+///
+/// [`FModRet`] programs run before the kernel function they are attached to,
+/// like [`FEntry`](crate::programs::FEntry) programs. When the program returns
+/// a non-zero value, the kernel function is skipped and that value is returned
+/// to its caller instead.
+///
+/// Only functions that allow error injection, such as the ones marked with
+/// `ALLOW_ERROR_INJECTION` and the `security_*` hooks, can be attached to.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.7.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[derive(thiserror::Error, Debug)]
+/// # enum Error {
+/// #     #[error(transparent)]
+/// #     BtfError(#[from] aya::BtfError),
+/// #     #[error(transparent)]
+/// #     Program(#[from] aya::programs::ProgramError),
+/// #     #[error(transparent)]
+/// #     Ebpf(#[from] aya::EbpfError),
+/// # }
+/// # let mut bpf = Ebpf::load_file("ebpf_programs.o")?;
+/// use aya::{Ebpf, programs::FModRet, BtfError, Btf};
+///
+/// let btf = Btf::from_sys_fs()?;
+/// let program: &mut FModRet = bpf.program_mut("fail_open").unwrap().try_into()?;
+/// program.load("security_file_open", &btf)?;
+/// program.attach()?;
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug)]
+#[doc(alias = "BPF_MODIFY_RETURN")]
+#[doc(alias = "BPF_PROG_TYPE_TRACING")]
+pub struct FModRet {
+    pub(crate) data: ProgramData<FModRetLink>,
+}
+
+impl FModRet {
+    /// The type of the program according to the kernel.
+    pub const PROGRAM_TYPE: ProgramType = ProgramType::Tracing;
+
+    /// Loads the program inside the kernel.
+    ///
+    /// Loads the program so it's executed before the kernel function
+    /// `fn_name`, with the ability to override its return value. The `btf`
+    /// argument must contain the BTF info for the running kernel.
+    ///
+    /// To attach to a function of a kernel module, `btf` must be the BTF of
+    /// that module, see [`Btf::from_sys_fs_module`].
+    pub fn load(&mut self, fn_name: &str, btf: &Btf) -> Result<(), ProgramError> {
+        self.data.expected_attach_type = Some(BPF_MODIFY_RETURN);
+        let (btf_id, btf_fd) = find_btf_attach_target(btf, fn_name, BtfKind::Func)?;
+        self.data.attach_btf_id = Some(btf_id);
+        self.data.attach_btf_obj_fd = btf_fd;
+        load_program(BPF_PROG_TYPE_TRACING, &mut self.data)
+    }
+
+    /// Attaches the program.
+    ///
+    /// The returned value can be used to detach, see [FModRet::detach].
+    pub fn attach(&mut self) -> Result<FModRetLinkId, ProgramError> {
+        attach_raw_tracepoint(&mut self.data, None)
+    }
+}
+
+define_link_wrapper!(
+    /// The link used by [FModRet] programs.
+    FModRetLink,
+    /// The type returned by [FModRet::attach]. Can be passed to [FModRet::detach].
+    FModRetLinkId,
+    FdLink,
+    FdLinkId,
+    FModRet,
+);
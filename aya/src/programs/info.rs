@@ -407,7 +407,8 @@ pub enum ProgramType {
     /// Introduced in kernel v4.18.
     #[doc(alias = "BPF_PROG_TYPE_LIRC_MODE2")]
     LircMode2 = bpf_prog_type::BPF_PROG_TYPE_LIRC_MODE2 as isize,
-    /// A Socket Reuseport program type.
+    /// A Socket Reuseport program type. See [`SkReuseport`](super::sk_reuseport::SkReuseport)
+    /// for the program implementation.
     ///
     /// Introduced in kernel v4.19.
     #[doc(alias = "BPF_PROG_TYPE_SK_REUSEPORT")]
@@ -423,7 +424,9 @@ pub enum ProgramType {
     /// Introduced in kernel v5.2.
     #[doc(alias = "BPF_PROG_TYPE_CGROUP_SYSCTL")]
     CgroupSysctl = bpf_prog_type::BPF_PROG_TYPE_CGROUP_SYSCTL as isize,
-    /// A Writable Raw Tracepoint program type.
+    /// A Writable Raw Tracepoint program type. See
+    /// [`RawTracePointWritable`](super::raw_trace_point::RawTracePointWritable) for the program
+    /// implementation.
     ///
     /// Introduced in kernel v5.2.
     #[doc(alias = "BPF_PROG_TYPE_RAW_TRACEPOINT_WRITABLE")]
@@ -435,12 +438,14 @@ pub enum ProgramType {
     #[doc(alias = "BPF_PROG_TYPE_CGROUP_SOCKOPT")]
     CgroupSockopt = bpf_prog_type::BPF_PROG_TYPE_CGROUP_SOCKOPT as isize,
     /// A Tracing program type. See [`FEntry`](super::fentry::FEntry), [`FExit`](super::fexit::FExit),
-    /// and [`BtfTracePoint`](super::tp_btf::BtfTracePoint) for the program implementations.
+    /// [`FModRet`](super::fmod_ret::FModRet) and [`BtfTracePoint`](super::tp_btf::BtfTracePoint) for
+    /// the program implementations.
     ///
     /// Introduced in kernel v5.5.
     #[doc(alias = "BPF_PROG_TYPE_TRACING")]
     Tracing = bpf_prog_type::BPF_PROG_TYPE_TRACING as isize,
-    /// A Struct Ops program type.
+    /// A Struct Ops program type. See [`StructOps`](super::struct_ops::StructOps) for the
+    /// program implementation.
     ///
     /// Introduced in kernel v5.6.
     #[doc(alias = "BPF_PROG_TYPE_STRUCT_OPS")]
@@ -451,8 +456,8 @@ pub enum ProgramType {
     /// Introduced in kernel v5.6.
     #[doc(alias = "BPF_PROG_TYPE_EXT")]
     Extension = bpf_prog_type::BPF_PROG_TYPE_EXT as isize,
-    /// A Linux Security Module (LSM) program type. See [`Lsm`](super::lsm::Lsm) for the program
-    /// implementation.
+    /// A Linux Security Module (LSM) program type. See [`Lsm`](super::lsm::Lsm) and
+    /// [`LsmCgroup`](super::lsm_cgroup::LsmCgroup) for the program implementations.
     ///
     /// Introduced in kernel v5.7.
     #[doc(alias = "BPF_PROG_TYPE_LSM")]
@@ -463,12 +468,14 @@ pub enum ProgramType {
     /// Introduced in kernel v5.9.
     #[doc(alias = "BPF_PROG_TYPE_SK_LOOKUP")]
     SkLookup = bpf_prog_type::BPF_PROG_TYPE_SK_LOOKUP as isize,
-    /// A Syscall program type.
+    /// A Syscall program type. See [`Syscall`](super::syscall::Syscall) for the program
+    /// implementation.
     ///
     /// Introduced in kernel v5.14.
     #[doc(alias = "BPF_PROG_TYPE_SYSCALL")]
     Syscall = bpf_prog_type::BPF_PROG_TYPE_SYSCALL as isize,
-    /// A Netfilter program type.
+    /// A Netfilter program type. See [`Netfilter`](super::netfilter::Netfilter) for the program
+    /// implementation.
     ///
     /// Introduced in kernel v6.4.
     #[doc(alias = "BPF_PROG_TYPE_NETFILTER")]
@@ -1,12 +1,15 @@
 //! Kernel space probes.
 use std::{
-    ffi::OsStr,
-    io,
+    ffi::{CString, OsStr, c_char},
+    fs, io,
     os::fd::AsFd as _,
     path::{Path, PathBuf},
 };
 
-use aya_obj::generated::{bpf_link_type, bpf_prog_type::BPF_PROG_TYPE_KPROBE};
+use aya_obj::generated::{
+    BPF_F_KPROBE_MULTI_RETURN, bpf_attach_type::BPF_TRACE_KPROBE_MULTI, bpf_link_type,
+    bpf_prog_type::BPF_PROG_TYPE_KPROBE,
+};
 use thiserror::Error;
 
 use crate::{
@@ -16,8 +19,9 @@ use crate::{
         load_program,
         perf_attach::{PerfLinkIdInner, PerfLinkInner},
         probe::{ProbeKind, attach},
+        utils::{find_tracefs_path, glob_match},
     },
-    sys::bpf_link_get_info_by_fd,
+    sys::{BpfLinkCreateArgs, LinkTarget, SyscallError, bpf_link_create, bpf_link_get_info_by_fd},
 };
 
 /// A kernel probe.
@@ -43,6 +47,19 @@ use crate::{
 /// program.attach("try_to_wake_up", 0)?;
 /// # Ok::<(), aya::EbpfError>(())
 /// ```
+///
+/// Programs declared in the `kprobe.multi` or `kretprobe.multi` sections can
+/// be attached to many functions at once with [`KProbe::attach_multi`]:
+///
+/// ```no_run
+/// # let mut bpf = Ebpf::load_file("ebpf_programs.o")?;
+/// use aya::{Ebpf, programs::{KProbe, KProbeMultiTarget}};
+///
+/// let program: &mut KProbe = bpf.program_mut("tcp_calls").unwrap().try_into()?;
+/// program.load()?;
+/// program.attach_multi(KProbeMultiTarget::Pattern("tcp_v4_*"))?;
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
 #[derive(Debug)]
 #[doc(alias = "BPF_PROG_TYPE_KPROBE")]
 pub struct KProbe {
@@ -50,6 +67,18 @@ pub struct KProbe {
     pub(crate) kind: ProbeKind,
 }
 
+/// The kernel functions to attach a [`KProbe`] to with [`KProbe::attach_multi`].
+#[derive(Debug, Clone, Copy)]
+pub enum KProbeMultiTarget<'a> {
+    /// The functions listed in `available_filter_functions` whose name matches
+    /// the glob pattern. `*` matches any sequence of characters and `?` matches
+    /// any single character.
+    Pattern(&'a str),
+    /// The given functions, along with the cookie returned by
+    /// `bpf_get_attach_cookie` when the program runs for each of them.
+    Symbols(&'a [(&'a str, u64)]),
+}
+
 impl KProbe {
     /// The type of the program according to the kernel.
     pub const PROGRAM_TYPE: ProgramType = ProgramType::KProbe;
@@ -91,6 +120,101 @@ impl KProbe {
         )
     }
 
+    /// Attaches the program with a cookie.
+    ///
+    /// Same as [`KProbe::attach`], but the eBPF program can retrieve `cookie`
+    /// with the `bpf_get_attach_cookie()` helper, which allows a single
+    /// program attached to several functions to tell them apart.
+    ///
+    /// Returns [`ProgramError::AttachCookieNotSupported`] if the kernel doesn't
+    /// support attach cookies.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.15.
+    pub fn attach_with_cookie<T: AsRef<OsStr>>(
+        &mut self,
+        fn_name: T,
+        offset: u64,
+        cookie: u64,
+    ) -> Result<KProbeLinkId, ProgramError> {
+        attach(
+            &mut self.data,
+            self.kind,
+            fn_name.as_ref(),
+            offset,
+            None, // pid
+            Some(cookie),
+        )
+    }
+
+    /// Attaches the program to multiple functions.
+    ///
+    /// All the functions are attached through a single `kprobe.multi` link,
+    /// which is much faster to set up than attaching to each function with
+    /// [`KProbe::attach`] and uses a single file descriptor.
+    ///
+    /// The program must be declared in a `kprobe.multi` or `kretprobe.multi`
+    /// section.
+    ///
+    /// The returned value can be used to detach, see [KProbe::detach].
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.18.
+    pub fn attach_multi(
+        &mut self,
+        target: KProbeMultiTarget<'_>,
+    ) -> Result<KProbeLinkId, ProgramError> {
+        let prog_fd = self.data.fd()?;
+        let prog_fd = prog_fd.as_fd();
+
+        let (names, cookies) = match target {
+            KProbeMultiTarget::Pattern(pattern) => (matching_functions(pattern)?, None),
+            KProbeMultiTarget::Symbols(symbols) => {
+                let (names, cookies): (Vec<_>, Vec<_>) = symbols
+                    .iter()
+                    .map(|&(name, cookie)| (name.to_owned(), cookie))
+                    .unzip();
+                (names, Some(cookies))
+            }
+        };
+        let names = names
+            .into_iter()
+            .map(|name| {
+                CString::new(name.as_str()).map_err(|_| KProbeError::InvalidFunctionName { name })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let syms = names
+            .iter()
+            .map(|name| name.as_ptr())
+            .collect::<Vec<*const c_char>>();
+
+        let flags = match self.kind {
+            ProbeKind::KRetProbe => BPF_F_KPROBE_MULTI_RETURN,
+            ProbeKind::KProbe | ProbeKind::UProbe | ProbeKind::URetProbe => 0,
+        };
+        let link_fd = bpf_link_create(
+            prog_fd,
+            LinkTarget::KProbeMulti,
+            BPF_TRACE_KPROBE_MULTI,
+            0,
+            Some(BpfLinkCreateArgs::KProbeMulti {
+                flags,
+                syms: &syms,
+                cookies: cookies.as_deref(),
+            }),
+        )
+        .map_err(|io_error| SyscallError {
+            call: "bpf_link_create",
+            io_error,
+        })?;
+
+        self.data
+            .links
+            .insert(KProbeLink::new(PerfLinkInner::FdLink(FdLink::new(link_fd))))
+    }
+
     /// Creates a program from a pinned entry on a bpffs.
     ///
     /// Existing links will not be populated. To work with existing links you should use [`crate::programs::links::PinnedLink`].
@@ -125,6 +249,49 @@ pub enum KProbeError {
         #[source]
         io_error: io::Error,
     },
+
+    /// No function matches the pattern passed to [`KProbe::attach_multi`].
+    #[error("no function matches `{pattern}`")]
+    NoMatchingFunctions {
+        /// The glob pattern
+        pattern: String,
+    },
+
+    /// A function name passed to [`KProbe::attach_multi`] contains a NUL byte.
+    #[error("invalid function name `{name}`")]
+    InvalidFunctionName {
+        /// The function name
+        name: String,
+    },
+}
+
+// Returns the functions that kprobes can be attached to whose name matches the glob `pattern`.
+fn matching_functions(pattern: &str) -> Result<Vec<String>, ProgramError> {
+    let filename = find_tracefs_path()?.join("available_filter_functions");
+    let functions = fs::read_to_string(&filename)
+        .map_err(|io_error| KProbeError::FileError { filename, io_error })?;
+
+    // Lines are formatted as `name` or `name [module]`.
+    let mut functions = functions
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        // ftrace lists functions whose address it couldn't resolve under this name
+        .filter(|name| !name.starts_with("__ftrace_invalid_address__"))
+        .filter(|name| glob_match(pattern, name))
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    // Static functions can be listed several times, but the kernel rejects
+    // duplicated names.
+    functions.sort_unstable();
+    functions.dedup();
+
+    if functions.is_empty() {
+        return Err(KProbeError::NoMatchingFunctions {
+            pattern: pattern.to_owned(),
+        }
+        .into());
+    }
+    Ok(functions)
 }
 
 impl TryFrom<KProbeLink> for FdLink {
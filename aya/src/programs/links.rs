@@ -8,7 +8,7 @@ use std::{
 
 use aya_obj::generated::{
     BPF_F_AFTER, BPF_F_ALLOW_MULTI, BPF_F_ALLOW_OVERRIDE, BPF_F_BEFORE, BPF_F_ID, BPF_F_LINK,
-    BPF_F_REPLACE, bpf_attach_type,
+    BPF_F_REPLACE, bpf_attach_type, bpf_link_type,
 };
 use hashbrown::hash_set::{Entry, HashSet};
 use thiserror::Error;
@@ -55,6 +55,78 @@ impl From<CgroupAttachMode> for u32 {
     }
 }
 
+/// The type of an eBPF link.
+#[non_exhaustive]
+#[doc(alias = "bpf_link_type")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LinkType {
+    /// A Raw Tracepoint link type.
+    ///
+    /// Introduced in kernel v5.7.
+    #[doc(alias = "BPF_LINK_TYPE_RAW_TRACEPOINT")]
+    RawTracePoint = bpf_link_type::BPF_LINK_TYPE_RAW_TRACEPOINT as isize,
+    /// A Tracing link type, used by fentry, fexit and BTF tracepoint programs.
+    ///
+    /// Introduced in kernel v5.7.
+    #[doc(alias = "BPF_LINK_TYPE_TRACING")]
+    Tracing = bpf_link_type::BPF_LINK_TYPE_TRACING as isize,
+    /// A cGroup link type.
+    ///
+    /// Introduced in kernel v5.7.
+    #[doc(alias = "BPF_LINK_TYPE_CGROUP")]
+    Cgroup = bpf_link_type::BPF_LINK_TYPE_CGROUP as isize,
+    /// An Iterator link type.
+    ///
+    /// Introduced in kernel v5.8.
+    #[doc(alias = "BPF_LINK_TYPE_ITER")]
+    Iter = bpf_link_type::BPF_LINK_TYPE_ITER as isize,
+    /// A Network Namespace link type, used by flow dissector and socket lookup programs.
+    ///
+    /// Introduced in kernel v5.8.
+    #[doc(alias = "BPF_LINK_TYPE_NETNS")]
+    Netns = bpf_link_type::BPF_LINK_TYPE_NETNS as isize,
+    /// An Express Data Path (XDP) link type.
+    ///
+    /// Introduced in kernel v5.9.
+    #[doc(alias = "BPF_LINK_TYPE_XDP")]
+    Xdp = bpf_link_type::BPF_LINK_TYPE_XDP as isize,
+    /// A Perf Event link type, used by kprobe, uprobe, tracepoint and perf event programs.
+    ///
+    /// Introduced in kernel v5.15.
+    #[doc(alias = "BPF_LINK_TYPE_PERF_EVENT")]
+    PerfEvent = bpf_link_type::BPF_LINK_TYPE_PERF_EVENT as isize,
+    /// A Kernel Probe multi-attach link type.
+    ///
+    /// Introduced in kernel v5.18.
+    #[doc(alias = "BPF_LINK_TYPE_KPROBE_MULTI")]
+    KProbeMulti = bpf_link_type::BPF_LINK_TYPE_KPROBE_MULTI as isize,
+    /// A Struct Ops link type.
+    ///
+    /// Introduced in kernel v6.4.
+    #[doc(alias = "BPF_LINK_TYPE_STRUCT_OPS")]
+    StructOps = bpf_link_type::BPF_LINK_TYPE_STRUCT_OPS as isize,
+    /// A Netfilter link type.
+    ///
+    /// Introduced in kernel v6.4.
+    #[doc(alias = "BPF_LINK_TYPE_NETFILTER")]
+    Netfilter = bpf_link_type::BPF_LINK_TYPE_NETFILTER as isize,
+    /// A Traffic Control Express (TCX) link type.
+    ///
+    /// Introduced in kernel v6.6.
+    #[doc(alias = "BPF_LINK_TYPE_TCX")]
+    Tcx = bpf_link_type::BPF_LINK_TYPE_TCX as isize,
+    /// A User Probe multi-attach link type.
+    ///
+    /// Introduced in kernel v6.6.
+    #[doc(alias = "BPF_LINK_TYPE_UPROBE_MULTI")]
+    UProbeMulti = bpf_link_type::BPF_LINK_TYPE_UPROBE_MULTI as isize,
+    /// A Netkit link type.
+    ///
+    /// Introduced in kernel v6.7.
+    #[doc(alias = "BPF_LINK_TYPE_NETKIT")]
+    Netkit = bpf_link_type::BPF_LINK_TYPE_NETKIT as isize,
+}
+
 #[derive(Debug)]
 pub(crate) struct Links<T: Link> {
     links: HashSet<T>,
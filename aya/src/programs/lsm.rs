@@ -7,7 +7,7 @@ use aya_obj::{
 
 use crate::programs::{
     FdLink, FdLinkId, ProgramData, ProgramError, ProgramType, define_link_wrapper, load_program,
-    utils::attach_raw_tracepoint,
+    utils::{attach_raw_tracepoint, find_btf_attach_target},
 };
 
 /// A program that attaches to Linux LSM hooks. Used to implement security policy and
@@ -63,11 +63,14 @@ impl Lsm {
     ///
     /// * `lsm_hook_name` - full name of the LSM hook that the program should
     ///   be attached to
+    /// * `btf` - btf information for the target system, or the BTF of
+    ///   a kernel module, see [`Btf::from_sys_fs_module`].
     pub fn load(&mut self, lsm_hook_name: &str, btf: &Btf) -> Result<(), ProgramError> {
         self.data.expected_attach_type = Some(BPF_LSM_MAC);
         let type_name = format!("bpf_lsm_{lsm_hook_name}");
-        self.data.attach_btf_id =
-            Some(btf.id_by_type_name_kind(type_name.as_str(), BtfKind::Func)?);
+        let (btf_id, btf_fd) = find_btf_attach_target(btf, &type_name, BtfKind::Func)?;
+        self.data.attach_btf_id = Some(btf_id);
+        self.data.attach_btf_obj_fd = btf_fd;
         load_program(BPF_PROG_TYPE_LSM, &mut self.data)
     }
 
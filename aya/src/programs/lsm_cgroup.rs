@@ -0,0 +1,128 @@
+//! LSM probes attached to cgroups.
+
+use std::os::fd::AsFd;
+
+use aya_obj::{
+    btf::{Btf, BtfKind},
+    generated::{bpf_attach_type::BPF_LSM_CGROUP, bpf_prog_type::BPF_PROG_TYPE_LSM},
+};
+
+use crate::{
+    programs::{
+        CgroupAttachMode, FdLink, FdLinkId, ProgramData, ProgramError, ProgramType,
+        define_link_wrapper, load_program, utils::find_btf_attach_target,
+    },
+    sys::{LinkTarget, SyscallError, bpf_link_create},
+};
+
+/// A program that attaches to Linux LSM hooks for the tasks of a cgroup.
+///
+/// [`LsmCgroup`] programs are [`Lsm`](super::Lsm) programs that only run for
+/// the tasks of the [cgroup] they are attached to and of its descendants. They
+/// must return 1 to allow the operation, or 0 to deny it with `EPERM`.
+///
+/// They commonly keep per-cgroup state in a
+/// [`CgroupStorage`](crate::maps::CgroupStorage) map.
+///
+/// LSM probes require a kernel compiled with `CONFIG_BPF_LSM=y` and `CONFIG_DEBUG_INFO_BTF=y`.
+/// Unlike [`Lsm`](super::Lsm) programs, they run even if the BPF LSM isn't
+/// enabled through the kernel's boot parameters.
+///
+/// [cgroup]: https://man7.org/linux/man-pages/man7/cgroups.7.html
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 6.0.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[derive(thiserror::Error, Debug)]
+/// # enum LsmError {
+/// #     #[error(transparent)]
+/// #     IO(#[from] std::io::Error),
+/// #     #[error(transparent)]
+/// #     BtfError(#[from] aya::BtfError),
+/// #     #[error(transparent)]
+/// #     Program(#[from] aya::programs::ProgramError),
+/// #     #[error(transparent)]
+/// #     Ebpf(#[from] aya::EbpfError),
+/// # }
+/// # let mut bpf = Ebpf::load_file("ebpf_programs.o")?;
+/// use std::fs::File;
+///
+/// use aya::{Btf, Ebpf, programs::{CgroupAttachMode, LsmCgroup}};
+///
+/// let btf = Btf::from_sys_fs()?;
+/// let file = File::open("/sys/fs/cgroup/app")?;
+/// let program: &mut LsmCgroup = bpf.program_mut("lsm_prog").unwrap().try_into()?;
+/// program.load("socket_bind", &btf)?;
+/// program.attach(file, CgroupAttachMode::Single)?;
+/// # Ok::<(), LsmError>(())
+/// ```
+#[derive(Debug)]
+#[doc(alias = "BPF_PROG_TYPE_LSM")]
+#[doc(alias = "BPF_LSM_CGROUP")]
+pub struct LsmCgroup {
+    pub(crate) data: ProgramData<LsmCgroupLink>,
+}
+
+impl LsmCgroup {
+    /// The type of the program according to the kernel.
+    pub const PROGRAM_TYPE: ProgramType = ProgramType::Lsm;
+
+    /// Loads the program inside the kernel.
+    ///
+    /// # Arguments
+    ///
+    /// * `lsm_hook_name` - full name of the LSM hook that the program should
+    ///   be attached to
+    /// * `btf` - btf information for the target system, or the BTF of
+    ///   a kernel module, see [`Btf::from_sys_fs_module`].
+    pub fn load(&mut self, lsm_hook_name: &str, btf: &Btf) -> Result<(), ProgramError> {
+        self.data.expected_attach_type = Some(BPF_LSM_CGROUP);
+        let type_name = format!("bpf_lsm_{lsm_hook_name}");
+        let (btf_id, btf_fd) = find_btf_attach_target(btf, &type_name, BtfKind::Func)?;
+        self.data.attach_btf_id = Some(btf_id);
+        self.data.attach_btf_obj_fd = btf_fd;
+        load_program(BPF_PROG_TYPE_LSM, &mut self.data)
+    }
+
+    /// Attaches the program to the given cgroup.
+    ///
+    /// The returned value can be used to detach, see [LsmCgroup::detach].
+    pub fn attach<T: AsFd>(
+        &mut self,
+        cgroup: T,
+        mode: CgroupAttachMode,
+    ) -> Result<LsmCgroupLinkId, ProgramError> {
+        let prog_fd = self.fd()?;
+        let prog_fd = prog_fd.as_fd();
+        let cgroup_fd = cgroup.as_fd();
+
+        let link_fd = bpf_link_create(
+            prog_fd,
+            LinkTarget::Fd(cgroup_fd),
+            BPF_LSM_CGROUP,
+            mode.into(),
+            None,
+        )
+        .map_err(|io_error| SyscallError {
+            call: "bpf_link_create",
+            io_error,
+        })?;
+        self.data
+            .links
+            .insert(LsmCgroupLink::new(FdLink::new(link_fd)))
+    }
+}
+
+define_link_wrapper!(
+    /// The link used by [LsmCgroup] programs.
+    LsmCgroupLink,
+    /// The type returned by [LsmCgroup::attach]. Can be passed to [LsmCgroup::detach].
+    LsmCgroupLinkId,
+    FdLink,
+    FdLinkId,
+    LsmCgroup,
+);
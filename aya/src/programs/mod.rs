@@ -39,6 +39,7 @@
 // modules we don't export
 mod info;
 mod probe;
+mod test_run;
 mod utils;
 
 // modules we explicitly export so their pub items (Links etc) get exported too
@@ -52,23 +53,30 @@ pub mod extension;
 pub mod fentry;
 pub mod fexit;
 pub mod flow_dissector;
+pub mod fmod_ret;
 pub mod iter;
 pub mod kprobe;
 pub mod links;
 pub mod lirc_mode2;
 pub mod lsm;
+pub mod lsm_cgroup;
+pub mod netfilter;
 pub mod perf_attach;
 pub mod perf_event;
 pub mod raw_trace_point;
 pub mod sk_lookup;
 pub mod sk_msg;
+pub mod sk_reuseport;
 pub mod sk_skb;
 pub mod sock_ops;
 pub mod socket_filter;
+pub mod struct_ops;
+pub mod syscall;
 pub mod tc;
 pub mod tp_btf;
 pub mod trace_point;
 pub mod uprobe;
+pub mod usdt;
 pub mod xdp;
 
 use std::{
@@ -90,6 +98,8 @@ use info::impl_info;
 pub use info::{ProgramInfo, ProgramType, loaded_programs};
 use libc::ENOSPC;
 use tc::SchedClassifierLink;
+use test_run::test_run;
+pub use test_run::{TestRunError, TestRunFlags, TestRunOptions, TestRunResult};
 use thiserror::Error;
 
 // re-export the main items needed to load and attach
@@ -104,23 +114,30 @@ pub use crate::programs::{
     fentry::FEntry,
     fexit::FExit,
     flow_dissector::FlowDissector,
+    fmod_ret::FModRet,
     iter::Iter,
-    kprobe::{KProbe, KProbeError},
-    links::{CgroupAttachMode, Link, LinkOrder},
+    kprobe::{KProbe, KProbeError, KProbeMultiTarget},
+    links::{CgroupAttachMode, Link, LinkOrder, LinkType},
     lirc_mode2::LircMode2,
     lsm::Lsm,
+    lsm_cgroup::LsmCgroup,
+    netfilter::{Netfilter, NetfilterFlags, NetfilterHook, NetfilterProtocolFamily},
     perf_event::{PerfEvent, PerfEventScope, PerfTypeId, SamplePolicy},
     probe::ProbeKind,
-    raw_trace_point::RawTracePoint,
+    raw_trace_point::{RawTracePoint, RawTracePointWritable},
     sk_lookup::SkLookup,
     sk_msg::SkMsg,
+    sk_reuseport::{SkReuseport, SkReuseportError, SkReuseportKind},
     sk_skb::{SkSkb, SkSkbKind},
     sock_ops::SockOps,
     socket_filter::{SocketFilter, SocketFilterError},
-    tc::{SchedClassifier, TcAttachType, TcError},
+    struct_ops::StructOps,
+    syscall::Syscall,
+    tc::{NetkitAttachType, SchedClassifier, TcAttachType, TcError},
     tp_btf::BtfTracePoint,
     trace_point::{TracePoint, TracePointError},
-    uprobe::{UProbe, UProbeError},
+    uprobe::{UProbe, UProbeError, UProbeMultiLocations},
+    usdt::{Usdt, UsdtError},
     xdp::{Xdp, XdpError, XdpFlags},
 };
 use crate::{
@@ -193,6 +210,10 @@ pub enum ProgramError {
     #[error(transparent)]
     UProbeError(#[from] UProbeError),
 
+    /// An error occurred while working with a [`Usdt`].
+    #[error(transparent)]
+    UsdtError(#[from] UsdtError),
+
     /// An error occurred while working with a [`TracePoint`].
     #[error(transparent)]
     TracePointError(#[from] TracePointError),
@@ -201,6 +222,10 @@ pub enum ProgramError {
     #[error(transparent)]
     SocketFilterError(#[from] SocketFilterError),
 
+    /// An error occurred while working with a [`SkReuseport`] program.
+    #[error(transparent)]
+    SkReuseportError(#[from] SkReuseportError),
+
     /// An error occurred while working with an [`Xdp`] program.
     #[error(transparent)]
     XdpError(#[from] XdpError),
@@ -213,10 +238,22 @@ pub enum ProgramError {
     #[error(transparent)]
     ExtensionError(#[from] ExtensionError),
 
+    /// An error occurred while test running a program.
+    #[error(transparent)]
+    TestRunError(#[from] TestRunError),
+
     /// An error occurred while working with BTF.
     #[error(transparent)]
     Btf(#[from] BtfError),
 
+    /// The split BTF containing the attach target isn't the BTF of a loaded
+    /// kernel module.
+    #[error("the BTF containing `{name}` isn't the BTF of a loaded kernel module")]
+    ModuleBtfNotFound {
+        /// attach target name
+        name: String,
+    },
+
     /// The program is not attached.
     #[error("the program name `{name}` is invalid")]
     InvalidName {
@@ -306,26 +343,42 @@ pub enum Program {
     PerfEvent(PerfEvent),
     /// A [`RawTracePoint`] program
     RawTracePoint(RawTracePoint),
+    /// A [`RawTracePointWritable`] program
+    RawTracePointWritable(RawTracePointWritable),
     /// A [`Lsm`] program
     Lsm(Lsm),
+    /// A [`LsmCgroup`] program
+    LsmCgroup(LsmCgroup),
     /// A [`BtfTracePoint`] program
     BtfTracePoint(BtfTracePoint),
     /// A [`FEntry`] program
     FEntry(FEntry),
     /// A [`FExit`] program
     FExit(FExit),
+    /// A [`FModRet`] program
+    FModRet(FModRet),
     /// A [`FlowDissector`] program
     FlowDissector(FlowDissector),
     /// A [`Extension`] program
     Extension(Extension),
     /// A [`SkLookup`] program
     SkLookup(SkLookup),
+    /// A [`SkReuseport`] program
+    SkReuseport(SkReuseport),
+    /// A [`Netfilter`] program
+    Netfilter(Netfilter),
+    /// A [`Syscall`] program
+    Syscall(Syscall),
     /// A [`CgroupSock`] program
     CgroupSock(CgroupSock),
     /// A [`CgroupDevice`] program
     CgroupDevice(CgroupDevice),
     /// An [`Iter`] program
     Iter(Iter),
+    /// A [`StructOps`] program
+    StructOps(StructOps),
+    /// A [`Usdt`] program
+    Usdt(Usdt),
 }
 
 impl Program {
@@ -347,16 +400,24 @@ impl Program {
             Self::LircMode2(_) => LircMode2::PROGRAM_TYPE,
             Self::PerfEvent(_) => PerfEvent::PROGRAM_TYPE,
             Self::RawTracePoint(_) => RawTracePoint::PROGRAM_TYPE,
+            Self::RawTracePointWritable(_) => RawTracePointWritable::PROGRAM_TYPE,
             Self::Lsm(_) => Lsm::PROGRAM_TYPE,
+            Self::LsmCgroup(_) => LsmCgroup::PROGRAM_TYPE,
             Self::BtfTracePoint(_) => BtfTracePoint::PROGRAM_TYPE,
             Self::FEntry(_) => FEntry::PROGRAM_TYPE,
             Self::FExit(_) => FExit::PROGRAM_TYPE,
+            Self::FModRet(_) => FModRet::PROGRAM_TYPE,
             Self::Extension(_) => Extension::PROGRAM_TYPE,
             Self::CgroupSockAddr(_) => CgroupSockAddr::PROGRAM_TYPE,
             Self::SkLookup(_) => SkLookup::PROGRAM_TYPE,
+            Self::SkReuseport(_) => SkReuseport::PROGRAM_TYPE,
+            Self::Netfilter(_) => Netfilter::PROGRAM_TYPE,
+            Self::Syscall(_) => Syscall::PROGRAM_TYPE,
             Self::CgroupSock(_) => CgroupSock::PROGRAM_TYPE,
             Self::CgroupDevice(_) => CgroupDevice::PROGRAM_TYPE,
             Self::Iter(_) => Iter::PROGRAM_TYPE,
+            Self::StructOps(_) => StructOps::PROGRAM_TYPE,
+            Self::Usdt(_) => Usdt::PROGRAM_TYPE,
             Self::FlowDissector(_) => FlowDissector::PROGRAM_TYPE,
         }
     }
@@ -379,17 +440,25 @@ impl Program {
             Self::LircMode2(p) => p.pin(path),
             Self::PerfEvent(p) => p.pin(path),
             Self::RawTracePoint(p) => p.pin(path),
+            Self::RawTracePointWritable(p) => p.pin(path),
             Self::Lsm(p) => p.pin(path),
+            Self::LsmCgroup(p) => p.pin(path),
             Self::BtfTracePoint(p) => p.pin(path),
             Self::FEntry(p) => p.pin(path),
             Self::FExit(p) => p.pin(path),
+            Self::FModRet(p) => p.pin(path),
             Self::FlowDissector(p) => p.pin(path),
             Self::Extension(p) => p.pin(path),
             Self::CgroupSockAddr(p) => p.pin(path),
             Self::SkLookup(p) => p.pin(path),
+            Self::SkReuseport(p) => p.pin(path),
+            Self::Netfilter(p) => p.pin(path),
+            Self::Syscall(p) => p.pin(path),
             Self::CgroupSock(p) => p.pin(path),
             Self::CgroupDevice(p) => p.pin(path),
             Self::Iter(p) => p.pin(path),
+            Self::StructOps(p) => p.pin(path),
+            Self::Usdt(p) => p.pin(path),
         }
     }
 
@@ -411,17 +480,25 @@ impl Program {
             Self::LircMode2(mut p) => p.unload(),
             Self::PerfEvent(mut p) => p.unload(),
             Self::RawTracePoint(mut p) => p.unload(),
+            Self::RawTracePointWritable(mut p) => p.unload(),
             Self::Lsm(mut p) => p.unload(),
+            Self::LsmCgroup(mut p) => p.unload(),
             Self::BtfTracePoint(mut p) => p.unload(),
             Self::FEntry(mut p) => p.unload(),
             Self::FExit(mut p) => p.unload(),
+            Self::FModRet(mut p) => p.unload(),
             Self::FlowDissector(mut p) => p.unload(),
             Self::Extension(mut p) => p.unload(),
             Self::CgroupSockAddr(mut p) => p.unload(),
             Self::SkLookup(mut p) => p.unload(),
+            Self::SkReuseport(mut p) => p.unload(),
+            Self::Netfilter(mut p) => p.unload(),
+            Self::Syscall(mut p) => p.unload(),
             Self::CgroupSock(mut p) => p.unload(),
             Self::CgroupDevice(mut p) => p.unload(),
             Self::Iter(mut p) => p.unload(),
+            Self::StructOps(mut p) => p.unload(),
+            Self::Usdt(mut p) => p.unload(),
         }
     }
 
@@ -445,20 +522,77 @@ impl Program {
             Self::LircMode2(p) => p.fd(),
             Self::PerfEvent(p) => p.fd(),
             Self::RawTracePoint(p) => p.fd(),
+            Self::RawTracePointWritable(p) => p.fd(),
             Self::Lsm(p) => p.fd(),
+            Self::LsmCgroup(p) => p.fd(),
             Self::BtfTracePoint(p) => p.fd(),
             Self::FEntry(p) => p.fd(),
             Self::FExit(p) => p.fd(),
+            Self::FModRet(p) => p.fd(),
             Self::FlowDissector(p) => p.fd(),
             Self::Extension(p) => p.fd(),
             Self::CgroupSockAddr(p) => p.fd(),
             Self::SkLookup(p) => p.fd(),
+            Self::SkReuseport(p) => p.fd(),
+            Self::Netfilter(p) => p.fd(),
+            Self::Syscall(p) => p.fd(),
             Self::CgroupSock(p) => p.fd(),
             Self::CgroupDevice(p) => p.fd(),
             Self::Iter(p) => p.fd(),
+            Self::StructOps(p) => p.fd(),
+            Self::Usdt(p) => p.fd(),
         }
     }
 
+    /// Sets the module BTF fds referenced by the kfunc calls of the program.
+    pub(crate) fn set_fd_array(&mut self, fd_array: Vec<Arc<crate::MockableFd>>) {
+        match self {
+            Self::KProbe(p) => p.data.fd_array = fd_array,
+            Self::UProbe(p) => p.data.fd_array = fd_array,
+            Self::TracePoint(p) => p.data.fd_array = fd_array,
+            Self::SocketFilter(p) => p.data.fd_array = fd_array,
+            Self::Xdp(p) => p.data.fd_array = fd_array,
+            Self::SkMsg(p) => p.data.fd_array = fd_array,
+            Self::SkSkb(p) => p.data.fd_array = fd_array,
+            Self::SockOps(p) => p.data.fd_array = fd_array,
+            Self::SchedClassifier(p) => p.data.fd_array = fd_array,
+            Self::CgroupSkb(p) => p.data.fd_array = fd_array,
+            Self::CgroupSysctl(p) => p.data.fd_array = fd_array,
+            Self::CgroupSockopt(p) => p.data.fd_array = fd_array,
+            Self::LircMode2(p) => p.data.fd_array = fd_array,
+            Self::PerfEvent(p) => p.data.fd_array = fd_array,
+            Self::RawTracePoint(p) => p.data.fd_array = fd_array,
+            Self::RawTracePointWritable(p) => p.data.fd_array = fd_array,
+            Self::Lsm(p) => p.data.fd_array = fd_array,
+            Self::LsmCgroup(p) => p.data.fd_array = fd_array,
+            Self::BtfTracePoint(p) => p.data.fd_array = fd_array,
+            Self::FEntry(p) => p.data.fd_array = fd_array,
+            Self::FExit(p) => p.data.fd_array = fd_array,
+            Self::FModRet(p) => p.data.fd_array = fd_array,
+            Self::FlowDissector(p) => p.data.fd_array = fd_array,
+            Self::Extension(p) => p.data.fd_array = fd_array,
+            Self::CgroupSockAddr(p) => p.data.fd_array = fd_array,
+            Self::SkLookup(p) => p.data.fd_array = fd_array,
+            Self::SkReuseport(p) => p.data.fd_array = fd_array,
+            Self::Netfilter(p) => p.data.fd_array = fd_array,
+            Self::Syscall(p) => p.data.fd_array = fd_array,
+            Self::CgroupSock(p) => p.data.fd_array = fd_array,
+            Self::CgroupDevice(p) => p.data.fd_array = fd_array,
+            Self::Iter(p) => p.data.fd_array = fd_array,
+            Self::StructOps(p) => p.data.fd_array = fd_array,
+            Self::Usdt(p) => p.data.fd_array = fd_array,
+        }
+    }
+
+    /// Runs the program against synthetic input, without attaching it.
+    ///
+    /// Returns an error if the kernel doesn't support running programs of
+    /// this type. See [`TestRunOptions`] for the input and [`TestRunResult`]
+    /// for the output of the run.
+    pub fn test_run(&self, opts: TestRunOptions<'_>) -> Result<TestRunResult, ProgramError> {
+        test_run(self.fd()?.as_fd(), opts)
+    }
+
     /// Returns information about a loaded program with the [`ProgramInfo`] structure.
     ///
     /// This information is populated at load time by the kernel and can be used
@@ -480,17 +614,25 @@ impl Program {
             Self::LircMode2(p) => p.info(),
             Self::PerfEvent(p) => p.info(),
             Self::RawTracePoint(p) => p.info(),
+            Self::RawTracePointWritable(p) => p.info(),
             Self::Lsm(p) => p.info(),
+            Self::LsmCgroup(p) => p.info(),
             Self::BtfTracePoint(p) => p.info(),
             Self::FEntry(p) => p.info(),
             Self::FExit(p) => p.info(),
+            Self::FModRet(p) => p.info(),
             Self::FlowDissector(p) => p.info(),
             Self::Extension(p) => p.info(),
             Self::CgroupSockAddr(p) => p.info(),
             Self::SkLookup(p) => p.info(),
+            Self::SkReuseport(p) => p.info(),
+            Self::Netfilter(p) => p.info(),
+            Self::Syscall(p) => p.info(),
             Self::CgroupSock(p) => p.info(),
             Self::CgroupDevice(p) => p.info(),
             Self::Iter(p) => p.info(),
+            Self::StructOps(p) => p.info(),
+            Self::Usdt(p) => p.info(),
         }
     }
 }
@@ -506,6 +648,8 @@ pub(crate) struct ProgramData<T: Link> {
     pub(crate) attach_btf_id: Option<u32>,
     pub(crate) attach_prog_fd: Option<ProgramFd>,
     pub(crate) btf_fd: Option<Arc<crate::MockableFd>>,
+    // The module BTF fds referenced by kfunc calls, starting at index 1 of fd_array.
+    pub(crate) fd_array: Vec<Arc<crate::MockableFd>>,
     pub(crate) verifier_log_level: VerifierLogLevel,
     pub(crate) path: Option<PathBuf>,
     pub(crate) flags: u32,
@@ -528,6 +672,7 @@ impl<T: Link> ProgramData<T> {
             attach_btf_id: None,
             attach_prog_fd: None,
             btf_fd,
+            fd_array: Vec::new(),
             verifier_log_level,
             path: None,
             flags: 0,
@@ -560,6 +705,7 @@ impl<T: Link> ProgramData<T> {
             attach_btf_id,
             attach_prog_fd: None,
             btf_fd: None,
+            fd_array: Vec::new(),
             verifier_log_level,
             path: Some(path.to_path_buf()),
             flags: 0,
@@ -625,17 +771,30 @@ fn pin_program<T: Link, P: AsRef<Path>>(data: &ProgramData<T>, path: P) -> Resul
 fn load_program<T: Link>(
     prog_type: bpf_prog_type,
     data: &mut ProgramData<T>,
+) -> Result<(), ProgramError> {
+    let expected_attach_type = data.expected_attach_type.map(|t| t as u32);
+    load_program_with_attach_type(prog_type, data, expected_attach_type)
+}
+
+// Like `load_program`, but takes the raw value of `expected_attach_type`. This is needed by
+// struct_ops programs, which pass the index of the struct member they implement instead of a
+// `bpf_attach_type`.
+fn load_program_with_attach_type<T: Link>(
+    prog_type: bpf_prog_type,
+    data: &mut ProgramData<T>,
+    expected_attach_type: Option<u32>,
 ) -> Result<(), ProgramError> {
     let ProgramData {
         name,
         obj,
         fd,
         links: _,
-        expected_attach_type,
+        expected_attach_type: _,
         attach_btf_obj_fd,
         attach_btf_id,
         attach_prog_fd,
         btf_fd,
+        fd_array,
         verifier_log_level,
         path: _,
         flags,
@@ -687,11 +846,12 @@ fn load_program<T: Link>(
         insns: instructions,
         license,
         kernel_version: target_kernel_version,
-        expected_attach_type: *expected_attach_type,
+        expected_attach_type,
         prog_btf_fd: btf_fd.as_ref().map(|f| f.as_fd()),
         attach_btf_obj_fd: attach_btf_obj_fd.as_ref().map(|fd| fd.as_fd()),
         attach_btf_id: *attach_btf_id,
         attach_prog_fd: attach_prog_fd.as_ref().map(|fd| fd.as_fd()),
+        fd_array: fd_array.iter().map(|fd| fd.as_fd()).collect(),
         func_info_rec_size: *func_info_rec_size,
         func_info: func_info.clone(),
         line_info_rec_size: *line_info_rec_size,
@@ -795,18 +955,26 @@ impl_program_unload!(
     LircMode2,
     PerfEvent,
     Lsm,
+    LsmCgroup,
     RawTracePoint,
+    RawTracePointWritable,
     BtfTracePoint,
     FEntry,
     FExit,
+    FModRet,
     FlowDissector,
     Extension,
     CgroupSockAddr,
     SkLookup,
+    SkReuseport,
+    Netfilter,
+    Syscall,
     SockOps,
     CgroupSock,
     CgroupDevice,
     Iter,
+    StructOps,
+    Usdt,
 );
 
 macro_rules! impl_fd {
@@ -837,18 +1005,63 @@ impl_fd!(
     LircMode2,
     PerfEvent,
     Lsm,
+    LsmCgroup,
     RawTracePoint,
+    RawTracePointWritable,
     BtfTracePoint,
     FEntry,
     FExit,
+    FModRet,
     FlowDissector,
     Extension,
     CgroupSockAddr,
     SkLookup,
+    SkReuseport,
+    Netfilter,
+    Syscall,
     SockOps,
     CgroupSock,
     CgroupDevice,
     Iter,
+    StructOps,
+    Usdt,
+);
+
+macro_rules! impl_test_run {
+    ($($struct_name:ident),+ $(,)?) => {
+        $(
+            impl $struct_name {
+                /// Runs the program against synthetic input, without attaching it.
+                ///
+                /// See [`TestRunOptions`] for the input and [`TestRunResult`] for the
+                /// output of the run.
+                ///
+                /// # Minimum kernel version
+                ///
+                /// The minimum kernel version required to use this feature is 4.12.
+                pub fn test_run(
+                    &self,
+                    opts: TestRunOptions<'_>,
+                ) -> Result<TestRunResult, ProgramError> {
+                    test_run(self.fd()?.as_fd(), opts)
+                }
+            }
+        )+
+    }
+}
+
+impl_test_run!(
+    SocketFilter,
+    Xdp,
+    SchedClassifier,
+    CgroupSkb,
+    RawTracePoint,
+    FEntry,
+    FExit,
+    FModRet,
+    FlowDissector,
+    SkLookup,
+    Netfilter,
 );
 
 /// Trait implemented by the [`Program`] types which support the kernel's
@@ -944,18 +1157,26 @@ impl_program_pin!(
     LircMode2,
     PerfEvent,
     Lsm,
+    LsmCgroup,
     RawTracePoint,
+    RawTracePointWritable,
     BtfTracePoint,
     FEntry,
     FExit,
+    FModRet,
     FlowDissector,
     Extension,
     CgroupSockAddr,
     SkLookup,
+    SkReuseport,
+    Netfilter,
+    Syscall,
     SockOps,
     CgroupSock,
     CgroupDevice,
     Iter,
+    StructOps,
+    Usdt,
 );
 
 macro_rules! impl_from_pin {
@@ -986,13 +1207,18 @@ impl_from_pin!(
     LircMode2,
     PerfEvent,
     Lsm,
+    LsmCgroup,
     RawTracePoint,
+    RawTracePointWritable,
     BtfTracePoint,
     FEntry,
     FExit,
+    FModRet,
     FlowDissector,
     Extension,
     SkLookup,
+    Netfilter,
+    Syscall,
     SockOps,
     CgroupDevice,
     Iter,
@@ -1096,12 +1322,18 @@ impl_from_prog_info!(
     LircMode2,
     PerfEvent,
     Lsm,
+    LsmCgroup,
     RawTracePoint,
+    RawTracePointWritable,
     unsafe BtfTracePoint,
     unsafe FEntry,
     unsafe FExit,
+    unsafe FModRet,
     Extension,
     SkLookup,
+    SkReuseport kind : SkReuseportKind,
+    Netfilter,
+    Syscall,
     CgroupDevice,
     Iter,
 );
@@ -1150,17 +1382,25 @@ impl_try_from_program!(
     LircMode2,
     PerfEvent,
     Lsm,
+    LsmCgroup,
     RawTracePoint,
+    RawTracePointWritable,
     BtfTracePoint,
     FEntry,
     FExit,
+    FModRet,
     FlowDissector,
     Extension,
     CgroupSockAddr,
     SkLookup,
+    SkReuseport,
+    Netfilter,
+    Syscall,
     CgroupSock,
     CgroupDevice,
     Iter,
+    StructOps,
+    Usdt,
 );
 
 impl_info!(
@@ -1178,18 +1418,26 @@ impl_info!(
     LircMode2,
     PerfEvent,
     Lsm,
+    LsmCgroup,
     RawTracePoint,
+    RawTracePointWritable,
     BtfTracePoint,
     FEntry,
     FExit,
+    FModRet,
     FlowDissector,
     Extension,
     CgroupSockAddr,
     SkLookup,
+    SkReuseport,
+    Netfilter,
+    Syscall,
     SockOps,
     CgroupSock,
     CgroupDevice,
     Iter,
+    StructOps,
+    Usdt,
 );
 
 // TODO(https://github.com/aya-rs/aya/issues/645): this API is currently used in tests. Stabilize
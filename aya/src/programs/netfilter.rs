@@ -0,0 +1,237 @@
+//! Netfilter programs.
+use std::os::fd::AsFd as _;
+
+use aya_obj::generated::{
+    BPF_F_NETFILTER_IP_DEFRAG, NFPROTO_IPV4, NFPROTO_IPV6, bpf_attach_type::BPF_NETFILTER,
+    bpf_link_type::BPF_LINK_TYPE_NETFILTER, bpf_prog_type::BPF_PROG_TYPE_NETFILTER, nf_inet_hooks,
+};
+
+use crate::{
+    programs::{
+        FdLink, FdLinkId, LinkError, ProgramData, ProgramError, ProgramType, define_link_wrapper,
+        load_program,
+    },
+    sys::{BpfLinkCreateArgs, LinkTarget, SyscallError, bpf_link_create, bpf_link_get_info_by_fd},
+};
+
+/// The protocol family of the packets a [`Netfilter`] program is run on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NetfilterProtocolFamily {
+    /// IPv4 packets.
+    #[doc(alias = "NFPROTO_IPV4")]
+    Ipv4,
+    /// IPv6 packets.
+    #[doc(alias = "NFPROTO_IPV6")]
+    Ipv6,
+}
+
+impl From<NetfilterProtocolFamily> for u32 {
+    fn from(protocol_family: NetfilterProtocolFamily) -> Self {
+        match protocol_family {
+            NetfilterProtocolFamily::Ipv4 => NFPROTO_IPV4,
+            NetfilterProtocolFamily::Ipv6 => NFPROTO_IPV6,
+        }
+    }
+}
+
+impl TryFrom<u32> for NetfilterProtocolFamily {
+    type Error = LinkError;
+
+    fn try_from(protocol_family: u32) -> Result<Self, Self::Error> {
+        match protocol_family {
+            NFPROTO_IPV4 => Ok(Self::Ipv4),
+            NFPROTO_IPV6 => Ok(Self::Ipv6),
+            _ => Err(LinkError::InvalidLink),
+        }
+    }
+}
+
+/// The netfilter hook a [`Netfilter`] program is attached to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NetfilterHook {
+    /// Packets entering the host, before the routing decision.
+    #[doc(alias = "NF_INET_PRE_ROUTING")]
+    PreRouting,
+    /// Packets delivered to a local socket.
+    #[doc(alias = "NF_INET_LOCAL_IN")]
+    LocalIn,
+    /// Packets forwarded to another host.
+    #[doc(alias = "NF_INET_FORWARD")]
+    Forward,
+    /// Packets sent by a local socket.
+    #[doc(alias = "NF_INET_LOCAL_OUT")]
+    LocalOut,
+    /// Packets leaving the host, after the routing decision.
+    #[doc(alias = "NF_INET_POST_ROUTING")]
+    PostRouting,
+}
+
+impl From<NetfilterHook> for u32 {
+    fn from(hook: NetfilterHook) -> Self {
+        let hook = match hook {
+            NetfilterHook::PreRouting => nf_inet_hooks::NF_INET_PRE_ROUTING,
+            NetfilterHook::LocalIn => nf_inet_hooks::NF_INET_LOCAL_IN,
+            NetfilterHook::Forward => nf_inet_hooks::NF_INET_FORWARD,
+            NetfilterHook::LocalOut => nf_inet_hooks::NF_INET_LOCAL_OUT,
+            NetfilterHook::PostRouting => nf_inet_hooks::NF_INET_POST_ROUTING,
+        };
+        hook as Self
+    }
+}
+
+impl TryFrom<u32> for NetfilterHook {
+    type Error = LinkError;
+
+    fn try_from(hook: u32) -> Result<Self, Self::Error> {
+        const PRE_ROUTING: u32 = nf_inet_hooks::NF_INET_PRE_ROUTING as u32;
+        const LOCAL_IN: u32 = nf_inet_hooks::NF_INET_LOCAL_IN as u32;
+        const FORWARD: u32 = nf_inet_hooks::NF_INET_FORWARD as u32;
+        const LOCAL_OUT: u32 = nf_inet_hooks::NF_INET_LOCAL_OUT as u32;
+        const POST_ROUTING: u32 = nf_inet_hooks::NF_INET_POST_ROUTING as u32;
+
+        match hook {
+            PRE_ROUTING => Ok(Self::PreRouting),
+            LOCAL_IN => Ok(Self::LocalIn),
+            FORWARD => Ok(Self::Forward),
+            LOCAL_OUT => Ok(Self::LocalOut),
+            POST_ROUTING => Ok(Self::PostRouting),
+            _ => Err(LinkError::InvalidLink),
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags passed to [`Netfilter::attach()`].
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct NetfilterFlags: u32 {
+        /// Reassemble IP fragments before the program runs.
+        ///
+        /// Introduced in kernel v6.6.
+        const IP_DEFRAG = BPF_F_NETFILTER_IP_DEFRAG;
+    }
+}
+
+/// A program that can be attached to netfilter hooks.
+///
+/// [`Netfilter`] programs run on the packets going through the IPv4 or IPv6
+/// netfilter hook they are attached to, alongside nftables and iptables rules.
+/// Programs attached to the same hook run in the order of their priority, and
+/// return `NF_ACCEPT` to let the packet through or `NF_DROP` to drop it.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 6.4.
+///
+/// # Examples
+///
+/// ```no_run
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::programs::{Netfilter, NetfilterFlags, NetfilterHook, NetfilterProtocolFamily};
+///
+/// let program: &mut Netfilter = bpf.program_mut("filter").unwrap().try_into()?;
+/// program.load()?;
+/// program.attach(
+///     NetfilterProtocolFamily::Ipv4,
+///     NetfilterHook::LocalIn,
+///     -128,
+///     NetfilterFlags::empty(),
+/// )?;
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+#[derive(Debug)]
+#[doc(alias = "BPF_PROG_TYPE_NETFILTER")]
+pub struct Netfilter {
+    pub(crate) data: ProgramData<NetfilterLink>,
+}
+
+impl Netfilter {
+    /// The type of the program according to the kernel.
+    pub const PROGRAM_TYPE: ProgramType = ProgramType::Netfilter;
+
+    /// Loads the program inside the kernel.
+    pub fn load(&mut self) -> Result<(), ProgramError> {
+        self.data.expected_attach_type = Some(BPF_NETFILTER);
+        load_program(BPF_PROG_TYPE_NETFILTER, &mut self.data)
+    }
+
+    /// Attaches the program to the given netfilter hook.
+    ///
+    /// Programs with a lower `priority` run first. The priority must be
+    /// strictly between `NF_IP_PRI_FIRST` and `NF_IP_PRI_LAST`, and
+    /// [`NetfilterFlags::IP_DEFRAG`] requires it to be greater than
+    /// `NF_IP_PRI_CONNTRACK_DEFRAG`.
+    ///
+    /// The returned value can be used to detach, see [`Netfilter::detach`].
+    pub fn attach(
+        &mut self,
+        protocol_family: NetfilterProtocolFamily,
+        hook: NetfilterHook,
+        priority: i32,
+        flags: NetfilterFlags,
+    ) -> Result<NetfilterLinkId, ProgramError> {
+        let prog_fd = self.fd()?;
+        let prog_fd = prog_fd.as_fd();
+
+        let link_fd = bpf_link_create(
+            prog_fd,
+            LinkTarget::Netfilter,
+            BPF_NETFILTER,
+            0,
+            Some(BpfLinkCreateArgs::Netfilter {
+                pf: protocol_family.into(),
+                hooknum: hook.into(),
+                priority,
+                flags: flags.bits(),
+            }),
+        )
+        .map_err(|io_error| SyscallError {
+            call: "bpf_link_create",
+            io_error,
+        })?;
+        self.data
+            .links
+            .insert(NetfilterLink::new(FdLink::new(link_fd)))
+    }
+}
+
+/// The netfilter hook a [`NetfilterLink`] is attached to, as reported by the
+/// kernel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NetfilterLinkInfo {
+    /// The protocol family of the hook.
+    pub protocol_family: NetfilterProtocolFamily,
+    /// The hook.
+    pub hook: NetfilterHook,
+    /// The priority of the program on the hook.
+    pub priority: i32,
+    /// The flags the link was created with.
+    pub flags: NetfilterFlags,
+}
+
+impl NetfilterLink {
+    /// Returns the netfilter hook the link is attached to.
+    pub fn info(&self) -> Result<NetfilterLinkInfo, LinkError> {
+        let info = bpf_link_get_info_by_fd(self.inner().fd.as_fd())?;
+        if info.type_ != BPF_LINK_TYPE_NETFILTER as u32 {
+            return Err(LinkError::InvalidLink);
+        }
+        // SAFETY: the kernel fills the netfilter member for netfilter links.
+        let netfilter = unsafe { info.__bindgen_anon_1.netfilter };
+        Ok(NetfilterLinkInfo {
+            protocol_family: netfilter.pf.try_into()?,
+            hook: netfilter.hooknum.try_into()?,
+            priority: netfilter.priority,
+            flags: NetfilterFlags::from_bits_retain(netfilter.flags),
+        })
+    }
+}
+
+define_link_wrapper!(
+    /// The link used by [`Netfilter`] programs.
+    NetfilterLink,
+    /// The type returned by [`Netfilter::attach`]. Can be passed to [`Netfilter::detach`].
+    NetfilterLinkId,
+    FdLink,
+    FdLinkId,
+    Netfilter,
+);
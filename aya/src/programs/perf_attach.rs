@@ -14,7 +14,7 @@ use crate::{
     },
     sys::{
         BpfLinkCreateArgs, LinkTarget, PerfEventIoctlRequest, SyscallError, bpf_link_create,
-        is_bpf_cookie_supported, perf_event_ioctl,
+        perf_event_ioctl,
     },
 };
 
@@ -86,7 +86,7 @@ pub(crate) fn perf_attach(
     fd: crate::MockableFd,
     cookie: Option<u64>,
 ) -> Result<PerfLinkInner, ProgramError> {
-    if cookie.is_some() && (!is_bpf_cookie_supported() || !FEATURES.bpf_perf_link()) {
+    if cookie.is_some() && (!FEATURES.bpf_cookie() || !FEATURES.bpf_perf_link()) {
         return Err(ProgramError::AttachCookieNotSupported);
     }
     if FEATURES.bpf_perf_link() {
@@ -153,6 +153,48 @@ impl PerfEvent {
         scope: PerfEventScope,
         sample_policy: SamplePolicy,
         inherit: bool,
+    ) -> Result<PerfEventLinkId, ProgramError> {
+        self.attach_inner(perf_type, config, scope, sample_policy, inherit, None)
+    }
+
+    /// Attaches to the given perf event with a cookie.
+    ///
+    /// Same as [`PerfEvent::attach`], but the eBPF program can retrieve
+    /// `cookie` with the `bpf_get_attach_cookie()` helper.
+    ///
+    /// Returns [`ProgramError::AttachCookieNotSupported`] if the kernel doesn't
+    /// support attach cookies.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.15.
+    pub fn attach_with_cookie(
+        &mut self,
+        perf_type: PerfTypeId,
+        config: u64,
+        scope: PerfEventScope,
+        sample_policy: SamplePolicy,
+        inherit: bool,
+        cookie: u64,
+    ) -> Result<PerfEventLinkId, ProgramError> {
+        self.attach_inner(
+            perf_type,
+            config,
+            scope,
+            sample_policy,
+            inherit,
+            Some(cookie),
+        )
+    }
+
+    fn attach_inner(
+        &mut self,
+        perf_type: PerfTypeId,
+        config: u64,
+        scope: PerfEventScope,
+        sample_policy: SamplePolicy,
+        inherit: bool,
+        cookie: Option<u64>,
     ) -> Result<PerfEventLinkId, ProgramError> {
         let prog_fd = self.fd()?;
         let prog_fd = prog_fd.as_fd();
@@ -183,7 +225,7 @@ impl PerfEvent {
             io_error,
         })?;
 
-        let link = perf_attach(prog_fd, fd, None /* cookie */)?;
+        let link = perf_attach(prog_fd, fd, cookie)?;
         self.data.links.insert(PerfEventLink::new(link))
     }
 }
@@ -3,7 +3,7 @@ use std::{
     fmt::Write as _,
     fs::{self, OpenOptions},
     io::{self, Write as _},
-    os::fd::AsFd as _,
+    os::fd::{AsFd as _, BorrowedFd},
     path::{Path, PathBuf},
     process,
     sync::atomic::{AtomicUsize, Ordering},
@@ -114,21 +114,38 @@ pub(crate) fn attach<T: Link + From<PerfLinkInner>>(
     pid: Option<pid_t>,
     cookie: Option<u64>,
 ) -> Result<T::Id, ProgramError> {
-    // https://github.com/torvalds/linux/commit/e12f03d7031a977356e3d7b75a68c2185ff8d155
-    // Use debugfs to create probe
     let prog_fd = program_data.fd()?;
     let prog_fd = prog_fd.as_fd();
-    let link = if !KernelVersion::at_least(4, 17, 0) {
+    let link = create_probe_link(prog_fd, kind, fn_name, offset, pid, cookie, None)?;
+    program_data.links.insert(T::from(link))
+}
+
+/// Creates the link of a probe without storing it in the program data.
+///
+/// `ref_ctr_offset` is the offset in the uprobe target of a reference counter
+/// incremented by the kernel while the probe is attached, such as a USDT
+/// semaphore.
+pub(crate) fn create_probe_link(
+    prog_fd: BorrowedFd<'_>,
+    kind: ProbeKind,
+    fn_name: &OsStr,
+    offset: u64,
+    pid: Option<pid_t>,
+    cookie: Option<u64>,
+    ref_ctr_offset: Option<u64>,
+) -> Result<PerfLinkInner, ProgramError> {
+    // https://github.com/torvalds/linux/commit/e12f03d7031a977356e3d7b75a68c2185ff8d155
+    // Use debugfs to create probe
+    if !KernelVersion::at_least(4, 17, 0) {
         if cookie.is_some() {
             return Err(ProgramError::AttachCookieNotSupported);
         }
         let (fd, event_alias) = create_as_trace_point(kind, fn_name, offset, pid)?;
         perf_attach_debugfs(prog_fd, fd, ProbeEvent { kind, event_alias })
     } else {
-        let fd = create_as_probe(kind, fn_name, offset, pid)?;
+        let fd = create_as_probe(kind, fn_name, offset, pid, ref_ctr_offset)?;
         perf_attach(prog_fd, fd, cookie)
-    }?;
-    program_data.links.insert(T::from(link))
+    }
 }
 
 pub(crate) fn detach_debug_fs(event: ProbeEvent) -> Result<(), ProgramError> {
@@ -154,6 +171,7 @@ fn create_as_probe(
     fn_name: &OsStr,
     offset: u64,
     pid: Option<pid_t>,
+    ref_ctr_offset: Option<u64>,
 ) -> Result<crate::MockableFd, ProgramError> {
     use ProbeKind::*;
 
@@ -176,7 +194,7 @@ fn create_as_probe(
         _ => None,
     };
 
-    perf_event_open_probe(perf_ty, ret_bit, fn_name, offset, pid)
+    perf_event_open_probe(perf_ty, ret_bit, fn_name, offset, pid, ref_ctr_offset)
         .map_err(|io_error| SyscallError {
             call: "perf_event_open",
             io_error,
@@ -1,7 +1,9 @@
 //! Raw tracepoints.
 use std::ffi::CString;
 
-use aya_obj::generated::bpf_prog_type::BPF_PROG_TYPE_RAW_TRACEPOINT;
+use aya_obj::generated::bpf_prog_type::{
+    BPF_PROG_TYPE_RAW_TRACEPOINT, BPF_PROG_TYPE_RAW_TRACEPOINT_WRITABLE,
+};
 
 use crate::programs::{
     FdLink, FdLinkId, ProgramData, ProgramError, ProgramType, define_link_wrapper, load_program,
@@ -65,3 +67,64 @@ define_link_wrapper!(
     FdLinkId,
     RawTracePoint,
 );
+
+/// A raw tracepoint program that can write to the buffer of the tracepoint.
+///
+/// Some tracepoints, such as the `nbd` ones, expose a writable buffer as their
+/// first argument. [`RawTracePointWritable`] programs can modify it, for
+/// instance to inject errors or rewrite requests.
+///
+/// The verifier records the largest offset of the buffer accessed by the
+/// program. When attaching, the kernel compares it against the writable size
+/// of the tracepoint and rejects the program with `EINVAL` if it is larger, or
+/// if the tracepoint isn't writable at all.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.2.
+///
+/// # Examples
+///
+/// ```no_run
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::programs::RawTracePointWritable;
+///
+/// let program: &mut RawTracePointWritable =
+///     bpf.program_mut("nbd_send_request").unwrap().try_into()?;
+/// program.load()?;
+/// program.attach("nbd_send_request")?;
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+#[derive(Debug)]
+#[doc(alias = "BPF_PROG_TYPE_RAW_TRACEPOINT_WRITABLE")]
+pub struct RawTracePointWritable {
+    pub(crate) data: ProgramData<RawTracePointWritableLink>,
+}
+
+impl RawTracePointWritable {
+    /// The type of the program according to the kernel.
+    pub const PROGRAM_TYPE: ProgramType = ProgramType::RawTracePointWritable;
+
+    /// Loads the program inside the kernel.
+    pub fn load(&mut self) -> Result<(), ProgramError> {
+        load_program(BPF_PROG_TYPE_RAW_TRACEPOINT_WRITABLE, &mut self.data)
+    }
+
+    /// Attaches the program to the given writable tracepoint.
+    ///
+    /// The returned value can be used to detach, see [RawTracePointWritable::detach].
+    pub fn attach(&mut self, tp_name: &str) -> Result<RawTracePointWritableLinkId, ProgramError> {
+        let tp_name_c = CString::new(tp_name).unwrap();
+        attach_raw_tracepoint(&mut self.data, Some(&tp_name_c))
+    }
+}
+
+define_link_wrapper!(
+    /// The link used by [RawTracePointWritable] programs.
+    RawTracePointWritableLink,
+    /// The type returned by [RawTracePointWritable::attach]. Can be passed to [RawTracePointWritable::detach].
+    RawTracePointWritableLinkId,
+    FdLink,
+    FdLinkId,
+    RawTracePointWritable,
+);
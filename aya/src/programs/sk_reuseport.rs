@@ -0,0 +1,205 @@
+//! Programs selecting a socket in a `SO_REUSEPORT` group.
+use std::{
+    io, mem,
+    os::fd::{AsFd, AsRawFd as _, RawFd},
+    path::Path,
+};
+
+use aya_obj::generated::{
+    SO_ATTACH_REUSEPORT_EBPF, SO_DETACH_REUSEPORT_BPF,
+    bpf_attach_type::{BPF_SK_REUSEPORT_SELECT, BPF_SK_REUSEPORT_SELECT_OR_MIGRATE},
+    bpf_prog_type::BPF_PROG_TYPE_SK_REUSEPORT,
+};
+use libc::{SOL_SOCKET, setsockopt};
+use thiserror::Error;
+
+use crate::{
+    VerifierLogLevel,
+    programs::{Link, ProgramData, ProgramError, ProgramType, id_as_key, load_program},
+};
+
+/// The type returned when attaching a [`SkReuseport`] fails.
+#[derive(Debug, Error)]
+pub enum SkReuseportError {
+    /// Setting the `SO_ATTACH_REUSEPORT_EBPF` socket option failed.
+    #[error("setsockopt SO_ATTACH_REUSEPORT_EBPF failed")]
+    SoAttachReuseportEbpfError {
+        /// original [`io::Error`]
+        #[source]
+        io_error: io::Error,
+    },
+}
+
+/// The kind of [`SkReuseport`] program.
+#[derive(Copy, Clone, Debug)]
+pub enum SkReuseportKind {
+    /// Selects the socket handling a new connection or packet.
+    Select,
+    /// Selects the socket handling a new connection or packet, or the socket
+    /// a request is migrated to when its listener is closed.
+    ///
+    /// Migration must be enabled with the `net.ipv4.tcp_migrate_req` sysctl.
+    SelectOrMigrate,
+}
+
+/// A program used to select the socket handling incoming connections or
+/// packets in a `SO_REUSEPORT` group.
+///
+/// [`SkReuseport`] programs are attached to a socket and run for the whole
+/// group of sockets bound to the same address with `SO_REUSEPORT`. They select
+/// the socket from a [`ReusePortSockArray`] with the `bpf_sk_select_reuseport`
+/// helper, falling back to the kernel's hash-based selection when they don't.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.19, and 5.14
+/// for [`SkReuseportKind::SelectOrMigrate`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[derive(Debug, thiserror::Error)]
+/// # enum Error {
+/// #     #[error(transparent)]
+/// #     IO(#[from] std::io::Error),
+/// #     #[error(transparent)]
+/// #     Map(#[from] aya::maps::MapError),
+/// #     #[error(transparent)]
+/// #     Program(#[from] aya::programs::ProgramError),
+/// #     #[error(transparent)]
+/// #     Ebpf(#[from] aya::EbpfError)
+/// # }
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// # fn reuseport_listener() -> std::io::Result<std::net::TcpListener> { unimplemented!() }
+/// use aya::programs::SkReuseport;
+///
+/// let listener = reuseport_listener()?;
+/// let prog: &mut SkReuseport = bpf.program_mut("select_worker").unwrap().try_into()?;
+/// prog.load()?;
+/// prog.attach(&listener)?;
+/// # Ok::<(), Error>(())
+/// ```
+///
+/// [`ReusePortSockArray`]: crate::maps::ReusePortSockArray
+#[derive(Debug)]
+#[doc(alias = "BPF_PROG_TYPE_SK_REUSEPORT")]
+pub struct SkReuseport {
+    pub(crate) data: ProgramData<SkReuseportLink>,
+    pub(crate) kind: SkReuseportKind,
+}
+
+impl SkReuseport {
+    /// The type of the program according to the kernel.
+    pub const PROGRAM_TYPE: ProgramType = ProgramType::SkReuseport;
+
+    /// Loads the program inside the kernel.
+    pub fn load(&mut self) -> Result<(), ProgramError> {
+        self.data.expected_attach_type = Some(match self.kind {
+            SkReuseportKind::Select => BPF_SK_REUSEPORT_SELECT,
+            SkReuseportKind::SelectOrMigrate => BPF_SK_REUSEPORT_SELECT_OR_MIGRATE,
+        });
+        load_program(BPF_PROG_TYPE_SK_REUSEPORT, &mut self.data)
+    }
+
+    /// Returns the kind of the program.
+    pub fn kind(&self) -> SkReuseportKind {
+        self.kind
+    }
+
+    /// Attaches the program to the `SO_REUSEPORT` group of the given socket.
+    ///
+    /// The socket must have `SO_REUSEPORT` set. The program replaces any
+    /// program previously attached to the group.
+    ///
+    /// The returned value can be used to detach from the group, see [SkReuseport::detach].
+    pub fn attach<T: AsFd>(&mut self, socket: T) -> Result<SkReuseportLinkId, ProgramError> {
+        let prog_fd = self.fd()?;
+        let prog_fd = prog_fd.as_fd();
+        let prog_fd = prog_fd.as_raw_fd();
+        let socket = socket.as_fd();
+        let socket = socket.as_raw_fd();
+
+        let ret = unsafe {
+            setsockopt(
+                socket,
+                SOL_SOCKET,
+                SO_ATTACH_REUSEPORT_EBPF as i32,
+                &prog_fd as *const _ as *const _,
+                mem::size_of::<RawFd>() as u32,
+            )
+        };
+        if ret < 0 {
+            return Err(SkReuseportError::SoAttachReuseportEbpfError {
+                io_error: io::Error::last_os_error(),
+            }
+            .into());
+        }
+
+        self.data.links.insert(SkReuseportLink { socket, prog_fd })
+    }
+
+    /// Detaches the program.
+    ///
+    /// See [`Self::attach`].
+    pub fn detach(&mut self, link_id: SkReuseportLinkId) -> Result<(), ProgramError> {
+        self.data.links.remove(link_id)
+    }
+
+    /// Takes ownership of the link referenced by the provided `link_id`.
+    ///
+    /// The caller takes the responsibility of managing the lifetime of the link. When the returned
+    /// [`SkReuseportLink`] is dropped, the link is detached.
+    pub fn take_link(
+        &mut self,
+        link_id: SkReuseportLinkId,
+    ) -> Result<SkReuseportLink, ProgramError> {
+        self.data.links.forget(link_id)
+    }
+
+    /// Creates a program from a pinned entry on a bpffs.
+    ///
+    /// Existing links will not be populated. To work with existing links you should use [`crate::programs::links::PinnedLink`].
+    ///
+    /// On drop, any managed links are detached and the program is unloaded. This will not result in
+    /// the program being unloaded from the kernel if it is still pinned.
+    pub fn from_pin<P: AsRef<Path>>(path: P, kind: SkReuseportKind) -> Result<Self, ProgramError> {
+        let data = ProgramData::from_pinned_path(path, VerifierLogLevel::default())?;
+        Ok(Self { data, kind })
+    }
+}
+
+/// The type returned by [SkReuseport::attach]. Can be passed to [SkReuseport::detach].
+#[derive(Debug, Hash, Eq, PartialEq)]
+pub struct SkReuseportLinkId(RawFd, RawFd);
+
+/// A SkReuseport Link
+#[derive(Debug)]
+pub struct SkReuseportLink {
+    socket: RawFd,
+    prog_fd: RawFd,
+}
+
+impl Link for SkReuseportLink {
+    type Id = SkReuseportLinkId;
+
+    fn id(&self) -> Self::Id {
+        SkReuseportLinkId(self.socket, self.prog_fd)
+    }
+
+    fn detach(self) -> Result<(), ProgramError> {
+        // Detaching requires kernel 5.3; on older kernels the program stays
+        // attached until the last socket of the group is closed.
+        unsafe {
+            setsockopt(
+                self.socket,
+                SOL_SOCKET,
+                SO_DETACH_REUSEPORT_BPF as i32,
+                &self.prog_fd as *const _ as *const _,
+                mem::size_of::<RawFd>() as u32,
+            );
+        }
+        Ok(())
+    }
+}
+
+id_as_key!(SkReuseportLink, SkReuseportLinkId);
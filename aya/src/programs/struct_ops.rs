@@ -0,0 +1,66 @@
+//! Struct ops programs.
+
+use aya_obj::generated::bpf_prog_type::BPF_PROG_TYPE_STRUCT_OPS;
+
+use crate::programs::{
+    FdLink, ProgramData, ProgramError, ProgramType, load_program_with_attach_type,
+};
+
+/// A program implementing a callback of a kernel operations structure.
+///
+/// `struct_ops` programs are referenced by a struct_ops map declared in the
+/// `.struct_ops` or `.struct_ops.link` section of the object file. They aren't
+/// attached individually: once all the programs referenced by the map are
+/// loaded, the map is registered with the kernel using
+/// [`StructOpsMap::register`](crate::maps::StructOpsMap::register).
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.6.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[derive(thiserror::Error, Debug)]
+/// # enum Error {
+/// #     #[error(transparent)]
+/// #     Map(#[from] aya::maps::MapError),
+/// #     #[error(transparent)]
+/// #     Program(#[from] aya::programs::ProgramError),
+/// #     #[error(transparent)]
+/// #     Ebpf(#[from] aya::EbpfError),
+/// # }
+/// use aya::{Ebpf, maps::StructOpsMap, programs::StructOps};
+///
+/// let mut bpf = Ebpf::load_file("ebpf_programs.o")?;
+/// for name in ["aya_ssthresh", "aya_cong_avoid", "aya_undo_cwnd"] {
+///     let program: &mut StructOps = bpf.program_mut(name).unwrap().try_into()?;
+///     program.load()?;
+/// }
+/// let mut ops: StructOpsMap<_> = bpf.take_map("aya_cc").unwrap().try_into()?;
+/// let link = ops.register(
+///     bpf.programs()
+///         .filter_map(|(name, program)| Some((name, program.fd().ok()?))),
+/// )?;
+/// // the struct_ops is unregistered when link is dropped
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug)]
+#[doc(alias = "BPF_PROG_TYPE_STRUCT_OPS")]
+pub struct StructOps {
+    pub(crate) data: ProgramData<FdLink>,
+    pub(crate) member_index: Option<u32>,
+}
+
+impl StructOps {
+    /// The type of the program according to the kernel.
+    pub const PROGRAM_TYPE: ProgramType = ProgramType::StructOps;
+
+    /// Loads the program inside the kernel.
+    ///
+    /// The kernel struct and the member implemented by the program are
+    /// determined from the struct_ops map referencing the program.
+    pub fn load(&mut self) -> Result<(), ProgramError> {
+        load_program_with_attach_type(BPF_PROG_TYPE_STRUCT_OPS, &mut self.data, self.member_index)
+    }
+}
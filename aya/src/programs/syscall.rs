@@ -0,0 +1,81 @@
+//! Syscall programs.
+
+use std::os::fd::AsFd as _;
+
+use aya_obj::generated::{BPF_F_SLEEPABLE, bpf_prog_type::BPF_PROG_TYPE_SYSCALL};
+
+use crate::{
+    Pod,
+    programs::{FdLink, ProgramData, ProgramError, ProgramType, load_program},
+    sys::{SyscallError, bpf_prog_test_run_syscall},
+};
+
+/// A program that can call the `bpf()` syscall from the kernel.
+///
+/// [`Syscall`] programs aren't attached to any hook: they are run on demand
+/// with [`Syscall::run`], against a context struct provided by the caller. They
+/// are always sleepable, and can use helpers such as `bpf_sys_bpf` to create
+/// maps, update them in batches or load other programs.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.14.
+///
+/// # Examples
+///
+/// ```no_run
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::programs::Syscall;
+///
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Args {
+///     value: u64,
+/// }
+///
+/// unsafe impl aya::Pod for Args {}
+///
+/// let program: &mut Syscall = bpf.program_mut("syscall").unwrap().try_into()?;
+/// program.load()?;
+///
+/// let mut args = Args { value: 42 };
+/// let ret = program.run(&mut args)?;
+/// println!("returned {ret}, value is now {}", args.value);
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+#[derive(Debug)]
+#[doc(alias = "BPF_PROG_TYPE_SYSCALL")]
+pub struct Syscall {
+    pub(crate) data: ProgramData<FdLink>,
+}
+
+impl Syscall {
+    /// The type of the program according to the kernel.
+    pub const PROGRAM_TYPE: ProgramType = ProgramType::Syscall;
+
+    /// Loads the program inside the kernel.
+    ///
+    /// The program is always loaded as sleepable.
+    pub fn load(&mut self) -> Result<(), ProgramError> {
+        self.data.flags |= BPF_F_SLEEPABLE;
+        load_program(BPF_PROG_TYPE_SYSCALL, &mut self.data)
+    }
+
+    /// Runs the program with `ctx` as its context.
+    ///
+    /// The program is run once, through `BPF_PROG_TEST_RUN`. Changes made by
+    /// the program to its context are written back to `ctx`.
+    ///
+    /// Returns the value returned by the program.
+    pub fn run<C: Pod>(&self, ctx: &mut C) -> Result<u32, ProgramError> {
+        let prog_fd = self.fd()?;
+        let prog_fd = prog_fd.as_fd();
+        bpf_prog_test_run_syscall(prog_fd, ctx).map_err(|io_error| {
+            SyscallError {
+                call: "bpf_prog_test_run",
+                io_error,
+            }
+            .into()
+        })
+    }
+}
@@ -8,7 +8,7 @@ use std::{
 
 use aya_obj::generated::{
     TC_H_CLSACT, TC_H_MIN_EGRESS, TC_H_MIN_INGRESS,
-    bpf_attach_type::{self, BPF_TCX_EGRESS, BPF_TCX_INGRESS},
+    bpf_attach_type::{self, BPF_NETKIT_PEER, BPF_NETKIT_PRIMARY, BPF_TCX_EGRESS, BPF_TCX_INGRESS},
     bpf_link_type,
     bpf_prog_type::BPF_PROG_TYPE_SCHED_CLS,
 };
@@ -41,6 +41,29 @@ pub enum TcAttachType {
     Custom(u32),
 }
 
+/// Netkit attach type.
+///
+/// Netkit devices come in pairs: a primary device, which stays in the host
+/// network namespace, and a peer device, which is usually moved to the
+/// network namespace of a container. Programs for both sides are attached
+/// through the primary device.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum NetkitAttachType {
+    /// Attach to the primary device, to run on the packets it transmits.
+    Primary,
+    /// Attach to the peer device, to run on the packets it transmits.
+    Peer,
+}
+
+impl From<NetkitAttachType> for bpf_attach_type {
+    fn from(attach_type: NetkitAttachType) -> Self {
+        match attach_type {
+            NetkitAttachType::Primary => BPF_NETKIT_PRIMARY,
+            NetkitAttachType::Peer => BPF_NETKIT_PEER,
+        }
+    }
+}
+
 /// A network traffic control classifier.
 ///
 /// [`SchedClassifier`] programs can be used to inspect, filter or redirect
@@ -221,6 +244,59 @@ impl SchedClassifier {
         self.do_attach(if_index, attach_type, options, true)
     }
 
+    /// Attaches the program to the netkit device `interface`, which must be
+    /// the primary device of a netkit pair.
+    ///
+    /// Netkit devices support the kernel's multi-prog API, so `order` defines
+    /// where the program is run relative to the programs already attached.
+    ///
+    /// The returned value can be used to detach, see [SchedClassifier::detach].
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 6.7.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let mut bpf = aya::Ebpf::load(&[])?;
+    /// use aya::programs::{LinkOrder, SchedClassifier, tc::NetkitAttachType};
+    ///
+    /// let prog: &mut SchedClassifier = bpf.program_mut("redirect_peer").unwrap().try_into()?;
+    /// prog.load()?;
+    /// prog.attach_netkit("nk0", NetkitAttachType::Peer, LinkOrder::default())?;
+    ///
+    /// # Ok::<(), aya::EbpfError>(())
+    /// ```
+    pub fn attach_netkit(
+        &mut self,
+        interface: &str,
+        attach_type: NetkitAttachType,
+        order: LinkOrder,
+    ) -> Result<SchedClassifierLinkId, ProgramError> {
+        let if_index = ifindex_from_ifname(interface).map_err(TcError::IoError)?;
+        let prog_fd = self.fd()?;
+        let prog_fd = prog_fd.as_fd();
+
+        let link_fd = bpf_link_create(
+            prog_fd,
+            LinkTarget::IfIndex(if_index),
+            attach_type.into(),
+            order.flags.bits(),
+            Some(BpfLinkCreateArgs::Netkit(&order.link_ref)),
+        )
+        .map_err(|io_error| SyscallError {
+            call: "bpf_mprog_attach",
+            io_error,
+        })?;
+
+        self.data
+            .links
+            .insert(SchedClassifierLink::new(TcLinkInner::FdLink(FdLink::new(
+                link_fd,
+            ))))
+    }
+
     /// Atomically replaces the program referenced by the provided link.
     ///
     /// Ownership of the link will transfer to this program.
@@ -352,26 +428,55 @@ impl SchedClassifier {
     ) -> Result<(u64, Vec<ProgramInfo>), ProgramError> {
         let if_index = ifindex_from_ifname(interface).map_err(TcError::IoError)?;
 
-        let (revision, prog_ids) = query(
+        query_programs(
             ProgQueryTarget::IfIndex(if_index),
             attach_type.tcx_attach_type()?,
-            0,
-            &mut None,
-        )?;
-
-        let prog_infos = prog_ids
-            .into_iter()
-            .map(|prog_id| {
-                let prog_fd = bpf_prog_get_fd_by_id(prog_id)?;
-                let prog_info = ProgramInfo::new_from_fd(prog_fd.as_fd())?;
-                Ok::<ProgramInfo, ProgramError>(prog_info)
-            })
-            .collect::<Result<_, _>>()?;
-
-        Ok((revision, prog_infos))
+        )
+    }
+
+    /// Queries a given netkit device for attached programs.
+    ///
+    /// `interface` must be the primary device of a netkit pair.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use aya::programs::tc::{NetkitAttachType, SchedClassifier};
+    /// # #[derive(Debug, thiserror::Error)]
+    /// # enum Error {
+    /// #     #[error(transparent)]
+    /// #     Program(#[from] aya::programs::ProgramError),
+    /// # }
+    /// let (revision, programs) = SchedClassifier::query_netkit("nk0", NetkitAttachType::Peer)?;
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn query_netkit(
+        interface: &str,
+        attach_type: NetkitAttachType,
+    ) -> Result<(u64, Vec<ProgramInfo>), ProgramError> {
+        let if_index = ifindex_from_ifname(interface).map_err(TcError::IoError)?;
+        query_programs(ProgQueryTarget::IfIndex(if_index), attach_type.into())
     }
 }
 
+fn query_programs(
+    target: ProgQueryTarget<'_>,
+    attach_type: bpf_attach_type,
+) -> Result<(u64, Vec<ProgramInfo>), ProgramError> {
+    let (revision, prog_ids) = query(target, attach_type, 0, &mut None)?;
+
+    let prog_infos = prog_ids
+        .into_iter()
+        .map(|prog_id| {
+            let prog_fd = bpf_prog_get_fd_by_id(prog_id)?;
+            let prog_info = ProgramInfo::new_from_fd(prog_fd.as_fd())?;
+            Ok::<ProgramInfo, ProgramError>(prog_info)
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((revision, prog_infos))
+}
+
 #[derive(Debug, Hash, Eq, PartialEq)]
 pub(crate) struct NlLinkId(u32, TcAttachType, u16, u32);
 
@@ -467,7 +572,9 @@ impl TryFrom<FdLink> for SchedClassifierLink {
 
     fn try_from(fd_link: FdLink) -> Result<Self, Self::Error> {
         let info = bpf_link_get_info_by_fd(fd_link.fd.as_fd())?;
-        if info.type_ == (bpf_link_type::BPF_LINK_TYPE_TCX as u32) {
+        if info.type_ == (bpf_link_type::BPF_LINK_TYPE_TCX as u32)
+            || info.type_ == (bpf_link_type::BPF_LINK_TYPE_NETKIT as u32)
+        {
             return Ok(Self::new(TcLinkInner::FdLink(fd_link)));
         }
         Err(LinkError::InvalidLink)
@@ -0,0 +1,108 @@
+//! Running loaded programs against synthetic input.
+
+use std::{os::fd::BorrowedFd, time::Duration};
+
+use aya_obj::generated::{BPF_F_TEST_RUN_ON_CPU, BPF_F_TEST_XDP_LIVE_FRAMES};
+use libc::ENOSPC;
+use thiserror::Error;
+
+use crate::{
+    programs::ProgramError,
+    sys::{SyscallError, bpf_prog_test_run},
+};
+
+bitflags::bitflags! {
+    /// Flags passed to `test_run`.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct TestRunFlags: u32 {
+        /// Run the program on [`TestRunOptions::cpu`] instead of the current
+        /// CPU. Only supported by raw tracepoint programs.
+        const RUN_ON_CPU = BPF_F_TEST_RUN_ON_CPU;
+        /// Run an XDP program in live frame mode: the packets are processed
+        /// as if they were received on the interface of the context, so that
+        /// redirects and transmits are performed.
+        const XDP_LIVE_FRAMES = BPF_F_TEST_XDP_LIVE_FRAMES;
+    }
+}
+
+/// The input of `test_run`.
+///
+/// What the kernel does with each field depends on the program type; see the
+/// documentation of `BPF_PROG_RUN` in the kernel for details.
+#[derive(Debug, Default)]
+pub struct TestRunOptions<'a> {
+    /// The packet the program runs against.
+    pub data_in: Option<&'a [u8]>,
+    /// The buffer receiving the packet after the program ran.
+    ///
+    /// If the buffer is too small, the packet is truncated and
+    /// [`TestRunError::OutputTooSmall`] is returned with the size of the
+    /// packet.
+    pub data_out: Option<&'a mut [u8]>,
+    /// The context the program runs with, such as a `struct __sk_buff` or a
+    /// `struct xdp_md`.
+    pub ctx_in: Option<&'a [u8]>,
+    /// The buffer receiving the context after the program ran.
+    ///
+    /// If the buffer is too small, the context is truncated and
+    /// [`TestRunError::OutputTooSmall`] is returned with the size of the
+    /// context.
+    pub ctx_out: Option<&'a mut [u8]>,
+    /// The number of times the program is run. Zero runs it once.
+    pub repeat: u32,
+    /// The CPU the program runs on with [`TestRunFlags::RUN_ON_CPU`].
+    pub cpu: u32,
+    /// The number of frames processed in a batch with
+    /// [`TestRunFlags::XDP_LIVE_FRAMES`]. Zero uses the kernel default.
+    pub batch_size: u32,
+    /// The flags of the run.
+    pub flags: TestRunFlags,
+}
+
+/// The output of `test_run`.
+#[derive(Debug, Clone, Copy)]
+pub struct TestRunResult {
+    /// The value returned by the program.
+    pub return_value: u32,
+    /// The size of the packet written to [`TestRunOptions::data_out`].
+    pub data_size_out: usize,
+    /// The size of the context written to [`TestRunOptions::ctx_out`].
+    pub ctx_size_out: usize,
+    /// The average duration of a run.
+    pub duration: Duration,
+}
+
+/// The error type returned by `test_run`.
+#[derive(Debug, Error)]
+pub enum TestRunError {
+    /// An output buffer is too small.
+    ///
+    /// The program ran and its outputs were truncated to the size of the
+    /// buffers. The sizes the buffers need are the ones in `result`.
+    #[error(
+        "the output buffers are too small for {} bytes of data and {} bytes of context",
+        result.data_size_out,
+        result.ctx_size_out
+    )]
+    OutputTooSmall {
+        /// The result of the run.
+        result: TestRunResult,
+    },
+}
+
+pub(crate) fn test_run(
+    prog_fd: BorrowedFd<'_>,
+    opts: TestRunOptions<'_>,
+) -> Result<TestRunResult, ProgramError> {
+    bpf_prog_test_run(prog_fd, opts).map_err(|(io_error, result)| {
+        if io_error.raw_os_error() == Some(ENOSPC) {
+            TestRunError::OutputTooSmall { result }.into()
+        } else {
+            SyscallError {
+                call: "bpf_prog_test_run",
+                io_error,
+            }
+            .into()
+        }
+    })
+}
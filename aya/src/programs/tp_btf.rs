@@ -7,7 +7,7 @@ use aya_obj::{
 
 use crate::programs::{
     FdLink, FdLinkId, ProgramData, ProgramError, ProgramType, define_link_wrapper, load_program,
-    utils::attach_raw_tracepoint,
+    utils::{attach_raw_tracepoint, find_btf_attach_target},
 };
 
 /// Marks a function as a [BTF-enabled raw tracepoint][1] eBPF program that can be attached at
@@ -60,12 +60,14 @@ impl BtfTracePoint {
     /// # Arguments
     ///
     /// * `tracepoint` - full name of the tracepoint that we should attach to
-    /// * `btf` - btf information for the target system
+    /// * `btf` - btf information for the target system, or the BTF of
+    ///   a kernel module, see [`Btf::from_sys_fs_module`].
     pub fn load(&mut self, tracepoint: &str, btf: &Btf) -> Result<(), ProgramError> {
         self.data.expected_attach_type = Some(BPF_TRACE_RAW_TP);
         let type_name = format!("btf_trace_{tracepoint}");
-        self.data.attach_btf_id =
-            Some(btf.id_by_type_name_kind(type_name.as_str(), BtfKind::Typedef)?);
+        let (btf_id, btf_fd) = find_btf_attach_target(btf, &type_name, BtfKind::Typedef)?;
+        self.data.attach_btf_id = Some(btf_id);
+        self.data.attach_btf_obj_fd = btf_fd;
         load_program(BPF_PROG_TYPE_TRACING, &mut self.data)
     }
 
@@ -75,6 +75,35 @@ impl TracePoint {
     ///
     /// The returned value can be used to detach, see [TracePoint::detach].
     pub fn attach(&mut self, category: &str, name: &str) -> Result<TracePointLinkId, ProgramError> {
+        self.attach_inner(category, name, None)
+    }
+
+    /// Attaches to a given trace point with a cookie.
+    ///
+    /// Same as [`TracePoint::attach`], but the eBPF program can retrieve
+    /// `cookie` with the `bpf_get_attach_cookie()` helper.
+    ///
+    /// Returns [`ProgramError::AttachCookieNotSupported`] if the kernel doesn't
+    /// support attach cookies.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.15.
+    pub fn attach_with_cookie(
+        &mut self,
+        category: &str,
+        name: &str,
+        cookie: u64,
+    ) -> Result<TracePointLinkId, ProgramError> {
+        self.attach_inner(category, name, Some(cookie))
+    }
+
+    fn attach_inner(
+        &mut self,
+        category: &str,
+        name: &str,
+        cookie: Option<u64>,
+    ) -> Result<TracePointLinkId, ProgramError> {
         let prog_fd = self.fd()?;
         let prog_fd = prog_fd.as_fd();
         let tracefs = find_tracefs_path()?;
@@ -84,7 +113,7 @@ impl TracePoint {
             io_error,
         })?;
 
-        let link = perf_attach(prog_fd, fd, None /* cookie */)?;
+        let link = perf_attach(prog_fd, fd, cookie)?;
         self.data.links.insert(TracePointLink::new(link))
     }
 }
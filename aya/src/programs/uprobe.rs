@@ -1,7 +1,8 @@
 //! User space probes.
 use std::{
+    collections::{BTreeMap, HashMap},
     error::Error,
-    ffi::{CStr, OsStr, OsString, c_char},
+    ffi::{CStr, CString, OsStr, OsString, c_char},
     fs,
     io::{self, BufRead as _, Cursor, Read as _},
     mem,
@@ -10,20 +11,25 @@ use std::{
     sync::LazyLock,
 };
 
-use aya_obj::generated::{bpf_link_type, bpf_prog_type::BPF_PROG_TYPE_KPROBE};
+use aya_obj::generated::{
+    BPF_F_UPROBE_MULTI_RETURN, bpf_attach_type::BPF_TRACE_UPROBE_MULTI, bpf_link_type,
+    bpf_prog_type::BPF_PROG_TYPE_KPROBE,
+};
 use libc::pid_t;
-use object::{Object as _, ObjectSection as _, ObjectSymbol as _, Symbol};
+use object::{Object as _, ObjectSection as _, ObjectSymbol as _, Symbol, SymbolKind};
 use thiserror::Error;
 
 use crate::{
     VerifierLogLevel,
+    features::is_link_type_supported,
     programs::{
-        FdLink, LinkError, ProgramData, ProgramError, ProgramType, define_link_wrapper,
+        FdLink, LinkError, LinkType, ProgramData, ProgramError, ProgramType, define_link_wrapper,
         load_program,
         perf_attach::{PerfLinkIdInner, PerfLinkInner},
         probe::{OsStringExt as _, ProbeKind, attach},
+        utils::glob_match,
     },
-    sys::bpf_link_get_info_by_fd,
+    sys::{BpfLinkCreateArgs, LinkTarget, SyscallError, bpf_link_create, bpf_link_get_info_by_fd},
 };
 
 const LD_SO_CACHE_FILE: &str = "/etc/ld.so.cache";
@@ -71,6 +77,17 @@ impl From<u64> for UProbeAttachLocation<'static> {
     }
 }
 
+/// The functions of the target object file to attach a [`UProbe`] to with
+/// [`UProbe::attach_multi`].
+#[derive(Debug, Clone, Copy)]
+pub enum UProbeMultiLocations<'a> {
+    /// The functions whose symbol name matches the glob pattern. `*` matches
+    /// any sequence of characters and `?` matches any single character.
+    Pattern(&'a str),
+    /// The functions with the given symbol names.
+    Symbols(&'a [&'a str]),
+}
+
 impl UProbe {
     /// The type of the program according to the kernel.
     pub const PROGRAM_TYPE: ProgramType = ProgramType::KProbe;
@@ -133,6 +150,89 @@ impl UProbe {
         attach(&mut self.data, self.kind, path, offset, pid, cookie)
     }
 
+    /// Attaches the program to multiple functions.
+    ///
+    /// Attaches the uprobe to the functions of the `target` selected by
+    /// `locations` through a single `uprobe.multi` link, which is much faster
+    /// to set up than attaching to each function with [`UProbe::attach`] and
+    /// uses a single file descriptor. `target` and `pid` have the same meaning
+    /// as for [`UProbe::attach`].
+    ///
+    /// If `cookies` is not `None`, it must contain one cookie per function: in
+    /// the order of the symbols for [`UProbeMultiLocations::Symbols`], or in
+    /// the order of the matching symbol names sorted alphabetically for
+    /// [`UProbeMultiLocations::Pattern`].
+    ///
+    /// The program must be declared in a `uprobe.multi` or `uretprobe.multi`
+    /// section.
+    ///
+    /// The returned value can be used to detach, see [UProbe::detach].
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 6.6. If the
+    /// kernel doesn't support `uprobe.multi` links,
+    /// [`UProbeError::MultiAttachNotSupported`] is returned.
+    pub fn attach_multi<T: AsRef<Path>>(
+        &mut self,
+        target: T,
+        locations: UProbeMultiLocations<'_>,
+        pid: Option<pid_t>,
+        cookies: Option<&[u64]>,
+    ) -> Result<UProbeLinkId, ProgramError> {
+        if !is_link_type_supported(LinkType::UProbeMulti)? {
+            return Err(UProbeError::MultiAttachNotSupported.into());
+        }
+        let prog_fd = self.data.fd()?;
+        let prog_fd = prog_fd.as_fd();
+
+        let proc_map = pid.map(ProcMap::new).transpose()?;
+        let path = resolve_attach_path(target.as_ref(), proc_map.as_ref())?;
+        let offsets = match locations {
+            UProbeMultiLocations::Pattern(pattern) => resolve_symbols_matching(path, pattern)?,
+            UProbeMultiLocations::Symbols(symbols) => resolve_symbols(path, symbols)?,
+        };
+        if let Some(cookies) = cookies {
+            if cookies.len() != offsets.len() {
+                return Err(UProbeError::CookieCountMismatch {
+                    expected: offsets.len(),
+                    actual: cookies.len(),
+                }
+                .into());
+            }
+        }
+        let c_path =
+            CString::new(path.as_os_str().as_bytes()).map_err(|_| UProbeError::InvalidTarget {
+                path: path.to_owned(),
+            })?;
+
+        let flags = match self.kind {
+            ProbeKind::URetProbe => BPF_F_UPROBE_MULTI_RETURN,
+            ProbeKind::UProbe | ProbeKind::KProbe | ProbeKind::KRetProbe => 0,
+        };
+        let link_fd = bpf_link_create(
+            prog_fd,
+            LinkTarget::UProbeMulti,
+            BPF_TRACE_UPROBE_MULTI,
+            0,
+            Some(BpfLinkCreateArgs::UProbeMulti {
+                flags,
+                path: &c_path,
+                offsets: &offsets,
+                cookies,
+                pid: pid.unwrap_or(0) as u32,
+            }),
+        )
+        .map_err(|io_error| SyscallError {
+            call: "bpf_link_create",
+            io_error,
+        })?;
+
+        self.data
+            .links
+            .insert(UProbeLink::new(PerfLinkInner::FdLink(FdLink::new(link_fd))))
+    }
+
     /// Creates a program from a pinned entry on a bpffs.
     ///
     /// Existing links will not be populated. To work with existing links you should use [`crate::programs::links::PinnedLink`].
@@ -145,7 +245,7 @@ impl UProbe {
     }
 }
 
-fn resolve_attach_path<'a, 'b, 'c, T>(
+pub(crate) fn resolve_attach_path<'a, 'b, 'c, T>(
     target: &'a Path,
     proc_map: Option<&'b ProcMap<T>>,
 ) -> Result<&'c Path, UProbeError>
@@ -236,7 +336,9 @@ impl TryFrom<FdLink> for UProbeLink {
 
     fn try_from(fd_link: FdLink) -> Result<Self, Self::Error> {
         let info = bpf_link_get_info_by_fd(fd_link.fd.as_fd())?;
-        if info.type_ == (bpf_link_type::BPF_LINK_TYPE_TRACING as u32) {
+        if info.type_ == (bpf_link_type::BPF_LINK_TYPE_TRACING as u32)
+            || info.type_ == (bpf_link_type::BPF_LINK_TYPE_UPROBE_MULTI as u32)
+        {
             return Ok(Self::new(PerfLinkInner::FdLink(fd_link)));
         }
         Err(LinkError::InvalidLink)
@@ -281,6 +383,19 @@ pub enum UProbeError {
         io_error: io::Error,
     },
 
+    /// Attaching to multiple functions requires kernel 6.6 or later.
+    #[error("attaching uprobes to multiple functions is not supported by this kernel")]
+    MultiAttachNotSupported,
+
+    /// The number of cookies doesn't match the number of functions.
+    #[error("expected {expected} cookies, got {actual}")]
+    CookieCountMismatch {
+        /// The number of functions.
+        expected: usize,
+        /// The number of cookies.
+        actual: usize,
+    },
+
     /// There was en error fetching the memory map for `pid`.
     #[error("error fetching libs for {pid}")]
     ProcMap {
@@ -416,13 +531,13 @@ impl<'a> ProcMapEntry<'a> {
 /// This is read from /proc/`pid`/maps.
 ///
 /// The information here may be used to resolve addresses to paths.
-struct ProcMap<T> {
+pub(crate) struct ProcMap<T> {
     pid: pid_t,
     data: T,
 }
 
 impl ProcMap<Vec<u8>> {
-    fn new(pid: pid_t) -> Result<Self, UProbeError> {
+    pub(crate) fn new(pid: pid_t) -> Result<Self, UProbeError> {
         let filename = PathBuf::from(format!("/proc/{pid}/maps"));
         let data = fs::read(&filename)
             .map_err(|io_error| UProbeError::FileError { filename, io_error })?;
@@ -716,6 +831,16 @@ fn resolve_symbol(path: &Path, symbol: &str) -> Result<u64, ResolveSymbolError>
         Ok,
     )?;
 
+    symbol_offset(&obj, &sym, symbol)
+}
+
+// Returns the offset in the file of `sym`, a symbol of `obj` or of its debug
+// object.
+fn symbol_offset(
+    obj: &object::File<'_>,
+    sym: &Symbol<'_, '_>,
+    symbol: &str,
+) -> Result<u64, ResolveSymbolError> {
     let needs_addr_translation = matches!(
         obj.kind(),
         object::ObjectKind::Dynamic | object::ObjectKind::Executable
@@ -737,6 +862,85 @@ fn resolve_symbol(path: &Path, symbol: &str) -> Result<u64, ResolveSymbolError>
     }
 }
 
+pub(crate) fn read_object(path: &Path) -> Result<Vec<u8>, UProbeError> {
+    fs::read(path).map_err(|io_error| UProbeError::FileError {
+        filename: path.to_owned(),
+        io_error,
+    })
+}
+
+pub(crate) fn parse_object<'a>(
+    path: &Path,
+    data: &'a [u8],
+) -> Result<object::File<'a>, UProbeError> {
+    object::read::File::parse(data).map_err(|error| UProbeError::FileError {
+        filename: path.to_owned(),
+        io_error: io::Error::new(io::ErrorKind::InvalidData, error),
+    })
+}
+
+// Resolves the offsets of `symbols` in the object file at `path`, parsing it
+// only once.
+fn resolve_symbols(path: &Path, symbols: &[&str]) -> Result<Vec<u64>, UProbeError> {
+    let data = read_object(path)?;
+    let obj = parse_object(path, &data)?;
+
+    let mut by_name = HashMap::new();
+    for sym in obj.dynamic_symbols().chain(obj.symbols()) {
+        if let Ok(name) = sym.name() {
+            by_name.entry(name).or_insert(sym);
+        }
+    }
+
+    symbols
+        .iter()
+        .map(|&symbol| {
+            match by_name.get(symbol) {
+                Some(sym) => symbol_offset(&obj, sym, symbol),
+                // the symbol may be in the debug object
+                None => resolve_symbol(path, symbol),
+            }
+            .map_err(|error| UProbeError::SymbolError {
+                symbol: symbol.to_owned(),
+                error: Box::new(error),
+            })
+        })
+        .collect()
+}
+
+// Resolves the offsets of the functions defined in the object file at `path`
+// whose symbol name matches `pattern`, sorted by symbol name.
+fn resolve_symbols_matching(path: &Path, pattern: &str) -> Result<Vec<u64>, UProbeError> {
+    let data = read_object(path)?;
+    let obj = parse_object(path, &data)?;
+    let symbol_error = |error| UProbeError::SymbolError {
+        symbol: pattern.to_owned(),
+        error: Box::new(error),
+    };
+
+    let mut by_name = BTreeMap::new();
+    for sym in obj.dynamic_symbols().chain(obj.symbols()) {
+        if sym.kind() != SymbolKind::Text || !sym.is_definition() {
+            continue;
+        }
+        if let Ok(name) = sym.name() {
+            if glob_match(pattern, name) {
+                by_name.entry(name).or_insert(sym);
+            }
+        }
+    }
+    if by_name.is_empty() {
+        return Err(symbol_error(ResolveSymbolError::Unknown(
+            pattern.to_owned(),
+        )));
+    }
+
+    by_name
+        .into_iter()
+        .map(|(name, sym)| symbol_offset(&obj, &sym, name).map_err(symbol_error))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use assert_matches::assert_matches;
@@ -1043,4 +1247,52 @@ mod tests {
             Ok(Some(path)) if path == Path::new("/usr/lib64/ld-linux-x86-64.so.2")
         );
     }
+
+    fn create_elf_with_functions(functions: &[(&str, u64)]) -> Vec<u8> {
+        let mut obj =
+            object::write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let section_id = obj.add_section(vec![], b".text".to_vec(), SectionKind::Text);
+        obj.append_section_data(section_id, &[0; 64], 8 /* align */);
+        for &(name, offset) in functions {
+            obj.add_symbol(object::write::Symbol {
+                name: name.as_bytes().to_vec(),
+                value: offset,
+                size: 8,
+                kind: SymbolKind::Text,
+                scope: object::SymbolScope::Dynamic,
+                weak: false,
+                section: object::write::SymbolSection::Section(section_id),
+                flags: object::SymbolFlags::None,
+            });
+        }
+        obj.write().unwrap()
+    }
+
+    #[test]
+    fn test_resolve_symbols() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.o");
+        fs::write(
+            &path,
+            create_elf_with_functions(&[("foo_b", 8), ("foo_a", 0), ("bar", 16)]),
+        )
+        .unwrap();
+
+        assert_matches!(
+            resolve_symbols_matching(&path, "foo_*"),
+            Ok(offsets) if offsets == [0, 8]
+        );
+        assert_matches!(
+            resolve_symbols(&path, &["bar", "foo_b"]),
+            Ok(offsets) if offsets == [16, 8]
+        );
+        assert_matches!(
+            resolve_symbols_matching(&path, "baz*"),
+            Err(UProbeError::SymbolError { symbol, .. }) if symbol == "baz*"
+        );
+        assert_matches!(
+            resolve_symbols(&path, &["bar", "baz"]),
+            Err(UProbeError::SymbolError { symbol, .. }) if symbol == "baz"
+        );
+    }
 }
@@ -0,0 +1,761 @@
+//! User statically-defined tracing probes.
+use std::{
+    mem,
+    os::fd::AsFd as _,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use aya_obj::generated::bpf_prog_type::BPF_PROG_TYPE_KPROBE;
+use libc::{E2BIG, pid_t};
+use object::{Architecture, Object as _, ObjectSection as _, ObjectSegment as _};
+use thiserror::Error;
+
+use crate::{
+    Pod,
+    maps::{MapFd, MapInfo},
+    programs::{
+        ProgramData, ProgramError, ProgramType, define_link_wrapper, id_as_key,
+        links::Link,
+        load_program,
+        perf_attach::PerfLinkInner,
+        probe::{ProbeKind, create_probe_link},
+        uprobe::{ProcMap, parse_object, read_object, resolve_attach_path},
+    },
+    sys::{SyscallError, bpf_map_delete_elem, bpf_map_update_elem},
+};
+
+const USDT_NOTE_SECTION: &str = ".note.stapsdt";
+const USDT_NOTE_TYPE: u32 = 3;
+const USDT_NOTE_NAME: &[u8] = b"stapsdt\0";
+const USDT_BASE_SECTION: &str = ".stapsdt.base";
+
+const USDT_MAX_ARG_COUNT: usize = 12;
+
+/// The default capacity of the `AYA_USDT_SPECS` map.
+///
+/// Must match `aya_ebpf::programs::usdt::USDT_MAX_SPEC_COUNT`.
+pub const USDT_MAX_SPEC_COUNT: u32 = 256;
+
+// The values of `UsdtArgSpec::arg_type`.
+const USDT_ARG_CONST: u32 = 0;
+const USDT_ARG_REG: u32 = 1;
+const USDT_ARG_REG_DEREF: u32 = 2;
+
+// The ids of the specs stored in the `AYA_USDT_SPECS` map, which are passed
+// to the programs as the attach cookie. They are unique in the process so
+// that programs sharing the map don't overwrite each other's specs.
+static NEXT_SPEC_ID: AtomicU32 = AtomicU32::new(0);
+
+/// A user statically-defined tracing probe.
+///
+/// USDT probes are static tracepoints that applications declare with the
+/// `DTRACE_PROBE` or `STAP_PROBE` macros of `sys/sdt.h`. Each probe is
+/// recorded in the `.note.stapsdt` section of the binary, along with the
+/// location of its arguments and an optional semaphore that the application
+/// can check to skip computing the arguments when the probe isn't attached.
+///
+/// When attaching, aya increments the semaphore and stores the location of the
+/// arguments in the `AYA_USDT_SPECS` map, from which `UsdtContext::arg` reads
+/// them in the eBPF program. Locations sharing the same argument layout share
+/// an entry of the map, which holds [`USDT_MAX_SPEC_COUNT`] entries across all
+/// the attached probes by default. Programs attaching to many probes or to
+/// probes with many distinct layouts can raise the limit with
+/// [`EbpfLoader::set_max_entries`](crate::EbpfLoader::set_max_entries):
+///
+/// ```no_run
+/// use aya::EbpfLoader;
+///
+/// let bpf = EbpfLoader::new()
+///     .set_max_entries("AYA_USDT_SPECS", 4096)
+///     .load_file("ebpf_programs.o")?;
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.15.
+///
+/// # Examples
+///
+/// ```no_run
+/// # let mut bpf = Ebpf::load_file("ebpf_programs.o")?;
+/// use aya::{Ebpf, programs::Usdt};
+///
+/// let program: &mut Usdt = bpf.program_mut("request_start").unwrap().try_into()?;
+/// program.load()?;
+/// program.attach("myapp", "request__start", "/usr/bin/myapp", None)?;
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+#[derive(Debug)]
+#[doc(alias = "BPF_PROG_TYPE_KPROBE")]
+pub struct Usdt {
+    pub(crate) data: ProgramData<UsdtLink>,
+    pub(crate) specs: Option<Arc<MapFd>>,
+}
+
+impl Usdt {
+    /// The type of the program according to the kernel.
+    pub const PROGRAM_TYPE: ProgramType = ProgramType::KProbe;
+
+    /// Loads the program inside the kernel.
+    pub fn load(&mut self) -> Result<(), ProgramError> {
+        load_program(BPF_PROG_TYPE_KPROBE, &mut self.data)
+    }
+
+    /// Attaches the program.
+    ///
+    /// Attaches the program to every location of the probe `name` of
+    /// `provider` defined in the `target`, and increments the semaphore of the
+    /// probe if it has one. If `pid` is not `None`, the program executes only
+    /// when the probe is hit by the given `pid`.
+    ///
+    /// The `target` argument can be an absolute path to a binary or library, or
+    /// a library name (eg: `"libc"`).
+    ///
+    /// The returned value can be used to detach, see [Usdt::detach].
+    pub fn attach<T: AsRef<Path>>(
+        &mut self,
+        provider: &str,
+        name: &str,
+        target: T,
+        pid: Option<pid_t>,
+    ) -> Result<UsdtLinkId, ProgramError> {
+        let prog_fd = self.data.fd()?;
+        let prog_fd = prog_fd.as_fd();
+
+        let proc_map = pid.map(ProcMap::new).transpose()?;
+        let path = resolve_attach_path(target.as_ref(), proc_map.as_ref())?;
+        let data = read_object(path)?;
+        let obj = parse_object(path, &data)?;
+        let probes = find_probes(path, &obj, provider, name)?;
+
+        let (specs, probes) = share_specs(probes);
+
+        let mut link = UsdtLinkInner {
+            links: Vec::with_capacity(probes.len()),
+            specs: self.specs.clone(),
+            spec_ids: Vec::with_capacity(specs.len()),
+        };
+        if let Some(map) = &link.specs {
+            let max_entries = MapInfo::new_from_fd(map.as_fd())?.max_entries();
+            let too_many_specs = || UsdtError::TooManySpecs {
+                count: specs.len(),
+                max_entries,
+            };
+            // Fail before attaching anything if the specs can't fit.
+            if specs.len() > max_entries as usize {
+                return Err(too_many_specs().into());
+            }
+            for spec in &specs {
+                let spec_id = NEXT_SPEC_ID.fetch_add(1, Ordering::Relaxed);
+                // On error, dropping `link` deletes the specs stored so far.
+                bpf_map_update_elem(map.as_fd(), Some(&spec_id), spec, 0).map_err(|io_error| {
+                    if io_error.raw_os_error() == Some(E2BIG) {
+                        too_many_specs().into()
+                    } else {
+                        ProgramError::from(SyscallError {
+                            call: "bpf_map_update_elem",
+                            io_error,
+                        })
+                    }
+                })?;
+                link.spec_ids.push(spec_id);
+            }
+        } else {
+            link.spec_ids.extend(
+                specs
+                    .iter()
+                    .map(|_| NEXT_SPEC_ID.fetch_add(1, Ordering::Relaxed)),
+            );
+        }
+
+        for (
+            UsdtProbe {
+                offset,
+                semaphore_offset,
+                spec: _,
+            },
+            index,
+        ) in probes
+        {
+            // On error, dropping `link` detaches the locations attached so far.
+            link.links.push(create_probe_link(
+                prog_fd,
+                ProbeKind::UProbe,
+                path.as_os_str(),
+                offset,
+                pid,
+                Some(u64::from(link.spec_ids[index])),
+                semaphore_offset,
+            )?);
+        }
+
+        self.data.links.insert(UsdtLink::new(link))
+    }
+}
+
+/// The type returned when attaching a [`Usdt`] fails.
+#[derive(Debug, Error)]
+pub enum UsdtError {
+    /// The target doesn't define the probe.
+    #[error("probe `{provider}:{name}` not found")]
+    ProbeNotFound {
+        /// The provider of the probe.
+        provider: String,
+        /// The name of the probe.
+        name: String,
+    },
+
+    /// The `.note.stapsdt` section of the target is malformed.
+    #[error("invalid `{}` section in `{}`", USDT_NOTE_SECTION, path.display())]
+    InvalidNote {
+        /// The path to the target.
+        path: PathBuf,
+    },
+
+    /// An address of the probe isn't mapped by any segment of the target.
+    #[error("address {address:#x} of `{}` is not in a loadable segment", path.display())]
+    InvalidAddress {
+        /// The path to the target.
+        path: PathBuf,
+        /// The address.
+        address: u64,
+    },
+
+    /// The arguments of the probe couldn't be parsed.
+    #[error("unsupported USDT arguments `{spec}`")]
+    InvalidArgSpec {
+        /// The argument specs of the probe.
+        spec: String,
+    },
+
+    /// The `AYA_USDT_SPECS` map is too small to hold the argument specs of the
+    /// probe.
+    ///
+    /// The map holds [`USDT_MAX_SPEC_COUNT`] specs by default, which can be
+    /// raised with [`EbpfLoader::set_max_entries`](crate::EbpfLoader::set_max_entries).
+    #[error(
+        "the {count} argument specs of the probe don't fit in the `AYA_USDT_SPECS` map of {max_entries} entries"
+    )]
+    TooManySpecs {
+        /// The number of distinct argument specs of the probe.
+        count: usize,
+        /// The capacity of the map.
+        max_entries: u32,
+    },
+}
+
+// The layout of these types must match the ones in aya_ebpf::programs::usdt.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct UsdtArgSpec {
+    // The value of constant arguments, or the offset from the register of
+    // dereferenced arguments.
+    val_off: u64,
+    arg_type: u32,
+    // The offset of the register in `pt_regs`.
+    reg_off: i16,
+    arg_signed: bool,
+    // The shift that truncates the 64 bit value to the size of the argument.
+    arg_bitshift: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct UsdtSpec {
+    args: [UsdtArgSpec; USDT_MAX_ARG_COUNT],
+    arg_cnt: u16,
+}
+
+unsafe impl Pod for UsdtSpec {}
+
+impl UsdtSpec {
+    fn new(args: &[UsdtArgSpec]) -> Self {
+        // Zero the padding, which is copied to the map.
+        let mut spec: Self = unsafe { mem::zeroed() };
+        spec.args[..args.len()].copy_from_slice(args);
+        spec.arg_cnt = args.len() as u16;
+        spec
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct UsdtNote<'a> {
+    provider: &'a str,
+    name: &'a str,
+    pc: u64,
+    base: u64,
+    semaphore: u64,
+    args: &'a str,
+}
+
+struct UsdtProbe {
+    offset: u64,
+    semaphore_offset: Option<u64>,
+    spec: UsdtSpec,
+}
+
+fn find_probes(
+    path: &Path,
+    obj: &object::File<'_>,
+    provider: &str,
+    name: &str,
+) -> Result<Vec<UsdtProbe>, UsdtError> {
+    let invalid_note = || UsdtError::InvalidNote {
+        path: path.to_owned(),
+    };
+    let notes = match obj.section_by_name(USDT_NOTE_SECTION) {
+        Some(section) => {
+            let data = section.data().map_err(|_| invalid_note())?;
+            parse_notes(data, obj.is_64(), obj.is_little_endian()).ok_or_else(invalid_note)?
+        }
+        None => Vec::new(),
+    };
+    let base_address = obj
+        .section_by_name(USDT_BASE_SECTION)
+        .map(|section| section.address());
+
+    let file_offset = |address| {
+        obj.segments()
+            .find_map(|segment| {
+                let (file_offset, _) = segment.file_range();
+                (segment.address()..segment.address() + segment.size())
+                    .contains(&address)
+                    .then(|| address - segment.address() + file_offset)
+            })
+            .ok_or_else(|| UsdtError::InvalidAddress {
+                path: path.to_owned(),
+                address,
+            })
+    };
+
+    let probes = notes
+        .into_iter()
+        .filter(|note| note.provider == provider && note.name == name)
+        .map(|note| {
+            let mut pc = note.pc;
+            // Prelinking moves the probes without updating the notes, which is
+            // detected through the address of the `.stapsdt.base` section.
+            if let Some(base_address) = base_address {
+                if note.base != 0 {
+                    pc = pc.wrapping_add(base_address).wrapping_sub(note.base);
+                }
+            }
+            let args = parse_arg_specs(obj.architecture(), note.args).ok_or_else(|| {
+                UsdtError::InvalidArgSpec {
+                    spec: note.args.to_owned(),
+                }
+            })?;
+            Ok(UsdtProbe {
+                offset: file_offset(pc)?,
+                semaphore_offset: (note.semaphore != 0)
+                    .then(|| file_offset(note.semaphore))
+                    .transpose()?,
+                spec: UsdtSpec::new(&args),
+            })
+        })
+        .collect::<Result<Vec<_>, UsdtError>>()?;
+
+    if probes.is_empty() {
+        return Err(UsdtError::ProbeNotFound {
+            provider: provider.to_owned(),
+            name: name.to_owned(),
+        });
+    }
+    Ok(probes)
+}
+
+// Returns the distinct specs of the probes, and the probes along with the index
+// of their spec. Locations with the same argument layout share a spec, so that
+// probes inlined in many places take few entries of the specs map.
+fn share_specs(probes: Vec<UsdtProbe>) -> (Vec<UsdtSpec>, Vec<(UsdtProbe, usize)>) {
+    let mut specs: Vec<UsdtSpec> = Vec::new();
+    let probes = probes
+        .into_iter()
+        .map(|probe| {
+            let index = specs
+                .iter()
+                .position(|spec| *spec == probe.spec)
+                .unwrap_or_else(|| {
+                    specs.push(probe.spec);
+                    specs.len() - 1
+                });
+            (probe, index)
+        })
+        .collect();
+    (specs, probes)
+}
+
+// Parses the notes of the `.note.stapsdt` section, skipping notes of other
+// types. Returns `None` if the section is malformed.
+fn parse_notes(data: &[u8], is_64: bool, little_endian: bool) -> Option<Vec<UsdtNote<'_>>> {
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+        let (head, tail) = data.split_at_checked(len)?;
+        *data = tail;
+        Some(head)
+    }
+    fn read_u64(data: &mut &[u8], size: usize, little_endian: bool) -> Option<u64> {
+        let bytes = take(data, size)?;
+        let mut buf = [0; 8];
+        Some(if little_endian {
+            buf[..size].copy_from_slice(bytes);
+            u64::from_le_bytes(buf)
+        } else {
+            buf[8 - size..].copy_from_slice(bytes);
+            u64::from_be_bytes(buf)
+        })
+    }
+    fn read_str<'a>(data: &mut &'a [u8]) -> Option<&'a str> {
+        let len = data.iter().position(|&b| b == 0)?;
+        let s = std::str::from_utf8(take(data, len)?).ok()?;
+        take(data, 1)?;
+        Some(s)
+    }
+    const fn align(len: usize) -> usize {
+        len.next_multiple_of(4)
+    }
+
+    let address_size = if is_64 { 8 } else { 4 };
+    let mut data = data;
+    let mut notes = Vec::new();
+    while !data.is_empty() {
+        let namesz = read_u64(&mut data, 4, little_endian)? as usize;
+        let descsz = read_u64(&mut data, 4, little_endian)? as usize;
+        let note_type = read_u64(&mut data, 4, little_endian)? as u32;
+        let name = take(&mut data, namesz)?;
+        take(&mut data, align(namesz) - namesz)?;
+        let mut desc = take(&mut data, descsz)?;
+        // The padding of the last note may be missing.
+        let padding = (align(descsz) - descsz).min(data.len());
+        take(&mut data, padding)?;
+
+        if note_type != USDT_NOTE_TYPE || name != USDT_NOTE_NAME {
+            continue;
+        }
+        notes.push(UsdtNote {
+            pc: read_u64(&mut desc, address_size, little_endian)?,
+            base: read_u64(&mut desc, address_size, little_endian)?,
+            semaphore: read_u64(&mut desc, address_size, little_endian)?,
+            provider: read_str(&mut desc)?,
+            name: read_str(&mut desc)?,
+            args: read_str(&mut desc)?,
+        });
+    }
+    Some(notes)
+}
+
+// Parses the space separated argument specs of a probe, such as
+// `-4@%edi 8@-8(%rbp)` on x86_64 or `-4@w0 8@[sp, 16]` on aarch64.
+fn parse_arg_specs(arch: Architecture, args: &str) -> Option<Vec<UsdtArgSpec>> {
+    let mut specs = Vec::new();
+    let mut args = args.trim_start();
+    while !args.is_empty() {
+        // aarch64 memory operands contain spaces, e.g. `[sp, 16]`.
+        let end = match args.find('[') {
+            Some(open) if args.find(' ').is_none_or(|space| open < space) => {
+                open + args[open..].find(']')? + 1
+            }
+            _ => args.find(' ').unwrap_or(args.len()),
+        };
+        let (arg, rest) = args.split_at(end);
+        if specs.len() == USDT_MAX_ARG_COUNT {
+            return None;
+        }
+        specs.push(parse_arg_spec(arch, arg)?);
+        args = rest.trim_start();
+    }
+    Some(specs)
+}
+
+fn parse_arg_spec(arch: Architecture, arg: &str) -> Option<UsdtArgSpec> {
+    let (size, location) = arg.split_once('@')?;
+    let size: i8 = size.parse().ok()?;
+    let arg_bitshift = match size.unsigned_abs() {
+        size @ (1 | 2 | 4 | 8) => 64 - size * 8,
+        _ => return None,
+    };
+    let (arg_type, reg_off, val_off) = match arch {
+        Architecture::X86_64 => parse_location_x86_64(location)?,
+        Architecture::Aarch64 => parse_location_aarch64(location)?,
+        _ => return None,
+    };
+    Some(UsdtArgSpec {
+        val_off: val_off as u64,
+        arg_type,
+        reg_off,
+        arg_signed: size < 0,
+        arg_bitshift,
+    })
+}
+
+fn parse_int(s: &str) -> Option<i64> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let value = match s.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => s.parse().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+// Returns the type, register offset and value of an x86_64 argument in AT&T
+// syntax: `%reg`, `off(%reg)` or `$imm`.
+fn parse_location_x86_64(location: &str) -> Option<(u32, i16, i64)> {
+    if let Some(imm) = location.strip_prefix('$') {
+        return Some((USDT_ARG_CONST, 0, parse_int(imm)?));
+    }
+    if let Some(reg) = location.strip_prefix('%') {
+        return Some((USDT_ARG_REG, reg_offset_x86_64(reg)?, 0));
+    }
+    let (off, reg) = location.strip_suffix(')')?.split_once("(%")?;
+    let off = if off.is_empty() { 0 } else { parse_int(off)? };
+    Some((USDT_ARG_REG_DEREF, reg_offset_x86_64(reg)?, off))
+}
+
+// Returns the offset of a register in the x86_64 `struct pt_regs`.
+fn reg_offset_x86_64(reg: &str) -> Option<i16> {
+    let offset = match reg {
+        "r15" | "r15d" | "r15w" | "r15b" => 0,
+        "r14" | "r14d" | "r14w" | "r14b" => 8,
+        "r13" | "r13d" | "r13w" | "r13b" => 16,
+        "r12" | "r12d" | "r12w" | "r12b" => 24,
+        "rbp" | "ebp" | "bp" | "bpl" => 32,
+        "rbx" | "ebx" | "bx" | "bl" => 40,
+        "r11" | "r11d" | "r11w" | "r11b" => 48,
+        "r10" | "r10d" | "r10w" | "r10b" => 56,
+        "r9" | "r9d" | "r9w" | "r9b" => 64,
+        "r8" | "r8d" | "r8w" | "r8b" => 72,
+        "rax" | "eax" | "ax" | "al" => 80,
+        "rcx" | "ecx" | "cx" | "cl" => 88,
+        "rdx" | "edx" | "dx" | "dl" => 96,
+        "rsi" | "esi" | "si" | "sil" => 104,
+        "rdi" | "edi" | "di" | "dil" => 112,
+        "rip" => 128,
+        "rsp" | "esp" | "sp" | "spl" => 152,
+        _ => return None,
+    };
+    Some(offset)
+}
+
+// Returns the type, register offset and value of an aarch64 argument: `reg`,
+// `[reg]`, `[reg, off]` or `imm`.
+fn parse_location_aarch64(location: &str) -> Option<(u32, i16, i64)> {
+    if let Some(mem) = location
+        .strip_prefix('[')
+        .and_then(|mem| mem.strip_suffix(']'))
+    {
+        let (reg, off) = match mem.split_once(',') {
+            Some((reg, off)) => (reg, parse_int(off.trim())?),
+            None => (mem, 0),
+        };
+        return Some((USDT_ARG_REG_DEREF, reg_offset_aarch64(reg.trim())?, off));
+    }
+    match reg_offset_aarch64(location) {
+        Some(reg_off) => Some((USDT_ARG_REG, reg_off, 0)),
+        None => Some((USDT_ARG_CONST, 0, parse_int(location)?)),
+    }
+}
+
+// Returns the offset of a register in the aarch64 `struct user_pt_regs`.
+fn reg_offset_aarch64(reg: &str) -> Option<i16> {
+    if reg == "sp" {
+        return Some(31 * 8);
+    }
+    let n: i16 = reg
+        .strip_prefix('x')
+        .or_else(|| reg.strip_prefix('w'))?
+        .parse()
+        .ok()?;
+    (0..31).contains(&n).then_some(n * 8)
+}
+
+#[derive(Debug, Hash, Eq, PartialEq)]
+pub(crate) struct UsdtLinkIdInner(u32);
+
+#[derive(Debug)]
+pub(crate) struct UsdtLinkInner {
+    links: Vec<PerfLinkInner>,
+    specs: Option<Arc<MapFd>>,
+    spec_ids: Vec<u32>,
+}
+
+impl Link for UsdtLinkInner {
+    type Id = UsdtLinkIdInner;
+
+    fn id(&self) -> Self::Id {
+        UsdtLinkIdInner(self.spec_ids[0])
+    }
+
+    fn detach(mut self) -> Result<(), ProgramError> {
+        for link in mem::take(&mut self.links) {
+            link.detach()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for UsdtLinkInner {
+    fn drop(&mut self) {
+        let Self {
+            links: _,
+            specs,
+            spec_ids,
+        } = self;
+        if let Some(specs) = specs {
+            for spec_id in spec_ids {
+                let _: Result<(), _> = bpf_map_delete_elem(specs.as_fd(), spec_id);
+            }
+        }
+    }
+}
+
+id_as_key!(UsdtLinkInner, UsdtLinkIdInner);
+
+define_link_wrapper!(
+    /// The link used by [Usdt] programs.
+    UsdtLink,
+    /// The type returned by [Usdt::attach]. Can be passed to [Usdt::detach].
+    UsdtLinkId,
+    UsdtLinkInner,
+    UsdtLinkIdInner,
+    Usdt,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(name: &[u8], note_type: u32, desc: &[u8]) -> Vec<u8> {
+        let mut note = Vec::new();
+        note.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        note.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+        note.extend_from_slice(&note_type.to_le_bytes());
+        note.extend_from_slice(name);
+        note.resize(note.len().next_multiple_of(4), 0);
+        note.extend_from_slice(desc);
+        note.resize(note.len().next_multiple_of(4), 0);
+        note
+    }
+
+    fn arg(arg_type: u32, reg_off: i16, val_off: i64, size: i8) -> UsdtArgSpec {
+        UsdtArgSpec {
+            val_off: val_off as u64,
+            arg_type,
+            reg_off,
+            arg_signed: size < 0,
+            arg_bitshift: 64 - size.unsigned_abs() * 8,
+        }
+    }
+
+    #[test]
+    fn test_parse_notes() {
+        let mut desc = Vec::new();
+        desc.extend_from_slice(&0x1234u64.to_le_bytes());
+        desc.extend_from_slice(&0x5678u64.to_le_bytes());
+        desc.extend_from_slice(&0x9abcu64.to_le_bytes());
+        desc.extend_from_slice(b"provider\0probe\0-4@%edi 8@%rsi\0");
+
+        let mut data = note(b"GNU\0", 1, &[0; 4]);
+        data.extend(note(USDT_NOTE_NAME, USDT_NOTE_TYPE, &desc));
+        assert_eq!(
+            parse_notes(&data, true, true),
+            Some(vec![UsdtNote {
+                provider: "provider",
+                name: "probe",
+                pc: 0x1234,
+                base: 0x5678,
+                semaphore: 0x9abc,
+                args: "-4@%edi 8@%rsi",
+            }])
+        );
+
+        // truncated
+        assert_eq!(parse_notes(&data[..data.len() - 8], true, true), None);
+    }
+
+    #[test]
+    fn test_parse_arg_specs_x86_64() {
+        assert_eq!(
+            parse_arg_specs(
+                Architecture::X86_64,
+                "-4@%edi 8@-8(%rbp) 2@(%rax) -1@$-5 8@0x10(%r12) 4@%r8d"
+            ),
+            Some(vec![
+                arg(USDT_ARG_REG, 112, 0, -4),
+                arg(USDT_ARG_REG_DEREF, 32, -8, 8),
+                arg(USDT_ARG_REG_DEREF, 80, 0, 2),
+                arg(USDT_ARG_CONST, 0, -5, -1),
+                arg(USDT_ARG_REG_DEREF, 24, 0x10, 8),
+                arg(USDT_ARG_REG, 72, 0, 4),
+            ])
+        );
+        assert_eq!(parse_arg_specs(Architecture::X86_64, ""), Some(vec![]));
+        assert_eq!(parse_arg_specs(Architecture::X86_64, "3@%eax"), None);
+        assert_eq!(parse_arg_specs(Architecture::X86_64, "4@%xmm0"), None);
+        assert_eq!(parse_arg_specs(Architecture::X86_64, "8@foo(%rip)"), None);
+    }
+
+    #[test]
+    fn test_parse_arg_specs_aarch64() {
+        assert_eq!(
+            parse_arg_specs(
+                Architecture::Aarch64,
+                "-4@w0 8@x30 8@[sp, 16] 4@[x1] 8@[x2, -8] -8@42"
+            ),
+            Some(vec![
+                arg(USDT_ARG_REG, 0, 0, -4),
+                arg(USDT_ARG_REG, 240, 0, 8),
+                arg(USDT_ARG_REG_DEREF, 248, 16, 8),
+                arg(USDT_ARG_REG_DEREF, 8, 0, 4),
+                arg(USDT_ARG_REG_DEREF, 16, -8, 8),
+                arg(USDT_ARG_CONST, 0, 42, -8),
+            ])
+        );
+        assert_eq!(parse_arg_specs(Architecture::Aarch64, "8@x31"), None);
+        assert_eq!(parse_arg_specs(Architecture::Aarch64, "8@[x1, 8"), None);
+    }
+
+    #[test]
+    fn test_share_specs() {
+        let probe = |offset, args: &[UsdtArgSpec]| UsdtProbe {
+            offset,
+            semaphore_offset: None,
+            spec: UsdtSpec::new(args),
+        };
+        let rdi = arg(USDT_ARG_REG, 112, 0, 8);
+        let rsi = arg(USDT_ARG_REG, 104, 0, 8);
+        let (specs, probes) = share_specs(vec![
+            probe(0x10, &[rdi]),
+            probe(0x20, &[rsi]),
+            probe(0x30, &[rdi]),
+            probe(0x40, &[rdi, rsi]),
+        ]);
+        assert_eq!(
+            specs,
+            [
+                UsdtSpec::new(&[rdi]),
+                UsdtSpec::new(&[rsi]),
+                UsdtSpec::new(&[rdi, rsi]),
+            ]
+        );
+        assert_eq!(
+            probes
+                .iter()
+                .map(|(probe, index)| (probe.offset, *index))
+                .collect::<Vec<_>>(),
+            [(0x10, 0), (0x20, 1), (0x30, 0), (0x40, 2)]
+        );
+    }
+
+    #[test]
+    fn test_parse_arg_specs_too_many() {
+        let args = vec!["8@%rax"; USDT_MAX_ARG_COUNT + 1].join(" ");
+        assert_eq!(parse_arg_specs(Architecture::X86_64, &args), None);
+    }
+}
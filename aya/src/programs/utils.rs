@@ -9,11 +9,40 @@ use std::{
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use aya_obj::btf::{Btf, BtfKind};
+use log::debug;
+
 use crate::{
     programs::{FdLink, Link, ProgramData, ProgramError},
     sys::{SyscallError, bpf_raw_tracepoint_open},
+    util::kernel_module_btf_fd,
 };
 
+/// Finds the BTF id of the attach target `name` of the given `kind` in `btf`.
+///
+/// When `btf` is the split BTF of a kernel module, loaded with
+/// [`Btf::from_sys_fs_module`], the fd of the BTF object of that module is
+/// returned too.
+pub(crate) fn find_btf_attach_target(
+    btf: &Btf,
+    name: &str,
+    kind: BtfKind,
+) -> Result<(u32, Option<crate::MockableFd>), ProgramError> {
+    let id = btf.id_by_type_name_kind(name, kind)?;
+    if btf.base().is_none() {
+        return Ok((id, None));
+    }
+    // the ids of split BTF are only meaningful along with the fd of the
+    // module BTF object they belong to
+    let module_not_found = || ProgramError::ModuleBtfNotFound {
+        name: name.to_owned(),
+    };
+    let module = btf.module_name().ok_or_else(module_not_found)?;
+    let fd = kernel_module_btf_fd(module)?.ok_or_else(module_not_found)?;
+    debug!("found attach target {name} in module {module}");
+    Ok((id, Some(fd)))
+}
+
 /// Attaches the program to a raw tracepoint.
 pub(crate) fn attach_raw_tracepoint<T: Link + From<FdLink>>(
     program_data: &mut ProgramData<T>,
@@ -94,3 +123,55 @@ pub(crate) fn get_fdinfo(fd: BorrowedFd<'_>, key: &str) -> Result<u32, ProgramEr
 
     Ok(0)
 }
+
+/// Matches `name` against a glob `pattern` supporting the `*` and `?` wildcards.
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = (pattern.as_bytes(), name.as_bytes());
+    let (mut p, mut n) = (0, 0);
+    // The position after the last `*` in the pattern, and the position in the
+    // name it's currently matched up to.
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                backtrack = Some((p, n));
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, n));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("tcp_v4_connect", "tcp_v4_connect"));
+        assert!(!glob_match("tcp_v4_connect", "tcp_v4_connect_init"));
+        assert!(glob_match("tcp_*", "tcp_v4_connect"));
+        assert!(glob_match("tcp_*", "tcp_"));
+        assert!(!glob_match("tcp_*", "udp_sendmsg"));
+        assert!(glob_match("*_sendmsg", "udp_sendmsg"));
+        assert!(glob_match("*send*", "tcp_sendmsg_locked"));
+        assert!(glob_match("tcp_v?_connect", "tcp_v6_connect"));
+        assert!(!glob_match("tcp_v?_connect", "tcp_v46_connect"));
+        assert!(glob_match("*a*b", "aaab"));
+        assert!(!glob_match("*a*b", "aaba"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("?", ""));
+    }
+}
@@ -4,6 +4,7 @@ use std::{
     io, iter,
     mem::{self, MaybeUninit},
     os::fd::{AsFd as _, AsRawFd as _, BorrowedFd, FromRawFd as _, RawFd},
+    time::Duration,
 };
 
 use assert_matches::assert_matches;
@@ -15,10 +16,11 @@ use aya_obj::{
         VarLinkage,
     },
     generated::{
-        BPF_ADD, BPF_ALU64, BPF_CALL, BPF_DW, BPF_EXIT, BPF_F_REPLACE, BPF_IMM, BPF_JMP, BPF_K,
-        BPF_LD, BPF_MEM, BPF_MOV, BPF_PSEUDO_MAP_VALUE, BPF_ST, BPF_X, bpf_attach_type, bpf_attr,
-        bpf_btf_info, bpf_cmd, bpf_func_id::*, bpf_insn, bpf_link_info, bpf_map_info, bpf_map_type,
-        bpf_prog_info, bpf_prog_type, bpf_stats_type,
+        BPF_ADD, BPF_ALU64, BPF_CALL, BPF_DW, BPF_EXIT, BPF_F_MMAPABLE, BPF_F_NO_PREALLOC,
+        BPF_F_REPLACE, BPF_F_SLEEPABLE, BPF_IMM, BPF_JMP, BPF_K, BPF_LD, BPF_MEM, BPF_MOV,
+        BPF_PSEUDO_MAP_VALUE, BPF_ST, BPF_X, bpf_attach_type, bpf_attr, bpf_btf_info, bpf_cmd,
+        bpf_func_id::*, bpf_insn, bpf_link_info, bpf_map_info, bpf_map_type, bpf_prog_info,
+        bpf_prog_type, bpf_stats_type,
     },
     maps::{LegacyMap, bpf_map_def},
 };
@@ -27,9 +29,9 @@ use libc::{ENOENT, ENOSPC};
 use crate::{
     Btf, FEATURES, Pod, VerifierLogLevel,
     maps::{MapData, PerCpuValues},
-    programs::links::LinkRef,
+    programs::{TestRunOptions, TestRunResult, links::LinkRef},
     sys::{Syscall, SyscallError, syscall},
-    util::KernelVersion,
+    util::{KernelVersion, page_size},
 };
 
 pub(crate) fn bpf_create_iter(link_fd: BorrowedFd<'_>) -> io::Result<crate::MockableFd> {
@@ -45,6 +47,7 @@ pub(crate) fn bpf_create_iter(link_fd: BorrowedFd<'_>) -> io::Result<crate::Mock
 pub(crate) fn bpf_create_map(
     name: &CStr,
     def: &aya_obj::Map,
+    inner_map_fd: Option<BorrowedFd<'_>>,
     btf_fd: Option<BorrowedFd<'_>>,
 ) -> io::Result<crate::MockableFd> {
     let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
@@ -55,6 +58,32 @@ pub(crate) fn bpf_create_map(
     u.value_size = def.value_size();
     u.max_entries = def.max_entries();
     u.map_flags = def.map_flags();
+    u.map_extra = def.map_extra();
+    if let Some(inner_map_fd) = inner_map_fd {
+        u.inner_map_fd = inner_map_fd.as_raw_fd() as u32;
+    }
+
+    // Keep this alive until the map is created.
+    let mut _local_storage_btf_fd = None;
+    if let aya_obj::Map::Legacy(_) = def {
+        use bpf_map_type::*;
+
+        // Local storage maps require BTF for their keys and values, which
+        // legacy map definitions don't have.
+        if let Ok(
+            BPF_MAP_TYPE_SK_STORAGE
+            | BPF_MAP_TYPE_INODE_STORAGE
+            | BPF_MAP_TYPE_TASK_STORAGE
+            | BPF_MAP_TYPE_CGRP_STORAGE,
+        ) = u.map_type.try_into()
+        {
+            let (btf_fd, key_type_id, value_type_id) = bpf_load_local_storage_btf(u.value_size)?;
+            u.btf_fd = btf_fd.as_raw_fd() as u32;
+            u.btf_key_type_id = key_type_id;
+            u.btf_value_type_id = value_type_id;
+            _local_storage_btf_fd = Some(btf_fd);
+        }
+    }
 
     if let aya_obj::Map::Btf(m) = def {
         use bpf_map_type::*;
@@ -89,6 +118,13 @@ pub(crate) fn bpf_create_map(
         }
     }
 
+    // struct_ops maps are typed by the kernel BTF, the object BTF is only used
+    // by the kernel to look up the names of the programs.
+    if let aya_obj::Map::StructOps(m) = def {
+        u.btf_vmlinux_value_type_id = m.btf_vmlinux_value_type_id();
+        u.btf_fd = btf_fd.map(|fd| fd.as_raw_fd()).unwrap_or_default() as u32;
+    }
+
     // https://github.com/torvalds/linux/commit/ad5b177bd73f5107d97c36f56395c4281fb6f089
     // The map name was added as a parameter in kernel 4.15+ so we skip adding it on
     // older kernels for compatibility
@@ -126,11 +162,12 @@ pub(crate) struct EbpfLoadProgramAttrs<'a> {
     pub(crate) insns: &'a [bpf_insn],
     pub(crate) license: &'a CStr,
     pub(crate) kernel_version: u32,
-    pub(crate) expected_attach_type: Option<bpf_attach_type>,
+    pub(crate) expected_attach_type: Option<u32>,
     pub(crate) prog_btf_fd: Option<BorrowedFd<'a>>,
     pub(crate) attach_btf_obj_fd: Option<BorrowedFd<'a>>,
     pub(crate) attach_btf_id: Option<u32>,
     pub(crate) attach_prog_fd: Option<BorrowedFd<'a>>,
+    pub(crate) fd_array: Vec<BorrowedFd<'a>>,
     pub(crate) func_info_rec_size: usize,
     pub(crate) func_info: FuncSecInfo,
     pub(crate) line_info_rec_size: usize,
@@ -157,7 +194,7 @@ pub(crate) fn bpf_load_program(
     u.prog_flags = aya_attr.flags;
     u.prog_type = aya_attr.ty as u32;
     if let Some(v) = aya_attr.expected_attach_type {
-        u.expected_attach_type = v as u32;
+        u.expected_attach_type = v;
     }
     u.insns = aya_attr.insns.as_ptr() as u64;
     u.insn_cnt = aya_attr.insns.len() as u32;
@@ -197,6 +234,13 @@ pub(crate) fn bpf_load_program(
     if let Some(v) = aya_attr.attach_btf_id {
         u.attach_btf_id = v;
     }
+    // index 0 of fd_array is reserved, kfunc calls with an offset of 0 refer to vmlinux
+    let fd_array: Vec<RawFd> = iter::once(0)
+        .chain(aya_attr.fd_array.iter().map(|fd| fd.as_raw_fd()))
+        .collect();
+    if !aya_attr.fd_array.is_empty() {
+        u.fd_array = fd_array.as_ptr() as u64;
+    }
     bpf_prog_load(&mut attr)
 }
 
@@ -368,6 +412,134 @@ pub(crate) fn bpf_map_get_next_key<K: Pod>(
     }
 }
 
+#[expect(clippy::too_many_arguments)]
+fn map_batch(
+    cmd: bpf_cmd,
+    fd: BorrowedFd<'_>,
+    in_batch: Option<&[u8]>,
+    out_batch: Option<&mut [u8]>,
+    keys: u64,
+    values: u64,
+    count: &mut u32,
+    elem_flags: u64,
+) -> io::Result<()> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+
+    let u = unsafe { &mut attr.batch };
+    u.map_fd = fd.as_raw_fd() as u32;
+    if let Some(in_batch) = in_batch {
+        u.in_batch = in_batch.as_ptr() as u64;
+    }
+    if let Some(out_batch) = out_batch {
+        u.out_batch = out_batch.as_mut_ptr() as u64;
+    }
+    u.keys = keys;
+    u.values = values;
+    u.count = *count;
+    u.elem_flags = elem_flags;
+
+    let ret = unit_sys_bpf(cmd, &mut attr);
+    // The kernel updates the count even when the call fails, e.g. with ENOENT
+    // once the last batch has been read.
+    *count = unsafe { attr.batch.count };
+    ret
+}
+
+/// Reads up to `keys.len()` entries of the map, starting after the position
+/// stored in `in_batch` or at the beginning of the map if `None`.
+///
+/// `out_batch` receives the position to pass as `in_batch` to read the next
+/// entries, and `count` the number of entries read, even if an error is
+/// returned. Fails with `ENOENT` once all the entries have been read.
+// since kernel 5.6
+pub(crate) fn bpf_map_lookup_batch<K: Pod>(
+    fd: BorrowedFd<'_>,
+    in_batch: Option<&[u8]>,
+    out_batch: &mut [u8],
+    keys: &mut [MaybeUninit<K>],
+    values: &mut [u8],
+    count: &mut u32,
+    elem_flags: u64,
+) -> io::Result<()> {
+    *count = keys.len() as u32;
+    map_batch(
+        bpf_cmd::BPF_MAP_LOOKUP_BATCH,
+        fd,
+        in_batch,
+        Some(out_batch),
+        keys.as_mut_ptr() as u64,
+        values.as_mut_ptr() as u64,
+        count,
+        elem_flags,
+    )
+}
+
+/// Same as [`bpf_map_lookup_batch`], but also deletes the entries read.
+// since kernel 5.6
+pub(crate) fn bpf_map_lookup_and_delete_batch<K: Pod>(
+    fd: BorrowedFd<'_>,
+    in_batch: Option<&[u8]>,
+    out_batch: &mut [u8],
+    keys: &mut [MaybeUninit<K>],
+    values: &mut [u8],
+    count: &mut u32,
+    elem_flags: u64,
+) -> io::Result<()> {
+    *count = keys.len() as u32;
+    map_batch(
+        bpf_cmd::BPF_MAP_LOOKUP_AND_DELETE_BATCH,
+        fd,
+        in_batch,
+        Some(out_batch),
+        keys.as_mut_ptr() as u64,
+        values.as_mut_ptr() as u64,
+        count,
+        elem_flags,
+    )
+}
+
+/// Updates the entries of the map with the given keys to the values stored
+/// one after the other in `values`.
+// since kernel 5.6
+pub(crate) fn bpf_map_update_batch<K: Pod>(
+    fd: BorrowedFd<'_>,
+    keys: &[K],
+    values: &[u8],
+    elem_flags: u64,
+) -> io::Result<()> {
+    let mut count = keys.len() as u32;
+    map_batch(
+        bpf_cmd::BPF_MAP_UPDATE_BATCH,
+        fd,
+        None,
+        None,
+        keys.as_ptr() as u64,
+        values.as_ptr() as u64,
+        &mut count,
+        elem_flags,
+    )
+}
+
+/// Deletes the entries of the map with the given keys.
+// since kernel 5.6
+pub(crate) fn bpf_map_delete_batch<K: Pod>(
+    fd: BorrowedFd<'_>,
+    keys: &[K],
+    elem_flags: u64,
+) -> io::Result<()> {
+    let mut count = keys.len() as u32;
+    map_batch(
+        bpf_cmd::BPF_MAP_DELETE_BATCH,
+        fd,
+        None,
+        None,
+        keys.as_ptr() as u64,
+        0,
+        &mut count,
+        elem_flags,
+    )
+}
+
 // since kernel 5.2
 pub(crate) fn bpf_map_freeze(fd: BorrowedFd<'_>) -> io::Result<()> {
     let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
@@ -380,15 +552,46 @@ pub(crate) enum LinkTarget<'f> {
     Fd(BorrowedFd<'f>),
     IfIndex(u32),
     Iter,
+    // since kernel 5.18
+    KProbeMulti,
+    // since kernel 6.6
+    UProbeMulti,
+    // since kernel 6.4
+    Netfilter,
 }
 
 // Models https://github.com/torvalds/linux/blob/2144da25/include/uapi/linux/bpf.h#L1724-L1782.
 pub(crate) enum BpfLinkCreateArgs<'a> {
     TargetBtfId(u32),
     // since kernel 5.15
-    PerfEvent { bpf_cookie: u64 },
+    PerfEvent {
+        bpf_cookie: u64,
+    },
     // since kernel 6.6
     Tcx(&'a LinkRef),
+    // since kernel 6.7
+    Netkit(&'a LinkRef),
+    // since kernel 5.18
+    KProbeMulti {
+        flags: u32,
+        syms: &'a [*const c_char],
+        cookies: Option<&'a [u64]>,
+    },
+    // since kernel 6.6
+    UProbeMulti {
+        flags: u32,
+        path: &'a CStr,
+        offsets: &'a [u64],
+        cookies: Option<&'a [u64]>,
+        pid: u32,
+    },
+    // since kernel 6.4
+    Netfilter {
+        pf: u32,
+        hooknum: u32,
+        priority: i32,
+        flags: u32,
+    },
 }
 
 // since kernel 5.7
@@ -415,6 +618,9 @@ pub(crate) fn bpf_link_create(
         // iterators:
         // https://github.com/torvalds/linux/blob/v6.12/kernel/bpf/bpf_iter.c#L517-L518
         LinkTarget::Iter => {}
+        // The functions kprobe.multi and uprobe.multi links attach to, and the
+        // hooks netfilter links attach to, are passed in `args`.
+        LinkTarget::KProbeMulti | LinkTarget::UProbeMulti | LinkTarget::Netfilter => {}
     };
     attr.link_create.attach_type = attach_type as u32;
     attr.link_create.flags = flags;
@@ -443,6 +649,63 @@ pub(crate) fn bpf_link_create(
                         .relative_id = id.to_owned();
                 }
             },
+            BpfLinkCreateArgs::Netkit(link_ref) => match link_ref {
+                LinkRef::Fd(fd) => {
+                    attr.link_create
+                        .__bindgen_anon_3
+                        .netkit
+                        .__bindgen_anon_1
+                        .relative_fd = fd.to_owned() as u32;
+                }
+                LinkRef::Id(id) => {
+                    attr.link_create
+                        .__bindgen_anon_3
+                        .netkit
+                        .__bindgen_anon_1
+                        .relative_id = id.to_owned();
+                }
+            },
+            BpfLinkCreateArgs::KProbeMulti {
+                flags,
+                syms,
+                cookies,
+            } => {
+                attr.link_create.__bindgen_anon_3.kprobe_multi.flags = flags;
+                attr.link_create.__bindgen_anon_3.kprobe_multi.cnt = syms.len() as u32;
+                attr.link_create.__bindgen_anon_3.kprobe_multi.syms = syms.as_ptr() as u64;
+                if let Some(cookies) = cookies {
+                    attr.link_create.__bindgen_anon_3.kprobe_multi.cookies =
+                        cookies.as_ptr() as u64;
+                }
+            }
+            BpfLinkCreateArgs::UProbeMulti {
+                flags,
+                path,
+                offsets,
+                cookies,
+                pid,
+            } => {
+                attr.link_create.__bindgen_anon_3.uprobe_multi.flags = flags;
+                attr.link_create.__bindgen_anon_3.uprobe_multi.path = path.as_ptr() as u64;
+                attr.link_create.__bindgen_anon_3.uprobe_multi.cnt = offsets.len() as u32;
+                attr.link_create.__bindgen_anon_3.uprobe_multi.offsets = offsets.as_ptr() as u64;
+                if let Some(cookies) = cookies {
+                    attr.link_create.__bindgen_anon_3.uprobe_multi.cookies =
+                        cookies.as_ptr() as u64;
+                }
+                attr.link_create.__bindgen_anon_3.uprobe_multi.pid = pid;
+            }
+            BpfLinkCreateArgs::Netfilter {
+                pf,
+                hooknum,
+                priority,
+                flags,
+            } => {
+                attr.link_create.__bindgen_anon_3.netfilter.pf = pf;
+                attr.link_create.__bindgen_anon_3.netfilter.hooknum = hooknum;
+                attr.link_create.__bindgen_anon_3.netfilter.priority = priority;
+                attr.link_create.__bindgen_anon_3.netfilter.flags = flags;
+            }
         }
     }
 
@@ -450,6 +713,17 @@ pub(crate) fn bpf_link_create(
     unsafe { fd_sys_bpf(bpf_cmd::BPF_LINK_CREATE, &mut attr) }
 }
 
+// since kernel 6.4
+pub(crate) fn bpf_link_create_struct_ops(map_fd: BorrowedFd<'_>) -> io::Result<crate::MockableFd> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+
+    attr.link_create.__bindgen_anon_1.map_fd = map_fd.as_raw_fd() as u32;
+    attr.link_create.attach_type = bpf_attach_type::BPF_STRUCT_OPS as u32;
+
+    // SAFETY: BPF_LINK_CREATE returns a new file descriptor.
+    unsafe { fd_sys_bpf(bpf_cmd::BPF_LINK_CREATE, &mut attr) }
+}
+
 // since kernel 5.7
 pub(crate) fn bpf_link_update(
     link_fd: BorrowedFd<'_>,
@@ -639,10 +913,16 @@ pub(crate) fn bpf_link_get_info_by_fd(fd: BorrowedFd<'_>) -> Result<bpf_link_inf
 pub(crate) fn btf_obj_get_info_by_fd(
     fd: BorrowedFd<'_>,
     buf: &mut [u8],
+    name: &mut [u8],
 ) -> Result<bpf_btf_info, SyscallError> {
     bpf_obj_get_info_by_fd(fd, |info: &mut bpf_btf_info| {
         info.btf = buf.as_mut_ptr() as _;
         info.btf_size = buf.len() as _;
+        // the kernel rejects a name pointer without a length
+        if !name.is_empty() {
+            info.name = name.as_mut_ptr() as _;
+            info.name_len = name.len() as _;
+        }
     })
 }
 
@@ -662,6 +942,78 @@ pub(crate) fn bpf_raw_tracepoint_open(
     unsafe { fd_sys_bpf(bpf_cmd::BPF_RAW_TRACEPOINT_OPEN, &mut attr) }
 }
 
+/// Introduced in kernel v4.12.
+// The kernel reports the sizes of the outputs even when the run fails because
+// a buffer is too small, so they're returned along with the error.
+pub(crate) fn bpf_prog_test_run(
+    prog_fd: BorrowedFd<'_>,
+    opts: TestRunOptions<'_>,
+) -> Result<TestRunResult, (io::Error, TestRunResult)> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+
+    let TestRunOptions {
+        data_in,
+        data_out,
+        ctx_in,
+        ctx_out,
+        repeat,
+        cpu,
+        batch_size,
+        flags,
+    } = opts;
+    let u = unsafe { &mut attr.test };
+    u.prog_fd = prog_fd.as_raw_fd() as u32;
+    if let Some(data_in) = data_in {
+        u.data_in = data_in.as_ptr() as u64;
+        u.data_size_in = data_in.len() as u32;
+    }
+    if let Some(data_out) = data_out {
+        u.data_out = data_out.as_mut_ptr() as u64;
+        u.data_size_out = data_out.len() as u32;
+    }
+    if let Some(ctx_in) = ctx_in {
+        u.ctx_in = ctx_in.as_ptr() as u64;
+        u.ctx_size_in = ctx_in.len() as u32;
+    }
+    if let Some(ctx_out) = ctx_out {
+        u.ctx_out = ctx_out.as_mut_ptr() as u64;
+        u.ctx_size_out = ctx_out.len() as u32;
+    }
+    u.repeat = repeat;
+    u.cpu = cpu;
+    u.batch_size = batch_size;
+    u.flags = flags.bits();
+
+    let ret = unit_sys_bpf(bpf_cmd::BPF_PROG_TEST_RUN, &mut attr);
+
+    let u = unsafe { &attr.test };
+    let result = TestRunResult {
+        return_value: u.retval,
+        data_size_out: u.data_size_out as usize,
+        ctx_size_out: u.ctx_size_out as usize,
+        duration: Duration::from_nanos(u.duration.into()),
+    };
+    ret.map(|()| result).map_err(|io_error| (io_error, result))
+}
+
+pub(crate) fn bpf_prog_test_run_syscall<C: Pod>(
+    prog_fd: BorrowedFd<'_>,
+    ctx: &mut C,
+) -> io::Result<u32> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+
+    let u = unsafe { &mut attr.test };
+    u.prog_fd = prog_fd.as_raw_fd() as u32;
+    // Syscall programs only take a context, which the kernel copies back to
+    // `ctx_in` after the run.
+    u.ctx_in = ctx as *mut _ as u64;
+    u.ctx_size_in = mem::size_of::<C>() as u32;
+
+    unit_sys_bpf(bpf_cmd::BPF_PROG_TEST_RUN, &mut attr)?;
+
+    Ok(unsafe { attr.test.retval })
+}
+
 pub(crate) fn bpf_load_btf(
     raw_btf: &[u8],
     log_buf: &mut [u8],
@@ -829,19 +1181,19 @@ pub(crate) fn is_bpf_global_data_supported() -> bool {
     let u = unsafe { &mut attr.__bindgen_anon_3 };
 
     let map = MapData::create(
-        aya_obj::Map::Legacy(LegacyMap {
-            def: bpf_map_def {
+        aya_obj::Map::Legacy(LegacyMap::new(
+            bpf_map_def {
                 map_type: bpf_map_type::BPF_MAP_TYPE_ARRAY as u32,
                 key_size: 4,
                 value_size: 32,
                 max_entries: 1,
                 ..Default::default()
             },
-            section_index: 0,
-            section_kind: EbpfSectionKind::Maps,
-            symbol_index: None,
-            data: Vec::new(),
-        }),
+            0,
+            EbpfSectionKind::Maps,
+            None,
+            Vec::new(),
+        )),
         "aya_global",
         None,
     );
@@ -917,6 +1269,22 @@ pub(crate) fn is_prog_id_supported(map_type: bpf_map_type) -> bool {
     fd.is_ok()
 }
 
+/// Tests whether array maps can be created with `BPF_F_MMAPABLE`.
+pub(crate) fn is_array_mmap_supported() -> bool {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    let u = unsafe { &mut attr.__bindgen_anon_1 };
+
+    u.map_type = bpf_map_type::BPF_MAP_TYPE_ARRAY as u32;
+    u.key_size = 4;
+    u.value_size = 4;
+    u.max_entries = 1;
+    u.map_flags = BPF_F_MMAPABLE;
+
+    // SAFETY: BPF_MAP_CREATE returns a new file descriptor.
+    let fd = unsafe { fd_sys_bpf(bpf_cmd::BPF_MAP_CREATE, &mut attr) };
+    fd.is_ok()
+}
+
 pub(crate) fn is_btf_supported() -> bool {
     let mut btf = Btf::new();
     let name_offset = btf.add_string("int");
@@ -1073,6 +1441,199 @@ pub(crate) fn is_btf_type_tag_supported() -> bool {
     bpf_load_btf(btf_bytes.as_slice(), &mut [], Default::default()).is_ok()
 }
 
+/// Loads a minimal program of type `prog_type` to probe for kernel support.
+///
+/// The program calls `helper_id` if set, and the verifier log is written to
+/// `log_buf` if it's not empty. Program types that can't be loaded without an
+/// attach target are loaded with a bogus one, see [`crate::features`] for how
+/// the resulting errors are interpreted.
+pub(crate) fn bpf_probe_prog_load(
+    prog_type: bpf_prog_type,
+    expected_attach_type: Option<bpf_attach_type>,
+    helper_id: Option<u32>,
+    log_buf: &mut [u8],
+) -> io::Result<crate::MockableFd> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    let u = unsafe { &mut attr.__bindgen_anon_3 };
+
+    let call = (BPF_JMP | BPF_CALL) as _;
+    let mov64_imm = (BPF_ALU64 | BPF_MOV | BPF_K) as _;
+    let exit = (BPF_JMP | BPF_EXIT) as _;
+    let insns: Vec<_> = helper_id
+        .map(|helper_id| new_insn(call, 0, 0, 0, helper_id as i32))
+        .into_iter()
+        .chain([new_insn(mov64_imm, 0, 0, 0, 0), new_insn(exit, 0, 0, 0, 0)])
+        .collect();
+
+    let gpl = c"GPL";
+    u.license = gpl.as_ptr() as u64;
+
+    u.insn_cnt = insns.len() as u32;
+    u.insns = insns.as_ptr() as u64;
+    u.prog_type = prog_type as u32;
+
+    let default_attach_type = match prog_type {
+        bpf_prog_type::BPF_PROG_TYPE_KPROBE => {
+            u.kern_version = KernelVersion::current()
+                .map(KernelVersion::code)
+                .unwrap_or(0);
+            None
+        }
+        bpf_prog_type::BPF_PROG_TYPE_CGROUP_SOCK_ADDR => {
+            Some(bpf_attach_type::BPF_CGROUP_INET4_CONNECT)
+        }
+        bpf_prog_type::BPF_PROG_TYPE_CGROUP_SOCKOPT => Some(bpf_attach_type::BPF_CGROUP_GETSOCKOPT),
+        bpf_prog_type::BPF_PROG_TYPE_LIRC_MODE2 => Some(bpf_attach_type::BPF_LIRC_MODE2),
+        bpf_prog_type::BPF_PROG_TYPE_SK_LOOKUP => Some(bpf_attach_type::BPF_SK_LOOKUP),
+        bpf_prog_type::BPF_PROG_TYPE_NETFILTER => Some(bpf_attach_type::BPF_NETFILTER),
+        bpf_prog_type::BPF_PROG_TYPE_TRACING => {
+            u.attach_btf_id = 1;
+            Some(bpf_attach_type::BPF_TRACE_FENTRY)
+        }
+        bpf_prog_type::BPF_PROG_TYPE_LSM => {
+            u.attach_btf_id = 1;
+            Some(bpf_attach_type::BPF_LSM_MAC)
+        }
+        bpf_prog_type::BPF_PROG_TYPE_EXT => {
+            u.attach_btf_id = 1;
+            None
+        }
+        bpf_prog_type::BPF_PROG_TYPE_SYSCALL => {
+            u.prog_flags = BPF_F_SLEEPABLE;
+            None
+        }
+        _ => None,
+    };
+    if let Some(attach_type) = expected_attach_type.or(default_attach_type) {
+        u.expected_attach_type = attach_type as u32;
+    }
+
+    if !log_buf.is_empty() {
+        u.log_level = VerifierLogLevel::DEBUG.bits();
+        u.log_buf = log_buf.as_mut_ptr() as u64;
+        u.log_size = log_buf.len() as u32;
+    }
+
+    bpf_prog_load(&mut attr)
+}
+
+/// Loads the BTF of the keys and values of a local storage map.
+///
+/// The keys are file descriptors and the values are treated as arrays of
+/// `value_size` bytes. Returns the BTF file descriptor and the ids of the key
+/// and value types.
+fn bpf_load_local_storage_btf(value_size: u32) -> io::Result<(crate::MockableFd, u32, u32)> {
+    let (btf, key_type_id, value_type_id) = Btf::local_storage(value_size);
+    let btf_fd = bpf_load_btf(btf.to_bytes().as_slice(), &mut [], Default::default())?;
+    Ok((btf_fd, key_type_id, value_type_id))
+}
+
+/// Creates a minimal map of type `map_type` to probe for kernel support.
+pub(crate) fn bpf_probe_map_create(map_type: bpf_map_type) -> io::Result<crate::MockableFd> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    let u = unsafe { &mut attr.__bindgen_anon_1 };
+
+    u.map_type = map_type as u32;
+    u.key_size = 4;
+    u.value_size = 4;
+    u.max_entries = 1;
+
+    // Keep these alive until the map is created.
+    let mut _btf_fd = None;
+    let mut _inner_map_fd = None;
+    match map_type {
+        bpf_map_type::BPF_MAP_TYPE_LPM_TRIE => {
+            u.key_size = 8;
+            u.value_size = 8;
+            u.map_flags = BPF_F_NO_PREALLOC;
+        }
+        bpf_map_type::BPF_MAP_TYPE_STACK_TRACE => u.value_size = 8,
+        bpf_map_type::BPF_MAP_TYPE_QUEUE
+        | bpf_map_type::BPF_MAP_TYPE_STACK
+        | bpf_map_type::BPF_MAP_TYPE_BLOOM_FILTER => u.key_size = 0,
+        bpf_map_type::BPF_MAP_TYPE_RINGBUF | bpf_map_type::BPF_MAP_TYPE_USER_RINGBUF => {
+            u.key_size = 0;
+            u.value_size = 0;
+            u.max_entries = page_size() as u32;
+        }
+        bpf_map_type::BPF_MAP_TYPE_CGROUP_STORAGE
+        | bpf_map_type::BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE => {
+            // sizeof(struct bpf_cgroup_storage_key)
+            u.key_size = 12;
+            u.max_entries = 0;
+        }
+        bpf_map_type::BPF_MAP_TYPE_SK_STORAGE
+        | bpf_map_type::BPF_MAP_TYPE_INODE_STORAGE
+        | bpf_map_type::BPF_MAP_TYPE_TASK_STORAGE
+        | bpf_map_type::BPF_MAP_TYPE_CGRP_STORAGE => {
+            let (btf_fd, key_type_id, value_type_id) = bpf_load_local_storage_btf(u.value_size)?;
+            u.btf_fd = btf_fd.as_raw_fd() as u32;
+            u.btf_key_type_id = key_type_id;
+            u.btf_value_type_id = value_type_id;
+            u.map_flags = BPF_F_NO_PREALLOC;
+            u.max_entries = 0;
+            _btf_fd = Some(btf_fd);
+        }
+        bpf_map_type::BPF_MAP_TYPE_STRUCT_OPS => {
+            // This fails with ENOTSUPP if struct_ops maps are supported.
+            u.btf_vmlinux_value_type_id = 1;
+        }
+        bpf_map_type::BPF_MAP_TYPE_ARRAY_OF_MAPS | bpf_map_type::BPF_MAP_TYPE_HASH_OF_MAPS => {
+            let inner_map_fd = bpf_probe_map_create(bpf_map_type::BPF_MAP_TYPE_ARRAY)?;
+            u.inner_map_fd = inner_map_fd.as_raw_fd() as u32;
+            _inner_map_fd = Some(inner_map_fd);
+        }
+        bpf_map_type::BPF_MAP_TYPE_ARENA => {
+            u.key_size = 0;
+            u.value_size = 0;
+            u.map_flags = BPF_F_MMAPABLE;
+        }
+        _ => {}
+    }
+
+    // SAFETY: BPF_MAP_CREATE returns a new file descriptor.
+    unsafe { fd_sys_bpf(bpf_cmd::BPF_MAP_CREATE, &mut attr) }
+}
+
+/// Creates a link of type `attach_type` with a bogus target to probe for
+/// kernel support.
+///
+/// This never succeeds; callers must check the errno to tell whether the
+/// kernel recognized the link type.
+pub(crate) fn bpf_probe_link_create(
+    prog_fd: BorrowedFd<'_>,
+    attach_type: bpf_attach_type,
+) -> io::Result<crate::MockableFd> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+
+    attr.link_create.__bindgen_anon_1.prog_fd = prog_fd.as_raw_fd() as u32;
+    // Invalid both as a file descriptor and as an interface index.
+    attr.link_create.__bindgen_anon_2.target_fd = u32::MAX;
+    attr.link_create.attach_type = attach_type as u32;
+
+    let sym = c"__aya_probe_nonexistent_symbol";
+    let syms = [sym.as_ptr()];
+    let path = c"/";
+    let offsets = [0u64];
+    match attach_type {
+        bpf_attach_type::BPF_TRACE_KPROBE_MULTI => {
+            let u = unsafe { &mut attr.link_create.__bindgen_anon_3.kprobe_multi };
+            u.syms = syms.as_ptr() as u64;
+            u.cnt = syms.len() as u32;
+        }
+        bpf_attach_type::BPF_TRACE_UPROBE_MULTI => {
+            let u = unsafe { &mut attr.link_create.__bindgen_anon_3.uprobe_multi };
+            u.path = path.as_ptr() as u64;
+            u.offsets = offsets.as_ptr() as u64;
+            u.cnt = offsets.len() as u32;
+        }
+        _ => {}
+    }
+
+    // SAFETY: BPF_LINK_CREATE returns a new file descriptor.
+    unsafe { fd_sys_bpf(bpf_cmd::BPF_LINK_CREATE, &mut attr) }
+}
+
 fn bpf_prog_load(attr: &mut bpf_attr) -> io::Result<crate::MockableFd> {
     // SAFETY: BPF_PROG_LOAD returns a new file descriptor.
     unsafe { fd_sys_bpf(bpf_cmd::BPF_PROG_LOAD, attr) }
@@ -1144,6 +1705,11 @@ pub(crate) fn iter_map_ids() -> impl Iterator<Item = Result<u32, SyscallError>>
     iter_obj_ids(bpf_cmd::BPF_MAP_GET_NEXT_ID, "bpf_map_get_next_id")
 }
 
+/// Introduced in kernel v5.4.
+pub(crate) fn iter_btf_ids() -> impl Iterator<Item = Result<u32, SyscallError>> {
+    iter_obj_ids(bpf_cmd::BPF_BTF_GET_NEXT_ID, "bpf_btf_get_next_id")
+}
+
 /// Introduced in kernel v5.8.
 pub(crate) fn bpf_enable_stats(
     stats_type: bpf_stats_type,
@@ -1193,10 +1759,11 @@ pub(crate) fn retry_with_verifier_logs<T>(
 
 #[cfg(test)]
 mod tests {
+    use aya_obj::generated::BPF_F_TEST_XDP_LIVE_FRAMES;
     use libc::{EBADF, EINVAL};
 
     use super::*;
-    use crate::sys::override_syscall;
+    use crate::{programs::TestRunFlags, sys::override_syscall};
 
     #[test]
     fn test_attach_with_attributes() {
@@ -1241,6 +1808,50 @@ mod tests {
         result.unwrap();
     }
 
+    #[test]
+    fn test_prog_test_run() {
+        const FAKE_FD: i32 = 4321;
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_TEST_RUN,
+                attr,
+            } => {
+                let u = unsafe { &mut attr.test };
+                assert_eq!(u.prog_fd, FAKE_FD as u32);
+                assert_eq!(u.data_size_in, 3);
+                assert_eq!(u.data_size_out, 8);
+                assert_eq!(u.ctx_in, 0);
+                assert_eq!(u.ctx_size_in, 0);
+                assert_eq!(u.repeat, 10);
+                assert_eq!(u.flags, BPF_F_TEST_XDP_LIVE_FRAMES);
+                u.retval = 2;
+                u.data_size_out = 3;
+                u.duration = 42;
+                Ok(0)
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EINVAL))),
+        });
+
+        let prog_fd = unsafe { BorrowedFd::borrow_raw(FAKE_FD) };
+        let mut data_out = [0; 8];
+        let result = bpf_prog_test_run(
+            prog_fd,
+            TestRunOptions {
+                data_in: Some(&[1, 2, 3]),
+                data_out: Some(&mut data_out),
+                repeat: 10,
+                flags: TestRunFlags::XDP_LIVE_FRAMES,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(result.return_value, 2);
+        assert_eq!(result.data_size_out, 3);
+        assert_eq!(result.ctx_size_out, 0);
+        assert_eq!(result.duration, Duration::from_nanos(42));
+    }
+
     #[test]
     fn test_perf_link_supported() {
         override_syscall(|call| match call {
@@ -1281,6 +1892,26 @@ mod tests {
         assert!(!supported);
     }
 
+    #[test]
+    fn test_array_mmap_supported() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_CREATE,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_1 };
+                assert_eq!(u.map_type, bpf_map_type::BPF_MAP_TYPE_ARRAY as u32);
+                assert_eq!(u.map_flags, BPF_F_MMAPABLE);
+                Ok(crate::MockableFd::mock_signed_fd().into())
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EINVAL))),
+        });
+        assert!(is_array_mmap_supported());
+
+        override_syscall(|_call| Err((-1, io::Error::from_raw_os_error(EINVAL))));
+        assert!(!is_array_mmap_supported());
+    }
+
     #[test]
     #[should_panic = "assertion failed: `BPF_MAP_TYPE_HASH` does not match `bpf_map_type::BPF_MAP_TYPE_CPUMAP | bpf_map_type::BPF_MAP_TYPE_DEVMAP |
 bpf_map_type::BPF_MAP_TYPE_DEVMAP_HASH`"]
@@ -17,9 +17,9 @@ use aya_obj::generated::{bpf_attr, bpf_cmd, perf_event_attr};
 pub(crate) use bpf::*;
 #[cfg(test)]
 pub(crate) use fake::*;
-#[doc(hidden)]
-pub use netlink::netlink_set_link_up;
 pub(crate) use netlink::*;
+#[doc(hidden)]
+pub use netlink::{netlink_add_netkit, netlink_set_link_up};
 pub(crate) use perf_event::*;
 use thiserror::Error;
 
@@ -12,11 +12,12 @@ use aya_obj::generated::{
     TCA_KIND, TCA_OPTIONS, XDP_FLAGS_REPLACE, ifinfomsg, nlmsgerr_attrs::NLMSGERR_ATTR_MSG, tcmsg,
 };
 use libc::{
-    AF_NETLINK, AF_UNSPEC, ETH_P_ALL, IFF_UP, IFLA_XDP, NETLINK_CAP_ACK, NETLINK_EXT_ACK,
-    NETLINK_ROUTE, NLA_ALIGNTO, NLA_F_NESTED, NLA_TYPE_MASK, NLM_F_ACK, NLM_F_CREATE, NLM_F_DUMP,
-    NLM_F_ECHO, NLM_F_EXCL, NLM_F_MULTI, NLM_F_REQUEST, NLMSG_DONE, NLMSG_ERROR, RTM_DELTFILTER,
-    RTM_GETTFILTER, RTM_NEWQDISC, RTM_NEWTFILTER, RTM_SETLINK, SOCK_RAW, SOL_NETLINK, getsockname,
-    nlattr, nlmsgerr, nlmsghdr, recv, send, setsockopt, sockaddr_nl, socket,
+    AF_NETLINK, AF_UNSPEC, ETH_P_ALL, IFF_UP, IFLA_IFNAME, IFLA_INFO_KIND, IFLA_LINKINFO, IFLA_XDP,
+    NETLINK_CAP_ACK, NETLINK_EXT_ACK, NETLINK_ROUTE, NLA_ALIGNTO, NLA_F_NESTED, NLA_TYPE_MASK,
+    NLM_F_ACK, NLM_F_CREATE, NLM_F_DUMP, NLM_F_ECHO, NLM_F_EXCL, NLM_F_MULTI, NLM_F_REQUEST,
+    NLMSG_DONE, NLMSG_ERROR, RTM_DELTFILTER, RTM_GETTFILTER, RTM_NEWLINK, RTM_NEWQDISC,
+    RTM_NEWTFILTER, RTM_SETLINK, SOCK_RAW, SOL_NETLINK, getsockname, nlattr, nlmsgerr, nlmsghdr,
+    recv, send, setsockopt, sockaddr_nl, socket,
 };
 use thiserror::Error;
 
@@ -330,6 +331,43 @@ pub unsafe fn netlink_set_link_up(if_index: i32) -> Result<(), NetlinkError> {
     Ok(())
 }
 
+#[doc(hidden)]
+pub unsafe fn netlink_add_netkit(name: &CStr) -> Result<(), NetlinkError> {
+    let sock = NetlinkSocket::open()?;
+
+    // Safety: Request is POD so this is safe
+    let mut req = unsafe { mem::zeroed::<Request>() };
+
+    let nlmsg_len = mem::size_of::<nlmsghdr>() + mem::size_of::<ifinfomsg>();
+    req.header = nlmsghdr {
+        nlmsg_len: nlmsg_len as u32,
+        nlmsg_flags: (NLM_F_REQUEST | NLM_F_ACK | NLM_F_EXCL | NLM_F_CREATE) as u16,
+        nlmsg_type: RTM_NEWLINK,
+        nlmsg_pid: 0,
+        nlmsg_seq: 1,
+    };
+    req.if_info.ifi_family = AF_UNSPEC as u8;
+
+    // add the IFLA_IFNAME attribute, and the IFLA_LINKINFO attribute with the
+    // kind of the link. The kernel names the peer device.
+    let attrs_buf = unsafe { request_attributes(&mut req, nlmsg_len) };
+    let name_len = write_attr_bytes(attrs_buf, 0, IFLA_IFNAME, name.to_bytes_with_nul())
+        .map_err(|e| NetlinkError(NetlinkErrorInternal::IoError(e)))?;
+    let mut link_info = NestedAttrs::new(&mut attrs_buf[name_len..], IFLA_LINKINFO);
+    link_info
+        .write_attr_bytes(IFLA_INFO_KIND, b"netkit\0")
+        .map_err(|e| NetlinkError(NetlinkErrorInternal::IoError(e)))?;
+    let link_info_len = link_info
+        .finish()
+        .map_err(|e| NetlinkError(NetlinkErrorInternal::IoError(e)))?;
+    req.header.nlmsg_len += (name_len + align_to(link_info_len, NLA_ALIGNTO as usize)) as u32;
+
+    sock.send(&bytes_of(&req)[..req.header.nlmsg_len as usize])?;
+    sock.recv()?;
+
+    Ok(())
+}
+
 #[repr(C)]
 struct Request {
     header: nlmsghdr,
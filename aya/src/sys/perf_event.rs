@@ -59,12 +59,17 @@ pub(crate) fn perf_event_open_bpf(cpu: c_int) -> io::Result<crate::MockableFd> {
     )
 }
 
+// The position of the reference counter offset in the config of uprobe perf
+// events, see PERF_UPROBE_REF_CTR_OFFSET_SHIFT in kernel/events/core.c.
+const PERF_UPROBE_REF_CTR_OFFSET_SHIFT: u64 = 32;
+
 pub(crate) fn perf_event_open_probe(
     ty: u32,
     ret_bit: Option<u32>,
     name: &OsStr,
     offset: u64,
     pid: Option<pid_t>,
+    ref_ctr_offset: Option<u64>,
 ) -> io::Result<crate::MockableFd> {
     use std::os::unix::ffi::OsStrExt as _;
 
@@ -73,6 +78,10 @@ pub(crate) fn perf_event_open_probe(
     if let Some(ret_bit) = ret_bit {
         attr.config = 1 << ret_bit;
     }
+    // since kernel 4.20
+    if let Some(ref_ctr_offset) = ref_ctr_offset {
+        attr.config |= ref_ctr_offset << PERF_UPROBE_REF_CTR_OFFSET_SHIFT;
+    }
 
     let c_name = CString::new(name.as_bytes()).unwrap();
 
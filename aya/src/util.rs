@@ -5,18 +5,29 @@ use std::{
     ffi::{CStr, CString},
     fmt::Display,
     fs::{self, File},
-    io::{self, BufRead, BufReader},
+    io::{self, BufRead, BufReader, Read as _},
     mem,
     num::ParseIntError,
+    os::fd::AsFd as _,
+    path::PathBuf,
     slice,
     str::{FromStr, Utf8Error},
+    sync::Arc,
 };
 
-use aya_obj::generated::{TC_H_MAJ_MASK, TC_H_MIN_MASK};
-use libc::{_SC_PAGESIZE, if_nametoindex, sysconf, uname, utsname};
+use aya_obj::{
+    btf::Btf,
+    generated::{TC_H_MAJ_MASK, TC_H_MIN_MASK},
+};
+use flate2::read::GzDecoder;
+use libc::{_SC_PAGESIZE, ENOENT, if_nametoindex, sysconf, uname, utsname};
 use log::warn;
+use object::Endianness;
 
-use crate::Pod;
+use crate::{
+    Pod,
+    sys::{SyscallError, bpf_btf_get_fd_by_id, btf_obj_get_info_by_fd, iter_btf_ids},
+};
 
 /// Represents a kernel version, in major.minor.release version.
 // Adapted from https://docs.rs/procfs/latest/procfs/sys/kernel/struct.Version.html.
@@ -278,6 +289,103 @@ fn parse_cpu_ranges(data: &str) -> Result<Vec<u32>, io::Error> {
         })
 }
 
+// Reads the config of the running kernel from `/proc/config.gz`, falling back to
+// `/boot/config-$(uname -r)` like libbpf does.
+pub(crate) fn kernel_config() -> Result<String, (PathBuf, io::Error)> {
+    const PROC_CONFIG: &str = "/proc/config.gz";
+
+    let mut config = String::new();
+    match File::open(PROC_CONFIG) {
+        Ok(file) => {
+            GzDecoder::new(file)
+                .read_to_string(&mut config)
+                .map_err(|error| (PathBuf::from(PROC_CONFIG), error))?;
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            let mut info = unsafe { mem::zeroed::<utsname>() };
+            if unsafe { uname(&mut info) } != 0 {
+                return Err((PathBuf::from(PROC_CONFIG), io::Error::last_os_error()));
+            }
+            // Safety: man 2 uname:
+            //
+            // The length of the arrays in a struct utsname is unspecified (see NOTES); the fields are
+            // terminated by a null byte ('\0').
+            let release = unsafe { CStr::from_ptr(info.release.as_ptr()) };
+            let path = PathBuf::from(format!("/boot/config-{}", release.to_string_lossy()));
+            config = fs::read_to_string(&path).map_err(|error| (path, error))?;
+        }
+        Err(error) => return Err((PathBuf::from(PROC_CONFIG), error)),
+    }
+    Ok(config)
+}
+
+// The BTF of a kernel module, as loaded in the kernel.
+pub(crate) struct ModuleBtf {
+    pub(crate) name: String,
+    pub(crate) fd: crate::MockableFd,
+    pub(crate) btf: Btf,
+}
+
+// Returns the names and the fds of the BTF objects of the loaded kernel modules.
+fn kernel_module_btf_fds() -> Result<Vec<(String, crate::MockableFd)>, SyscallError> {
+    let mut modules = Vec::new();
+    for id in iter_btf_ids() {
+        let fd = match bpf_btf_get_fd_by_id(id?) {
+            Ok(fd) => fd,
+            // the module was unloaded in the meantime
+            Err(SyscallError { io_error, .. }) if io_error.raw_os_error() == Some(ENOENT) => {
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        // MODULE_NAME_LEN
+        let mut name = [0u8; 64];
+        let info = btf_obj_get_info_by_fd(fd.as_fd(), &mut [], &mut name)?;
+        let Ok(name) = CStr::from_bytes_until_nul(&name) else {
+            continue;
+        };
+        let name = name.to_string_lossy();
+        // skip program BTF and vmlinux
+        if info.kernel_btf == 0 || name == "vmlinux" {
+            continue;
+        }
+        modules.push((name.into_owned(), fd));
+    }
+    Ok(modules)
+}
+
+// Returns the fd of the BTF object of the kernel module `module`, if it's loaded.
+pub(crate) fn kernel_module_btf_fd(
+    module: &str,
+) -> Result<Option<crate::MockableFd>, SyscallError> {
+    Ok(kernel_module_btf_fds()?
+        .into_iter()
+        .find_map(|(name, fd)| (name == module).then_some(fd)))
+}
+
+// Returns the BTF objects of the loaded kernel modules, parsed on top of `vmlinux`.
+pub(crate) fn kernel_module_btfs(vmlinux: &Arc<Btf>) -> Result<Vec<ModuleBtf>, SyscallError> {
+    let mut modules = Vec::new();
+    for (name, fd) in kernel_module_btf_fds()? {
+        let mut data = Vec::new();
+        loop {
+            let info = btf_obj_get_info_by_fd(fd.as_fd(), &mut data, &mut [])?;
+            let btf_size = info.btf_size as usize;
+            if btf_size > data.len() {
+                data.resize(btf_size, 0);
+                continue;
+            }
+            break;
+        }
+        match Btf::parse_split(&data, Arc::clone(vmlinux), Endianness::default()) {
+            Ok(btf) => modules.push(ModuleBtf { name, fd, btf }),
+            Err(err) => warn!("failed to parse BTF of module {name}: {err}"),
+        }
+    }
+    Ok(modules)
+}
+
 /// Loads kernel symbols from `/proc/kallsyms`.
 ///
 /// See [`crate::maps::StackTraceMap`] for an example on how to use this to resolve kernel addresses to symbols.
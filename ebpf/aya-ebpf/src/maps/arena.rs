@@ -0,0 +1,163 @@
+use core::{
+    cell::UnsafeCell,
+    ffi::c_void,
+    ptr::{self, NonNull},
+};
+
+use aya_ebpf_cty::c_int;
+
+use crate::{
+    bindings::{BPF_F_MMAPABLE, bpf_map_def, bpf_map_type::BPF_MAP_TYPE_ARENA},
+    maps::PinningType,
+};
+
+/// Lets the kernel pick the NUMA node of the pages allocated with
+/// [`Arena::alloc_pages`].
+pub const NUMA_NO_NODE: c_int = -1;
+
+unsafe extern "C" {
+    fn bpf_arena_alloc_pages(
+        map: *mut c_void,
+        addr: *mut c_void,
+        page_cnt: u32,
+        node_id: c_int,
+        flags: u64,
+    ) -> *mut c_void;
+
+    fn bpf_arena_free_pages(map: *mut c_void, ptr: *mut c_void, page_cnt: u32);
+}
+
+/// Arenas are followed by the start address of their user space mapping.
+#[repr(C)]
+struct ArenaDef {
+    def: bpf_map_def,
+    map_extra: u64,
+}
+
+/// A memory region shared between eBPF programs and user space.
+///
+/// Pages are allocated with [`Arena::alloc_pages`], which is backed by the
+/// `bpf_arena_alloc_pages` kfunc. Kfuncs are resolved through the kernel BTF,
+/// so the program must be linked with `-C link-arg=--btf`. The allocation
+/// kfuncs can sleep, so they can only be called from sleepable programs.
+///
+/// The pointers returned by [`Arena::alloc_pages`] are kernel addresses. They
+/// must be converted with [`cast_user`] before being stored in the arena for
+/// user space, and pointers read from the arena must be converted with
+/// [`cast_kern`] before being dereferenced.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 6.9.
+#[repr(transparent)]
+pub struct Arena {
+    def: UnsafeCell<ArenaDef>,
+}
+
+unsafe impl Sync for Arena {}
+
+impl Arena {
+    /// Creates an arena of `max_pages` pages, mapped at `map_extra` in user
+    /// space, or at an address picked by the kernel if it's zero. In that case
+    /// user space must map the arena before loading the programs using it.
+    pub const fn new(max_pages: u32, map_extra: u64, flags: u32) -> Arena {
+        Arena::new_with_pinning(max_pages, map_extra, flags, PinningType::None)
+    }
+
+    pub const fn pinned(max_pages: u32, map_extra: u64, flags: u32) -> Arena {
+        Arena::new_with_pinning(max_pages, map_extra, flags, PinningType::ByName)
+    }
+
+    const fn new_with_pinning(
+        max_pages: u32,
+        map_extra: u64,
+        flags: u32,
+        pinning: PinningType,
+    ) -> Arena {
+        Arena {
+            def: UnsafeCell::new(ArenaDef {
+                def: bpf_map_def {
+                    type_: BPF_MAP_TYPE_ARENA,
+                    key_size: 0,
+                    value_size: 0,
+                    max_entries: max_pages,
+                    map_flags: flags | BPF_F_MMAPABLE,
+                    id: 0,
+                    pinning: pinning as u32,
+                },
+                map_extra,
+            }),
+        }
+    }
+
+    /// Allocates `page_cnt` contiguous pages, at `addr` if it's given.
+    ///
+    /// Returns the kernel address of the first page, or `None` if the pages
+    /// couldn't be allocated.
+    #[inline(always)]
+    pub fn alloc_pages(
+        &self,
+        addr: Option<NonNull<c_void>>,
+        page_cnt: u32,
+        node_id: c_int,
+        flags: u64,
+    ) -> Option<NonNull<c_void>> {
+        let addr = addr.map_or(ptr::null_mut(), NonNull::as_ptr);
+        let ptr =
+            unsafe { bpf_arena_alloc_pages(self.def.get().cast(), addr, page_cnt, node_id, flags) };
+        NonNull::new(cast_kern(ptr))
+    }
+
+    /// Frees `page_cnt` pages starting at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// The pages must not be accessed after they're freed, by eBPF programs or
+    /// by user space.
+    #[inline(always)]
+    pub unsafe fn free_pages(&self, ptr: *mut c_void, page_cnt: u32) {
+        unsafe { bpf_arena_free_pages(self.def.get().cast(), ptr, page_cnt) }
+    }
+}
+
+/// Converts a user space address in the arena to a kernel address that can be
+/// dereferenced by the program.
+#[inline(always)]
+pub fn cast_kern<T>(ptr: *mut T) -> *mut T {
+    #[cfg(target_arch = "bpf")]
+    unsafe {
+        let mut ptr = ptr;
+        core::arch::asm!(
+            "{ptr} = addr_space_cast({ptr}, 0, 1)",
+            ptr = inout(reg) ptr,
+        );
+        ptr
+    }
+    // We only need this for doc tests which are compiled for the host target
+    #[cfg(not(target_arch = "bpf"))]
+    {
+        let _ = ptr;
+        unimplemented!()
+    }
+}
+
+/// Converts a kernel address in the arena to the user space address it's
+/// mapped at, so that it can be followed by user space.
+#[inline(always)]
+pub fn cast_user<T>(ptr: *mut T) -> *mut T {
+    #[cfg(target_arch = "bpf")]
+    unsafe {
+        let mut ptr = ptr;
+        core::arch::asm!(
+            "{ptr} = addr_space_cast({ptr}, 1, 0)",
+            ptr = inout(reg) ptr,
+        );
+        ptr
+    }
+    // We only need this for doc tests which are compiled for the host target
+    #[cfg(not(target_arch = "bpf"))]
+    {
+        let _ = ptr;
+        unimplemented!()
+    }
+}
@@ -3,9 +3,9 @@ use core::{cell::UnsafeCell, marker::PhantomData, mem, ptr::NonNull};
 use aya_ebpf_cty::c_long;
 
 use crate::{
-    bindings::{bpf_map_def, bpf_map_type::BPF_MAP_TYPE_ARRAY},
+    bindings::{BPF_F_MMAPABLE, bpf_map_def, bpf_map_type::BPF_MAP_TYPE_ARRAY},
     insert, lookup,
-    maps::PinningType,
+    maps::{InnerMap, PinningType},
 };
 
 #[repr(transparent)]
@@ -16,6 +16,8 @@ pub struct Array<T> {
 
 unsafe impl<T: Sync> Sync for Array<T> {}
 
+unsafe impl<T> InnerMap for Array<T> {}
+
 impl<T> Array<T> {
     pub const fn with_max_entries(max_entries: u32, flags: u32) -> Array<T> {
         Array {
@@ -32,6 +34,12 @@ impl<T> Array<T> {
         }
     }
 
+    /// Creates an array with the `BPF_F_MMAPABLE` flag, which user space can
+    /// map into its memory to access the elements without syscalls.
+    pub const fn mmapable(max_entries: u32, flags: u32) -> Array<T> {
+        Self::with_max_entries(max_entries, flags | BPF_F_MMAPABLE)
+    }
+
     pub const fn pinned(max_entries: u32, flags: u32) -> Array<T> {
         Array {
             def: UnsafeCell::new(bpf_map_def {
@@ -0,0 +1,73 @@
+use core::{cell::UnsafeCell, mem};
+
+use crate::{
+    bindings::{bpf_map_def, bpf_map_type::BPF_MAP_TYPE_ARRAY_OF_MAPS},
+    lookup,
+    maps::{InnerMap, PinningType},
+};
+
+/// An array of maps.
+///
+/// The inner map passed to the constructor is used as a template: user space
+/// can store any map with the same layout in the array. Looking up an index
+/// returns a handle to the stored map, which can be used like any other map.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya_ebpf::{macros::map, maps::{Array, ArrayOfMaps}};
+///
+/// #[map]
+/// static TENANTS: ArrayOfMaps<Array<u64>> =
+///     ArrayOfMaps::with_max_entries(16, 0, Array::with_max_entries(1, 0));
+///
+/// # fn count(tenant: u32) -> Option<()> {
+/// let counters = TENANTS.get(tenant)?;
+/// let counter = counters.get_ptr_mut(0)?;
+/// unsafe { *counter += 1 };
+/// # Some(())
+/// # }
+/// ```
+#[repr(C)]
+pub struct ArrayOfMaps<T> {
+    def: UnsafeCell<bpf_map_def>,
+    // The loader reads the definition of the inner map template right after
+    // the definition of the outer map.
+    _inner: T,
+}
+
+unsafe impl<T: InnerMap> Sync for ArrayOfMaps<T> {}
+
+impl<T: InnerMap> ArrayOfMaps<T> {
+    pub const fn with_max_entries(max_entries: u32, flags: u32, inner: T) -> ArrayOfMaps<T> {
+        ArrayOfMaps {
+            def: UnsafeCell::new(build_def(max_entries, flags, PinningType::None)),
+            _inner: inner,
+        }
+    }
+
+    pub const fn pinned(max_entries: u32, flags: u32, inner: T) -> ArrayOfMaps<T> {
+        ArrayOfMaps {
+            def: UnsafeCell::new(build_def(max_entries, flags, PinningType::ByName)),
+            _inner: inner,
+        }
+    }
+
+    /// Returns the map stored at the given index.
+    #[inline(always)]
+    pub fn get(&self, index: u32) -> Option<&T> {
+        unsafe { lookup(self.def.get(), &index).map(|p| p.as_ref()) }
+    }
+}
+
+const fn build_def(max_entries: u32, flags: u32, pin: PinningType) -> bpf_map_def {
+    bpf_map_def {
+        type_: BPF_MAP_TYPE_ARRAY_OF_MAPS,
+        key_size: mem::size_of::<u32>() as u32,
+        value_size: mem::size_of::<u32>() as u32,
+        max_entries,
+        map_flags: flags,
+        id: 0,
+        pinning: pin as u32,
+    }
+}
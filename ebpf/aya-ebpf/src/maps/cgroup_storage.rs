@@ -0,0 +1,110 @@
+use core::{cell::UnsafeCell, marker::PhantomData, mem};
+
+use crate::{
+    bindings::{
+        bpf_cgroup_storage_key, bpf_map_def,
+        bpf_map_type::{BPF_MAP_TYPE_CGROUP_STORAGE, BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE},
+    },
+    helpers::bpf_get_local_storage,
+    maps::PinningType,
+};
+
+/// Storage shared by the programs attached to a cgroup.
+///
+/// The kernel creates a value for each cgroup and attach type the programs
+/// using the map are attached with. A program can only access the value of the
+/// cgroup it runs for.
+#[repr(transparent)]
+pub struct CgroupStorage<T> {
+    def: UnsafeCell<bpf_map_def>,
+    _t: PhantomData<T>,
+}
+
+unsafe impl<T> Sync for CgroupStorage<T> {}
+
+impl<T> CgroupStorage<T> {
+    pub const fn new(flags: u32) -> CgroupStorage<T> {
+        CgroupStorage {
+            def: UnsafeCell::new(build_def::<T>(
+                BPF_MAP_TYPE_CGROUP_STORAGE,
+                flags,
+                PinningType::None,
+            )),
+            _t: PhantomData,
+        }
+    }
+
+    pub const fn pinned(flags: u32) -> CgroupStorage<T> {
+        CgroupStorage {
+            def: UnsafeCell::new(build_def::<T>(
+                BPF_MAP_TYPE_CGROUP_STORAGE,
+                flags,
+                PinningType::ByName,
+            )),
+            _t: PhantomData,
+        }
+    }
+
+    /// Returns a pointer to the value of the cgroup the program runs for.
+    ///
+    /// The value is shared by all the CPUs, so concurrent updates must be
+    /// atomic.
+    #[inline(always)]
+    pub fn get_ptr_mut(&self) -> *mut T {
+        unsafe { bpf_get_local_storage(self.def.get().cast(), 0).cast() }
+    }
+}
+
+/// Similar to [`CgroupStorage`] but each CPU holds a separate value for a
+/// given cgroup.
+#[repr(transparent)]
+pub struct PerCpuCgroupStorage<T> {
+    def: UnsafeCell<bpf_map_def>,
+    _t: PhantomData<T>,
+}
+
+unsafe impl<T> Sync for PerCpuCgroupStorage<T> {}
+
+impl<T> PerCpuCgroupStorage<T> {
+    pub const fn new(flags: u32) -> PerCpuCgroupStorage<T> {
+        PerCpuCgroupStorage {
+            def: UnsafeCell::new(build_def::<T>(
+                BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE,
+                flags,
+                PinningType::None,
+            )),
+            _t: PhantomData,
+        }
+    }
+
+    pub const fn pinned(flags: u32) -> PerCpuCgroupStorage<T> {
+        PerCpuCgroupStorage {
+            def: UnsafeCell::new(build_def::<T>(
+                BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE,
+                flags,
+                PinningType::ByName,
+            )),
+            _t: PhantomData,
+        }
+    }
+
+    /// Returns a pointer to the value of the cgroup the program runs for, on
+    /// the current CPU.
+    #[inline(always)]
+    pub fn get_ptr_mut(&self) -> *mut T {
+        unsafe { bpf_get_local_storage(self.def.get().cast(), 0).cast() }
+    }
+}
+
+const fn build_def<T>(ty: u32, flags: u32, pin: PinningType) -> bpf_map_def {
+    bpf_map_def {
+        type_: ty,
+        key_size: mem::size_of::<bpf_cgroup_storage_key>() as u32,
+        value_size: mem::size_of::<T>() as u32,
+        // The kernel sizes the map from the number of cgroups programs are attached to.
+        max_entries: 0,
+        map_flags: flags,
+        id: 0,
+        pinning: pin as u32,
+    }
+}
@@ -8,7 +8,7 @@ use aya_ebpf_cty::c_long;
 use crate::{
     bindings::{bpf_map_def, bpf_map_type::BPF_MAP_TYPE_HASH},
     insert, lookup,
-    maps::PinningType,
+    maps::{InnerMap, PinningType},
     remove,
 };
 
@@ -21,6 +21,8 @@ pub struct HashMap<K, V> {
 
 unsafe impl<K: Sync, V: Sync> Sync for HashMap<K, V> {}
 
+unsafe impl<K, V> InnerMap for HashMap<K, V> {}
+
 impl<K, V> HashMap<K, V> {
     pub const fn with_max_entries(max_entries: u32, flags: u32) -> HashMap<K, V> {
         HashMap {
@@ -97,6 +99,8 @@ pub struct LruHashMap<K, V> {
 
 unsafe impl<K: Sync, V: Sync> Sync for LruHashMap<K, V> {}
 
+unsafe impl<K, V> InnerMap for LruHashMap<K, V> {}
+
 impl<K, V> LruHashMap<K, V> {
     pub const fn with_max_entries(max_entries: u32, flags: u32) -> LruHashMap<K, V> {
         LruHashMap {
@@ -173,6 +177,8 @@ pub struct PerCpuHashMap<K, V> {
 
 unsafe impl<K, V> Sync for PerCpuHashMap<K, V> {}
 
+unsafe impl<K, V> InnerMap for PerCpuHashMap<K, V> {}
+
 impl<K, V> PerCpuHashMap<K, V> {
     pub const fn with_max_entries(max_entries: u32, flags: u32) -> PerCpuHashMap<K, V> {
         PerCpuHashMap {
@@ -249,6 +255,8 @@ pub struct LruPerCpuHashMap<K, V> {
 
 unsafe impl<K, V> Sync for LruPerCpuHashMap<K, V> {}
 
+unsafe impl<K, V> InnerMap for LruPerCpuHashMap<K, V> {}
+
 impl<K, V> LruPerCpuHashMap<K, V> {
     pub const fn with_max_entries(max_entries: u32, flags: u32) -> LruPerCpuHashMap<K, V> {
         LruPerCpuHashMap {
@@ -0,0 +1,76 @@
+use core::{cell::UnsafeCell, marker::PhantomData, mem};
+
+use crate::{
+    bindings::{bpf_map_def, bpf_map_type::BPF_MAP_TYPE_HASH_OF_MAPS},
+    lookup,
+    maps::{InnerMap, PinningType},
+};
+
+/// A hash map of maps.
+///
+/// The inner map passed to the constructor is used as a template: user space
+/// can store any map with the same layout in the hash map. Looking up a key
+/// returns a handle to the stored map, which can be used like any other map.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya_ebpf::{macros::map, maps::{HashMap, HashOfMaps}};
+///
+/// #[map]
+/// static TENANTS: HashOfMaps<u32, HashMap<u32, u64>> =
+///     HashOfMaps::with_max_entries(16, 0, HashMap::with_max_entries(1024, 0));
+///
+/// # fn count(tenant: u32, port: u32) -> Option<()> {
+/// let counters = TENANTS.get(&tenant)?;
+/// let counter = counters.get_ptr_mut(&port)?;
+/// unsafe { *counter += 1 };
+/// # Some(())
+/// # }
+/// ```
+#[repr(C)]
+pub struct HashOfMaps<K, T> {
+    def: UnsafeCell<bpf_map_def>,
+    // The loader reads the definition of the inner map template right after
+    // the definition of the outer map.
+    _inner: T,
+    _k: PhantomData<K>,
+}
+
+unsafe impl<K: Sync, T: InnerMap> Sync for HashOfMaps<K, T> {}
+
+impl<K, T: InnerMap> HashOfMaps<K, T> {
+    pub const fn with_max_entries(max_entries: u32, flags: u32, inner: T) -> HashOfMaps<K, T> {
+        HashOfMaps {
+            def: UnsafeCell::new(build_def::<K>(max_entries, flags, PinningType::None)),
+            _inner: inner,
+            _k: PhantomData,
+        }
+    }
+
+    pub const fn pinned(max_entries: u32, flags: u32, inner: T) -> HashOfMaps<K, T> {
+        HashOfMaps {
+            def: UnsafeCell::new(build_def::<K>(max_entries, flags, PinningType::ByName)),
+            _inner: inner,
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns the map associated with `key`.
+    #[inline(always)]
+    pub fn get(&self, key: &K) -> Option<&T> {
+        unsafe { lookup(self.def.get(), key).map(|p| p.as_ref()) }
+    }
+}
+
+const fn build_def<K>(max_entries: u32, flags: u32, pin: PinningType) -> bpf_map_def {
+    bpf_map_def {
+        type_: BPF_MAP_TYPE_HASH_OF_MAPS,
+        key_size: mem::size_of::<K>() as u32,
+        value_size: mem::size_of::<u32>() as u32,
+        max_entries,
+        map_flags: flags,
+        id: 0,
+        pinning: pin as u32,
+    }
+}
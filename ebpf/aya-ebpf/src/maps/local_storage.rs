@@ -0,0 +1,186 @@
+use core::{cell::UnsafeCell, marker::PhantomData, mem, ptr};
+
+use aya_ebpf_cty::{c_long, c_void};
+
+use crate::{
+    bindings::{
+        BPF_F_NO_PREALLOC, BPF_LOCAL_STORAGE_GET_F_CREATE, bpf_map_def,
+        bpf_map_type::{
+            BPF_MAP_TYPE_CGRP_STORAGE, BPF_MAP_TYPE_INODE_STORAGE, BPF_MAP_TYPE_SK_STORAGE,
+            BPF_MAP_TYPE_TASK_STORAGE,
+        },
+        cgroup, task_struct,
+    },
+    helpers::{
+        bpf_cgrp_storage_delete, bpf_cgrp_storage_get, bpf_inode_storage_delete,
+        bpf_inode_storage_get, bpf_sk_storage_delete, bpf_sk_storage_get, bpf_task_storage_delete,
+        bpf_task_storage_get,
+    },
+    maps::PinningType,
+};
+
+macro_rules! local_storage {
+    (
+        $(#[$meta:meta])*
+        $ty:ident($key:ident: $key_ty:ty) {
+            map_type: $map_type:ident,
+            get: $get:ident,
+            delete: $delete:ident,
+            safety: $safety:literal $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(transparent)]
+        pub struct $ty<T> {
+            def: UnsafeCell<bpf_map_def>,
+            _t: PhantomData<T>,
+        }
+
+        unsafe impl<T: Sync> Sync for $ty<T> {}
+
+        impl<T> $ty<T> {
+            pub const fn new(flags: u32) -> $ty<T> {
+                $ty {
+                    def: UnsafeCell::new(build_def::<T>($map_type, flags, PinningType::None)),
+                    _t: PhantomData,
+                }
+            }
+
+            pub const fn pinned(flags: u32) -> $ty<T> {
+                $ty {
+                    def: UnsafeCell::new(build_def::<T>($map_type, flags, PinningType::ByName)),
+                    _t: PhantomData,
+                }
+            }
+
+            #[doc = concat!(
+                "Returns a pointer to the value stored for `", stringify!($key), "`, if any."
+            )]
+            ///
+            /// # Safety
+            ///
+            #[doc = $safety]
+            #[inline(always)]
+            pub unsafe fn get_ptr_mut(&self, $key: $key_ty) -> Option<*mut T> {
+                let value = unsafe { $get(self.def.get().cast(), $key, ptr::null_mut(), 0) };
+                (!value.is_null()).then_some(value.cast())
+            }
+
+            #[doc = concat!(
+                "Returns a pointer to the value stored for `",
+                stringify!($key),
+                "`, creating it from"
+            )]
+            /// `value`, or zeroed if `value` is `None`, if there's none yet.
+            ///
+            /// # Safety
+            ///
+            #[doc = $safety]
+            #[inline(always)]
+            pub unsafe fn get_or_insert_ptr_mut(
+                &self,
+                $key: $key_ty,
+                value: Option<&T>,
+            ) -> Option<*mut T> {
+                let value = value.map_or(ptr::null_mut(), |value| ptr::from_ref(value).cast_mut());
+                let value = unsafe {
+                    $get(
+                        self.def.get().cast(),
+                        $key,
+                        value.cast(),
+                        BPF_LOCAL_STORAGE_GET_F_CREATE.into(),
+                    )
+                };
+                (!value.is_null()).then_some(value.cast())
+            }
+
+            #[doc = concat!("Removes the value stored for `", stringify!($key), "`.")]
+            ///
+            /// # Safety
+            ///
+            #[doc = $safety]
+            #[inline(always)]
+            pub unsafe fn delete(&self, $key: $key_ty) -> Result<(), c_long> {
+                to_result(unsafe { $delete(self.def.get().cast(), $key) })
+            }
+        }
+    };
+}
+
+local_storage! {
+    /// Storage attached to sockets.
+    ///
+    /// The kernel stores at most one value for each socket, which is freed along
+    /// with it.
+    SkStorage(sk: *mut c_void) {
+        map_type: BPF_MAP_TYPE_SK_STORAGE,
+        get: bpf_sk_storage_get,
+        delete: bpf_sk_storage_delete,
+        safety: "`sk` must be a pointer to a socket, such as the `sk` field of a `bpf_sock` \
+                 or a `struct sock` pointer from BTF.",
+    }
+}
+
+local_storage! {
+    /// Storage attached to inodes.
+    ///
+    /// The kernel stores at most one value for each inode, which is freed along
+    /// with it.
+    InodeStorage(inode: *mut c_void) {
+        map_type: BPF_MAP_TYPE_INODE_STORAGE,
+        get: bpf_inode_storage_get,
+        delete: bpf_inode_storage_delete,
+        safety: "`inode` must be a pointer to an inode, such as a `struct inode` pointer \
+                 from BTF.",
+    }
+}
+
+local_storage! {
+    /// Storage attached to tasks.
+    ///
+    /// The kernel stores at most one value for each task, which is freed along
+    /// with it.
+    TaskStorage(task: *mut task_struct) {
+        map_type: BPF_MAP_TYPE_TASK_STORAGE,
+        get: bpf_task_storage_get,
+        delete: bpf_task_storage_delete,
+        safety: "`task` must be a pointer to a task, such as the one returned by \
+                 `bpf_get_current_task_btf`.",
+    }
+}
+
+local_storage! {
+    /// Storage attached to cgroups.
+    ///
+    /// The kernel stores at most one value for each cgroup, which is freed along
+    /// with it.
+    CgrpStorage(cgroup: *mut cgroup) {
+        map_type: BPF_MAP_TYPE_CGRP_STORAGE,
+        get: bpf_cgrp_storage_get,
+        delete: bpf_cgrp_storage_delete,
+        safety: "`cgroup` must be a pointer to a cgroup, such as a `struct cgroup` pointer \
+                 from BTF.",
+    }
+}
+
+// The delete helpers don't agree on their return type.
+#[inline(always)]
+fn to_result(ret: impl Into<c_long>) -> Result<(), c_long> {
+    match ret.into() {
+        0 => Ok(()),
+        ret => Err(ret),
+    }
+}
+
+const fn build_def<T>(ty: u32, flags: u32, pin: PinningType) -> bpf_map_def {
+    bpf_map_def {
+        type_: ty,
+        key_size: mem::size_of::<i32>() as u32,
+        value_size: mem::size_of::<T>() as u32,
+        // The values are allocated along with the objects they're attached to.
+        max_entries: 0,
+        map_flags: flags | BPF_F_NO_PREALLOC,
+        id: 0,
+        pinning: pin as u32,
+    }
+}
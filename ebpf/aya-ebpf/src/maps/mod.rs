@@ -5,14 +5,30 @@ pub(crate) enum PinningType {
     ByName = 1,
 }
 
+/// A map that can be stored in an [`ArrayOfMaps`] or a [`HashOfMaps`].
+///
+/// # Safety
+///
+/// Looking up a map-of-maps returns a pointer to the kernel's inner map, which
+/// is then used as a reference to the implementing type. Implementors must be
+/// `#[repr(transparent)]` wrappers around their map definition and must only use
+/// its address, never its contents, after loading.
+pub unsafe trait InnerMap {}
+
+pub mod arena;
 pub mod array;
+pub mod array_of_maps;
 pub mod bloom_filter;
+pub mod cgroup_storage;
 pub mod hash_map;
+pub mod hash_of_maps;
+pub mod local_storage;
 pub mod lpm_trie;
 pub mod per_cpu_array;
 pub mod perf;
 pub mod program_array;
 pub mod queue;
+pub mod reuseport_sock_array;
 pub mod ring_buf;
 pub mod sock_hash;
 pub mod sock_map;
@@ -20,14 +36,20 @@ pub mod stack;
 pub mod stack_trace;
 pub mod xdp;
 
+pub use arena::Arena;
 pub use array::Array;
+pub use array_of_maps::ArrayOfMaps;
 pub use bloom_filter::BloomFilter;
+pub use cgroup_storage::{CgroupStorage, PerCpuCgroupStorage};
 pub use hash_map::{HashMap, LruHashMap, LruPerCpuHashMap, PerCpuHashMap};
+pub use hash_of_maps::HashOfMaps;
+pub use local_storage::{CgrpStorage, InodeStorage, SkStorage, TaskStorage};
 pub use lpm_trie::LpmTrie;
 pub use per_cpu_array::PerCpuArray;
 pub use perf::{PerfEventArray, PerfEventByteArray};
 pub use program_array::ProgramArray;
 pub use queue::Queue;
+pub use reuseport_sock_array::ReusePortSockArray;
 pub use ring_buf::RingBuf;
 pub use sock_hash::SockHash;
 pub use sock_map::SockMap;
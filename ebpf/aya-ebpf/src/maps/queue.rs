@@ -3,7 +3,7 @@ use core::{cell::UnsafeCell, marker::PhantomData, mem};
 use crate::{
     bindings::{bpf_map_def, bpf_map_type::BPF_MAP_TYPE_QUEUE},
     helpers::{bpf_map_pop_elem, bpf_map_push_elem},
-    maps::PinningType,
+    maps::{InnerMap, PinningType},
 };
 
 #[repr(transparent)]
@@ -14,6 +14,8 @@ pub struct Queue<T> {
 
 unsafe impl<T: Sync> Sync for Queue<T> {}
 
+unsafe impl<T> InnerMap for Queue<T> {}
+
 impl<T> Queue<T> {
     pub const fn with_max_entries(max_entries: u32, flags: u32) -> Queue<T> {
         Queue {
@@ -0,0 +1,47 @@
+use core::{cell::UnsafeCell, ffi::c_void, mem};
+
+use crate::{
+    bindings::{bpf_map_def, bpf_map_type::BPF_MAP_TYPE_REUSEPORT_SOCKARRAY},
+    maps::PinningType,
+};
+
+#[repr(transparent)]
+pub struct ReusePortSockArray {
+    def: UnsafeCell<bpf_map_def>,
+}
+
+unsafe impl Sync for ReusePortSockArray {}
+
+impl ReusePortSockArray {
+    pub const fn with_max_entries(max_entries: u32, flags: u32) -> ReusePortSockArray {
+        ReusePortSockArray {
+            def: UnsafeCell::new(bpf_map_def {
+                type_: BPF_MAP_TYPE_REUSEPORT_SOCKARRAY,
+                key_size: mem::size_of::<u32>() as u32,
+                value_size: mem::size_of::<u64>() as u32,
+                max_entries,
+                map_flags: flags,
+                id: 0,
+                pinning: PinningType::None as u32,
+            }),
+        }
+    }
+
+    pub const fn pinned(max_entries: u32, flags: u32) -> ReusePortSockArray {
+        ReusePortSockArray {
+            def: UnsafeCell::new(bpf_map_def {
+                type_: BPF_MAP_TYPE_REUSEPORT_SOCKARRAY,
+                key_size: mem::size_of::<u32>() as u32,
+                value_size: mem::size_of::<u64>() as u32,
+                max_entries,
+                map_flags: flags,
+                id: 0,
+                pinning: PinningType::ByName as u32,
+            }),
+        }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *mut c_void {
+        self.def.get().cast()
+    }
+}
@@ -11,7 +11,7 @@ use crate::{
         bpf_ringbuf_discard, bpf_ringbuf_output, bpf_ringbuf_query, bpf_ringbuf_reserve,
         bpf_ringbuf_submit,
     },
-    maps::PinningType,
+    maps::{InnerMap, PinningType},
 };
 
 #[cfg(unstable)]
@@ -32,6 +32,8 @@ pub struct RingBuf {
 
 unsafe impl Sync for RingBuf {}
 
+unsafe impl InnerMap for RingBuf {}
+
 /// A ring buffer entry, returned from [`RingBuf::reserve`].
 ///
 /// You must [`submit`] or [`discard`] this entry before it gets dropped.
@@ -0,0 +1,74 @@
+use core::ffi::c_void;
+
+use crate::{EbpfContext, args::FromBtfArgument};
+
+pub struct FModRetContext {
+    ctx: *mut c_void,
+}
+
+impl FModRetContext {
+    pub fn new(ctx: *mut c_void) -> FModRetContext {
+        FModRetContext { ctx }
+    }
+
+    /// Returns the `n`th argument passed to the probed function, starting from 0.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #![expect(non_camel_case_types)]
+    /// # #![expect(dead_code)]
+    /// # use aya_ebpf::programs::FModRetContext;
+    /// # struct file {}
+    /// unsafe fn try_fail_open(ctx: FModRetContext) -> Result<i32, i32> {
+    ///     let file: *const file = ctx.arg(0);
+    ///
+    ///     // Do something with file
+    ///
+    ///     Ok(0)
+    /// }
+    /// ```
+    #[expect(clippy::missing_safety_doc)]
+    pub unsafe fn arg<T: FromBtfArgument>(&self, n: usize) -> T {
+        unsafe { T::from_argument(self.ctx.cast(), n) }
+    }
+
+    /// Returns the return value of the probed function so far.
+    ///
+    /// This is the value returned by the previous `fmod_ret` program attached
+    /// to the function, or 0 if this is the first one. `arg_count` must be the
+    /// number of arguments of the probed function, as the return value is
+    /// passed right after them.
+    ///
+    /// # Safety
+    ///
+    /// `arg_count` must match the number of arguments of the probed function,
+    /// which is checked by the verifier.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #![expect(dead_code)]
+    /// # use aya_ebpf::{cty::c_int, programs::FModRetContext};
+    /// unsafe fn try_fail_open(ctx: FModRetContext) -> Result<i32, i32> {
+    ///     // int security_file_open(struct file *file)
+    ///     let ret: c_int = ctx.ret(1);
+    ///
+    ///     // Don't override the decision of a previous program.
+    ///     if ret != 0 {
+    ///         return Ok(ret);
+    ///     }
+    ///
+    ///     Ok(-1)
+    /// }
+    /// ```
+    pub unsafe fn ret<T: FromBtfArgument>(&self, arg_count: usize) -> T {
+        unsafe { T::from_argument(self.ctx.cast(), arg_count) }
+    }
+}
+
+impl EbpfContext for FModRetContext {
+    fn as_ptr(&self) -> *mut c_void {
+        self.ctx
+    }
+}
@@ -0,0 +1,41 @@
+use core::ffi::c_void;
+
+use crate::EbpfContext;
+
+/// The context of netfilter programs, `struct bpf_nf_ctx` in the kernel.
+#[repr(C)]
+struct NfCtx {
+    state: *const c_void,
+    skb: *mut c_void,
+}
+
+pub struct NfContext {
+    ctx: *mut NfCtx,
+}
+
+impl NfContext {
+    pub fn new(ctx: *mut c_void) -> NfContext {
+        NfContext { ctx: ctx.cast() }
+    }
+
+    /// Returns a pointer to the kernel `struct sk_buff` of the packet.
+    ///
+    /// The verifier only allows reading its fields through BTF-aware accesses
+    /// or helpers such as `bpf_probe_read_kernel`.
+    pub fn skb(&self) -> *mut c_void {
+        unsafe { (*self.ctx).skb }
+    }
+
+    /// Returns a pointer to the kernel `struct nf_hook_state` of the hook the
+    /// program runs on, which holds the hook number, the protocol family and
+    /// the input and output devices.
+    pub fn state(&self) -> *const c_void {
+        unsafe { (*self.ctx).state }
+    }
+}
+
+impl EbpfContext for NfContext {
+    fn as_ptr(&self) -> *mut c_void {
+        self.ctx.cast()
+    }
+}
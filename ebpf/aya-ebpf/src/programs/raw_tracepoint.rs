@@ -22,3 +22,61 @@ impl EbpfContext for RawTracePointContext {
         self.ctx.cast()
     }
 }
+
+/// The context of a writable raw tracepoint.
+///
+/// The first argument of a writable tracepoint points to a buffer that the
+/// program can modify. The verifier only accepts accesses to the buffer at
+/// constant offsets and records the largest one, which the kernel compares to
+/// the writable size of the tracepoint when the program is attached.
+pub struct RawTracePointWritableContext {
+    ctx: *mut bpf_raw_tracepoint_args,
+}
+
+impl RawTracePointWritableContext {
+    pub fn new(ctx: *mut c_void) -> RawTracePointWritableContext {
+        RawTracePointWritableContext { ctx: ctx.cast() }
+    }
+
+    #[expect(clippy::missing_safety_doc)]
+    pub unsafe fn arg<T: FromRawTracepointArgs>(&self, n: usize) -> T {
+        unsafe { T::from_argument(&*self.ctx, n) }
+    }
+
+    /// Returns a pointer to the writable buffer of the tracepoint.
+    pub fn buffer(&self) -> *mut c_void {
+        unsafe { self.arg::<*const c_void>(0).cast_mut() }
+    }
+
+    /// Reads a `T` at byte offset `OFFSET` of the writable buffer.
+    ///
+    /// # Safety
+    ///
+    /// `OFFSET + size_of::<T>()` must not exceed the writable size of the
+    /// tracepoint, otherwise attaching the program fails.
+    pub unsafe fn read<T, const OFFSET: usize>(&self) -> T {
+        unsafe { self.buffer().byte_add(OFFSET).cast::<T>().read_unaligned() }
+    }
+
+    /// Writes `value` at byte offset `OFFSET` of the writable buffer.
+    ///
+    /// # Safety
+    ///
+    /// `OFFSET + size_of::<T>()` must not exceed the writable size of the
+    /// tracepoint, otherwise attaching the program fails. The caller must
+    /// also make sure that the written value is valid for the tracepoint.
+    pub unsafe fn write<T, const OFFSET: usize>(&self, value: T) {
+        unsafe {
+            self.buffer()
+                .byte_add(OFFSET)
+                .cast::<T>()
+                .write_unaligned(value)
+        }
+    }
+}
+
+impl EbpfContext for RawTracePointWritableContext {
+    fn as_ptr(&self) -> *mut c_void {
+        self.ctx.cast()
+    }
+}
@@ -0,0 +1,66 @@
+use core::ffi::c_void;
+
+use crate::{
+    EbpfContext, bindings::sk_reuseport_md, helpers::bpf_sk_select_reuseport,
+    maps::ReusePortSockArray,
+};
+
+pub struct SkReuseportContext {
+    pub md: *mut sk_reuseport_md,
+}
+
+impl SkReuseportContext {
+    pub fn new(md: *mut sk_reuseport_md) -> SkReuseportContext {
+        SkReuseportContext { md }
+    }
+
+    /// Returns the length of the packet.
+    #[expect(clippy::len_without_is_empty)]
+    #[inline]
+    pub fn len(&self) -> u32 {
+        unsafe { (*self.md).len }
+    }
+
+    /// Returns the ethernet protocol of the packet, in network byte order.
+    #[inline]
+    pub fn eth_protocol(&self) -> u32 {
+        unsafe { (*self.md).eth_protocol }
+    }
+
+    /// Returns the IP protocol of the packet.
+    #[inline]
+    pub fn ip_protocol(&self) -> u32 {
+        unsafe { (*self.md).ip_protocol }
+    }
+
+    /// Returns whether the sockets of the group are bound to `INADDR_ANY`.
+    #[inline]
+    pub fn bind_inany(&self) -> bool {
+        unsafe { (*self.md).bind_inany != 0 }
+    }
+
+    /// Returns the hash of the packet computed by the kernel.
+    #[inline]
+    pub fn hash(&self) -> u32 {
+        unsafe { (*self.md).hash }
+    }
+
+    /// Selects the socket stored at `index` in `map` to handle the packet or
+    /// connection.
+    pub fn select_reuseport(
+        &self,
+        map: &ReusePortSockArray,
+        mut index: u32,
+        flags: u64,
+    ) -> Result<(), i64> {
+        let index: *mut _ = &mut index;
+        let ret = unsafe { bpf_sk_select_reuseport(self.md, map.as_ptr(), index.cast(), flags) };
+        if ret == 0 { Ok(()) } else { Err(ret) }
+    }
+}
+
+impl EbpfContext for SkReuseportContext {
+    fn as_ptr(&self) -> *mut c_void {
+        self.md as *mut _
+    }
+}
@@ -0,0 +1,41 @@
+use core::ffi::c_void;
+
+use crate::{EbpfContext, args::FromBtfArgument};
+
+pub struct StructOpsContext {
+    ctx: *mut c_void,
+}
+
+impl StructOpsContext {
+    pub fn new(ctx: *mut c_void) -> StructOpsContext {
+        StructOpsContext { ctx }
+    }
+
+    /// Returns the `n`th argument passed to the struct_ops callback, starting from 0.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #![expect(non_camel_case_types)]
+    /// # #![expect(dead_code)]
+    /// # use aya_ebpf::programs::StructOpsContext;
+    /// # struct sock {}
+    /// unsafe fn try_ssthresh(ctx: StructOpsContext) -> Result<i32, i32> {
+    ///     let sk: *const sock = ctx.arg(0);
+    ///
+    ///     // Do something with sk
+    ///
+    ///     Ok(2)
+    /// }
+    /// ```
+    #[expect(clippy::missing_safety_doc)]
+    pub unsafe fn arg<T: FromBtfArgument>(&self, n: usize) -> T {
+        unsafe { T::from_argument(self.ctx.cast(), n) }
+    }
+}
+
+impl EbpfContext for StructOpsContext {
+    fn as_ptr(&self) -> *mut c_void {
+        self.ctx
+    }
+}
@@ -0,0 +1,30 @@
+use core::ffi::c_void;
+
+use crate::EbpfContext;
+
+/// The context of syscall programs: the struct passed by user space when
+/// running the program, of type `T`.
+pub struct SyscallContext<T> {
+    ctx: *mut T,
+}
+
+impl<T> SyscallContext<T> {
+    pub fn new(ctx: *mut c_void) -> SyscallContext<T> {
+        SyscallContext { ctx: ctx.cast() }
+    }
+
+    /// Returns a pointer to the struct passed by user space.
+    ///
+    /// The kernel rejects runs whose context is smaller than the accesses made
+    /// by the program, and copies the struct back to user space once the
+    /// program returns.
+    pub fn args(&self) -> *mut T {
+        self.ctx
+    }
+}
+
+impl<T> EbpfContext for SyscallContext<T> {
+    fn as_ptr(&self) -> *mut c_void {
+        self.ctx.cast()
+    }
+}
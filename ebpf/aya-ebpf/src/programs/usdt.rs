@@ -0,0 +1,153 @@
+use core::ffi::{c_long, c_void};
+
+#[cfg(any(
+    bpf_target_arch = "x86_64",
+    bpf_target_arch = "arm",
+    bpf_target_arch = "powerpc64",
+    bpf_target_arch = "mips"
+))]
+use crate::bindings::pt_regs;
+// aarch64 uses user_pt_regs instead of pt_regs
+#[cfg(any(bpf_target_arch = "aarch64", bpf_target_arch = "s390x"))]
+use crate::bindings::user_pt_regs as pt_regs;
+// riscv64 uses user_regs_struct instead of pt_regs
+#[cfg(bpf_target_arch = "riscv64")]
+use crate::bindings::user_regs_struct as pt_regs;
+use crate::{
+    EbpfContext,
+    helpers::{bpf_get_attach_cookie, bpf_probe_read_kernel, bpf_probe_read_user},
+    maps::HashMap,
+};
+
+/// The maximum number of arguments of a USDT probe.
+pub const USDT_MAX_ARG_COUNT: usize = 12;
+
+/// The default capacity of the `AYA_USDT_SPECS` map.
+///
+/// Each entry holds the argument spec shared by the locations of an attached
+/// probe with the same argument layout. User space can raise the capacity with
+/// `EbpfLoader::set_max_entries("AYA_USDT_SPECS", n)`; attaching fails with
+/// `UsdtError::TooManySpecs` when the map is full.
+pub const USDT_MAX_SPEC_COUNT: u32 = 256;
+
+const ENOENT: c_long = 2;
+const ESRCH: c_long = 3;
+const EINVAL: c_long = 22;
+
+// The values of `UsdtArgSpec::arg_type`.
+const USDT_ARG_CONST: u32 = 0;
+const USDT_ARG_REG: u32 = 1;
+const USDT_ARG_REG_DEREF: u32 = 2;
+
+// The layout of these types must match the ones in aya::programs::usdt.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct UsdtArgSpec {
+    // The value of constant arguments, or the offset from the register of
+    // dereferenced arguments.
+    val_off: u64,
+    arg_type: u32,
+    // The offset of the register in `pt_regs`.
+    reg_off: i16,
+    arg_signed: bool,
+    // The shift that truncates the 64 bit value to the size of the argument.
+    arg_bitshift: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct UsdtSpec {
+    args: [UsdtArgSpec; USDT_MAX_ARG_COUNT],
+    arg_cnt: u16,
+}
+
+/// The argument specs of the attached probes, keyed by the attach cookie.
+///
+/// Populated by aya when attaching a USDT program.
+#[unsafe(link_section = "maps")]
+#[unsafe(export_name = "AYA_USDT_SPECS")]
+static AYA_USDT_SPECS: HashMap<u32, UsdtSpec> = HashMap::with_max_entries(USDT_MAX_SPEC_COUNT, 0);
+
+pub struct UsdtContext {
+    pub regs: *mut pt_regs,
+}
+
+impl UsdtContext {
+    pub fn new(ctx: *mut c_void) -> UsdtContext {
+        UsdtContext {
+            regs: ctx as *mut pt_regs,
+        }
+    }
+
+    /// Returns the number of arguments of the probe.
+    ///
+    /// Returns `Err(-ESRCH)` if the program wasn't attached by aya.
+    pub fn arg_count(&self) -> Result<usize, c_long> {
+        Ok(usize::from(self.spec()?.arg_cnt))
+    }
+
+    /// Returns the `n`th argument of the probe, starting from 0.
+    ///
+    /// The argument is sign or zero extended to 64 bits according to its spec.
+    /// Returns `Err(-ENOENT)` if the probe has fewer than `n + 1` arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use aya_ebpf::programs::UsdtContext;
+    /// fn try_request_start(ctx: UsdtContext) -> Result<u32, i64> {
+    ///     let request_id = ctx.arg(0)?;
+    ///     let size = ctx.arg(1)? as u32;
+    ///
+    ///     // Do something with request_id and size
+    ///
+    ///     Ok(0)
+    /// }
+    /// ```
+    pub fn arg(&self, n: usize) -> Result<i64, c_long> {
+        let spec = self.spec()?;
+        if n >= USDT_MAX_ARG_COUNT || n >= usize::from(spec.arg_cnt) {
+            return Err(-ENOENT);
+        }
+        let UsdtArgSpec {
+            val_off,
+            arg_type,
+            reg_off,
+            arg_signed,
+            arg_bitshift,
+        } = spec.args[n];
+
+        // The verifier doesn't allow reading pt_regs at variable offsets directly.
+        let read_reg = || unsafe {
+            bpf_probe_read_kernel((self.regs as *const u8).offset(reg_off as isize) as *const u64)
+        };
+        let val = match arg_type {
+            USDT_ARG_CONST => val_off,
+            USDT_ARG_REG => read_reg()?,
+            USDT_ARG_REG_DEREF => {
+                let addr = read_reg()?.wrapping_add(val_off);
+                unsafe { bpf_probe_read_user(addr as *const u64) }?
+            }
+            _ => return Err(-EINVAL),
+        };
+
+        let shift = u32::from(arg_bitshift);
+        let val = val << shift;
+        Ok(if arg_signed {
+            (val as i64) >> shift
+        } else {
+            (val >> shift) as i64
+        })
+    }
+
+    fn spec(&self) -> Result<&UsdtSpec, c_long> {
+        let spec_id = unsafe { bpf_get_attach_cookie(self.as_ptr()) } as u32;
+        unsafe { AYA_USDT_SPECS.get(&spec_id) }.ok_or(-ESRCH)
+    }
+}
+
+impl EbpfContext for UsdtContext {
+    fn as_ptr(&self) -> *mut c_void {
+        self.regs as *mut c_void
+    }
+}
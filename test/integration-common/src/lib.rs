@@ -1,5 +1,14 @@
 #![no_std]
 
+pub mod arena {
+    /// A node of the linked list allocated in the arena.
+    #[repr(C)]
+    pub struct Node {
+        pub next: *mut Node,
+        pub value: u64,
+    }
+}
+
 pub mod bpf_probe_read {
     pub const RESULT_BUF_LEN: usize = 1024;
 
@@ -25,6 +34,10 @@ pub mod raw_tracepoint {
 
     #[cfg(feature = "user")]
     unsafe impl aya::Pod for SysEnterEvent {}
+
+    /// The value written to the `from` field of the `struct nbd_request` sent
+    /// by the nbd driver.
+    pub const NBD_REQUEST_FROM: u64 = 0x0123_4567_89ab_cdef;
 }
 
 pub mod ring_buf {
@@ -56,6 +69,17 @@ pub mod ring_buf {
     unsafe impl aya::Pod for Registers {}
 }
 
+pub mod syscall {
+    #[derive(Copy, Clone)]
+    #[repr(C)]
+    pub struct Args {
+        pub value: u64,
+    }
+
+    #[cfg(feature = "user")]
+    unsafe impl aya::Pod for Args {}
+}
+
 pub mod strncmp {
     #[derive(Copy, Clone)]
     #[repr(C)]
@@ -0,0 +1,21 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    macros::{map, usdt},
+    maps::RingBuf,
+    programs::UsdtContext,
+};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+#[map]
+static RING_BUF: RingBuf = RingBuf::with_byte_size(0, 0);
+
+#[usdt]
+pub fn usdt(ctx: UsdtContext) {
+    for n in 0..2 {
+        let arg = ctx.arg(n).unwrap_or(i64::MIN);
+        let _res = RING_BUF.output(&arg.to_le_bytes(), 0);
+    }
+}
@@ -17,6 +17,12 @@ use xtask::AYA_BUILD_INTEGRATION_BPF;
 fn main() {
     println!("cargo:rerun-if-env-changed={}", AYA_BUILD_INTEGRATION_BPF);
 
+    // The arena kfuncs are resolved through the BTF of the program. This script
+    // is shared with integration-ebpf-usdt, which has no arena program.
+    if env::var("CARGO_PKG_NAME").unwrap() == "integration-ebpf" {
+        println!("cargo:rustc-link-arg-bin=arena=--btf");
+    }
+
     let build_integration_bpf = env::var(AYA_BUILD_INTEGRATION_BPF)
         .as_deref()
         .map(str::parse)
@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+use core::ptr;
+
+use aya_ebpf::{
+    macros::{map, syscall},
+    maps::{
+        Arena,
+        arena::{NUMA_NO_NODE, cast_kern, cast_user},
+    },
+    programs::SyscallContext,
+};
+use integration_common::arena::Node;
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+// The kernel picks the user space address, so user space maps the arena before
+// loading the program.
+#[map]
+static ARENA: Arena = Arena::new(2, 0, 0);
+
+/// Allocates a list of two nodes and returns its head to user space.
+#[syscall]
+pub fn arena_alloc(ctx: SyscallContext<u64>) -> i32 {
+    let Some(page) = ARENA.alloc_pages(None, 1, NUMA_NO_NODE, 0) else {
+        return 1;
+    };
+    let head = page.as_ptr().cast::<Node>();
+    unsafe {
+        let tail = head.add(1);
+        head.write(Node {
+            next: cast_user(tail),
+            value: 1,
+        });
+        tail.write(Node {
+            next: ptr::null_mut(),
+            value: 0,
+        });
+        // Follow the pointer stored for user space.
+        (*cast_kern((*head).next)).value = 2;
+        *ctx.args() = cast_user(head) as u64;
+    }
+    0
+}
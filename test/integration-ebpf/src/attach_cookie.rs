@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    EbpfContext, helpers,
+    macros::{kprobe, map, tracepoint},
+    maps::Array,
+    programs::{ProbeContext, TracePointContext},
+};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+#[map]
+static COOKIES: Array<u64> = Array::with_max_entries(2, 0);
+
+fn record<C: EbpfContext>(ctx: &C, index: u32) {
+    let cookie = unsafe { helpers::bpf_get_attach_cookie(ctx.as_ptr()) };
+    if let Some(ptr) = COOKIES.get_ptr_mut(index) {
+        unsafe { *ptr = cookie };
+    }
+}
+
+#[kprobe]
+pub fn kprobe_cookie(ctx: ProbeContext) -> u32 {
+    record(&ctx, 0);
+    0
+}
+
+#[tracepoint]
+pub fn tracepoint_cookie(ctx: TracePointContext) -> u32 {
+    record(&ctx, 1);
+    0
+}
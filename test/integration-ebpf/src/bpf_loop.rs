@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    cty::{c_long, c_void},
+    helpers::bpf_loop,
+    macros::{map, uprobe},
+    maps::Array,
+    programs::ProbeContext,
+};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+#[map]
+static RESULT: Array<u64> = Array::with_max_entries(1, 0);
+
+extern "C" fn sum(index: u32, ctx: *mut c_void) -> c_long {
+    let sum = unsafe { &mut *(ctx as *mut u64) };
+    *sum += u64::from(index);
+    0
+}
+
+#[uprobe]
+pub fn test_bpf_loop(ctx: ProbeContext) -> Result<(), c_long> {
+    let nr_loops: u32 = ctx.arg(0).ok_or(-1)?;
+    let mut total = 0u64;
+    let ret = unsafe {
+        bpf_loop(
+            nr_loops,
+            sum as *mut c_void,
+            &mut total as *mut u64 as *mut c_void,
+            0,
+        )
+    };
+    if ret < 0 {
+        return Err(ret);
+    }
+
+    let ptr = RESULT.get_ptr_mut(0).ok_or(-1)?;
+    unsafe { *ptr = total };
+
+    Ok(())
+}
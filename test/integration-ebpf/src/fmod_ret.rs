@@ -0,0 +1,14 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{macros::fmod_ret, programs::FModRetContext};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+// int bpf_modify_return_test(int a, int *b)
+#[fmod_ret(function = "bpf_modify_return_test")]
+pub fn modify_return(ctx: FModRetContext) -> i32 {
+    let a: i32 = unsafe { ctx.arg(0) };
+    let ret: i32 = unsafe { ctx.ret(2) };
+    a + ret + 41
+}
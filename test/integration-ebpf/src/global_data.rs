@@ -0,0 +1,27 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{macros::uprobe, programs::ProbeContext};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+#[unsafe(no_mangle)]
+static mut COUNTER: u64 = 0;
+
+#[unsafe(no_mangle)]
+static mut INCREMENT: u64 = 1;
+
+#[unsafe(no_mangle)]
+static MAX: u64 = 100;
+
+#[uprobe]
+pub fn global_data(_ctx: ProbeContext) -> u32 {
+    let counter = &raw mut COUNTER;
+    unsafe {
+        let value = counter.read_volatile() + (&raw const INCREMENT).read_volatile();
+        if value <= core::ptr::read_volatile(&MAX) {
+            counter.write_volatile(value);
+        }
+    }
+    0
+}
@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    helpers::bpf_get_current_task_btf,
+    macros::{map, uprobe},
+    maps::{Array, CgrpStorage, SkStorage, TaskStorage},
+    programs::ProbeContext,
+};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+#[map]
+static SOCKETS: SkStorage<u64> = SkStorage::new(0);
+
+#[map]
+static TASKS: TaskStorage<u64> = TaskStorage::new(0);
+
+#[map]
+static CGROUPS: CgrpStorage<u64> = CgrpStorage::new(0);
+
+#[map]
+static RESULT: Array<u64> = Array::with_max_entries(1, 0);
+
+#[uprobe]
+pub fn task_storage(_ctx: ProbeContext) -> u32 {
+    let task = unsafe { bpf_get_current_task_btf() };
+    if let Some(count) = unsafe { TASKS.get_or_insert_ptr_mut(task, None) } {
+        let count = unsafe {
+            *count += 1;
+            *count
+        };
+        let _ = RESULT.set(0, &count, 0);
+    }
+    0
+}
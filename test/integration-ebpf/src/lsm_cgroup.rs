@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    helpers::bpf_get_current_pid_tgid,
+    macros::{lsm_cgroup, map},
+    maps::{Array, CgroupStorage},
+    programs::LsmContext,
+};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+/// The id of the thread whose binds are denied.
+#[map]
+static TARGET_TID: Array<u32> = Array::with_max_entries(1, 0);
+
+/// The number of binds denied in the cgroup.
+#[map]
+static DENIED: CgroupStorage<u64> = CgroupStorage::new(0);
+
+#[lsm_cgroup(hook = "socket_bind")]
+pub fn deny_bind(_ctx: LsmContext) -> i32 {
+    let tid = bpf_get_current_pid_tgid() as u32;
+    match TARGET_TID.get(0) {
+        Some(&target) if target == tid => {
+            let denied = DENIED.get_ptr_mut();
+            unsafe { *denied += 1 };
+            0
+        }
+        _ => 1,
+    }
+}
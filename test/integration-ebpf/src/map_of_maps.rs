@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    macros::{map, uprobe},
+    maps::{Array, ArrayOfMaps, HashOfMaps},
+    programs::ProbeContext,
+};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+#[map]
+static OUTER_ARRAY: ArrayOfMaps<Array<u32>> =
+    ArrayOfMaps::with_max_entries(1, 0, Array::with_max_entries(1, 0));
+
+#[map]
+static OUTER_HASH: HashOfMaps<u32, Array<u32>> =
+    HashOfMaps::with_max_entries(1, 0, Array::with_max_entries(1, 0));
+
+#[map]
+static INNER_0: Array<u32> = Array::with_max_entries(1, 0);
+
+#[map]
+static INNER_1: Array<u32> = Array::with_max_entries(1, 0);
+
+#[uprobe]
+pub fn map_of_maps(ctx: ProbeContext) -> u32 {
+    let Some(value) = ctx.arg::<u32>(0) else {
+        return 1;
+    };
+    if let Some(inner) = OUTER_ARRAY.get(0) {
+        let _ = inner.set(0, &value, 0);
+    }
+    if let Some(inner) = OUTER_HASH.get(&42) {
+        let _ = inner.set(0, &(value + 1), 0);
+    }
+    0
+}
@@ -0,0 +1,21 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    macros::{map, uprobe},
+    maps::Array,
+    programs::ProbeContext,
+};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+#[map]
+static VALUES: Array<u64> = Array::mmapable(4, 0);
+
+#[uprobe]
+pub fn mmap_array(_ctx: ProbeContext) -> u32 {
+    if let (Some(input), Some(output)) = (VALUES.get(0), VALUES.get_ptr_mut(1)) {
+        unsafe { *output = *input + 1 };
+    }
+    0
+}
@@ -0,0 +1,22 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    macros::{fentry, map},
+    maps::Array,
+    programs::FEntryContext,
+};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+#[map]
+static CALLS: Array<u64> = Array::with_max_entries(1, 0);
+
+// clsact_init is defined by the sch_ingress module.
+#[fentry(function = "clsact_init")]
+pub fn clsact_init(_ctx: FEntryContext) -> i32 {
+    if let Some(calls) = CALLS.get_ptr_mut(0) {
+        unsafe { *calls += 1 };
+    }
+    0
+}
@@ -0,0 +1,26 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    macros::{map, netfilter},
+    maps::Array,
+    programs::NfContext,
+};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+const NF_ACCEPT: i32 = 1;
+
+/// The number of packets the program saw.
+#[map]
+static PACKETS: Array<u64> = Array::with_max_entries(1, 0);
+
+#[netfilter]
+pub fn count_packets(ctx: NfContext) -> i32 {
+    if !ctx.skb().is_null()
+        && let Some(packets) = PACKETS.get_ptr_mut(0)
+    {
+        unsafe { *packets += 1 };
+    }
+    NF_ACCEPT
+}
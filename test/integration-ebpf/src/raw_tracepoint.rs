@@ -4,11 +4,11 @@
 use aya_ebpf::{
     macros::{map, raw_tracepoint},
     maps::Array,
-    programs::RawTracePointContext,
+    programs::{RawTracePointContext, RawTracePointWritableContext},
 };
 #[cfg(not(test))]
 extern crate ebpf_panic;
-use integration_common::raw_tracepoint::SysEnterEvent;
+use integration_common::raw_tracepoint::{NBD_REQUEST_FROM, SysEnterEvent};
 
 #[map]
 static RESULT: Array<SysEnterEvent> = Array::with_max_entries(1, 0);
@@ -27,3 +27,18 @@ pub fn sys_enter(ctx: RawTracePointContext) -> i32 {
 
     0
 }
+
+#[raw_tracepoint(tracepoint = "sys_enter", writable)]
+pub fn sys_enter_writable(ctx: RawTracePointWritableContext) -> i32 {
+    unsafe { ctx.write::<u32, 0>(0) };
+    0
+}
+
+// The tracepoint is named `nbd_send_request_tp` since 6.16, the name is set
+// when attaching.
+#[raw_tracepoint(tracepoint = "nbd_send_request", writable)]
+pub fn nbd_send_request(ctx: RawTracePointWritableContext) -> i32 {
+    // `from` is a big endian u64 at offset 16 of `struct nbd_request`.
+    unsafe { ctx.write::<u64, 16>(NBD_REQUEST_FROM.to_be()) };
+    0
+}
@@ -0,0 +1,25 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    macros::{map, sk_reuseport},
+    maps::ReusePortSockArray,
+    programs::SkReuseportContext,
+};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+const SK_DROP: u32 = 0;
+const SK_PASS: u32 = 1;
+
+#[map]
+static WORKERS: ReusePortSockArray = ReusePortSockArray::with_max_entries(2, 0);
+
+/// Steers every packet to the socket at index 1.
+#[sk_reuseport]
+pub fn select_worker(ctx: SkReuseportContext) -> u32 {
+    match ctx.select_reuseport(&WORKERS, 1, 0) {
+        Ok(()) => SK_PASS,
+        Err(_) => SK_DROP,
+    }
+}
@@ -0,0 +1,25 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    macros::{map, syscall},
+    maps::Array,
+    programs::SyscallContext,
+};
+use integration_common::syscall::Args;
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+/// The number of times the program ran.
+#[map]
+static RUNS: Array<u64> = Array::with_max_entries(1, 0);
+
+#[syscall]
+pub fn double(ctx: SyscallContext<Args>) -> i32 {
+    if let Some(runs) = RUNS.get_ptr_mut(0) {
+        unsafe { *runs += 1 };
+    }
+    let args = ctx.args();
+    unsafe { (*args).value *= 2 };
+    42
+}
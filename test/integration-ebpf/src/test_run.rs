@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use aya_ebpf::{
+    bindings::xdp_action,
+    macros::{map, raw_tracepoint, xdp},
+    maps::Array,
+    programs::{RawTracePointContext, XdpContext},
+};
+#[cfg(not(test))]
+extern crate ebpf_panic;
+
+/// The number of times the XDP program ran.
+#[map]
+static RUNS: Array<u64> = Array::with_max_entries(1, 0);
+
+/// Increments the first byte of the packet.
+#[xdp]
+pub fn test_run_xdp(ctx: XdpContext) -> u32 {
+    if let Some(runs) = RUNS.get_ptr_mut(0) {
+        unsafe { *runs += 1 };
+    }
+    let data = ctx.data();
+    if data + 1 > ctx.data_end() {
+        return xdp_action::XDP_ABORTED;
+    }
+    let first = data as *mut u8;
+    unsafe { *first = (*first).wrapping_add(1) };
+    xdp_action::XDP_DROP
+}
+
+/// Returns the sum of the first two arguments.
+#[raw_tracepoint]
+pub fn test_run_raw_tp(ctx: RawTracePointContext) -> i32 {
+    let a: u64 = unsafe { ctx.arg(0) };
+    let b: u64 = unsafe { ctx.arg(1) };
+    a.wrapping_add(b) as i32
+}
@@ -0,0 +1,39 @@
+// clang-format off
+#include <vmlinux.h>
+#include <bpf/bpf_helpers.h>
+// clang-format on
+
+#define __arena __attribute__((address_space(1)))
+
+struct {
+  __uint(type, BPF_MAP_TYPE_ARENA);
+  __uint(map_flags, BPF_F_MMAPABLE);
+  __uint(max_entries, 2);
+  __ulong(map_extra, 1ull << 44);
+} ARENA SEC(".maps");
+
+extern void __arena *bpf_arena_alloc_pages(void *map, void __arena *addr,
+                                           __u32 page_cnt, int node_id,
+                                           __u64 flags) __ksym;
+
+struct node {
+  struct node __arena *next;
+  __u64 value;
+};
+
+SEC("syscall")
+int arena_alloc(__u64 *ctx) {
+  struct node __arena *nodes = bpf_arena_alloc_pages(&ARENA, NULL, 1, -1, 0);
+  if (!nodes)
+    return 1;
+
+  nodes[0].next = &nodes[1];
+  nodes[0].value = 1;
+  nodes[1].next = NULL;
+  nodes[1].value = 2;
+
+  *ctx = (__u64)nodes;
+  return 0;
+}
+
+char _license[] SEC("license") = "GPL";
@@ -0,0 +1,35 @@
+// clang-format off
+#include <vmlinux.h>
+#include <bpf/bpf_helpers.h>
+// clang-format on
+
+extern unsigned int LINUX_KERNEL_VERSION __kconfig;
+extern bool CONFIG_BPF __kconfig;
+extern enum libbpf_tristate CONFIG_BPF_JIT __kconfig;
+extern int CONFIG_HZ __kconfig;
+extern char CONFIG_DEFAULT_HOSTNAME[8] __kconfig;
+extern bool CONFIG_AYA_MISSING __kconfig __weak;
+
+struct {
+  __uint(type, BPF_MAP_TYPE_ARRAY);
+  __type(key, __u32);
+  __type(value, __u64);
+  __uint(max_entries, 6);
+} RESULTS SEC(".maps");
+
+static void set_result(__u32 key, __u64 value) {
+  bpf_map_update_elem(&RESULTS, &key, &value, BPF_ANY);
+}
+
+SEC("uprobe")
+int kconfig(void *ctx) {
+  set_result(0, LINUX_KERNEL_VERSION);
+  set_result(1, CONFIG_BPF);
+  set_result(2, CONFIG_BPF_JIT);
+  set_result(3, CONFIG_HZ);
+  set_result(4, CONFIG_DEFAULT_HOSTNAME[1]);
+  set_result(5, CONFIG_AYA_MISSING);
+  return 0;
+}
+
+char _license[] SEC("license") = "GPL";
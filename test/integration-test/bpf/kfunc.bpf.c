@@ -0,0 +1,34 @@
+// clang-format off
+#include <vmlinux.h>
+#include <bpf/bpf_helpers.h>
+// clang-format on
+
+extern void bpf_rcu_read_lock(void) __ksym;
+extern void bpf_rcu_read_unlock(void) __ksym;
+extern void aya_missing_kfunc(void) __ksym __weak;
+
+struct {
+  __uint(type, BPF_MAP_TYPE_ARRAY);
+  __type(key, __u32);
+  __type(value, __u64);
+  __uint(max_entries, 2);
+} RESULTS SEC(".maps");
+
+static void set_result(__u32 key, __u64 value) {
+  bpf_map_update_elem(&RESULTS, &key, &value, BPF_ANY);
+}
+
+SEC("uprobe")
+int kfunc(void *ctx) {
+  bpf_rcu_read_lock();
+  set_result(0, 1);
+  bpf_rcu_read_unlock();
+
+  if (bpf_ksym_exists(aya_missing_kfunc)) {
+    aya_missing_kfunc();
+    set_result(1, 1);
+  }
+  return 0;
+}
+
+char _license[] SEC("license") = "GPL";
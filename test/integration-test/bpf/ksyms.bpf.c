@@ -0,0 +1,32 @@
+// clang-format off
+#include <vmlinux.h>
+#include <bpf/bpf_helpers.h>
+// clang-format on
+
+extern const struct rq runqueues __ksym;
+extern const void bpf_prog_active __ksym;
+extern const void aya_missing_ksym __ksym __weak;
+
+struct {
+  __uint(type, BPF_MAP_TYPE_ARRAY);
+  __type(key, __u32);
+  __type(value, __u64);
+  __uint(max_entries, 3);
+} RESULTS SEC(".maps");
+
+static void set_result(__u32 key, __u64 value) {
+  bpf_map_update_elem(&RESULTS, &key, &value, BPF_ANY);
+}
+
+SEC("uprobe")
+int ksyms(void *ctx) {
+  const struct rq *rq = bpf_per_cpu_ptr(&runqueues, 0);
+  if (rq) {
+    set_result(0, rq->cpu + 1);
+  }
+  set_result(1, (__u64)&bpf_prog_active);
+  set_result(2, (__u64)&aya_missing_ksym);
+  return 0;
+}
+
+char _license[] SEC("license") = "GPL";
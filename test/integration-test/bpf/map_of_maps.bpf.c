@@ -0,0 +1,26 @@
+// clang-format off
+#include <vmlinux.h>
+#include <bpf/bpf_helpers.h>
+// clang-format on
+
+struct inner_map {
+  __uint(type, BPF_MAP_TYPE_ARRAY);
+  __type(key, __u32);
+  __type(value, __u32);
+  __uint(max_entries, 1);
+} inner_0 SEC(".maps"), inner_1 SEC(".maps");
+
+struct {
+  __uint(type, BPF_MAP_TYPE_ARRAY_OF_MAPS);
+  __type(key, __u32);
+  __uint(max_entries, 4);
+  __array(values, struct inner_map);
+} outer SEC(".maps") = {
+    .values =
+        {
+            [1] = &inner_0,
+            [3] = &inner_1,
+        },
+};
+
+char _license[] SEC("license") = "GPL";
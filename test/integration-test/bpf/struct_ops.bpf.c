@@ -0,0 +1,34 @@
+// clang-format off
+#include <vmlinux.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_tracing.h>
+// clang-format on
+
+char _license[] SEC("license") = "GPL";
+
+SEC("struct_ops")
+__u32 BPF_PROG(aya_ssthresh, struct sock *sk) { return 2; }
+
+SEC("struct_ops")
+void BPF_PROG(aya_cong_avoid, struct sock *sk, __u32 ack, __u32 acked) {}
+
+SEC("struct_ops")
+__u32 BPF_PROG(aya_undo_cwnd, struct sock *sk) { return 2; }
+
+// Registered through a BPF link.
+SEC(".struct_ops.link")
+struct tcp_congestion_ops aya_cc = {
+    .ssthresh = (void *)aya_ssthresh,
+    .cong_avoid = (void *)aya_cong_avoid,
+    .undo_cwnd = (void *)aya_undo_cwnd,
+    .name = "aya_cc",
+};
+
+// Registered by updating the map.
+SEC(".struct_ops")
+struct tcp_congestion_ops aya_cc_legacy = {
+    .ssthresh = (void *)aya_ssthresh,
+    .cong_avoid = (void *)aya_cong_avoid,
+    .undo_cwnd = (void *)aya_undo_cwnd,
+    .name = "aya_cc_legacy",
+};
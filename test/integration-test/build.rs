@@ -50,10 +50,16 @@ fn main() -> Result<()> {
         .no_deps()
         .exec()
         .context("MetadataCommand::exec")?;
-    let integration_ebpf_package = packages
+    let integration_ebpf_packages = ["integration-ebpf", "integration-ebpf-usdt"]
         .into_iter()
-        .find(|Package { name, .. }| name == "integration-ebpf")
-        .ok_or_else(|| anyhow!("integration-ebpf package not found"))?;
+        .map(|name| {
+            packages
+                .iter()
+                .find(|package| package.name == name)
+                .cloned()
+                .ok_or_else(|| anyhow!("{name} package not found"))
+        })
+        .collect::<Result<Vec<_>>>()?;
 
     let manifest_dir =
         env::var_os("CARGO_MANIFEST_DIR").ok_or(anyhow!("CARGO_MANIFEST_DIR not set"))?;
@@ -62,11 +68,17 @@ fn main() -> Result<()> {
     let out_dir = PathBuf::from(out_dir);
 
     const C_BPF: &[(&str, bool)] = &[
+        ("arena.bpf.c", false),
         ("ext.bpf.c", false),
         ("iter.bpf.c", true),
+        ("kconfig.bpf.c", false),
+        ("kfunc.bpf.c", false),
+        ("ksyms.bpf.c", false),
         ("main.bpf.c", false),
+        ("map_of_maps.bpf.c", false),
         ("multimap-btf.bpf.c", false),
         ("reloc.bpf.c", true),
+        ("struct_ops.bpf.c", false),
         ("text_64_64_reloc.c", false),
         ("variables_reloc.bpf.c", false),
     ];
@@ -171,7 +183,7 @@ fn main() -> Result<()> {
             }
         }
 
-        aya_build::build_ebpf([integration_ebpf_package], aya_build::Toolchain::default())?;
+        aya_build::build_ebpf(integration_ebpf_packages, aya_build::Toolchain::default())?;
     } else {
         for (src, build_btf) in C_BPF {
             let dst = out_dir.join(src).with_extension("o");
@@ -182,13 +194,14 @@ fn main() -> Result<()> {
             }
         }
 
-        let Package { targets, .. } = integration_ebpf_package;
-        for Target { name, kind, .. } in targets {
-            if *kind != [TargetKind::Bin] {
-                continue;
+        for Package { targets, .. } in integration_ebpf_packages {
+            for Target { name, kind, .. } in targets {
+                if *kind != [TargetKind::Bin] {
+                    continue;
+                }
+                let dst = out_dir.join(name);
+                fs::write(&dst, []).with_context(|| format!("failed to create {dst:?}"))?;
             }
-            let dst = out_dir.join(name);
-            fs::write(&dst, []).with_context(|| format!("failed to create {dst:?}"))?;
         }
     }
     Ok(())
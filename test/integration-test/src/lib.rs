@@ -1,23 +1,42 @@
 use aya::include_bytes_aligned;
 
+pub const ARENA_BPF: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/arena.bpf.o"));
 pub const EXT: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/ext.bpf.o"));
 pub const ITER_TASK: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/iter.bpf.o"));
+pub const KCONFIG: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/kconfig.bpf.o"));
+pub const KFUNC: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/kfunc.bpf.o"));
+pub const KSYMS: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/ksyms.bpf.o"));
 pub const MAIN: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/main.bpf.o"));
+pub const MAP_OF_MAPS_BPF: &[u8] =
+    include_bytes_aligned!(concat!(env!("OUT_DIR"), "/map_of_maps.bpf.o"));
 pub const MULTIMAP_BTF: &[u8] =
     include_bytes_aligned!(concat!(env!("OUT_DIR"), "/multimap-btf.bpf.o"));
 pub const RELOC_BPF: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/reloc.bpf.o"));
 pub const RELOC_BTF: &[u8] =
     include_bytes_aligned!(concat!(env!("OUT_DIR"), "/reloc.bpf.target.o"));
+pub const STRUCT_OPS: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/struct_ops.bpf.o"));
 pub const TEXT_64_64_RELOC: &[u8] =
     include_bytes_aligned!(concat!(env!("OUT_DIR"), "/text_64_64_reloc.o"));
 pub const VARIABLES_RELOC: &[u8] =
     include_bytes_aligned!(concat!(env!("OUT_DIR"), "/variables_reloc.bpf.o"));
 
+pub const ARENA: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/arena"));
+pub const ATTACH_COOKIE: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/attach_cookie"));
+pub const BPF_LOOP: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/bpf_loop"));
 pub const BPF_PROBE_READ: &[u8] =
     include_bytes_aligned!(concat!(env!("OUT_DIR"), "/bpf_probe_read"));
+pub const FMOD_RET: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/fmod_ret"));
+pub const GLOBAL_DATA: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/global_data"));
+pub const KPROBE_MULTI: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/kprobe_multi"));
+pub const LOCAL_STORAGE: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/local_storage"));
 pub const LOG: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/log"));
+pub const LSM_CGROUP: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/lsm_cgroup"));
+pub const MAP_OF_MAPS: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/map_of_maps"));
 pub const MAP_TEST: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/map_test"));
 pub const MEMMOVE_TEST: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/memmove_test"));
+pub const MMAP_ARRAY: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/mmap_array"));
+pub const MODULE_BTF: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/module_btf"));
+pub const NETFILTER: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/netfilter"));
 pub const NAME_TEST: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/name_test"));
 pub const PASS: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/pass"));
 pub const RAW_TRACEPOINT: &[u8] =
@@ -25,13 +44,18 @@ pub const RAW_TRACEPOINT: &[u8] =
 pub const REDIRECT: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/redirect"));
 pub const RELOCATIONS: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/relocations"));
 pub const RING_BUF: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/ring_buf"));
+pub const SK_REUSEPORT: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/sk_reuseport"));
 pub const SIMPLE_PROG: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/simple_prog"));
 pub const STRNCMP: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/strncmp"));
+pub const SYSCALL: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/syscall"));
 pub const TCX: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/tcx"));
 pub const TEST: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/test"));
+pub const TEST_RUN: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/test_run"));
 pub const TWO_PROGS: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/two_progs"));
 pub const XDP_SEC: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/xdp_sec"));
 pub const UPROBE_COOKIE: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/uprobe_cookie"));
+pub const UPROBE_MULTI: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/uprobe_multi"));
+pub const USDT: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/usdt"));
 pub const SOCKET_FILTER: &[u8] = include_bytes_aligned!(concat!(env!("OUT_DIR"), "/socket_filter"));
 
 #[cfg(test)]
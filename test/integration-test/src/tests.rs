@@ -1,17 +1,40 @@
+mod arena;
+mod attach_cookie;
+mod bpf_loop;
 mod bpf_probe_read;
 mod btf_relocations;
 mod elf;
+mod feature_probe;
+mod fmod_ret;
+mod global_data;
 mod info;
 mod iter;
+mod kconfig;
+mod kfunc;
+mod kprobe_multi;
+mod ksyms;
 mod load;
+mod local_storage;
 mod log;
+mod lsm_cgroup;
+mod map_batch;
+mod map_of_maps;
+mod mmap_array;
+mod netfilter;
+mod netkit;
 mod raw_tracepoint;
 mod rbpf;
 mod relocations;
 mod ring_buf;
+mod sk_reuseport;
 mod smoke;
 mod socket_filter;
 mod strncmp;
+mod struct_ops;
+mod syscall;
 mod tcx;
+mod test_run;
 mod uprobe_cookie;
+mod uprobe_multi;
+mod usdt;
 mod xdp;
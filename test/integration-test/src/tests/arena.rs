@@ -0,0 +1,81 @@
+use assert_matches::assert_matches;
+use aya::{
+    Ebpf,
+    maps::{Arena, MapError, MmapArena},
+    programs::Syscall,
+    util::KernelVersion,
+};
+use integration_common::arena::Node;
+use test_log::test;
+
+#[test]
+fn arena() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(6, 9, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, arena maps were added in 6.9.0; see https://github.com/torvalds/linux/commit/317460317a02"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::ARENA_BPF).unwrap();
+    let prog: &mut Syscall = bpf.program_mut("arena_alloc").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+
+    let mut head = 0u64;
+    assert_eq!(prog.run(&mut head).unwrap(), 0);
+
+    let arena = Arena::try_from(bpf.map("ARENA").unwrap()).unwrap();
+    assert_eq!(arena.pages(), 2);
+    assert_eq!(arena.map_extra(), 1 << 44);
+
+    let memory = arena.mmap().unwrap();
+    assert_eq!(memory.as_ptr() as u64, 1 << 44);
+    assert!(memory.contains(head as *const u8));
+
+    assert_eq!(list_values(&memory, head), [1, 2]);
+
+    assert_matches!(
+        memory.ptr_at(memory.len()),
+        Err(MapError::OutOfBounds { .. })
+    );
+}
+
+#[test]
+fn arena_rust() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(6, 9, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, arena maps were added in 6.9.0; see https://github.com/torvalds/linux/commit/317460317a02"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::ARENA).unwrap();
+
+    // The arena has no user space address until it's mapped, and the verifier
+    // rejects programs using it until then.
+    let arena = Arena::try_from(bpf.take_map("ARENA").unwrap()).unwrap();
+    assert_eq!(arena.map_extra(), 0);
+    let memory = arena.mmap().unwrap();
+
+    let prog: &mut Syscall = bpf.program_mut("arena_alloc").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+
+    let mut head = 0u64;
+    assert_eq!(prog.run(&mut head).unwrap(), 0);
+    assert!(memory.contains(head as *const u8));
+    assert_eq!(list_values(&memory, head), [1, 2]);
+}
+
+fn list_values(memory: &MmapArena<'_>, head: u64) -> Vec<u64> {
+    let mut values = Vec::new();
+    let mut node = head as *const Node;
+    while !node.is_null() {
+        assert!(memory.contains(node.cast()));
+        let Node { next, value } = unsafe { node.read_volatile() };
+        values.push(value);
+        node = next;
+    }
+    values
+}
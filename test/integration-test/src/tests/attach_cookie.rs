@@ -0,0 +1,46 @@
+use std::{thread, time::Duration};
+
+use aya::{
+    Ebpf,
+    maps::Array,
+    programs::{KProbe, TracePoint},
+    util::KernelVersion,
+};
+use test_log::test;
+
+#[test]
+fn attach_cookie() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 15, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, bpf_get_attach_cookie was added in 5.15.0; see https://github.com/torvalds/linux/commit/7adfc6c9b315"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::ATTACH_COOKIE).unwrap();
+
+    let prog: &mut KProbe = bpf
+        .program_mut("kprobe_cookie")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    prog.load().unwrap();
+    prog.attach_with_cookie("try_to_wake_up", 0, 1234).unwrap();
+
+    let prog: &mut TracePoint = bpf
+        .program_mut("tracepoint_cookie")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    prog.load().unwrap();
+    prog.attach_with_cookie("sched", "sched_switch", 5678)
+        .unwrap();
+
+    // Sleeping wakes up and switches tasks, which triggers both programs.
+    thread::sleep(Duration::from_millis(10));
+
+    let cookies = Array::<_, u64>::try_from(bpf.map("COOKIES").unwrap()).unwrap();
+    assert_eq!(cookies.get(&0, 0).unwrap(), 1234);
+    assert_eq!(cookies.get(&1, 0).unwrap(), 5678);
+}
@@ -0,0 +1,36 @@
+use aya::{Ebpf, maps::Array, programs::UProbe, util::KernelVersion};
+use test_log::test;
+
+#[test]
+fn bpf_loop() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 17, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, bpf_loop was added in 5.17.0; see https://github.com/torvalds/linux/commit/e6f2dd0f8067"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::BPF_LOOP).unwrap();
+    let prog: &mut UProbe = bpf
+        .program_mut("test_bpf_loop")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    prog.load().unwrap();
+    prog.attach("trigger_bpf_loop", "/proc/self/exe", None, None)
+        .unwrap();
+
+    let array = Array::<_, u64>::try_from(bpf.map("RESULT").unwrap()).unwrap();
+    for nr_loops in [0, 1, 10] {
+        trigger_bpf_loop(nr_loops);
+        let expected = (0..u64::from(nr_loops)).sum::<u64>();
+        assert_eq!(array.get(&0, 0).unwrap(), expected);
+    }
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub extern "C" fn trigger_bpf_loop(nr_loops: u32) {
+    core::hint::black_box(nr_loops);
+}
@@ -0,0 +1,115 @@
+use aya::{
+    features::{
+        is_helper_supported, is_link_type_supported, is_map_supported, is_program_supported,
+    },
+    maps::MapType,
+    programs::{LinkType, ProgramType},
+    util::KernelVersion,
+};
+use aya_obj::generated::bpf_func_id;
+use test_log::test;
+
+#[test]
+fn probe_supported_programs() {
+    let current = KernelVersion::current().unwrap();
+
+    assert!(is_program_supported(ProgramType::SocketFilter).unwrap());
+    assert!(is_program_supported(ProgramType::KProbe).unwrap());
+    assert!(is_program_supported(ProgramType::Xdp).unwrap());
+    assert_eq!(
+        is_program_supported(ProgramType::Tracing).unwrap(),
+        current >= KernelVersion::new(5, 5, 0)
+    );
+    assert_eq!(
+        is_program_supported(ProgramType::Extension).unwrap(),
+        current >= KernelVersion::new(5, 6, 0)
+    );
+    assert_eq!(
+        is_program_supported(ProgramType::SkLookup).unwrap(),
+        current >= KernelVersion::new(5, 9, 0)
+    );
+    assert_eq!(
+        is_program_supported(ProgramType::Syscall).unwrap(),
+        current >= KernelVersion::new(5, 14, 0)
+    );
+    assert_eq!(
+        is_program_supported(ProgramType::Netfilter).unwrap(),
+        current >= KernelVersion::new(6, 4, 0)
+    );
+}
+
+#[test]
+fn probe_supported_maps() {
+    let current = KernelVersion::current().unwrap();
+
+    assert!(is_map_supported(MapType::Hash).unwrap());
+    assert!(is_map_supported(MapType::Array).unwrap());
+    assert!(is_map_supported(MapType::LpmTrie).unwrap());
+    assert!(is_map_supported(MapType::ArrayOfMaps).unwrap());
+    assert!(is_map_supported(MapType::CgroupStorage).unwrap());
+    assert!(is_map_supported(MapType::Queue).unwrap());
+    assert_eq!(
+        is_map_supported(MapType::RingBuf).unwrap(),
+        current >= KernelVersion::new(5, 8, 0)
+    );
+    assert_eq!(
+        is_map_supported(MapType::BloomFilter).unwrap(),
+        current >= KernelVersion::new(5, 16, 0)
+    );
+    assert_eq!(
+        is_map_supported(MapType::UserRingBuf).unwrap(),
+        current >= KernelVersion::new(6, 1, 0)
+    );
+}
+
+#[test]
+fn probe_supported_helpers() {
+    let current = KernelVersion::current().unwrap();
+
+    assert!(
+        is_helper_supported(
+            ProgramType::SocketFilter,
+            bpf_func_id::BPF_FUNC_map_lookup_elem as u32
+        )
+        .unwrap()
+    );
+    // bpf_override_return is only available to kprobes.
+    assert!(
+        !is_helper_supported(
+            ProgramType::SocketFilter,
+            bpf_func_id::BPF_FUNC_override_return as u32
+        )
+        .unwrap()
+    );
+    assert!(!is_helper_supported(ProgramType::SocketFilter, u32::MAX / 2).unwrap());
+    assert_eq!(
+        is_helper_supported(ProgramType::KProbe, bpf_func_id::BPF_FUNC_loop as u32).unwrap(),
+        current >= KernelVersion::new(5, 17, 0)
+    );
+}
+
+#[test]
+fn probe_supported_link_types() {
+    let current = KernelVersion::current().unwrap();
+
+    assert_eq!(
+        is_link_type_supported(LinkType::Cgroup).unwrap(),
+        current >= KernelVersion::new(5, 7, 0)
+    );
+    assert_eq!(
+        is_link_type_supported(LinkType::Netns).unwrap(),
+        current >= KernelVersion::new(5, 8, 0)
+    );
+    assert_eq!(
+        is_link_type_supported(LinkType::PerfEvent).unwrap(),
+        current >= KernelVersion::new(5, 15, 0)
+    );
+    assert_eq!(
+        is_link_type_supported(LinkType::Tcx).unwrap(),
+        current >= KernelVersion::new(6, 6, 0)
+    );
+    assert_eq!(
+        is_link_type_supported(LinkType::UProbeMulti).unwrap(),
+        current >= KernelVersion::new(6, 6, 0)
+    );
+}
@@ -0,0 +1,38 @@
+use aya::{Btf, Ebpf, programs::FModRet, util::KernelVersion};
+use test_log::test;
+
+#[test]
+fn fmod_ret() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 7, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, fmod_ret was added in 5.7.0; see https://github.com/torvalds/linux/commit/ae24082331d9"
+        );
+        return;
+    }
+
+    let btf = Btf::from_sys_fs().unwrap();
+    let mut bpf = Ebpf::load(crate::FMOD_RET).unwrap();
+    let prog: &mut FModRet = bpf
+        .program_mut("modify_return")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    prog.load("bpf_modify_return_test", &btf).unwrap();
+
+    // Running a tracing program calls `bpf_modify_return_test(1, &b)`, which
+    // increments `b` from 2 to 3 and returns `1 + b`. The run returns the sum
+    // of the return values of the test functions in the lower 16 bits, and how
+    // many of them had side effects on `b` in the upper 16 bits. Newer
+    // kernels call more test functions, so only the difference is checked.
+    let test_run = |prog: &FModRet| prog.test_run(Default::default()).unwrap().return_value;
+    let unmodified = test_run(prog);
+
+    let link = prog.attach().unwrap();
+    // The program skips the function, returning 42 instead of 4 without
+    // incrementing `b`.
+    assert_eq!(test_run(prog), unmodified - (1 << 16) - 4 + 42);
+
+    prog.detach(link).unwrap();
+    assert_eq!(test_run(prog), unmodified);
+}
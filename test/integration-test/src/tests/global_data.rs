@@ -0,0 +1,57 @@
+use assert_matches::assert_matches;
+use aya::{Ebpf, GlobalError, programs::UProbe, util::KernelVersion};
+use test_log::test;
+
+#[test]
+fn global_data() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 5, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, mmapable arrays were added in 5.5.0; see https://github.com/torvalds/linux/commit/fc9702273e2e"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::GLOBAL_DATA).unwrap();
+    let prog: &mut UProbe = bpf.program_mut("global_data").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+    prog.attach("trigger_global_data", "/proc/self/exe", None, None)
+        .unwrap();
+
+    assert_eq!(bpf.global::<u64>("MAX").unwrap().read(), 100);
+
+    trigger_global_data();
+    assert_eq!(bpf.global::<u64>("COUNTER").unwrap().read(), 1);
+
+    bpf.global_mut::<u64>("INCREMENT").unwrap().write(10);
+    trigger_global_data();
+    assert_eq!(bpf.global::<u64>("COUNTER").unwrap().read(), 11);
+
+    let mut counter = bpf.global_mut::<u64>("COUNTER").unwrap();
+    counter.write(95);
+    trigger_global_data();
+    assert_eq!(counter.read(), 95);
+
+    assert_matches!(
+        bpf.global_mut::<u64>("MAX"),
+        Err(GlobalError::ReadOnly { name }) if name == "MAX"
+    );
+    assert_matches!(
+        bpf.global::<u32>("COUNTER"),
+        Err(GlobalError::InvalidSize {
+            size: 8,
+            expected: 4,
+            ..
+        })
+    );
+    assert_matches!(
+        bpf.global::<u64>("MISSING"),
+        Err(GlobalError::NotFound { name }) if name == "MISSING"
+    );
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub extern "C" fn trigger_global_data() {
+    core::hint::black_box(());
+}
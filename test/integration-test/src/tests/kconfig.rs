@@ -0,0 +1,39 @@
+use aya::{EbpfLoader, maps::Array, programs::UProbe, util::KernelVersion};
+use test_log::test;
+
+#[test]
+fn kconfig() {
+    let mut bpf = EbpfLoader::new()
+        .kconfig(
+            "\
+CONFIG_BPF=y
+CONFIG_BPF_JIT=m
+CONFIG_HZ=250
+CONFIG_DEFAULT_HOSTNAME=\"(none)\"
+",
+        )
+        .load(crate::KCONFIG)
+        .unwrap();
+
+    let prog: &mut UProbe = bpf.program_mut("kconfig").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+    prog.attach("trigger_kconfig", "/proc/self/exe", None, None)
+        .unwrap();
+
+    trigger_kconfig();
+
+    let results = Array::<_, u64>::try_from(bpf.map("RESULTS").unwrap()).unwrap();
+    let kernel_version = KernelVersion::current().unwrap().code();
+    assert_eq!(results.get(&0, 0).unwrap(), u64::from(kernel_version));
+    assert_eq!(results.get(&1, 0).unwrap(), 1);
+    assert_eq!(results.get(&2, 0).unwrap(), 2);
+    assert_eq!(results.get(&3, 0).unwrap(), 250);
+    assert_eq!(results.get(&4, 0).unwrap(), u64::from(b'n'));
+    assert_eq!(results.get(&5, 0).unwrap(), 0);
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub extern "C" fn trigger_kconfig() {
+    core::hint::black_box(trigger_kconfig);
+}
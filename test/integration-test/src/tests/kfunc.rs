@@ -0,0 +1,33 @@
+use aya::{Ebpf, maps::Array, programs::UProbe, util::KernelVersion};
+use test_log::test;
+
+#[test]
+fn kfunc() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(6, 4, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, support for checking the existence of kfuncs was added in 6.4.0"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::KFUNC).unwrap();
+
+    let prog: &mut UProbe = bpf.program_mut("kfunc").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+    prog.attach("trigger_kfunc", "/proc/self/exe", None, None)
+        .unwrap();
+
+    trigger_kfunc();
+
+    let results = Array::<_, u64>::try_from(bpf.map("RESULTS").unwrap()).unwrap();
+    assert_eq!(results.get(&0, 0).unwrap(), 1);
+    // the missing weak kfunc is never called
+    assert_eq!(results.get(&1, 0).unwrap(), 0);
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub extern "C" fn trigger_kfunc() {
+    core::hint::black_box(trigger_kfunc);
+}
@@ -0,0 +1,90 @@
+use assert_matches::assert_matches;
+use aya::{
+    Ebpf, EbpfLoader,
+    maps::ring_buf::RingBuf,
+    programs::{KProbe, KProbeError, KProbeMultiTarget, ProgramError},
+    util::KernelVersion,
+};
+use test_log::test;
+
+// Called by getpid(2), which `std::process::id` calls.
+const GETPID_FUNCTION: &str = "__task_pid_nr_ns";
+
+fn load() -> Option<Ebpf> {
+    const RING_BUF_BYTE_SIZE: u32 = 4096; // arbitrary, but big enough
+
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 18, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, kprobe.multi was added in 5.18.0; see https://github.com/torvalds/linux/commit/0dcac2725406"
+        );
+        return None;
+    }
+
+    let mut bpf = EbpfLoader::new()
+        .set_max_entries("RING_BUF", RING_BUF_BYTE_SIZE)
+        .load(crate::KPROBE_MULTI)
+        .unwrap();
+    prog_mut(&mut bpf).load().unwrap();
+    Some(bpf)
+}
+
+fn prog_mut(bpf: &mut Ebpf) -> &mut KProbe {
+    bpf.program_mut("kprobe_multi").unwrap().try_into().unwrap()
+}
+
+fn cookies(bpf: &mut Ebpf) -> Vec<u64> {
+    let mut ring_buf = RingBuf::try_from(bpf.map_mut("RING_BUF").unwrap()).unwrap();
+    let mut cookies = Vec::new();
+    while let Some(read) = ring_buf.next() {
+        let read = read.as_ref();
+        match read.try_into() {
+            Ok(read) => cookies.push(u64::from_le_bytes(read)),
+            Err(std::array::TryFromSliceError { .. }) => {
+                panic!("invalid ring buffer data: {read:x?}")
+            }
+        }
+    }
+    cookies
+}
+
+#[test]
+fn kprobe_multi_symbols() {
+    let Some(mut bpf) = load() else {
+        return;
+    };
+    let prog = prog_mut(&mut bpf);
+    let link = prog
+        .attach_multi(KProbeMultiTarget::Symbols(&[(GETPID_FUNCTION, 42)]))
+        .unwrap();
+
+    let _pid = std::process::id();
+    prog.detach(link).unwrap();
+
+    let cookies = cookies(&mut bpf);
+    assert!(cookies.contains(&42), "{cookies:?}");
+}
+
+#[test]
+fn kprobe_multi_pattern() {
+    let Some(mut bpf) = load() else {
+        return;
+    };
+    let prog = prog_mut(&mut bpf);
+    let link = prog
+        .attach_multi(KProbeMultiTarget::Pattern("__task_pid_nr_n?"))
+        .unwrap();
+
+    let _pid = std::process::id();
+    prog.detach(link).unwrap();
+
+    let cookies = cookies(&mut bpf);
+    assert!(!cookies.is_empty());
+
+    assert_matches!(
+        prog_mut(&mut bpf).attach_multi(KProbeMultiTarget::Pattern("aya_no_such_function_*")),
+        Err(ProgramError::KProbeError(KProbeError::NoMatchingFunctions { pattern })) => {
+            assert_eq!(pattern, "aya_no_such_function_*");
+        }
+    );
+}
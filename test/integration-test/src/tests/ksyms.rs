@@ -0,0 +1,32 @@
+use aya::{Ebpf, maps::Array, programs::UProbe, util::kernel_symbols};
+use test_log::test;
+
+#[test]
+fn ksyms() {
+    let mut bpf = Ebpf::load(crate::KSYMS).unwrap();
+
+    let prog: &mut UProbe = bpf.program_mut("ksyms").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+    prog.attach("trigger_ksyms", "/proc/self/exe", None, None)
+        .unwrap();
+
+    trigger_ksyms();
+
+    let bpf_prog_active = kernel_symbols()
+        .unwrap()
+        .into_iter()
+        .find_map(|(address, name)| (name == "bpf_prog_active").then_some(address))
+        .unwrap();
+
+    let results = Array::<_, u64>::try_from(bpf.map("RESULTS").unwrap()).unwrap();
+    // `runqueues` resolved through BTF, for CPU 0
+    assert_eq!(results.get(&0, 0).unwrap(), 1);
+    assert_eq!(results.get(&1, 0).unwrap(), bpf_prog_active);
+    assert_eq!(results.get(&2, 0).unwrap(), 0);
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub extern "C" fn trigger_ksyms() {
+    core::hint::black_box(trigger_ksyms);
+}
@@ -0,0 +1,89 @@
+use std::{
+    fs::File,
+    io,
+    net::{Ipv4Addr, UdpSocket},
+    os::fd::{FromRawFd as _, OwnedFd},
+};
+
+use assert_matches::assert_matches;
+use aya::{
+    Ebpf,
+    maps::{Array, CgrpStorage, MapError, SkStorage, TaskStorage},
+    programs::UProbe,
+    util::KernelVersion,
+};
+use test_log::test;
+
+fn pidfd_open(pid: u32) -> io::Result<OwnedFd> {
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+}
+
+#[test]
+fn local_storage_user_space() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(6, 2, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, cgroup local storage was added in 6.2.0; see https://github.com/torvalds/linux/commit/c4bcfb38a95e"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::LOCAL_STORAGE).unwrap();
+
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let mut sockets: SkStorage<_, u64> = bpf.map_mut("SOCKETS").unwrap().try_into().unwrap();
+    assert_matches!(sockets.get(&socket, 0), Err(MapError::KeyNotFound));
+    sockets.insert(&socket, 42, 0).unwrap();
+    assert_eq!(sockets.get(&socket, 0).unwrap(), 42);
+    sockets.remove(&socket).unwrap();
+    assert_matches!(sockets.get(&socket, 0), Err(MapError::KeyNotFound));
+
+    let pidfd = pidfd_open(std::process::id()).unwrap();
+    let mut tasks: TaskStorage<_, u64> = bpf.map_mut("TASKS").unwrap().try_into().unwrap();
+    tasks.insert(&pidfd, 1, 0).unwrap();
+    assert_eq!(tasks.get(&pidfd, 0).unwrap(), 1);
+    tasks.remove(&pidfd).unwrap();
+    assert_matches!(tasks.get(&pidfd, 0), Err(MapError::KeyNotFound));
+
+    let cgroup = File::open("/sys/fs/cgroup").unwrap();
+    let mut cgroups: CgrpStorage<_, u64> = bpf.map_mut("CGROUPS").unwrap().try_into().unwrap();
+    cgroups.insert(&cgroup, 7, 0).unwrap();
+    assert_eq!(cgroups.get(&cgroup, 0).unwrap(), 7);
+    cgroups.remove(&cgroup).unwrap();
+    assert_matches!(cgroups.get(&cgroup, 0), Err(MapError::KeyNotFound));
+}
+
+#[test]
+fn task_storage() {
+    let kernel_version = KernelVersion::current().unwrap();
+    // The object also holds a cgroup local storage map.
+    if kernel_version < KernelVersion::new(6, 2, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, cgroup local storage was added in 6.2.0; see https://github.com/torvalds/linux/commit/c4bcfb38a95e"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::LOCAL_STORAGE).unwrap();
+    let prog: &mut UProbe = bpf.program_mut("task_storage").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+    prog.attach("trigger_task_storage", "/proc/self/exe", None, None)
+        .unwrap();
+
+    // The storage is created on the first call and kept for the next ones.
+    trigger_task_storage();
+    trigger_task_storage();
+
+    let result: Array<_, u64> = bpf.map("RESULT").unwrap().try_into().unwrap();
+    assert_eq!(result.get(&0, 0).unwrap(), 2);
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub extern "C" fn trigger_task_storage() {
+    core::hint::black_box(trigger_task_storage);
+}
@@ -0,0 +1,59 @@
+use std::{
+    fs::File,
+    io,
+    net::{Ipv4Addr, UdpSocket},
+    os::unix::fs::MetadataExt as _,
+};
+
+use aya::{
+    Btf, Ebpf,
+    maps::{Array, CgroupStorage, CgroupStorageKey},
+    programs::{CgroupAttachMode, LsmCgroup},
+    util::KernelVersion,
+};
+use aya_obj::generated::bpf_attach_type::BPF_LSM_CGROUP;
+use test_log::test;
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+#[test]
+fn lsm_cgroup() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(6, 0, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, lsm_cgroup programs were added in 6.0.0; see https://github.com/torvalds/linux/commit/69fd337a975c"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::LSM_CGROUP).unwrap();
+    let btf = Btf::from_sys_fs().unwrap();
+
+    // Only deny the binds of this thread, other tests run concurrently in the
+    // same cgroup.
+    let tid = unsafe { libc::gettid() } as u32;
+    let mut target: Array<_, u32> = bpf.map_mut("TARGET_TID").unwrap().try_into().unwrap();
+    target.set(0, tid, 0).unwrap();
+
+    let cgroup = File::open(CGROUP_ROOT).unwrap();
+    let cgroup_id = cgroup.metadata().unwrap().ino();
+    let prog: &mut LsmCgroup = bpf.program_mut("deny_bind").unwrap().try_into().unwrap();
+    prog.load("socket_bind", &btf).unwrap();
+    let link_id = prog
+        .attach(&cgroup, CgroupAttachMode::AllowMultiple)
+        .unwrap();
+
+    let err = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied, "{err}");
+
+    {
+        let denied: CgroupStorage<_, u64> = bpf.map("DENIED").unwrap().try_into().unwrap();
+        let key = CgroupStorageKey::new(cgroup_id, BPF_LSM_CGROUP as u32);
+        assert_eq!(denied.get(&key, 0).unwrap(), 1);
+    }
+
+    let prog: &mut LsmCgroup = bpf.program_mut("deny_bind").unwrap().try_into().unwrap();
+    prog.detach(link_id).unwrap();
+
+    let _socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+}
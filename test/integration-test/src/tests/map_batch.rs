@@ -0,0 +1,44 @@
+use aya::{
+    Ebpf,
+    maps::{Array, HashMap},
+};
+use test_log::test;
+
+#[test]
+fn hash_map_batch() {
+    let mut bpf = Ebpf::load(crate::MAP_TEST).unwrap();
+    let mut map = HashMap::<_, u32, u8>::try_from(bpf.map_mut("BAR").unwrap()).unwrap();
+
+    map.insert_batch(&[(1, 10), (2, 20), (3, 30), (4, 40)], 0)
+        .unwrap();
+    let mut entries = map.iter_batched(3).collect::<Result<Vec<_>, _>>().unwrap();
+    entries.sort();
+    assert_eq!(entries, [(1, 10), (2, 20), (3, 30), (4, 40)]);
+
+    map.remove_batch(&[1, 3]).unwrap();
+    let mut entries = map.drain_batched(1).collect::<Result<Vec<_>, _>>().unwrap();
+    entries.sort();
+    assert_eq!(entries, [(2, 20), (4, 40)]);
+    assert_eq!(map.keys().count(), 0);
+}
+
+#[test]
+fn array_batch() {
+    let mut bpf = Ebpf::load(crate::MAP_TEST).unwrap();
+    let mut array = Array::<_, u32>::try_from(bpf.map_mut("FOO").unwrap()).unwrap();
+
+    array.set_batch(&[(1, 100), (7, 700)], 0).unwrap();
+    let entries = array
+        .iter_batched(4)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(entries.len(), 10);
+    for (index, value) in entries {
+        let expected = match index {
+            1 => 100,
+            7 => 700,
+            _ => 0,
+        };
+        assert_eq!(value, expected, "index {index}");
+    }
+}
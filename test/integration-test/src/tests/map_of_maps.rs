@@ -0,0 +1,88 @@
+use assert_matches::assert_matches;
+use aya::{
+    Ebpf,
+    maps::{Array, ArrayOfMaps, HashOfMaps, Map, MapError},
+    programs::UProbe,
+};
+use test_log::test;
+
+#[test]
+fn map_of_maps() {
+    let mut bpf = Ebpf::load(crate::MAP_OF_MAPS).unwrap();
+    let prog: &mut UProbe = bpf.program_mut("map_of_maps").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+    prog.attach("trigger_map_of_maps", "/proc/self/exe", None, None)
+        .unwrap();
+
+    let inner_0 = bpf.take_map("INNER_0").unwrap();
+    let inner_1 = bpf.take_map("INNER_1").unwrap();
+    let (Map::Array(inner_0_data), Map::Array(inner_1_data)) = (&inner_0, &inner_1) else {
+        panic!("inner maps should be arrays");
+    };
+    let inner_0_id = inner_0_data.info().unwrap().id();
+    let inner_1_id = inner_1_data.info().unwrap().id();
+
+    let mut outer_array = ArrayOfMaps::try_from(bpf.take_map("OUTER_ARRAY").unwrap()).unwrap();
+    outer_array.set(0, inner_0_data, 0).unwrap();
+    assert_eq!(outer_array.get(&0, 0).unwrap(), inner_0_id);
+
+    let mut outer_hash =
+        HashOfMaps::<_, u32>::try_from(bpf.take_map("OUTER_HASH").unwrap()).unwrap();
+    outer_hash.insert(42, inner_1_data, 0).unwrap();
+    assert_eq!(outer_hash.get(&42, 0).unwrap(), inner_1_id);
+    assert_eq!(
+        outer_hash.keys().collect::<Result<Vec<_>, _>>().unwrap(),
+        [42]
+    );
+
+    trigger_map_of_maps(10);
+
+    let array_0 = Array::<_, u32>::try_from(&inner_0).unwrap();
+    let array_1 = Array::<_, u32>::try_from(&inner_1).unwrap();
+    assert_eq!(array_0.get(&0, 0).unwrap(), 10);
+    assert_eq!(array_1.get(&0, 0).unwrap(), 11);
+
+    // Swap the map stored in the array, the program now writes to both maps
+    // through INNER_1.
+    outer_array.set(0, inner_1_data, 0).unwrap();
+    assert_eq!(outer_array.get(&0, 0).unwrap(), inner_1_id);
+
+    trigger_map_of_maps(20);
+
+    assert_eq!(array_0.get(&0, 0).unwrap(), 10);
+    assert_eq!(array_1.get(&0, 0).unwrap(), 21);
+
+    outer_array.clear_index(&0).unwrap();
+    outer_hash.remove(&42).unwrap();
+
+    trigger_map_of_maps(30);
+
+    assert_eq!(array_0.get(&0, 0).unwrap(), 10);
+    assert_eq!(array_1.get(&0, 0).unwrap(), 21);
+}
+
+#[test]
+fn map_of_maps_values() {
+    let mut bpf = Ebpf::load(crate::MAP_OF_MAPS_BPF).unwrap();
+
+    let inner_0 = bpf.take_map("inner_0").unwrap();
+    let inner_1 = bpf.take_map("inner_1").unwrap();
+    let (Map::Array(inner_0), Map::Array(inner_1)) = (&inner_0, &inner_1) else {
+        panic!("inner maps should be arrays");
+    };
+    let inner_0_id = inner_0.info().unwrap().id();
+    let inner_1_id = inner_1.info().unwrap().id();
+
+    // The maps are stored at the indices given by the `.values` initialiser.
+    let outer = ArrayOfMaps::try_from(bpf.take_map("outer").unwrap()).unwrap();
+    assert_eq!(outer.get(&1, 0).unwrap(), inner_0_id);
+    assert_eq!(outer.get(&3, 0).unwrap(), inner_1_id);
+    assert_matches!(outer.get(&0, 0), Err(MapError::KeyNotFound));
+    assert_matches!(outer.get(&2, 0), Err(MapError::KeyNotFound));
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub extern "C" fn trigger_map_of_maps(value: u32) {
+    core::hint::black_box(value);
+}
@@ -0,0 +1,75 @@
+use std::sync::atomic::Ordering;
+
+use assert_matches::assert_matches;
+use aya::{
+    Ebpf,
+    maps::{Array, MapError},
+    programs::UProbe,
+    util::KernelVersion,
+};
+use test_log::test;
+
+#[test]
+fn mmap_array() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 5, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, mmapable arrays were added in 5.5.0; see https://github.com/torvalds/linux/commit/fc9702273e2e"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::MMAP_ARRAY).unwrap();
+    let prog: &mut UProbe = bpf.program_mut("mmap_array").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+    prog.attach("trigger_mmap_array", "/proc/self/exe", None, None)
+        .unwrap();
+
+    let mut array = Array::<_, u64>::try_from(bpf.map_mut("VALUES").unwrap()).unwrap();
+    let mut values = array.mmap_mut().unwrap();
+    assert_eq!(values.len(), 4);
+
+    values.write(0, 41).unwrap();
+    trigger_mmap_array();
+    assert_eq!(values.read(1).unwrap(), 42);
+
+    values
+        .atomic_u64(2)
+        .unwrap()
+        .fetch_add(5, Ordering::Relaxed);
+    values.write(3, 7).unwrap();
+
+    assert_matches!(
+        values.read(4),
+        Err(MapError::OutOfBounds {
+            index: 4,
+            max_entries: 4
+        })
+    );
+    assert_matches!(
+        values.atomic_u32(0),
+        Err(MapError::InvalidValueSize {
+            size: 8,
+            expected: 4
+        })
+    );
+    drop(values);
+
+    assert_eq!(array.get(&2, 0).unwrap(), 5);
+    let read_only = array.mmap().unwrap();
+    assert_eq!(read_only.read(2).unwrap(), 5);
+    assert_eq!(read_only.read(3).unwrap(), 7);
+}
+
+#[test]
+fn mmap_array_not_mmapable() {
+    let bpf = Ebpf::load(crate::MAP_TEST).unwrap();
+    let array = Array::<_, u32>::try_from(bpf.map("FOO").unwrap()).unwrap();
+    assert_matches!(array.mmap(), Err(MapError::NotMmapable));
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub extern "C" fn trigger_mmap_array() {
+    core::hint::black_box(());
+}
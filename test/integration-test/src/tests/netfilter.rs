@@ -0,0 +1,65 @@
+use std::net::UdpSocket;
+
+use aya::{
+    Ebpf,
+    maps::Array,
+    programs::{Netfilter, NetfilterFlags, NetfilterHook, NetfilterProtocolFamily, links::FdLink},
+    util::KernelVersion,
+};
+use test_log::test;
+
+use crate::utils::NetNsGuard;
+
+#[test]
+fn netfilter() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(6, 4, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, netfilter programs were added in 6.4.0; see https://github.com/torvalds/linux/commit/84601d6ee68a"
+        );
+        return;
+    }
+
+    // Netfilter hooks are per network namespace.
+    let _netns = NetNsGuard::new();
+
+    let mut bpf = Ebpf::load(crate::NETFILTER).unwrap();
+    let prog: &mut Netfilter = bpf
+        .program_mut("count_packets")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    prog.load().unwrap();
+    let link_id = prog
+        .attach(
+            NetfilterProtocolFamily::Ipv4,
+            NetfilterHook::LocalOut,
+            -128,
+            NetfilterFlags::empty(),
+        )
+        .unwrap();
+
+    let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sock.send_to(b"hello", sock.local_addr().unwrap()).unwrap();
+
+    let packets: Array<_, u64> = Array::try_from(bpf.map("PACKETS").unwrap()).unwrap();
+    assert_eq!(packets.get(&0, 0).unwrap(), 1);
+
+    let prog: &mut Netfilter = bpf
+        .program_mut("count_packets")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let link = prog.take_link(link_id).unwrap();
+    let info = link.info().unwrap();
+    assert_eq!(info.protocol_family, NetfilterProtocolFamily::Ipv4);
+    assert_eq!(info.hook, NetfilterHook::LocalOut);
+    assert_eq!(info.priority, -128);
+    assert_eq!(info.flags, NetfilterFlags::empty());
+
+    // Dropping the link detaches the program.
+    let _: FdLink = link.into();
+    sock.send_to(b"hello", sock.local_addr().unwrap()).unwrap();
+    let packets: Array<_, u64> = Array::try_from(bpf.map("PACKETS").unwrap()).unwrap();
+    assert_eq!(packets.get(&0, 0).unwrap(), 1);
+}
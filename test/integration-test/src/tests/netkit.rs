@@ -0,0 +1,67 @@
+use aya::{
+    Ebpf, netlink_add_netkit,
+    programs::{LinkOrder, NetkitAttachType, SchedClassifier},
+    util::KernelVersion,
+};
+use test_log::test;
+
+use crate::utils::NetNsGuard;
+
+#[test]
+fn netkit() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(6, 7, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, netkit devices were added in 6.7.0; see https://github.com/torvalds/linux/commit/35dfaad7188c"
+        );
+        return;
+    }
+
+    let _netns = NetNsGuard::new();
+    unsafe { netlink_add_netkit(c"nk0") }.unwrap();
+
+    // Each program needs its own `Ebpf` instance since the same program can't
+    // be attached twice to the same device.
+    let mut first = Ebpf::load(crate::TCX).unwrap();
+    let first: &mut SchedClassifier = first.program_mut("tcx_next").unwrap().try_into().unwrap();
+    first.load().unwrap();
+    let first_link_id = first
+        .attach_netkit("nk0", NetkitAttachType::Primary, LinkOrder::default())
+        .unwrap();
+
+    let mut second = Ebpf::load(crate::TCX).unwrap();
+    let second: &mut SchedClassifier = second.program_mut("tcx_next").unwrap().try_into().unwrap();
+    second.load().unwrap();
+    second
+        .attach_netkit("nk0", NetkitAttachType::Primary, LinkOrder::first())
+        .unwrap();
+
+    let mut peer = Ebpf::load(crate::TCX).unwrap();
+    let peer: &mut SchedClassifier = peer.program_mut("tcx_next").unwrap().try_into().unwrap();
+    peer.load().unwrap();
+    peer.attach_netkit("nk0", NetkitAttachType::Peer, LinkOrder::default())
+        .unwrap();
+
+    let ids = |programs: &[aya::programs::ProgramInfo]| {
+        programs.iter().map(|p| p.id()).collect::<Vec<_>>()
+    };
+
+    let (revision, programs) =
+        SchedClassifier::query_netkit("nk0", NetkitAttachType::Primary).unwrap();
+    assert_eq!(revision, 3);
+    assert_eq!(
+        ids(&programs),
+        [second.info().unwrap().id(), first.info().unwrap().id()]
+    );
+
+    let (revision, programs) =
+        SchedClassifier::query_netkit("nk0", NetkitAttachType::Peer).unwrap();
+    assert_eq!(revision, 2);
+    assert_eq!(ids(&programs), [peer.info().unwrap().id()]);
+
+    first.detach(first_link_id).unwrap();
+    let (revision, programs) =
+        SchedClassifier::query_netkit("nk0", NetkitAttachType::Primary).unwrap();
+    assert_eq!(revision, 4);
+    assert_eq!(ids(&programs), [second.info().unwrap().id()]);
+}
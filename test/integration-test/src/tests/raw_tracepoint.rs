@@ -1,5 +1,26 @@
-use aya::{Ebpf, maps::Array, programs::RawTracePoint};
-use integration_common::raw_tracepoint::SysEnterEvent;
+use std::{
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read as _, Write as _},
+    os::{
+        fd::{AsRawFd as _, RawFd},
+        unix::net::UnixStream,
+    },
+    path::Path,
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+
+use assert_matches::assert_matches;
+use aya::{
+    Ebpf,
+    maps::Array,
+    programs::{ProgramError, RawTracePoint, RawTracePointWritable},
+    sys::SyscallError,
+    util::KernelVersion,
+};
+use integration_common::raw_tracepoint::{NBD_REQUEST_FROM, SysEnterEvent};
+use test_log::test;
 
 fn get_event(bpf: &mut Ebpf) -> SysEnterEvent {
     let map: Array<_, SysEnterEvent> = Array::try_from(bpf.map_mut("RESULT").unwrap()).unwrap();
@@ -38,3 +59,177 @@ fn raw_tracepoint() {
         assert_ne!(common_flags, 0);
     }
 }
+
+#[test]
+fn raw_tracepoint_writable() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 2, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, writable raw tracepoints were added in 5.2.0; see https://github.com/torvalds/linux/commit/9df1c28bb752"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::RAW_TRACEPOINT).unwrap();
+    let prog: &mut RawTracePointWritable = bpf
+        .program_mut("sys_enter_writable")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    prog.load().unwrap();
+
+    // sys_enter has no writable buffer, so the kernel rejects a program that
+    // writes to it.
+    assert_matches!(
+        prog.attach("sys_enter"),
+        Err(ProgramError::SyscallError(SyscallError {
+            call: "bpf_raw_tracepoint_open",
+            io_error,
+        })) if io_error.raw_os_error() == Some(libc::EINVAL)
+    );
+}
+
+// The ioctls of `linux/nbd.h`.
+const NBD_SET_SOCK: u64 = 0xab00;
+const NBD_SET_BLKSIZE: u64 = 0xab01;
+const NBD_DO_IT: u64 = 0xab03;
+const NBD_CLEAR_SOCK: u64 = 0xab04;
+const NBD_SET_SIZE_BLOCKS: u64 = 0xab07;
+const NBD_DISCONNECT: u64 = 0xab08;
+const NBD_SET_TIMEOUT: u64 = 0xab09;
+
+const NBD_REQUEST_MAGIC: u32 = 0x25609513;
+const NBD_REPLY_MAGIC: u32 = 0x67446698;
+const NBD_CMD_READ: u32 = 0;
+const NBD_CMD_WRITE: u32 = 1;
+const NBD_CMD_DISC: u32 = 2;
+
+fn nbd_ioctl(fd: RawFd, request: u64, arg: u64) {
+    let ret = unsafe { libc::ioctl(fd, request as _, arg) };
+    assert_eq!(
+        ret,
+        0,
+        "nbd ioctl {request:#x}: {}",
+        std::io::Error::last_os_error()
+    );
+}
+
+// Serves the requests of the nbd driver with zeroes until it disconnects, and
+// returns the `from` field of every request.
+fn serve_nbd(mut sock: UnixStream) -> Vec<u64> {
+    let mut froms = Vec::new();
+    loop {
+        // `struct nbd_request`, which is packed.
+        let mut request = [0; 28];
+        match sock.read_exact(&mut request) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return froms,
+            Err(err) => panic!("failed to read nbd request: {err}"),
+        }
+        let magic = u32::from_be_bytes(request[0..4].try_into().unwrap());
+        assert_eq!(magic, NBD_REQUEST_MAGIC);
+        let cmd = u32::from_be_bytes(request[4..8].try_into().unwrap()) & 0xffff;
+        let cookie = &request[8..16];
+        let from = u64::from_be_bytes(request[16..24].try_into().unwrap());
+        let len = u32::from_be_bytes(request[24..28].try_into().unwrap()) as usize;
+        if cmd == NBD_CMD_DISC {
+            return froms;
+        }
+        froms.push(from);
+        if cmd == NBD_CMD_WRITE {
+            let mut data = vec![0; len];
+            sock.read_exact(&mut data).unwrap();
+        }
+
+        let mut reply = Vec::with_capacity(16 + len);
+        reply.extend_from_slice(&NBD_REPLY_MAGIC.to_be_bytes());
+        reply.extend_from_slice(&0u32.to_be_bytes());
+        reply.extend_from_slice(cookie);
+        if cmd == NBD_CMD_READ {
+            reply.resize(reply.len() + len, 0);
+        }
+        sock.write_all(&reply).unwrap();
+    }
+}
+
+#[test]
+fn raw_tracepoint_writable_nbd() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 2, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, writable raw tracepoints were added in 5.2.0; see https://github.com/torvalds/linux/commit/9df1c28bb752"
+        );
+        return;
+    }
+    // `nbd_send_request` is the only writable tracepoint outside of the kernel
+    // selftests, the program rewrites the requests sent to the nbd server.
+    match Command::new("/sbin/modprobe").arg("nbd").status() {
+        Ok(status) if status.success() => {}
+        result => {
+            eprintln!("skipping test, failed to load the nbd module: {result:?}");
+            return;
+        }
+    }
+
+    let mut bpf = Ebpf::load(crate::RAW_TRACEPOINT).unwrap();
+    let prog: &mut RawTracePointWritable = bpf
+        .program_mut("nbd_send_request")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    prog.load().unwrap();
+    // Tracepoints without a trace event gained a `_tp` suffix in 6.16.
+    match prog.attach("nbd_send_request") {
+        Ok(_) => {}
+        Err(ProgramError::SyscallError(SyscallError { io_error, .. }))
+            if io_error.raw_os_error() == Some(libc::ENOENT) =>
+        {
+            prog.attach("nbd_send_request_tp").unwrap();
+        }
+        Err(err) => panic!("failed to attach: {err}"),
+    }
+
+    let (client, server) = UnixStream::pair().unwrap();
+    let nbd = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/nbd0")
+        .unwrap();
+    let fd = nbd.as_raw_fd();
+    nbd_ioctl(fd, NBD_SET_BLKSIZE, 512);
+    nbd_ioctl(fd, NBD_SET_SIZE_BLOCKS, 8);
+    nbd_ioctl(fd, NBD_SET_TIMEOUT, 10);
+    nbd_ioctl(fd, NBD_SET_SOCK, client.as_raw_fd() as u64);
+    drop(client);
+
+    let server = thread::spawn(move || serve_nbd(server));
+    // Returns once the device is disconnected.
+    let device = thread::spawn(move || unsafe { libc::ioctl(fd, NBD_DO_IT as _, 0) });
+
+    // The pid of the thread serving the device is published once it's started.
+    let start = Instant::now();
+    while !Path::new("/sys/block/nbd0/pid").exists() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "nbd device not started"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+    let mut data = [0xff; 512];
+    File::open("/dev/nbd0")
+        .unwrap()
+        .read_exact(&mut data)
+        .unwrap();
+    assert_eq!(data, [0; 512]);
+
+    nbd_ioctl(fd, NBD_DISCONNECT, 0);
+    let froms = server.join().unwrap();
+    let _: i32 = device.join().unwrap();
+    nbd_ioctl(fd, NBD_CLEAR_SOCK, 0);
+
+    // Every request went through the program before being sent.
+    assert!(!froms.is_empty());
+    for from in froms {
+        assert_eq!(from, NBD_REQUEST_FROM);
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket},
+    os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd},
+};
+
+use assert_matches::assert_matches;
+use aya::{
+    Ebpf,
+    maps::ReusePortSockArray,
+    programs::{SkReuseport, SkReuseportKind},
+    util::KernelVersion,
+};
+use test_log::test;
+
+use crate::utils::NetNsGuard;
+
+/// Creates a UDP socket with `SO_REUSEPORT` set and binds it to `addr`.
+fn reuseport_socket(addr: SocketAddrV4) -> UdpSocket {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    assert!(fd >= 0, "socket: {}", io::Error::last_os_error());
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let enable: libc::c_int = 1;
+    let ret = unsafe {
+        libc::setsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_REUSEPORT,
+            std::ptr::from_ref(&enable).cast(),
+            size_of_val(&enable) as libc::socklen_t,
+        )
+    };
+    assert_eq!(ret, 0, "setsockopt: {}", io::Error::last_os_error());
+
+    let sockaddr = libc::sockaddr_in {
+        sin_family: libc::AF_INET as libc::sa_family_t,
+        sin_port: addr.port().to_be(),
+        sin_addr: libc::in_addr {
+            s_addr: u32::from(*addr.ip()).to_be(),
+        },
+        sin_zero: [0; 8],
+    };
+    let ret = unsafe {
+        libc::bind(
+            fd.as_raw_fd(),
+            std::ptr::from_ref(&sockaddr).cast(),
+            size_of_val(&sockaddr) as libc::socklen_t,
+        )
+    };
+    assert_eq!(ret, 0, "bind: {}", io::Error::last_os_error());
+
+    let socket = UdpSocket::from(fd);
+    socket.set_nonblocking(true).unwrap();
+    socket
+}
+
+#[test]
+fn sk_reuseport() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(4, 19, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, sk_reuseport programs were added in 4.19.0; see https://github.com/torvalds/linux/commit/2dbb9b9e6df6"
+        );
+        return;
+    }
+
+    let _netns = NetNsGuard::new();
+
+    let first = reuseport_socket(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0));
+    let SocketAddr::V4(addr) = first.local_addr().unwrap() else {
+        panic!("unexpected address family");
+    };
+    let second = reuseport_socket(addr);
+
+    let mut bpf = Ebpf::load(crate::SK_REUSEPORT).unwrap();
+    let mut workers: ReusePortSockArray<_> = bpf.take_map("WORKERS").unwrap().try_into().unwrap();
+    workers.set(0, &first, 0).unwrap();
+    workers.set(1, &second, 0).unwrap();
+
+    let prog: &mut SkReuseport = bpf
+        .program_mut("select_worker")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_matches!(prog.kind(), SkReuseportKind::Select);
+    prog.load().unwrap();
+    prog.attach(&first).unwrap();
+
+    let client = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    const DATAGRAMS: usize = 8;
+    for _ in 0..DATAGRAMS {
+        client.send_to(b"hello", addr).unwrap();
+    }
+
+    // Every datagram is steered to the socket at index 1.
+    let mut buf = [0u8; 16];
+    for _ in 0..DATAGRAMS {
+        assert_eq!(second.recv(&mut buf).unwrap(), 5);
+    }
+    assert_eq!(
+        first.recv(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+}
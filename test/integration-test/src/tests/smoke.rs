@@ -1,6 +1,9 @@
+use std::sync::Arc;
+
 use aya::{
-    Ebpf, EbpfLoader,
-    programs::{Extension, TracePoint, Xdp, XdpFlags, tc},
+    Btf, BtfError, Ebpf, EbpfLoader,
+    maps::Array,
+    programs::{Extension, FEntry, TracePoint, Xdp, XdpFlags, tc},
     util::KernelVersion,
 };
 use test_log::test;
@@ -84,3 +87,48 @@ fn extension() {
         .load(pass.fd().unwrap().try_clone().unwrap(), "xdp_pass")
         .unwrap();
 }
+
+#[test]
+fn module_btf() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 11, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, support for module BTF was added in 5.11.0; see https://github.com/torvalds/linux/commit/36e68442"
+        );
+        return;
+    }
+
+    // Adding a clsact qdisc loads the sch_ingress module, see the modprobe test.
+    {
+        let _netns = NetNsGuard::new();
+        tc::qdisc_add_clsact("lo").unwrap();
+    }
+
+    let vmlinux = Arc::new(Btf::from_sys_fs().unwrap());
+    let btf = match Btf::from_sys_fs_module("sch_ingress", vmlinux) {
+        Ok(btf) => btf,
+        Err(BtfError::FileError { path, error }) => {
+            eprintln!(
+                "skipping test, sch_ingress isn't a kernel module: {}: {error}",
+                path.display()
+            );
+            return;
+        }
+        Err(err) => panic!("failed to load the BTF of sch_ingress: {err}"),
+    };
+    assert_eq!(btf.module_name(), Some("sch_ingress"));
+
+    let mut bpf = Ebpf::load(crate::MODULE_BTF).unwrap();
+    let prog: &mut FEntry = bpf.program_mut("clsact_init").unwrap().try_into().unwrap();
+    prog.load("clsact_init", &btf).unwrap();
+    prog.attach().unwrap();
+
+    {
+        let _netns = NetNsGuard::new();
+        tc::qdisc_add_clsact("lo").unwrap();
+    }
+
+    // other tests adding clsact qdiscs concurrently may trigger the program too
+    let calls = Array::<_, u64>::try_from(bpf.map("CALLS").unwrap()).unwrap();
+    assert_ne!(calls.get(&0, 0).unwrap(), 0);
+}
@@ -0,0 +1,74 @@
+use std::fs;
+
+use aya::{
+    Ebpf,
+    maps::StructOpsMap,
+    programs::{StructOps, links::FdLink},
+    util::KernelVersion,
+};
+use test_log::test;
+
+fn congestion_control_available(name: &str) -> bool {
+    fs::read_to_string("/proc/sys/net/ipv4/tcp_available_congestion_control")
+        .unwrap()
+        .split_whitespace()
+        .any(|available| available == name)
+}
+
+fn load() -> Ebpf {
+    let mut bpf = Ebpf::load(crate::STRUCT_OPS).unwrap();
+    for name in ["aya_ssthresh", "aya_cong_avoid", "aya_undo_cwnd"] {
+        let prog: &mut StructOps = bpf.program_mut(name).unwrap().try_into().unwrap();
+        prog.load().unwrap();
+    }
+    bpf
+}
+
+fn register(bpf: &mut Ebpf, map: &str) -> aya::maps::StructOpsLink {
+    let mut ops: StructOpsMap<_> = bpf.take_map(map).unwrap().try_into().unwrap();
+    ops.register(
+        bpf.programs()
+            .filter_map(|(name, prog)| Some((name, prog.fd().ok()?))),
+    )
+    .unwrap()
+}
+
+#[test]
+fn struct_ops_map() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 6, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, struct_ops was added in 5.6.0; see https://github.com/torvalds/linux/commit/27ae7997a661"
+        );
+        return;
+    }
+
+    let mut bpf = load();
+    assert!(!congestion_control_available("aya_cc_legacy"));
+
+    let link = register(&mut bpf, "aya_cc_legacy");
+    assert!(congestion_control_available("aya_cc_legacy"));
+    assert!(FdLink::try_from(link).is_err());
+
+    // the conversion failed and dropped the link, unregistering the struct_ops
+    assert!(!congestion_control_available("aya_cc_legacy"));
+}
+
+#[test]
+fn struct_ops_link() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(6, 4, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, struct_ops links were added in 6.4.0; see https://github.com/torvalds/linux/commit/68b04864ca42"
+        );
+        return;
+    }
+
+    let mut bpf = load();
+    let link = register(&mut bpf, "aya_cc");
+    assert!(congestion_control_available("aya_cc"));
+
+    let link = FdLink::try_from(link).unwrap();
+    drop(link);
+    assert!(!congestion_control_available("aya_cc"));
+}
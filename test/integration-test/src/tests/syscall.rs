@@ -0,0 +1,27 @@
+use aya::{Ebpf, maps::Array, programs::Syscall, util::KernelVersion};
+use integration_common::syscall::Args;
+use test_log::test;
+
+#[test]
+fn syscall() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 14, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, syscall programs were added in 5.14.0; see https://github.com/torvalds/linux/commit/79a7f8bdb159"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::SYSCALL).unwrap();
+    let prog: &mut Syscall = bpf.program_mut("double").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+
+    let mut args = Args { value: 21 };
+    assert_eq!(prog.run(&mut args).unwrap(), 42);
+    assert_eq!(args.value, 42);
+    assert_eq!(prog.run(&mut args).unwrap(), 42);
+    assert_eq!(args.value, 84);
+
+    let runs: Array<_, u64> = Array::try_from(bpf.map("RUNS").unwrap()).unwrap();
+    assert_eq!(runs.get(&0, 0).unwrap(), 2);
+}
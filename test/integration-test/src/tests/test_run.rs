@@ -0,0 +1,117 @@
+use assert_matches::assert_matches;
+use aya::{
+    Ebpf,
+    maps::Array,
+    programs::{ProgramError, RawTracePoint, TestRunError, TestRunFlags, TestRunOptions, Xdp},
+    util::KernelVersion,
+};
+use test_log::test;
+
+// An Ethernet header followed by a payload byte.
+const PACKET: [u8; 15] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x88, 0xb5, 0x2a,
+];
+
+fn runs(bpf: &Ebpf) -> u64 {
+    let runs: Array<_, u64> = Array::try_from(bpf.map("RUNS").unwrap()).unwrap();
+    runs.get(&0, 0).unwrap()
+}
+
+#[test]
+fn test_run_xdp() {
+    let mut bpf = Ebpf::load(crate::TEST_RUN).unwrap();
+    let prog: &mut Xdp = bpf.program_mut("test_run_xdp").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+
+    let mut data_out = [0; 64];
+    let result = prog
+        .test_run(TestRunOptions {
+            data_in: Some(&PACKET),
+            data_out: Some(&mut data_out),
+            ..Default::default()
+        })
+        .unwrap();
+    const XDP_DROP: u32 = 1;
+    assert_eq!(result.return_value, XDP_DROP);
+    assert_eq!(result.data_size_out, PACKET.len());
+    let mut expected = PACKET;
+    expected[0] = expected[0].wrapping_add(1);
+    assert_eq!(&data_out[..result.data_size_out], expected);
+    assert_eq!(runs(&bpf), 1);
+}
+
+#[test]
+fn test_run_xdp_data_out_too_small() {
+    let mut bpf = Ebpf::load(crate::TEST_RUN).unwrap();
+    let prog: &mut Xdp = bpf.program_mut("test_run_xdp").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+
+    let mut data_out = [0; 4];
+    assert_matches!(
+        prog.test_run(TestRunOptions {
+            data_in: Some(&PACKET),
+            data_out: Some(&mut data_out),
+            ..Default::default()
+        }),
+        Err(ProgramError::TestRunError(TestRunError::OutputTooSmall { result })) => {
+            assert_eq!(result.data_size_out, PACKET.len());
+        }
+    );
+    // The packet is truncated to the size of the buffer.
+    assert_eq!(data_out, [PACKET[0].wrapping_add(1), 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn test_run_xdp_live_frames() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 18, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, BPF_F_TEST_XDP_LIVE_FRAMES was added in 5.18.0; see https://github.com/torvalds/linux/commit/b530e9e1063e"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::TEST_RUN).unwrap();
+    let prog: &mut Xdp = bpf.program_mut("test_run_xdp").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+
+    prog.test_run(TestRunOptions {
+        data_in: Some(&PACKET),
+        repeat: 8,
+        flags: TestRunFlags::XDP_LIVE_FRAMES,
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(runs(&bpf), 8);
+}
+
+#[test]
+fn test_run_raw_tracepoint() {
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 10, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, BPF_PROG_TEST_RUN support for raw tracepoints was added in 5.10.0; see https://github.com/torvalds/linux/commit/1b4d60ec162f"
+        );
+        return;
+    }
+
+    let mut bpf = Ebpf::load(crate::TEST_RUN).unwrap();
+    let prog: &mut RawTracePoint = bpf
+        .program_mut("test_run_raw_tp")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    prog.load().unwrap();
+
+    let args = [40u64, 2];
+    let ctx_in: Vec<u8> = args.iter().flat_map(|arg| arg.to_ne_bytes()).collect();
+    let result = prog
+        .test_run(TestRunOptions {
+            ctx_in: Some(&ctx_in),
+            cpu: 0,
+            flags: TestRunFlags::RUN_ON_CPU,
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(result.return_value, 42);
+}
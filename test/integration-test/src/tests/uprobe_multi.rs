@@ -0,0 +1,135 @@
+use assert_matches::assert_matches;
+use aya::{
+    Ebpf, EbpfLoader,
+    features::is_link_type_supported,
+    maps::ring_buf::RingBuf,
+    programs::{LinkType, ProgramError, UProbe, UProbeError, UProbeMultiLocations},
+};
+use test_log::test;
+
+fn load() -> Ebpf {
+    const RING_BUF_BYTE_SIZE: u32 = 512; // arbitrary, but big enough
+
+    let mut bpf = EbpfLoader::new()
+        .set_max_entries("RING_BUF", RING_BUF_BYTE_SIZE)
+        .load(crate::UPROBE_MULTI)
+        .unwrap();
+    prog_mut(&mut bpf).load().unwrap();
+    bpf
+}
+
+fn prog_mut(bpf: &mut Ebpf) -> &mut UProbe {
+    bpf.program_mut("uprobe_multi").unwrap().try_into().unwrap()
+}
+
+fn cookies(bpf: &mut Ebpf) -> Vec<u64> {
+    let mut ring_buf = RingBuf::try_from(bpf.map_mut("RING_BUF").unwrap()).unwrap();
+    let mut cookies = Vec::new();
+    while let Some(read) = ring_buf.next() {
+        let read = read.as_ref();
+        match read.try_into() {
+            Ok(read) => cookies.push(u64::from_le_bytes(read)),
+            Err(std::array::TryFromSliceError { .. }) => {
+                panic!("invalid ring buffer data: {read:x?}")
+            }
+        }
+    }
+    cookies
+}
+
+fn trigger() {
+    uprobe_multi_trigger_ebpf_program_a(1);
+    uprobe_multi_trigger_ebpf_program_b(2);
+    uprobe_multi_trigger_ebpf_program_a(1);
+}
+
+#[test]
+fn uprobe_multi_symbols() {
+    let mut bpf = load();
+    let prog = prog_mut(&mut bpf);
+    let result = prog.attach_multi(
+        "/proc/self/exe",
+        UProbeMultiLocations::Symbols(&[
+            "uprobe_multi_trigger_ebpf_program_a",
+            "uprobe_multi_trigger_ebpf_program_b",
+        ]),
+        None,
+        Some(&[1, 2]),
+    );
+
+    if !is_link_type_supported(LinkType::UProbeMulti).unwrap() {
+        assert_matches!(
+            result,
+            Err(ProgramError::UProbeError(
+                UProbeError::MultiAttachNotSupported
+            ))
+        );
+        eprintln!(
+            "skipping test, uprobe.multi links aren't supported; they were added in 6.6.0, see https://github.com/torvalds/linux/commit/89ae89f53d20"
+        );
+        return;
+    }
+    let link = result.unwrap();
+
+    trigger();
+    prog.detach(link).unwrap();
+    trigger();
+
+    assert_eq!(cookies(&mut bpf), [1, 2, 1]);
+}
+
+#[test]
+fn uprobe_multi_pattern() {
+    if !is_link_type_supported(LinkType::UProbeMulti).unwrap() {
+        eprintln!(
+            "skipping test, uprobe.multi links aren't supported; they were added in 6.6.0, see https://github.com/torvalds/linux/commit/89ae89f53d20"
+        );
+        return;
+    }
+
+    let mut bpf = load();
+    let prog = prog_mut(&mut bpf);
+
+    assert_matches!(
+        prog.attach_multi(
+            "/proc/self/exe",
+            UProbeMultiLocations::Pattern("uprobe_multi_trigger_ebpf_program_*"),
+            None,
+            Some(&[1, 2, 3]),
+        ),
+        Err(ProgramError::UProbeError(
+            UProbeError::CookieCountMismatch {
+                expected: 2,
+                actual: 3,
+            }
+        ))
+    );
+
+    // The matching symbols are sorted by name, so program_a gets the first
+    // cookie.
+    let link = prog
+        .attach_multi(
+            "/proc/self/exe",
+            UProbeMultiLocations::Pattern("uprobe_multi_trigger_ebpf_program_*"),
+            None,
+            Some(&[3, 4]),
+        )
+        .unwrap();
+
+    trigger();
+    prog.detach(link).unwrap();
+
+    assert_eq!(cookies(&mut bpf), [3, 4, 3]);
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub extern "C" fn uprobe_multi_trigger_ebpf_program_a(arg: u64) {
+    std::hint::black_box(arg);
+}
+
+#[unsafe(no_mangle)]
+#[inline(never)]
+pub extern "C" fn uprobe_multi_trigger_ebpf_program_b(arg: u32) {
+    std::hint::black_box(arg);
+}
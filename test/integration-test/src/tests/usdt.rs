@@ -0,0 +1,163 @@
+use std::sync::atomic::{AtomicU16, Ordering};
+
+use assert_matches::assert_matches;
+use aya::{
+    Ebpf, EbpfLoader,
+    maps::ring_buf::RingBuf,
+    programs::{ProgramError, Usdt, UsdtError, usdt::USDT_MAX_SPEC_COUNT},
+    util::KernelVersion,
+};
+use test_log::test;
+
+// Incremented by the kernel while the probe is attached.
+#[unsafe(link_section = ".probes")]
+static USDT_SEMAPHORE: AtomicU16 = AtomicU16::new(0);
+
+fn load(max_specs: u32) -> Option<Ebpf> {
+    const RING_BUF_BYTE_SIZE: u32 = 512; // arbitrary, but big enough
+
+    let kernel_version = KernelVersion::current().unwrap();
+    if kernel_version < KernelVersion::new(5, 15, 0) {
+        eprintln!(
+            "skipping test on kernel {kernel_version:?}, bpf_get_attach_cookie was added in 5.15.0; see https://github.com/torvalds/linux/commit/7adfc6c9b315"
+        );
+        return None;
+    }
+
+    let mut bpf = EbpfLoader::new()
+        .set_max_entries("RING_BUF", RING_BUF_BYTE_SIZE)
+        .set_max_entries("AYA_USDT_SPECS", max_specs)
+        .load(crate::USDT)
+        .unwrap();
+    prog_mut(&mut bpf).load().unwrap();
+    Some(bpf)
+}
+
+fn prog_mut(bpf: &mut Ebpf) -> &mut Usdt {
+    bpf.program_mut("usdt").unwrap().try_into().unwrap()
+}
+
+fn args(bpf: &mut Ebpf) -> Vec<i64> {
+    let mut ring_buf = RingBuf::try_from(bpf.map_mut("RING_BUF").unwrap()).unwrap();
+    let mut args = Vec::new();
+    while let Some(read) = ring_buf.next() {
+        let read = read.as_ref();
+        match read.try_into() {
+            Ok(read) => args.push(i64::from_le_bytes(read)),
+            Err(std::array::TryFromSliceError { .. }) => {
+                panic!("invalid ring buffer data: {read:x?}")
+            }
+        }
+    }
+    args
+}
+
+#[test]
+fn usdt() {
+    let Some(mut bpf) = load(USDT_MAX_SPEC_COUNT) else {
+        return;
+    };
+    let prog = prog_mut(&mut bpf);
+
+    assert_eq!(USDT_SEMAPHORE.load(Ordering::Relaxed), 0);
+    let link = prog
+        .attach("aya", "usdt_trigger", "/proc/self/exe", None)
+        .unwrap();
+    assert_eq!(USDT_SEMAPHORE.load(Ordering::Relaxed), 1);
+
+    usdt_trigger_ebpf_program(-42, 0xdead_beef_cafe);
+    prog.detach(link).unwrap();
+    assert_eq!(USDT_SEMAPHORE.load(Ordering::Relaxed), 0);
+    usdt_trigger_ebpf_program(1, 2);
+
+    assert_eq!(args(&mut bpf), [-42, 0xdead_beef_cafe]);
+}
+
+#[test]
+fn usdt_probe_not_found() {
+    let Some(mut bpf) = load(USDT_MAX_SPEC_COUNT) else {
+        return;
+    };
+    assert_matches!(
+        prog_mut(&mut bpf).attach("aya", "no_such_probe", "/proc/self/exe", None),
+        Err(ProgramError::UsdtError(UsdtError::ProbeNotFound { provider, name })) => {
+            assert_eq!(provider, "aya");
+            assert_eq!(name, "no_such_probe");
+        }
+    );
+}
+
+#[test]
+fn usdt_too_many_specs() {
+    let Some(mut bpf) = load(1) else {
+        return;
+    };
+    let prog = prog_mut(&mut bpf);
+
+    // The spec of the probe fills the map.
+    let link = prog
+        .attach("aya", "usdt_trigger", "/proc/self/exe", None)
+        .unwrap();
+    assert_matches!(
+        prog.attach("aya", "usdt_trigger", "/proc/self/exe", None),
+        Err(ProgramError::UsdtError(UsdtError::TooManySpecs {
+            count: 1,
+            max_entries: 1
+        }))
+    );
+
+    // Detaching frees the entry.
+    prog.detach(link).unwrap();
+    let link = prog
+        .attach("aya", "usdt_trigger", "/proc/self/exe", None)
+        .unwrap();
+    prog.detach(link).unwrap();
+}
+
+// Defines the probe `aya:usdt_trigger` like `STAP_PROBE2` of `sys/sdt.h`.
+macro_rules! usdt_probe {
+    ($args:literal, $($operands:tt)*) => {
+        std::arch::asm!(
+            "990: nop",
+            ".pushsection .note.stapsdt, \"\", \"note\"",
+            ".balign 4",
+            ".4byte 992f-991f, 994f-993f, 3",
+            "991: .asciz \"stapsdt\"",
+            "992: .balign 4",
+            "993: .8byte 990b",
+            ".8byte 0",
+            ".8byte {semaphore}",
+            ".asciz \"aya\"",
+            ".asciz \"usdt_trigger\"",
+            concat!(".asciz \"", $args, "\""),
+            "994: .balign 4",
+            ".popsection",
+            semaphore = sym USDT_SEMAPHORE,
+            $($operands)*
+        )
+    };
+}
+
+#[inline(never)]
+pub fn usdt_trigger_ebpf_program(a: i32, b: u64) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        usdt_probe!(
+            "-4@{a:e} 8@{b}",
+            a = in(reg) a,
+            b = in(reg) b,
+            options(att_syntax, nostack, preserves_flags),
+        );
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        usdt_probe!(
+            "-4@{a:w} 8@{b}",
+            a = in(reg) a,
+            b = in(reg) b,
+            options(nostack, preserves_flags),
+        );
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    std::hint::black_box((a, b));
+}
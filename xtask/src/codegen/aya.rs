@@ -133,6 +133,8 @@ fn codegen_bindings(opts: &SysrootOptions, libbpf_dir: &Path) -> Result<()> {
             "BPF_.*",
             "SO_ATTACH_BPF",
             "SO_DETACH_BPF",
+            "SO_ATTACH_REUSEPORT_EBPF",
+            "SO_DETACH_REUSEPORT_BPF",
             // BTF
             "BTF_INT_.*",
             "BTF_KIND_.*",